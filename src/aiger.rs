@@ -0,0 +1,395 @@
+use crate::cnf::{Clause, Literal, Var};
+use crate::dimacs::{open_file, DimacsFile};
+use crate::error::{Error, Result};
+use crate::solver::interpolant::{Interpolant, InterpolantNode};
+use crate::symbols::SymbolTable;
+use itertools::Itertools;
+use std::io::Read;
+use std::ops::RangeInclusive;
+
+/// And-inverter graph in the AIGER format, see A. Biere, K. Heljanko, and S. Wieringa,
+/// “AIGER 1.9 and Beyond,” Tech. Rep. 11/2, Institute for Formal Models and Verification,
+/// Johannes Kepler University, 2011. Literals are 2 * variable + sign, 0 and 1 are the constants.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Aiger {
+    pub max_var: usize,
+    pub inputs: Vec<u32>,
+    /// (current state, next state, initial value), where the initial value is 0, 1 or the
+    /// current state literal itself for uninitialized latches
+    pub latches: Vec<(u32, u32, u32)>,
+    pub outputs: Vec<u32>,
+    pub bad: Vec<u32>,
+    pub constraints: Vec<u32>,
+    /// (lhs, rhs0, rhs1) with lhs = rhs0 ∧ rhs1
+    pub ands: Vec<(u32, u32, u32)>,
+}
+
+impl Aiger {
+    /// Combinational graph with the interpolant as its only output, where CNF variable v is
+    /// input v, e.g. to pass interpolants to a model checker
+    pub fn from_interpolant(interpolant: &Interpolant, num_vars: usize) -> Self {
+        let mut aiger = Aiger {
+            max_var: num_vars,
+            inputs: (1..=num_vars as u32).map(|var| 2 * var).collect(),
+            ..Default::default()
+        };
+        let mut and = |rhs0: u32, rhs1: u32| {
+            aiger.max_var += 1;
+            let lhs = 2 * aiger.max_var as u32;
+            aiger.ands.push((lhs, rhs0, rhs1));
+            lhs
+        };
+        let mut literals: Vec<u32> = Vec::with_capacity(interpolant.nodes.len());
+        for node in &interpolant.nodes {
+            let literal = match *node {
+                InterpolantNode::Const(value) => value as u32,
+                InterpolantNode::Literal(lit) => {
+                    2 * lit.var().index() as u32 + lit.negative() as u32
+                }
+                InterpolantNode::And(left, right) => and(literals[left], literals[right]),
+                // a ∨ b = ¬(¬a ∧ ¬b)
+                InterpolantNode::Or(left, right) => {
+                    and(literals[left] ^ 1, literals[right] ^ 1) ^ 1
+                }
+            };
+            literals.push(literal);
+        }
+        aiger.outputs.push(literals[interpolant.root]);
+        aiger
+    }
+
+    /// The graph in the ASCII format. The header only has the numbers of bad state literals
+    /// and invariant constraints if there are any.
+    pub fn to_ascii(&self) -> String {
+        let mut header = vec![
+            self.max_var,
+            self.inputs.len(),
+            self.latches.len(),
+            self.outputs.len(),
+            self.ands.len(),
+        ];
+        if !self.bad.is_empty() || !self.constraints.is_empty() {
+            header.extend([self.bad.len(), self.constraints.len()]);
+        }
+        let latches = self.latches.iter().map(|(current, next, init)| match init {
+            0 => format!("{} {}", current, next),
+            _ => format!("{} {} {}", current, next, init),
+        });
+        let ands = (self.ands.iter()).map(|(lhs, rhs0, rhs1)| format!("{} {} {}", lhs, rhs0, rhs1));
+        let literals = (self.inputs.iter())
+            .map(|lit| lit.to_string())
+            .chain(latches)
+            .chain(self.outputs.iter().map(|lit| lit.to_string()))
+            .chain(self.bad.iter().map(|lit| lit.to_string()))
+            .chain(self.constraints.iter().map(|lit| lit.to_string()))
+            .chain(ands);
+        let mut ascii = format!("aag {}\n", header.iter().join(" "));
+        for line in literals {
+            ascii.push_str(&line);
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    /// Tseitin encoding of the graph, which is satisfiable iff an output or bad state literal
+    /// can be true while all invariant constraints hold and the latches have their initial
+    /// values, i.e. the properties are checked with a bound of 0. AIGER variable v is CNF
+    /// variable v, the constants use the additional variable max_var + 1.
+    pub fn to_cnf(&self) -> DimacsFile {
+        let true_var = Var::new(self.max_var + 1);
+        let to_literal = |lit: u32| {
+            let var = Var::new((lit / 2) as usize);
+            let positive = lit.is_multiple_of(2);
+            match var.index() {
+                0 => Literal::from_value(true_var, !positive),
+                _ => Literal::from_value(var, positive),
+            }
+        };
+
+        let mut clauses = vec![vec![Literal::from_value(true_var, true)]];
+        for (lhs, rhs0, rhs1) in &self.ands {
+            let (lhs, rhs0, rhs1) = (to_literal(*lhs), to_literal(*rhs0), to_literal(*rhs1));
+            clauses.push(vec![-lhs, rhs0]);
+            clauses.push(vec![-lhs, rhs1]);
+            clauses.push(vec![lhs, -rhs0, -rhs1]);
+        }
+        for (latch, _, init) in &self.latches {
+            match init {
+                0 => clauses.push(vec![-to_literal(*latch)]),
+                1 => clauses.push(vec![to_literal(*latch)]),
+                _ => {}
+            }
+        }
+        for constraint in &self.constraints {
+            clauses.push(vec![to_literal(*constraint)]);
+        }
+        clauses.push(
+            self.outputs
+                .iter()
+                .chain(&self.bad)
+                .map(|lit| to_literal(*lit))
+                .collect(),
+        );
+
+        let clauses = clauses
+            .into_iter()
+            .map(|literals| literals.into_iter().sorted().dedup().collect_vec())
+            .filter(|literals| !literals.iter().any(|lit| literals.contains(&-*lit)))
+            .map(Clause::from)
+            .collect();
+        DimacsFile {
+            num_vars: true_var.index(),
+            clauses,
+            projection: None,
+            quantifiers: vec![],
+            symbols: SymbolTable::new(),
+            objective: None,
+            a_clauses: None,
+            warnings: vec![],
+        }
+    }
+}
+
+pub fn aiger_from_file(path: &str) -> Result<Aiger> {
+    let mut bytes = vec![];
+    open_file(path)?.read_to_end(&mut bytes)?;
+    aiger_from_bytes(&bytes)
+}
+
+/// Parses both the ASCII (`aag`) and the binary (`aig`) format including the sections of
+/// AIGER 1.9. Justice and fairness properties, symbols and comments are ignored.
+pub fn aiger_from_bytes(bytes: &[u8]) -> Result<Aiger> {
+    let mut parser = Parser {
+        bytes,
+        position: 0,
+        line: 1,
+    };
+
+    let header_line = parser.next_line()?;
+    let header = header_line.split_whitespace().collect_vec();
+    let invalid_header = || Error::InvalidHeader(header_line.clone());
+    if !(6..=10).contains(&header.len()) || !["aag", "aig"].contains(&header[0]) {
+        return Err(invalid_header());
+    }
+    let binary = header[0] == "aig";
+    let counts = header[1..]
+        .iter()
+        .map(|count| count.parse::<u32>().map_err(|_| invalid_header()))
+        .collect::<Result<Vec<_>>>()?;
+    let count = |i: usize| counts.get(i).copied().unwrap_or(0);
+    let (max_var, num_inputs, num_latches, num_outputs, num_ands) =
+        (count(0), count(1), count(2), count(3), count(4));
+    let (num_bad, num_constraints, num_justice, num_fairness) =
+        (count(5), count(6), count(7), count(8));
+
+    let mut aiger = Aiger {
+        max_var: max_var as usize,
+        ..Default::default()
+    };
+    for i in 0..num_inputs {
+        aiger.inputs.push(match binary {
+            true => 2 * (i + 1),
+            false => parser.next_literals(1..=1, max_var)?[0],
+        });
+    }
+    for i in 0..num_latches {
+        let current = 2 * (num_inputs + i + 1);
+        let literals = match binary {
+            true => [vec![current], parser.next_literals(1..=2, max_var)?].concat(),
+            false => parser.next_literals(2..=3, max_var)?,
+        };
+        aiger
+            .latches
+            .push((literals[0], literals[1], *literals.get(2).unwrap_or(&0)));
+    }
+    for _ in 0..num_outputs {
+        aiger.outputs.push(parser.next_literals(1..=1, max_var)?[0]);
+    }
+    for _ in 0..num_bad {
+        aiger.bad.push(parser.next_literals(1..=1, max_var)?[0]);
+    }
+    for _ in 0..num_constraints {
+        aiger
+            .constraints
+            .push(parser.next_literals(1..=1, max_var)?[0]);
+    }
+    let justice_sizes = (0..num_justice)
+        .map(|_| Ok(parser.next_literals(1..=1, u32::MAX)?[0]))
+        .collect::<Result<Vec<_>>>()?;
+    for _ in 0..justice_sizes.iter().sum::<u32>() + num_fairness {
+        parser.next_literals(1..=1, max_var)?;
+    }
+
+    for i in 0..num_ands {
+        let gate = match binary {
+            true => {
+                let lhs = 2 * (num_inputs + num_latches + i + 1);
+                let (delta0, delta1) = (parser.next_delta()?, parser.next_delta()?);
+                let rhs0 = lhs.checked_sub(delta0);
+                let rhs1 = rhs0.and_then(|rhs0| rhs0.checked_sub(delta1));
+                let (Some(rhs0), Some(rhs1)) = (rhs0, rhs1) else {
+                    return Err(Error::Parse(format!("Invalid delta in AND gate {}", lhs)));
+                };
+                (lhs, rhs0, rhs1)
+            }
+            false => {
+                let literals = parser.next_literals(3..=3, max_var)?;
+                (literals[0], literals[1], literals[2])
+            }
+        };
+        aiger.ands.push(gate);
+    }
+
+    Ok(aiger)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn syntax_error(&self, message: String) -> Error {
+        Error::Syntax {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<String> {
+        let rest = &self.bytes[self.position..];
+        if rest.is_empty() {
+            return Err(self.syntax_error("Unexpected end of file".to_string()));
+        }
+        let end = rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+        self.position += (end + 1).min(rest.len());
+        self.line += 1;
+        Ok(String::from_utf8_lossy(&rest[..end]).trim().to_string())
+    }
+
+    /// Parses a line with the given number of literals, all of them at most 2 * max_var + 1
+    fn next_literals(&mut self, count: RangeInclusive<usize>, max_var: u32) -> Result<Vec<u32>> {
+        let line_number = self.line;
+        let line = self.next_line()?;
+        let literals = line
+            .split_whitespace()
+            .map(|token| token.parse::<u32>().ok().filter(|lit| lit / 2 <= max_var))
+            .collect::<Option<Vec<_>>>();
+        match literals {
+            Some(literals) if count.contains(&literals.len()) => Ok(literals),
+            _ => Err(Error::Syntax {
+                line: line_number,
+                message: format!("Invalid line \"{}\"", line),
+            }),
+        }
+    }
+
+    /// Unsigned LEB128 encoded difference of the binary format
+    fn next_delta(&mut self) -> Result<u32> {
+        let mut delta = 0u32;
+        for shift in (0..32).step_by(7) {
+            let Some(byte) = self.bytes.get(self.position) else {
+                return Err(Error::Parse("Unexpected end of AND gates".to_string()));
+            };
+            self.position += 1;
+            delta |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(delta);
+            }
+        }
+        Err(Error::Parse("Invalid delta in AND gate".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+
+    fn is_sat(aiger: &Aiger) -> bool {
+        let cnf = aiger.to_cnf();
+        let config = Config::default();
+        Solver::new(cnf.clauses, cnf.num_vars, config)
+            .solve()
+            .is_sat()
+    }
+
+    #[test]
+    fn parse_ascii_and_binary() {
+        // output 8 = 2 ∧ 4 and a latch 6 with initial value 1
+        let ascii =
+            aiger_from_bytes(b"aag 4 2 1 1 1\n2\n4\n6 8 1\n8\n8 4 2\nc\ncomment\n").unwrap();
+        let binary = aiger_from_bytes(b"aig 4 2 1 1 1\n8 1\n8\n\x04\x02i0 a\n").unwrap();
+        let expected = Aiger {
+            max_var: 4,
+            inputs: vec![2, 4],
+            latches: vec![(6, 8, 1)],
+            outputs: vec![8],
+            ands: vec![(8, 4, 2)],
+            ..Default::default()
+        };
+        assert_eq!(ascii, expected);
+        assert_eq!(binary, expected);
+        assert!(is_sat(&ascii));
+    }
+
+    #[test]
+    fn tseitin_encoding() {
+        // 6 = 2 ∧ ¬2 is never true
+        let contradiction = aiger_from_bytes(b"aag 3 1 0 1 1\n2\n6\n6 2 3\n").unwrap();
+        assert!(!is_sat(&contradiction));
+        // ¬6 with 6 = 2 ∧ 4 is true for most inputs, unless the constraint 6 holds
+        let negated = aiger_from_bytes(b"aag 3 2 0 1 1\n2\n4\n7\n6 4 2\n").unwrap();
+        assert!(is_sat(&negated));
+        let constrained = aiger_from_bytes(b"aag 3 2 0 0 1 1 1\n2\n4\n7\n6\n6 4 2\n").unwrap();
+        assert!(!is_sat(&constrained));
+        // latches start at 0 unless initialized
+        let latch = aiger_from_bytes(b"aag 1 0 1 1 0\n2 3\n2\n").unwrap();
+        assert!(!is_sat(&latch));
+    }
+
+    #[test]
+    fn write_ascii() {
+        let ascii = "aag 5 2 1 1 2 1 1\n2\n4\n6 8 1\n8\n9\n7\n8 4 2\n10 8 7\n";
+        assert_eq!(
+            aiger_from_bytes(ascii.as_bytes()).unwrap().to_ascii(),
+            ascii
+        );
+        let ascii = "aag 3 1 1 1 1\n2\n4 6\n6\n6 4 3\n";
+        assert_eq!(
+            aiger_from_bytes(ascii.as_bytes()).unwrap().to_ascii(),
+            ascii
+        );
+
+        // 1 ∨ ¬2 is false for ¬1 ∧ 2, which the constraints require
+        let interpolant = Interpolant {
+            nodes: vec![
+                InterpolantNode::Const(false),
+                InterpolantNode::Const(true),
+                InterpolantNode::Literal(Literal::new(1)),
+                InterpolantNode::Literal(Literal::new(-2)),
+                InterpolantNode::Or(2, 3),
+            ],
+            root: 4,
+        };
+        let mut aiger = Aiger::from_interpolant(&interpolant, 2);
+        assert_eq!(aiger.to_ascii(), "aag 3 2 0 1 1\n2\n4\n7\n6 3 4\n");
+        assert!(is_sat(&aiger));
+        aiger.constraints = vec![3, 4];
+        assert!(!is_sat(&aiger));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(matches!(
+            aiger_from_bytes(b"aag 1 1 0 1 0\n2\n4\n"),
+            Err(Error::Syntax { line: 3, .. })
+        ));
+        assert!(matches!(
+            aiger_from_bytes(b"aig 1 0 0"),
+            Err(Error::InvalidHeader(_))
+        ));
+    }
+}
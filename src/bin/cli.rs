@@ -1,86 +1,983 @@
-use clap::{arg, Parser};
-use std::collections::HashMap;
-use utopia::cnf::{check_assignment, Clause, VarId};
-use utopia::dimacs::{clauses_from_dimacs_file, solution_to_dimacs};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use itertools::Itertools;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use utopia::aiger::{aiger_from_file, Aiger};
+use utopia::cnf::{Clause, Literal, Model, SolveResult, Var};
+use utopia::dimacs::{
+    clauses_from_dimacs_file, clauses_to_dimacs, gcnf_from_file, solution_to_dimacs,
+    status_to_dimacs, values_to_dimacs, wcnf_from_file, without_compression_extension, DimacsFile,
+};
+use utopia::error::{Error, Result};
+use utopia::formula::{formula_from_file, TseitinEncoder};
+use utopia::maxsat::{solve_maxsat, MaxSatResult};
+use utopia::mus::{minimal_unsatisfiable_group_subset, minimal_unsatisfiable_subset, MusResult};
+use utopia::optimize::{minimize, OptimizationResult};
+use utopia::pb::{opb_from_file, PbEncoder, PbEncoding};
+use utopia::presimplify::presimplify;
+use utopia::proof::checker::{check_proof, proof_from_file, CheckResult};
+use utopia::qbf::solve_2qbf;
+use utopia::solver::checkpoint::Checkpoint;
+use utopia::solver::clause_learning::{AdditionalClause, LearningScheme};
 use utopia::solver::config::Config;
-use utopia::solver::heuristic::HeuristicType;
-use utopia::solver::progress::ProgressPrintingInterval;
+use utopia::solver::heuristic::{HeuristicType, ScoreInit};
+use utopia::solver::log::Verbosity;
+use utopia::solver::params::Params;
+use utopia::solver::phases::PhaseMode;
+use utopia::solver::presets::{ConfigMode, InstanceFeatures, Preset};
+use utopia::solver::progress::{ProgressFormat, ProgressPrintingInterval};
 use utopia::solver::restarts::RestartPolicy;
-use utopia::solver::statistics::StateStatistics;
+use utopia::solver::statistics::{StateStatistics, StatisticsFormat};
+use utopia::solver::trail::{BacktrackingMode, ReuseStrategy};
 use utopia::solver::Solver;
+use utopia::symbols::SymbolTable;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(index = 1)]
-    file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(index = 1, required = true)]
+    file: Option<String>,
 
     /// Proof file
     #[arg(short, long, help = "Path to put proof file")]
     proof: Option<String>,
 
+    /// Only write the lemmas the empty clause depends on to the proof, tracked during the search
+    #[arg(long, default_value = "false")]
+    trim_proof: bool,
+
+    /// Write the proof with the resolution chain of every lemma to this file in LRAT format
+    #[arg(long)]
+    lrat: Option<String>,
+
     #[arg(long, default_value = "vsids")]
     heuristic: HeuristicType,
 
     #[arg(short, long, default_value = "glucose-ema")]
     restart_policy: RestartPolicy,
 
+    /// Conflicts between restarts for the custom restart policy, e.g. 100,200,400
+    #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u64).range(1..))]
+    restart_schedule: Vec<u64>,
+
+    /// Conflicts per element of the Luby sequence for the luby restart policy, overrides the
+    /// parameter file [default: 32]
+    #[arg(long)]
+    luby_unit: Option<usize>,
+
+    /// Read the numeric parameters of the search from this TOML or JSON file, missing ones keep
+    /// their defaults
+    #[arg(long)]
+    params: Option<String>,
+
+    /// Write the numeric parameters to this file, as JSON if it ends in .json and as TOML
+    /// otherwise, e.g. as a template for tuning
+    #[arg(long)]
+    dump_params: Option<String>,
+
     #[arg(long, default_value = "false")]
     no_inprocessing: bool,
 
+    /// Remove duplicate and subsumed clauses of the input before creating the solver
+    #[arg(long, default_value = "false")]
+    presimplify: bool,
+
+    /// Interval of the progress: short (or fast), medium, long (or slow), conflicts:<n>, off (or none)
     #[arg(long, default_value = "medium")]
     progress_printing: ProgressPrintingInterval,
+
+    /// Format of the progress printed to stderr
+    #[arg(long, default_value = "table")]
+    progress: ProgressFormat,
+
+    /// Disable colors, which are also disabled if stderr is not a terminal
+    #[arg(long, default_value = "false")]
+    no_color: bool,
+
+    /// Stop the search after this many seconds and report UNKNOWN
+    #[arg(long)]
+    time_limit: Option<f64>,
+
+    /// Stop the search once the resident memory exceeds this many MB and report UNKNOWN
+    #[arg(long)]
+    mem_limit: Option<usize>,
+
+    /// Only print the status line, without the `v` lines of the model, the MUS or the certificate
+    /// of a QBF
+    #[arg(long, default_value = "false")]
+    no_witness: bool,
+
+    /// Only print the literals of a prime implicant in the `v` line, other variables don't matter
+    #[arg(long, default_value = "false", conflicts_with = "no_witness")]
+    prime_implicant: bool,
+
+    /// Print the values of the variables named by `c var <var> <name>` lines as `name=true` or
+    /// `name=false` in the `v` line instead of the literals
+    #[arg(long, default_value = "false", conflicts_with = "no_witness")]
+    named_output: bool,
+
+    /// Print the witnesses flipped to extend the model to the clauses removed by inprocessing,
+    /// each with the clause that required it, as `c flip <witness> by <clause> 0` lines
+    #[arg(long, default_value = "false", conflicts_with = "no_witness")]
+    reconstruction_trace: bool,
+
+    /// Only print the result
+    #[arg(short, long, default_value = "false", conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print clause database reductions, inprocessing and mode switches, and with -vv every
+    /// restart
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    #[arg(long, default_value = "non-chronological")]
+    backtracking: BacktrackingMode,
+
+    /// Implication points at which the resolution of a conflict stops
+    #[arg(long, default_value = "first-uip")]
+    learning_scheme: LearningScheme,
+
+    /// Second clause learned from each conflict
+    #[arg(long, default_value = "none")]
+    additional_clause: AdditionalClause,
+
+    /// Value a decision variable is assigned first
+    #[arg(long, default_value = "saved")]
+    phase_mode: PhaseMode,
+
+    /// Initial scores of the heuristic and initial phases, computed from the clauses
+    #[arg(long, default_value = "default")]
+    score_init: ScoreInit,
+
+    /// Always backtrack to level 0 on restarts instead of reusing the trail
+    #[arg(long, default_value = "false")]
+    no_trail_reuse: bool,
+
+    /// Which decision levels a restart reuses
+    #[arg(long, default_value = "preferred")]
+    reuse_strategy: ReuseStrategy,
+
+    /// Save the assignments undone by backjumps and assign their implications again once their
+    /// decisions hold again
+    #[arg(long, default_value = "false")]
+    trail_saving: bool,
+
+    /// Don't set the saved phases by local search every few restarts
+    #[arg(long, default_value = "false")]
+    no_walk: bool,
+
+    /// Alternate between a focused mode and a stable mode with rare restarts and target phases
+    #[arg(long, default_value = "false")]
+    stabilization: bool,
+
+    /// Heuristic of the stable mode
+    #[arg(long, default_value = "vmtf")]
+    stable_heuristic: HeuristicType,
+
+    /// Count the models, projected onto the variables of `c p show` lines if present
+    #[arg(long, default_value = "false")]
+    count: bool,
+
+    /// Write a checkpoint to this file if the search stops without a result, e.g. at a limit
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    /// Continue the search from a checkpoint of the same formula
+    #[arg(long)]
+    resume: Option<String>,
+
+    /// Only simplify the formula without search and write it to this file in DIMACS format,
+    /// together with the map to extend its models to `<SIMPLIFY>.map`
+    #[arg(long)]
+    simplify: Option<String>,
+
+    /// Detect XOR constraints and propagate them with Gaussian elimination, which isn't logged
+    /// in proofs
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = ["proof", "lrat", "core", "interpolant"]
+    )]
+    xor: bool,
+
+    /// CNF encoding of the constraints of OPB files
+    #[arg(long, default_value = "auto")]
+    pb_encoding: PbEncoding,
+
+    /// Format of the input file
+    #[arg(long, default_value = "auto")]
+    format: InputFormat,
+
+    /// Use the options as given (default) or adapt the heuristic, restart and inprocessing
+    /// options to features of the instance (auto)
+    #[arg(long, default_value = "default")]
+    config: ConfigMode,
+
+    /// Base inprocessing and progress printing on conflicts instead of time for reproducible runs
+    #[arg(long, default_value = "false")]
+    deterministic: bool,
+
+    /// Write the final statistics to this file
+    #[arg(long)]
+    stats_out: Option<String>,
+
+    /// Format of the statistics file
+    #[arg(long, default_value = "json")]
+    stats_format: StatisticsFormat,
+
+    /// Write an unsatisfiable core extracted from the proof to this file in DIMACS format
+    #[arg(long)]
+    core: Option<String>,
+
+    /// Write a Craig interpolant of the partition given by `c part A` and `c part B` lines to
+    /// this file in the ASCII AIGER format
+    #[arg(long)]
+    interpolant: Option<String>,
+
+    /// Write the assignments, reasons and unassignments of these variables to the trace file
+    #[arg(long, value_delimiter = ',')]
+    trace_vars: Vec<usize>,
+
+    /// File for `--trace-vars`, stderr by default
+    #[arg(long)]
+    trace_file: Option<String>,
+
+    /// Write the implication graphs of the first conflicts to this directory as DOT and JSON
+    #[arg(long)]
+    dump_conflict_graphs: Option<String>,
+
+    /// Number of conflicts for `--dump-conflict-graphs`
+    #[arg(long, default_value = "10")]
+    num_conflict_graphs: usize,
+
+    /// Threads updating the watches of the clauses in parallel (experimental)
+    #[cfg(feature = "parallel")]
+    #[arg(long, default_value = "1")]
+    propagation_threads: usize,
+}
+
+impl Args {
+    fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    fn file(&self) -> &str {
+        self.file
+            .as_deref()
+            .expect("file is required without a subcommand")
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check a DRAT proof of unsatisfiability of a CNF
+    Check {
+        /// CNF in DIMACS format
+        cnf: String,
+        /// Proof in the textual DRAT format
+        proof: String,
+    },
+    /// Print a minimal unsatisfiable subset of the clauses of a CNF in DIMACS format, or of the
+    /// groups of a `.gcnf` file
+    Mus {
+        /// CNF in DIMACS or GCNF format
+        cnf: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+enum InputFormat {
+    /// Detected by the file extension
+    #[clap(name = "auto")]
+    Auto,
+    #[clap(name = "dimacs")]
+    Dimacs,
+    /// DIMACS with a quantifier prefix, solved as a QBF with at most two quantifier blocks
+    #[clap(name = "qdimacs")]
+    Qdimacs,
+    #[clap(name = "wcnf")]
+    Wcnf,
+    #[clap(name = "opb")]
+    Opb,
+    #[clap(name = "aiger")]
+    Aiger,
+    /// Boolean formula in infix syntax, e.g. `(a & !b) | ite(c, d, e)`
+    #[clap(name = "formula")]
+    Formula,
+}
+
+impl InputFormat {
+    fn detect(path: &str) -> Self {
+        let path = without_compression_extension(path);
+        if path.ends_with(".wcnf") {
+            InputFormat::Wcnf
+        } else if path.ends_with(".opb") {
+            InputFormat::Opb
+        } else if path.ends_with(".aag") || path.ends_with(".aig") {
+            InputFormat::Aiger
+        } else if path.ends_with(".qdimacs") {
+            InputFormat::Qdimacs
+        } else {
+            InputFormat::Dimacs
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
 
-    println!("{}", BANNER);
-    let dimacs = clauses_from_dimacs_file(&args.file).unwrap();
-
-    let mut solver = Solver::new(
-        dimacs.clauses.clone(),
-        dimacs.num_vars,
-        Config::new(
-            args.heuristic.clone(),
-            args.proof.clone(),
-            args.restart_policy,
-            !args.no_inprocessing,
-            args.progress_printing.clone(),
-        ),
+    if !args.quiet {
+        println!("{}", BANNER);
+    }
+
+    if let Some(Command::Check { cnf, proof }) = &args.command {
+        return run_check(cnf, proof);
+    }
+
+    let mut config = Config::new(
+        args.heuristic.clone(),
+        args.proof.clone(),
+        args.restart_policy,
+        !args.no_inprocessing,
+        args.progress_printing.clone(),
+        args.backtracking,
     );
+    config.restart_schedule = args.restart_schedule.iter().map(|&n| n as usize).collect();
+    if let Some(params_file) = &args.params {
+        config.params = or_exit(Params::from_file(params_file));
+    }
+    if let Some(luby_unit) = args.luby_unit {
+        config.params.luby_unit = luby_unit;
+    }
+    or_exit(config.params.validate());
+    if let Some(params_file) = &args.dump_params {
+        or_exit(config.params.write(params_file));
+    }
+    config.gaussian_elimination = args.xor;
+    config.core_extraction = args.core.is_some();
+    config.proof_trimming = args.trim_proof;
+    config.lrat_file = args.lrat.clone();
+    config.resolution_chains = args.interpolant.is_some();
+    config.deterministic = args.deterministic;
+    config.phase_mode = args.phase_mode;
+    config.score_init = args.score_init;
+    config.learning_scheme = args.learning_scheme;
+    config.additional_clause = args.additional_clause;
+    config.trail_reuse = !args.no_trail_reuse;
+    config.reuse_strategy = args.reuse_strategy;
+    config.trail_saving = args.trail_saving;
+    config.walk = !args.no_walk;
+    config.stabilization = args.stabilization;
+    config.stable_heuristic = args.stable_heuristic.clone();
+    config.verbosity = args.verbosity();
+    config.progress_format = args.progress;
+    // the live view can't be redrawn in place in a file or pipe
+    #[cfg(feature = "tui")]
+    if args.progress == ProgressFormat::Tui && !std::io::stderr().is_terminal() {
+        config.progress_format = ProgressFormat::Table;
+    }
+    config.time_limit = args.time_limit.map(std::time::Duration::from_secs_f64);
+    config.memory_limit = args.mem_limit;
+    config.trace_vars = args.trace_vars.iter().copied().map(Var::new).collect();
+    config.trace_file = args.trace_file.clone();
+    config.conflict_graph_dir = args.dump_conflict_graphs.clone();
+    config.num_conflict_graphs = args.num_conflict_graphs;
+    #[cfg(feature = "parallel")]
+    {
+        config.propagation_threads = args.propagation_threads;
+    }
+    // every solver of every mode shares the flag of the signal handler
+    let terminate = Arc::<AtomicBool>::default();
+    config.terminate = Some(terminate.clone());
+    stop_on_signals(terminate);
+    if let Some(Command::Mus { cnf }) = &args.command {
+        return run_mus(&args, cnf, config);
+    }
+    if args.no_color || !std::io::stderr().is_terminal() {
+        colored::control::set_override(false);
+    }
+
+    let format = match args.format {
+        InputFormat::Auto => InputFormat::detect(args.file()),
+        format => format,
+    };
+    let mut dimacs = match format {
+        InputFormat::Wcnf => return run_maxsat(&args, &config),
+        InputFormat::Opb => return run_pb(&args, config),
+        InputFormat::Formula => return run_formula(&args, config),
+        InputFormat::Aiger => or_exit(aiger_from_file(args.file())).to_cnf(),
+        _ => or_exit(clauses_from_dimacs_file(args.file())),
+    };
+    for warning in &dimacs.warnings {
+        println!("c Warning: {}", warning);
+    }
+    if args.interpolant.is_some() && dimacs.a_clauses.is_none() {
+        or_exit(Err(Error::Unsupported(
+            "Interpolants require a partition given by `c part A` and `c part B` lines".to_string(),
+        )))
+    }
+    if args.presimplify {
+        if args.lrat.is_some() || args.interpolant.is_some() {
+            // the ids of the clauses in the LRAT proof and the partition refer to the input
+            or_exit(Err(Error::Unsupported(
+                "LRAT proofs and interpolants of presimplified formulas are not supported"
+                    .to_string(),
+            )))
+        }
+        let stats = presimplify(&mut dimacs.clauses, dimacs.num_vars);
+        if !args.quiet {
+            println!(
+                "c Presimplification removed {} duplicate and {} subsumed clauses",
+                stats.num_duplicates, stats.num_subsumed
+            );
+        }
+    }
+    if format == InputFormat::Qdimacs || !dimacs.quantifiers.is_empty() {
+        return run_qbf(&args, &dimacs, &config);
+    }
+    if let Some(objective) = &dimacs.objective {
+        let mut encoder = PbEncoder::new(dimacs.num_vars, PbEncoding::Totalizer);
+        encoder.clauses = dimacs.clauses.clone();
+        return run_optimization(&args, encoder, objective, &config, |model| {
+            println!("{}", values_to_dimacs(model.iter()))
+        });
+    }
+    if args.config == ConfigMode::Auto {
+        let features = InstanceFeatures::compute(&dimacs.clauses, dimacs.num_vars);
+        let preset = Preset::select(&features);
+        preset.apply(&mut config);
+        if !args.quiet {
+            println!("c Selected the {} preset", preset);
+        }
+    }
+    let mut solver = match &args.resume {
+        Some(checkpoint_file) => resume(checkpoint_file, &dimacs.clauses, config),
+        None => Solver::new(dimacs.clauses, dimacs.num_vars, config),
+    };
+
+    if args.count {
+        let status = print_count(&args, &mut solver, dimacs.projection.as_deref());
+        write_stats(&args, solver.stats());
+        std::process::exit(exit_code(status));
+    }
 
-    let solution = solver.solve();
+    if let Some(simplified_file) = &args.simplify {
+        return run_simplify(&mut solver, simplified_file);
+    }
+
+    let result = solver.solve();
+    if let (SolveResult::Unsat, Some(core_file)) = (&result, &args.core) {
+        write_core(&solver, dimacs.num_vars, core_file);
+    }
+    if let (SolveResult::Unsat, Some(interpolant_file), Some(a_clauses)) =
+        (&result, &args.interpolant, &dimacs.a_clauses)
+    {
+        write_interpolant(&solver, a_clauses, dimacs.num_vars, interpolant_file);
+    }
+    if let (SolveResult::Unknown(_), Some(checkpoint_file)) = (&result, &args.checkpoint) {
+        println!("c Writing checkpoint to {}", checkpoint_file);
+        or_exit(solver.checkpoint().write(checkpoint_file));
+    }
+    write_stats(&args, solver.stats());
 
-    let output = create_output(&args, dimacs.clauses, &solution, solver.stats());
+    let output = create_output(&args, &solver, &result, &dimacs.symbols);
     println!("{}", output);
+    if let Some(err) = solver.debug_output_error() {
+        println!("c Warning: {}", err);
+    }
+    if let Some(err) = solver.proof_error() {
+        eprintln!("c Error: {}", err);
+        std::process::exit(1);
+    }
+    std::process::exit(exit_code(Status::from(&result)));
+}
+
+/// Stops the search on SIGINT or SIGTERM, so the statistics, the partial proof and the unknown
+/// status are still written. A second signal exits immediately.
+fn stop_on_signals(terminate: Arc<AtomicBool>) {
+    let handler = move || {
+        if terminate.swap(true, Ordering::Relaxed) {
+            std::process::exit(exit_code(Status::Unknown));
+        }
+    };
+    if let Err(error) = ctrlc::set_handler(handler) {
+        println!("c Warning: Can't handle signals: {}", error);
+    }
+}
+
+/// Outcome of every mode, e.g. the formula of a MUS is unsatisfiable and a non-zero model count
+/// is satisfiable
+#[derive(Clone, Copy)]
+enum Status {
+    Sat,
+    Unsat,
+    /// Optimal solution of MaxSAT or an objective
+    Optimum,
+    Unknown,
+}
+
+impl From<&SolveResult> for Status {
+    fn from(result: &SolveResult) -> Self {
+        match result {
+            SolveResult::Sat(_) => Status::Sat,
+            SolveResult::Unsat => Status::Unsat,
+            SolveResult::Unknown(_) => Status::Unknown,
+        }
+    }
+}
+
+/// Exit code of the SAT competition: 10 for SAT, 20 for UNSAT and 0 if unknown, and 30 for
+/// optima like in the MaxSAT Evaluations
+fn exit_code(status: Status) -> i32 {
+    match status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Optimum => 30,
+        Status::Unknown => 0,
+    }
+}
+
+/// Prints the model count, or `s UNKNOWN` if counting was stopped before it was complete
+fn print_count(args: &Args, solver: &mut Solver, projection: Option<&[Var]>) -> Status {
+    let count = match projection {
+        Some(projection) => solver.count_projected_models(projection),
+        None => solver.count_models(),
+    };
+    let count = match count {
+        Err(Error::Stopped(_)) => None,
+        count => Some(or_exit(count)),
+    };
+    if !args.quiet {
+        println!(
+            "c\nc {}",
+            solver.stats().to_table().to_string().replace('\n', "\nc ")
+        );
+    }
+    match count {
+        Some(count) => {
+            println!("s mc {}", count);
+            match count.is_zero() {
+                true => Status::Unsat,
+                false => Status::Sat,
+            }
+        }
+        None => {
+            println!("s UNKNOWN");
+            Status::Unknown
+        }
+    }
+}
+
+/// Solves a formula after the Plaisted-Greenbaum encoding and prints the model using the
+/// variable names. Models are counted projected onto the named variables.
+fn run_formula(args: &Args, config: Config) {
+    let formula = or_exit(formula_from_file(args.file()));
+    let mut encoder = TseitinEncoder::new(true);
+    encoder.assert(&formula);
+    let mut solver = Solver::new(encoder.clauses.clone(), encoder.num_vars, config);
+
+    if args.count {
+        let projection = encoder
+            .names
+            .iter()
+            .map(|(_, var_id)| *var_id)
+            .collect_vec();
+        let status = print_count(args, &mut solver, Some(&projection));
+        std::process::exit(exit_code(status));
+    }
+    let result = solver.solve();
+    println!("{}", status_to_dimacs(&result));
+    if let (SolveResult::Sat(model), false) = (&result, args.no_witness) {
+        let values = encoder
+            .named_values(model)
+            .map(|(name, value)| match value {
+                true => name.to_string(),
+                false => format!("-{}", name),
+            })
+            .join(" ");
+        println!("v {}", values);
+    }
+    std::process::exit(exit_code(Status::from(&result)));
+}
+
+/// Writes the simplified formula and its map. If simplification already refutes the formula,
+/// the empty clause and an empty map are written and `s UNSATISFIABLE` is printed.
+fn run_simplify(solver: &mut Solver, simplified_file: &str) {
+    let is_sat = solver.simplify();
+    let formula = solver.simplified_formula();
+    println!(
+        "c Writing simplified formula with {} variables and {} clauses to {}",
+        formula.num_vars,
+        formula.clauses.len(),
+        simplified_file
+    );
+    or_exit(std::fs::write(simplified_file, formula.to_dimacs()).map_err(Error::from));
+    let map_file = format!("{}.map", simplified_file);
+    or_exit(std::fs::write(map_file, formula.map_to_string()).map_err(Error::from));
+    if !is_sat {
+        println!("s UNSATISFIABLE");
+        std::process::exit(exit_code(Status::Unsat));
+    }
+}
+
+/// Restores the solver from a checkpoint, which has to belong to the given clauses
+fn resume(checkpoint_file: &str, clauses: &[Clause], config: Config) -> Solver {
+    if config.proof_file.is_some() || config.lrat_file.is_some() || config.resolution_chains {
+        or_exit(Err(Error::Unsupported(
+            "Proofs of resumed searches are not supported".to_string(),
+        )))
+    }
+    let checkpoint = or_exit(Checkpoint::read(checkpoint_file));
+    if checkpoint.original_clauses != clauses {
+        or_exit(Err(Error::Checkpoint(
+            "it belongs to a different formula".to_string(),
+        )))
+    }
+    println!("c Resuming from {}", checkpoint_file);
+    Solver::resume(checkpoint, config)
+}
+
+fn write_core(solver: &Solver, num_vars: usize, core_file: &str) {
+    let Some(core) = solver.core_clauses() else {
+        println!("c The proof could not be verified, no core was written");
+        return;
+    };
+    println!("c Writing core of {} clauses to {}", core.len(), core_file);
+    or_exit(std::fs::write(core_file, clauses_to_dimacs(&core, num_vars)).map_err(Error::from));
+}
+
+fn write_interpolant(solver: &Solver, a_clauses: &[usize], num_vars: usize, file: &str) {
+    let Some(interpolant) = solver.interpolant(a_clauses) else {
+        println!("c The proof has lemmas not derived by resolution, no interpolant was written");
+        return;
+    };
+    println!(
+        "c Writing interpolant over {} variables to {}",
+        interpolant.vars().len(),
+        file
+    );
+    let aiger = Aiger::from_interpolant(&interpolant, num_vars);
+    or_exit(std::fs::write(file, aiger.to_ascii()).map_err(Error::from));
+}
+
+fn write_stats(args: &Args, stats: &StateStatistics) {
+    if let Some(stats_file) = &args.stats_out {
+        let output = stats.to_format(args.stats_format);
+        or_exit(std::fs::write(stats_file, output).map_err(Error::from));
+    }
+}
+
+/// Checks a proof like DRAT-trim, printing `s VERIFIED` or `s NOT VERIFIED`
+fn run_check(cnf_file: &str, proof_file: &str) {
+    let cnf = or_exit(clauses_from_dimacs_file(cnf_file));
+    let proof = or_exit(proof_from_file(proof_file));
+    match check_proof(&cnf.clauses, cnf.num_vars, &proof) {
+        CheckResult::Verified => println!("s VERIFIED"),
+        CheckResult::NotImplied { line, lemma } => {
+            println!(
+                "c Lemma in line {} is neither RUP nor RAT: {} 0",
+                line,
+                lemma.iter().join(" ")
+            );
+            println!("s NOT VERIFIED");
+        }
+        CheckResult::NoConflict => {
+            println!("c Unit propagation on the proof does not yield a conflict");
+            println!("s NOT VERIFIED");
+        }
+    }
+}
+
+/// Prints the MUS in DIMACS format after the status line, or only the status line if the
+/// formula is satisfiable or the search was stopped
+fn run_mus(args: &Args, cnf_file: &str, mut config: Config) {
+    // every call of the incremental solver would print a table
+    config.progress_printing_interval = ProgressPrintingInterval::Off;
+    if without_compression_extension(cnf_file).ends_with(".gcnf") {
+        return run_group_mus(args, cnf_file, &config);
+    }
+    let cnf = or_exit(clauses_from_dimacs_file(cnf_file));
+    let status = match minimal_unsatisfiable_subset(&cnf.clauses, cnf.num_vars, &config) {
+        MusResult::Mus(mus) => {
+            println!("s UNSATISFIABLE");
+            println!("c MUS of {} of {} clauses", mus.len(), cnf.clauses.len());
+            if !args.no_witness {
+                let clauses = mus
+                    .into_iter()
+                    .map(|index| cnf.clauses[index].clone())
+                    .collect_vec();
+                print!("{}", clauses_to_dimacs(&clauses, cnf.num_vars));
+            }
+            Status::Unsat
+        }
+        MusResult::Sat => {
+            println!("s SATISFIABLE");
+            Status::Sat
+        }
+        MusResult::Unknown(_) => {
+            println!("s UNKNOWN");
+            Status::Unknown
+        }
+    };
+    std::process::exit(exit_code(status));
+}
+
+/// Prints the groups of the MUS like the MUS track of the SAT Competition 2011, as a `v` line of
+/// group numbers terminated by 0
+fn run_group_mus(args: &Args, gcnf_file: &str, config: &Config) {
+    let gcnf = or_exit(gcnf_from_file(gcnf_file));
+    let result =
+        minimal_unsatisfiable_group_subset(&gcnf.background, &gcnf.groups, gcnf.num_vars, config);
+    let status = match result {
+        MusResult::Mus(mus) => {
+            println!("s UNSATISFIABLE");
+            println!("c MUS of {} of {} groups", mus.len(), gcnf.groups.len());
+            if !args.no_witness {
+                println!("{}", values_to_dimacs(mus.iter().map(|index| index + 1)));
+            }
+            Status::Unsat
+        }
+        MusResult::Sat => {
+            println!("s SATISFIABLE");
+            Status::Sat
+        }
+        MusResult::Unknown(_) => {
+            println!("s UNKNOWN");
+            Status::Unknown
+        }
+    };
+    std::process::exit(exit_code(status));
+}
+
+/// Prints the result in the format of the QBF Evaluations, with the certificate of the outermost
+/// quantifier block if there is one, and -1 as the result if the search was stopped
+fn run_qbf(args: &Args, qbf: &DimacsFile, config: &Config) {
+    let result = match solve_2qbf(qbf, config) {
+        Err(Error::Stopped(_)) => {
+            println!("s cnf -1 {} {}", qbf.num_vars, qbf.clauses.len());
+            std::process::exit(exit_code(Status::Unknown));
+        }
+        result => or_exit(result),
+    };
+    println!(
+        "s cnf {} {} {}",
+        result.is_true as u8,
+        qbf.num_vars,
+        qbf.clauses.len()
+    );
+    if !args.no_witness {
+        for lit in result.certificate.into_iter().flatten() {
+            println!("V {} 0", lit);
+        }
+    }
+    std::process::exit(exit_code(match result.is_true {
+        true => Status::Sat,
+        false => Status::Unsat,
+    }));
+}
+
+/// Prints the result in the format of the MaxSAT Evaluations. If the search is stopped, the best
+/// model so far is printed after `s SATISFIABLE`.
+fn run_maxsat(args: &Args, config: &Config) {
+    let wcnf = or_exit(wcnf_from_file(args.file()));
+    let print_model = |model: &Model| {
+        if args.no_witness {
+            return;
+        }
+        let values = Var::range(wcnf.num_vars)
+            .map(|var_id| if model[var_id] { '1' } else { '0' })
+            .collect::<String>();
+        println!("v {}", values);
+    };
+    let status = match solve_maxsat(&wcnf, config, |cost| println!("o {}", cost)) {
+        MaxSatResult::Optimum { model, .. } => {
+            println!("s OPTIMUM FOUND");
+            print_model(&model);
+            Status::Optimum
+        }
+        MaxSatResult::Stopped { model, .. } => {
+            println!("s SATISFIABLE");
+            print_model(&model);
+            Status::Sat
+        }
+        MaxSatResult::Unsat => {
+            println!("s UNSATISFIABLE");
+            Status::Unsat
+        }
+        MaxSatResult::Unknown(_) => {
+            println!("s UNKNOWN");
+            Status::Unknown
+        }
+    };
+    std::process::exit(exit_code(status));
+}
+
+/// Minimizes the objective by linear search and prints the value of every improved model as an
+/// `o` line. If the search is stopped, the best model so far is printed after `s SATISFIABLE`.
+fn run_optimization(
+    args: &Args,
+    encoder: PbEncoder,
+    objective: &[(i64, Literal)],
+    config: &Config,
+    print_model: impl Fn(&Model),
+) {
+    let print_model = |model: &Model| {
+        if !args.no_witness {
+            print_model(model);
+        }
+    };
+    let status = match minimize(encoder, objective, config, |value| println!("o {}", value)) {
+        OptimizationResult::Optimum { model, .. } => {
+            println!("s OPTIMUM FOUND");
+            print_model(&model);
+            Status::Optimum
+        }
+        OptimizationResult::Stopped { model, .. } => {
+            println!("s SATISFIABLE");
+            print_model(&model);
+            Status::Sat
+        }
+        OptimizationResult::Unsat => {
+            println!("s UNSATISFIABLE");
+            Status::Unsat
+        }
+        OptimizationResult::Unknown(_) => {
+            println!("s UNKNOWN");
+            Status::Unknown
+        }
+    };
+    std::process::exit(exit_code(status));
+}
+
+/// Solves the constraints of an OPB file after encoding them into CNF and prints the result in
+/// the format of the Pseudo-Boolean Competitions, minimizing the objective function if present
+fn run_pb(args: &Args, config: Config) {
+    let opb = or_exit(opb_from_file(args.file()));
+    let mut encoder = PbEncoder::new(opb.num_vars, args.pb_encoding);
+    for constraint in &opb.constraints {
+        encoder.encode(constraint);
+    }
+    let print_model = |model: &Model| {
+        let values = Var::range(opb.num_vars)
+            .map(|var_id| match model[var_id] {
+                true => format!("x{}", var_id),
+                false => format!("-x{}", var_id),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("v {}", values);
+    };
+    if let Some(objective) = &opb.objective {
+        return run_optimization(args, encoder, objective, &config, print_model);
+    }
+
+    let mut solver = Solver::new(encoder.clauses, encoder.next_var, config);
+    for (literals, bound) in &encoder.cardinality_constraints {
+        solver.add_cardinality_constraint(literals, *bound);
+    }
+    let result = solver.solve();
+    if let SolveResult::Sat(model) = &result {
+        if !opb
+            .constraints
+            .iter()
+            .all(|constraint| constraint.is_satisfied(model))
+        {
+            println!("c WRONG SOLUTION");
+        }
+    }
+    println!("{}", status_to_dimacs(&result));
+    if let (SolveResult::Sat(model), false) = (&result, args.no_witness) {
+        print_model(model);
+    }
+    std::process::exit(exit_code(Status::from(&result)));
+}
+
+fn or_exit<T>(result: Result<T>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("c Error: {}", err);
+        std::process::exit(1);
+    })
 }
 
 fn create_output(
     args: &Args,
-    cnf: Vec<Clause>,
-    solution: &Option<HashMap<VarId, bool>>,
-    stats: &StateStatistics,
+    solver: &Solver,
+    result: &SolveResult,
+    symbols: &SymbolTable,
 ) -> String {
+    let implicant = match args.prime_implicant && result.is_sat() {
+        true => solver.minimize_model(),
+        false => None,
+    };
+    let solution = match (args.no_witness, implicant) {
+        (true, _) => status_to_dimacs(result).to_string(),
+        (false, Some(implicant)) if args.named_output => format!(
+            "{}\nv {}",
+            status_to_dimacs(result),
+            symbols.named_values(implicant)
+        ),
+        (false, None) if args.named_output && result.is_sat() => format!(
+            "{}\nv {}",
+            status_to_dimacs(result),
+            symbols.named_model(result.model().unwrap())
+        ),
+        (false, Some(implicant)) => format!(
+            "{}\n{}",
+            status_to_dimacs(result),
+            values_to_dimacs(implicant)
+        ),
+        (false, None) => solution_to_dimacs(result),
+    };
+    let solution = match args.reconstruction_trace && result.is_sat() {
+        true => format!("{}{}", reconstruction_trace(solver), solution),
+        false => solution,
+    };
+    if args.quiet {
+        return solution;
+    }
     let mut output = String::new();
-    output.push_str(format!("\nFile\n{}\n", args.file).as_str());
-    output.push_str(format!("\n{}\n", stats.to_table()).as_str());
-    // verify solution
-    if let Some(solution) = solution.clone() {
-        if check_assignment(&cnf, solution) {
-            output.push_str("Solution has been verified and is correct\n");
-        } else {
-            output.push_str("WRONG SOLUTION\n");
+    output.push_str(format!("\nFile\n{}\n", args.file()).as_str());
+    output.push_str(format!("\n{}\n", solver.stats().to_table()).as_str());
+    if let Some(model) = result.model() {
+        match solver.validate_model(model) {
+            Ok(()) => output.push_str("Solution has been verified and is correct\n"),
+            Err(violated) => output.push_str(
+                format!("WRONG SOLUTION, {} clauses are violated\n", violated.len()).as_str(),
+            ),
         }
-    } else if let Some(out) = args.proof.clone() {
+    } else if let (SolveResult::Unsat, Some(out), None) =
+        (result, args.proof.clone(), solver.proof_error())
+    {
         output.push_str(format!("Proof has been written to:\n {}\n", out).as_str());
     }
 
     output = output.replace('\n', "\nc ");
-    output.push_str(format!("\n{}", solution_to_dimacs(solution.clone())).as_str());
+    output.push_str(format!("\n{}", solution).as_str());
     output
 }
 
+/// The witnesses flipped by the model reconstruction as comment lines, see
+/// `Solver::reconstruction_trace`
+fn reconstruction_trace(solver: &Solver) -> String {
+    solver
+        .reconstruction_trace()
+        .iter()
+        .map(|(witness, clause)| {
+            let literals = clause.literals.iter().map(|lit| lit.to_string()).join(" ");
+            format!("c flip {} by {} 0\n", witness, literals)
+        })
+        .collect()
+}
+
 const BANNER: &str = r#"c          _                     
 c    _   _| |_ ___  _ __  _  __ _ 
 c   | | | | __/ _ \| '_ \| |/ _` |
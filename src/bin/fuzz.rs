@@ -0,0 +1,123 @@
+use clap::{Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use utopia::dimacs::clauses_to_dimacs;
+use utopia::solver::config::Config;
+use utopia::solver::heuristic::HeuristicType;
+use utopia::solver::progress::ProgressPrintingInterval;
+use utopia::solver::restarts::RestartPolicy;
+use utopia::tester::{cross_check, max_var, random_cnf, shrink, MAX_BRUTE_FORCE_VARS};
+
+/// Cross-checks the solver against brute force on random small formulas with random
+/// configurations. A mismatch is shrunk and printed in DIMACS format.
+#[derive(Parser)]
+#[command(about, long_about = None)]
+struct Args {
+    /// Seed of the first formula, each following formula uses the next seed
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    #[arg(long, default_value = "1000")]
+    iterations: u64,
+
+    /// Maximum number of variables, each formula has between the clause length and this many
+    #[arg(long, default_value = "14")]
+    max_vars: usize,
+
+    /// Clauses per variable
+    #[arg(long, default_value = "4.26")]
+    ratio: f64,
+
+    #[arg(long, default_value = "3")]
+    clause_length: usize,
+}
+
+fn pick<T: ValueEnum + Clone>(rng: &mut StdRng) -> T {
+    let variants = T::value_variants();
+    variants[rng.gen_range(0..variants.len())].clone()
+}
+
+fn name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value().unwrap().get_name().to_string()
+}
+
+/// Picks the parts of the configuration that change the search, returns it with the matching
+/// command line options
+fn random_config(rng: &mut StdRng) -> (Config, String) {
+    let restart_policy = match pick(rng) {
+        // the custom policy needs a schedule
+        RestartPolicy::Custom => RestartPolicy::Luby,
+        restart_policy => restart_policy,
+    };
+    let mut config = Config::new(
+        pick::<HeuristicType>(rng),
+        None,
+        restart_policy,
+        rng.gen(),
+        ProgressPrintingInterval::Off,
+        pick(rng),
+    );
+    config.phase_mode = pick(rng);
+    config.score_init = pick(rng);
+    config.learning_scheme = pick(rng);
+    config.additional_clause = pick(rng);
+    config.trail_reuse = rng.gen();
+    config.reuse_strategy = pick(rng);
+    config.trail_saving = rng.gen();
+    config.walk = rng.gen();
+    config.stabilization = rng.gen();
+    config.params.luby_unit = rng.gen_range(1..=64);
+    config.deterministic = true;
+
+    let description = format!(
+        "--deterministic --heuristic {} --restart-policy {} --backtracking {} --phase-mode {} --score-init {} --learning-scheme {} --additional-clause {} --reuse-strategy {} --luby-unit {}{}{}{}{}{}",
+        name(&config.heuristic),
+        name(&config.restart_policy),
+        name(&config.backtracking_mode),
+        name(&config.phase_mode),
+        name(&config.score_init),
+        name(&config.learning_scheme),
+        name(&config.additional_clause),
+        name(&config.reuse_strategy),
+        config.params.luby_unit,
+        if config.inprocessing { "" } else { " --no-inprocessing" },
+        if config.trail_reuse { "" } else { " --no-trail-reuse" },
+        if config.trail_saving { " --trail-saving" } else { "" },
+        if config.walk { "" } else { " --no-walk" },
+        if config.stabilization { " --stabilization" } else { "" },
+    );
+    (config, description)
+}
+
+fn main() {
+    let args = Args::parse();
+    assert!(
+        args.max_vars <= MAX_BRUTE_FORCE_VARS,
+        "At most {} variables",
+        MAX_BRUTE_FORCE_VARS
+    );
+
+    for seed in args.seed..args.seed + args.iterations {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let num_vars = rng.gen_range(args.clause_length..=args.max_vars);
+        let clauses = random_cnf(num_vars, args.ratio, args.clause_length, seed);
+        let (config, description) = random_config(&mut rng);
+
+        let Some(mismatch) = cross_check(&clauses, num_vars, &config) else {
+            continue;
+        };
+        println!("c seed {}: {}", seed, mismatch);
+        println!("c configuration: {}", description);
+        let shrunk = shrink(&clauses, |clauses| {
+            cross_check(clauses, max_var(clauses), &config).is_some()
+        });
+        println!(
+            "c shrunk from {} to {} clauses",
+            clauses.len(),
+            shrunk.len()
+        );
+        print!("{}", clauses_to_dimacs(&shrunk, max_var(&shrunk)));
+        std::process::exit(1);
+    }
+    println!("c {} formulas agree with brute force", args.iterations);
+}
@@ -1,80 +1,156 @@
+use alloc::{vec, vec::Vec};
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::BuildHasherDefault;
+use core::ops::{Index, IndexMut, Neg};
+use core::str::FromStr;
 use fnv::FnvHasher;
-use std::collections::{HashMap, HashSet};
-use std::fmt::{Debug, Display, Formatter};
-use std::hash::BuildHasherDefault;
-use std::ops::Neg;
-use std::str::FromStr;
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
 
 type FastHasher = BuildHasherDefault<FnvHasher>;
 use crate::solver::trail::{Assignment, Trail};
 
-pub fn check_assignment(clauses: &[Clause], assignment: HashMap<VarId, bool>) -> bool {
-    clauses.iter().all(|clause| {
-        clause.clone().any(|lit| {
-            if let Some(assignment_value) = assignment.get(&(lit.id())) {
-                (lit.positive()) == *assignment_value
-            } else {
-                false
-            }
-        })
-    })
+pub fn check_assignment(clauses: &[Clause], model: &Model) -> bool {
+    clauses
+        .iter()
+        .all(|clause| clause.literals.iter().any(|lit| model.value(*lit)))
 }
 
-pub type VarId = usize;
+/// Variable, numbered from 1 like in DIMACS, so per-variable vectors have an unused entry at 0
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Var(u32);
+
+impl Var {
+    pub fn new(index: usize) -> Self {
+        Var(index as u32)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// The variables 1..=num_vars
+    pub fn range(num_vars: usize) -> impl DoubleEndedIterator<Item = Var> + Clone {
+        (1..=num_vars as u32).map(Var)
+    }
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+impl FromStr for Var {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Var).map_err(|_| ())
+    }
+}
+
+impl Debug for Var {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for Var {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<T> Index<Var> for [T] {
+    type Output = T;
+
+    fn index(&self, var: Var) -> &Self::Output {
+        &self[var.index()]
+    }
+}
+
+impl<T> IndexMut<Var> for [T] {
+    fn index_mut(&mut self, var: Var) -> &mut Self::Output {
+        &mut self[var.index()]
+    }
+}
+
+impl<T> Index<Var> for Vec<T> {
+    type Output = T;
+
+    fn index(&self, var: Var) -> &Self::Output {
+        &self[var.index()]
+    }
+}
+
+impl<T> IndexMut<Var> for Vec<T> {
+    fn index_mut(&mut self, var: Var) -> &mut Self::Output {
+        &mut self[var.index()]
+    }
+}
+
+/// Literal encoded as `2 * var + sign` with the sign bit set for negative literals, so the
+/// literals of a variable are adjacent and can index watch lists directly. The signed DIMACS
+/// view is available by `Literal::new`, `From<i32>` and `Display`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "i32", into = "i32")]
 pub struct Literal {
-    value: i32,
+    code: u32,
 }
 
 impl Literal {
     pub fn new(value: i32) -> Self {
-        Literal { value }
+        Literal {
+            code: value.unsigned_abs() << 1 | (value < 0) as u32,
+        }
     }
 
-    pub fn from_value(id: VarId, positive: bool) -> Self {
+    pub fn from_value(var: Var, positive: bool) -> Self {
         Literal {
-            value: if positive { id as i32 } else { -(id as i32) },
+            code: var.0 << 1 | !positive as u32,
         }
     }
 
-    pub fn id(&self) -> VarId {
-        self.value.unsigned_abs() as VarId
+    pub fn var(&self) -> Var {
+        Var(self.code >> 1)
+    }
+
+    /// Index of the literal, `2 * var` for positive and `2 * var + 1` for negative literals
+    pub fn code(&self) -> usize {
+        self.code as usize
+    }
+
+    /// The signed DIMACS representation
+    pub fn to_i32(&self) -> i32 {
+        match self.positive() {
+            true => self.var().0 as i32,
+            false => -(self.var().0 as i32),
+        }
     }
 
     pub fn positive(&self) -> bool {
-        self.value > 0
+        self.code & 1 == 0
     }
     pub fn negative(&self) -> bool {
-        self.value < 0
+        self.code & 1 == 1
     }
 
-    pub fn id_val(&self) -> (VarId, bool) {
-        (self.id(), self.positive())
+    pub fn var_val(&self) -> (Var, bool) {
+        (self.var(), self.positive())
     }
 
-    pub fn is_true(&self, vars: &[Option<bool>]) -> bool {
-        vars[self.id()] == Some(self.positive())
+    pub fn is_true(&self, vars: &PartialAssignment) -> bool {
+        vars.is_true(*self)
     }
 
-    pub fn is_false(&self, vars: &[Option<bool>]) -> bool {
-        vars[self.id()] == Some(self.negative())
+    pub fn is_false(&self, vars: &PartialAssignment) -> bool {
+        vars.is_false(*self)
     }
 
-    pub fn non_false(&self, vars: &[Option<bool>]) -> bool {
-        vars[self.id()] != Some(self.negative())
+    pub fn non_false(&self, vars: &PartialAssignment) -> bool {
+        !vars.is_false(*self)
     }
 
-    pub fn is_free(&self, vars: &[Option<bool>]) -> bool {
-        vars[self.id()].is_none()
+    pub fn is_free(&self, vars: &PartialAssignment) -> bool {
+        !vars.is_assigned(self.var())
     }
 
-    pub fn value(&self, vars: &[Option<bool>]) -> Option<bool> {
-        if self.is_free(vars) {
-            None
-        } else {
-            Some(self.is_true(vars))
-        }
+    pub fn value(&self, vars: &PartialAssignment) -> Option<bool> {
+        vars.value_of(*self)
     }
 }
 
@@ -93,6 +169,12 @@ impl From<i32> for Literal {
     }
 }
 
+impl From<Literal> for i32 {
+    fn from(literal: Literal) -> Self {
+        literal.to_i32()
+    }
+}
+
 impl From<Assignment> for Literal {
     fn from(assignment: Assignment) -> Self {
         assignment.literal
@@ -103,46 +185,49 @@ impl Neg for Literal {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        Literal::new(-self.value)
+        Literal {
+            code: self.code ^ 1,
+        }
     }
 }
 
 impl Debug for Literal {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.to_i32())
     }
 }
 
 impl Display for Literal {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.to_i32())
     }
 }
 
 pub type ClauseId = usize;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Clause {
     pub literals: Vec<Literal>,
-    pub blocking_literal: Literal,
+    /// Literal block distance of learned clauses, `None` for clauses of the formula. The
+    /// metadata the search updates is kept by the `ClauseDatabase`.
     pub lbd: Option<usize>,
 }
 
 impl Clause {
     pub fn from_literals_and_lbd(literals: Vec<Literal>, lbd: usize) -> Self {
         Clause {
-            blocking_literal: *literals.first().unwrap_or(&Literal::new(0)),
             literals,
             lbd: Some(lbd),
         }
     }
 
+    /// Recomputes the LBD of a learned clause, which may have improved since it was learned
     pub fn update_lbd(&mut self, trail: &mut Trail) {
         if let Some(old_lbd) = self.lbd {
             let new_lbd = self
                 .literals
                 .iter()
-                .map(|lit| trail.var_decision_level[lit.id()])
+                .map(|lit| trail.var_decision_level[lit.var()])
                 .collect::<HashSet<_, FastHasher>>()
                 .len();
 
@@ -152,19 +237,19 @@ impl Clause {
         }
     }
 
-    pub fn is_satisfied(&self, vars: &[Option<bool>]) -> bool {
-        if self.check_blocking_literal(vars) {
-            return true;
-        }
+    pub fn is_satisfied(&self, vars: &PartialAssignment) -> bool {
         self.literals.iter().any(|lit| lit.is_true(vars))
     }
 
-    pub fn is_conflict(&self, vars: &[Option<bool>]) -> bool {
+    pub fn is_conflict(&self, vars: &PartialAssignment) -> bool {
         self.literals.iter().all(|lit| lit.is_false(vars))
     }
 
-    pub fn check_blocking_literal(&self, vars: &[Option<bool>]) -> bool {
-        self.blocking_literal.is_true(vars)
+    /// Whether the clause contains a literal and its negation
+    pub fn is_tautology(&self) -> bool {
+        self.literals
+            .iter()
+            .any(|lit| self.literals.contains(&-*lit))
     }
 
     pub fn resolution(self, other: Self) -> Self {
@@ -185,7 +270,6 @@ impl Clause {
 impl From<Vec<Literal>> for Clause {
     fn from(literals: Vec<Literal>) -> Self {
         Clause {
-            blocking_literal: *literals.first().unwrap_or(&Literal::new(0)),
             literals,
             lbd: None,
         }
@@ -211,13 +295,181 @@ impl Iterator for Clause {
 }
 
 impl Display for Clause {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:?}", self.literals)
     }
 }
 
-pub type SolutionAssignment = HashMap<VarId, bool>;
-pub type Solution = Option<SolutionAssignment>;
+const VARS_PER_WORD: usize = 32;
+const ASSIGNED: u64 = 0b10;
+
+/// Partial assignment of the variables 1..=num_vars with two bits per variable, whether it is
+/// assigned and its value, packed into words so the lookups of propagation touch few cache lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialAssignment {
+    words: Vec<u64>,
+    num_vars: usize,
+}
+
+impl PartialAssignment {
+    /// All variables unassigned
+    pub fn new(num_vars: usize) -> Self {
+        PartialAssignment {
+            words: vec![0; (num_vars + 1).div_ceil(VARS_PER_WORD)],
+            num_vars,
+        }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    #[inline]
+    fn bits(&self, var: Var) -> u64 {
+        debug_assert!(var.index() <= self.num_vars);
+        let index = var.index();
+        self.words[index / VARS_PER_WORD] >> (2 * (index % VARS_PER_WORD)) & 0b11
+    }
+
+    #[inline]
+    pub fn value(&self, var: Var) -> Option<bool> {
+        match self.bits(var) {
+            0b10 => Some(false),
+            0b11 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// The value of the literal, i.e. of its variable with the sign applied
+    #[inline]
+    pub fn value_of(&self, lit: Literal) -> Option<bool> {
+        self.value(lit.var()).map(|value| value == lit.positive())
+    }
+
+    #[inline]
+    pub fn is_assigned(&self, var: Var) -> bool {
+        self.bits(var) & ASSIGNED != 0
+    }
+
+    #[inline]
+    pub fn is_true(&self, lit: Literal) -> bool {
+        self.bits(lit.var()) == ASSIGNED | lit.positive() as u64
+    }
+
+    #[inline]
+    pub fn is_false(&self, lit: Literal) -> bool {
+        self.bits(lit.var()) == ASSIGNED | lit.negative() as u64
+    }
+
+    pub fn set(&mut self, var: Var, value: Option<bool>) {
+        debug_assert!(var.index() <= self.num_vars);
+        let index = var.index();
+        let shift = 2 * (index % VARS_PER_WORD);
+        let bits = value.map_or(0, |value| ASSIGNED | value as u64);
+        let word = &mut self.words[index / VARS_PER_WORD];
+        *word = *word & !(0b11 << shift) | bits << shift;
+    }
+
+    /// Makes the literal true
+    pub fn assign(&mut self, lit: Literal) {
+        self.set(lit.var(), Some(lit.positive()));
+    }
+
+    pub fn unassign(&mut self, var: Var) {
+        self.set(var, None);
+    }
+
+    /// The values of the variables 1..=num_vars
+    pub fn values(&self) -> impl Iterator<Item = Option<bool>> + '_ {
+        Var::range(self.num_vars).map(|var| self.value(var))
+    }
+}
+
+/// Total assignment of the variables 1..=num_vars, stored densely
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    values: Vec<bool>,
+}
+
+impl Model {
+    /// The value of variable i is at index i - 1
+    pub fn new(values: Vec<bool>) -> Self {
+        Model { values }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn value(&self, lit: Literal) -> bool {
+        self[lit.var()] == lit.positive()
+    }
+
+    /// The literals that are true in the model, ordered by variable
+    pub fn iter(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.values
+            .iter()
+            .zip(Var::range(self.values.len()))
+            .map(|(value, var)| Literal::from_value(var, *value))
+    }
+
+    pub(crate) fn set(&mut self, var: Var, value: bool) {
+        self.values[var.index() - 1] = value;
+    }
+
+    /// Drops all variables after `num_vars`, e.g. auxiliary variables of encodings
+    pub fn truncate(&mut self, num_vars: usize) {
+        self.values.truncate(num_vars);
+    }
+}
+
+impl Index<Var> for Model {
+    type Output = bool;
+
+    fn index(&self, var: Var) -> &Self::Output {
+        &self.values[var.index() - 1]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownReason {
+    /// The search was stopped before it could decide the formula
+    Interrupted,
+    TimeLimit,
+    ConflictLimit,
+    MemoryLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveResult {
+    Sat(Model),
+    Unsat,
+    Unknown(UnknownReason),
+}
+
+impl SolveResult {
+    pub fn is_sat(&self) -> bool {
+        matches!(self, SolveResult::Sat(_))
+    }
+
+    pub fn is_unsat(&self) -> bool {
+        matches!(self, SolveResult::Unsat)
+    }
+
+    pub fn model(&self) -> Option<&Model> {
+        match self {
+            SolveResult::Sat(model) => Some(model),
+            _ => None,
+        }
+    }
+
+    pub fn into_model(self) -> Option<Model> {
+        match self {
+            SolveResult::Sat(model) => Some(model),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -238,4 +490,20 @@ mod tests {
         let clause2 = Clause::from("-2 -3 4");
         assert_eq!(clause1.resolution(clause2), Clause::from("1 4"));
     }
+
+    #[test]
+    fn partial_assignment() {
+        let mut vars = PartialAssignment::new(40);
+        vars.assign(Literal::new(33));
+        vars.assign(Literal::new(-40));
+        vars.assign(Literal::new(-1));
+        assert_eq!(vars.value(Var::new(33)), Some(true));
+        assert_eq!(vars.value(Var::new(32)), None);
+        assert!(vars.is_true(Literal::new(-40)) && vars.is_false(Literal::new(40)));
+        assert_eq!(vars.value_of(Literal::new(1)), Some(false));
+
+        vars.unassign(Var::new(33));
+        assert!(!vars.is_assigned(Var::new(33)));
+        assert_eq!(vars.values().flatten().count(), 2);
+    }
 }
@@ -1,130 +1,830 @@
-use flate2::read::GzDecoder;
-use std::collections::HashMap;
-use std::io::Read;
+#[cfg(feature = "compression")]
+use bzip2::read::MultiBzDecoder;
+#[cfg(feature = "compression")]
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
+#[cfg(feature = "compression")]
+use xz2::read::XzDecoder;
 
-use crate::cnf::{Clause, Literal, VarId};
+use crate::cnf::{Clause, Literal, SolveResult, Var};
+use crate::error::{Error, Result};
+use crate::qbf::Quantifier;
+use crate::symbols::SymbolTable;
 use itertools::Itertools;
 
 pub struct DimacsFile {
     pub num_vars: usize,
     pub clauses: Vec<Clause>,
+    /// Projection variables given by `c p show <vars> 0` lines, as used for projected model counting
+    pub projection: Option<Vec<Var>>,
+    /// Quantifier prefix of QDIMACS files from the outermost to the innermost block, empty for
+    /// plain CNFs
+    pub quantifiers: Vec<(Quantifier, Vec<Var>)>,
+    /// Names of the variables given by `c var <var> <name>` lines
+    pub symbols: SymbolTable,
+    /// Linear objective to be minimized, given by a `c min <weight> <literal> ... 0` line
+    pub objective: Option<Vec<(i64, Literal)>>,
+    /// Indices of the clauses of part A of a partition for Craig interpolation. `c part A` and
+    /// `c part B` lines assign the following clauses to a part, clauses before the first such
+    /// line belong to A.
+    pub a_clauses: Option<Vec<usize>>,
+    /// Deviations from the header that were tolerated in lenient mode
+    pub warnings: Vec<Error>,
 }
-pub fn clauses_from_dimacs_file(path: &str) -> Result<DimacsFile, String> {
-    if !Path::new(path).exists() {
-        return Err(format!("File {} not found", path));
+
+/// Handling of files that don't match their header. Declared variables that occur in no clause
+/// are part of the formula in both modes, as are empty formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// A missing header, more variables or a different number of clauses than declared and a
+    /// missing 0 after the last clause are errors
+    Strict,
+    /// These deviations are collected as warnings instead, and the number of variables is
+    /// padded to the largest used one
+    Lenient,
+}
+
+/// Weighted partial MaxSAT instance
+pub struct WcnfFile {
+    pub num_vars: usize,
+    pub hard_clauses: Vec<Clause>,
+    pub soft_clauses: Vec<(u64, Clause)>,
+}
+
+/// Group-oriented CNF of the MUS track of the SAT Competition 2011, where the clauses are
+/// partitioned into groups, e.g. by the requirement they encode
+pub struct GcnfFile {
+    pub num_vars: usize,
+    /// Clauses of group 0, which belong to every subset
+    pub background: Vec<Clause>,
+    /// Clauses of the groups 1..=num_groups, group i at index i - 1
+    pub groups: Vec<Vec<Clause>>,
+}
+
+pub fn clauses_from_dimacs_file(path: &str) -> Result<DimacsFile> {
+    clauses_from_dimacs_reader(open_file(path)?, ParseMode::Lenient)
+}
+
+pub fn wcnf_from_file(path: &str) -> Result<WcnfFile> {
+    wcnf_from_string(read_file(path)?)
+}
+
+pub fn gcnf_from_file(path: &str) -> Result<GcnfFile> {
+    gcnf_from_string(read_file(path)?)
+}
+
+/// Compression formats of input files, as used for the benchmarks of the SAT Competitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+const COMPRESSIONS: [(Compression, &str, &[u8]); 4] = [
+    (Compression::Gzip, ".gz", &[0x1f, 0x8b]),
+    (
+        Compression::Xz,
+        ".xz",
+        &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+    ),
+    (Compression::Zstd, ".zst", &[0x28, 0xb5, 0x2f, 0xfd]),
+    (Compression::Bzip2, ".bz2", b"BZh"),
+];
+
+impl Compression {
+    /// Detects the compression by the extension of the path or else by the magic bytes at the
+    /// start of the file
+    fn detect(path: &str, start: &[u8]) -> Self {
+        COMPRESSIONS
+            .iter()
+            .find(|(_, extension, _)| path.ends_with(extension))
+            .or_else(|| {
+                COMPRESSIONS
+                    .iter()
+                    .find(|(_, _, magic)| start.starts_with(magic))
+            })
+            .map_or(Compression::None, |(compression, _, _)| *compression)
     }
+}
 
-    clauses_from_dimacs(if path.ends_with(".gz") {
-        let file = std::fs::File::open(path).map_err(|err| err.to_string())?;
-        let mut decoder = GzDecoder::new(file);
-        let mut result_string = String::new();
-        decoder
-            .read_to_string(&mut result_string)
-            .map_err(|e| e.to_string())?;
-        result_string
+/// The path without the extension of a compression format, e.g. to determine the file type
+pub fn without_compression_extension(path: &str) -> &str {
+    COMPRESSIONS
+        .iter()
+        .find_map(|(_, extension, _)| path.strip_suffix(extension))
+        .unwrap_or(path)
+}
+
+/// Opens the file for buffered reading, decompressing it on the fly. The path `-` denotes stdin.
+pub(crate) fn open_file(path: &str) -> Result<Box<dyn BufRead>> {
+    let mut reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else if Path::new(path).exists() {
+        Box::new(BufReader::new(File::open(path)?))
     } else {
-        std::fs::read_to_string(path).map_err(|e| e.to_string())?
+        return Err(Error::FileNotFound(path.to_string()));
+    };
+    Ok(match Compression::detect(path, reader.fill_buf()?) {
+        Compression::None => Box::new(reader),
+        #[cfg(not(feature = "compression"))]
+        compression => {
+            return Err(Error::Unsupported(format!(
+                "{:?} compressed input without the compression feature",
+                compression
+            )))
+        }
+        #[cfg(feature = "compression")]
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        #[cfg(feature = "compression")]
+        Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        #[cfg(feature = "compression")]
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        #[cfg(feature = "compression")]
+        Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(reader))),
     })
 }
 
-pub fn clauses_from_dimacs(input: String) -> Result<DimacsFile, String> {
-    let mut file_content: Vec<String> = input
-        .lines()
-        .map(String::from)
-        .filter(|line| !line.starts_with('c'))
-        .filter(|line| !line.starts_with('%'))
-        .filter(|line| !line.is_empty())
-        .collect();
+pub(crate) fn read_file(path: &str) -> Result<String> {
+    let mut content = String::new();
+    open_file(path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+pub fn clauses_from_dimacs(input: String) -> Result<DimacsFile> {
+    clauses_from_dimacs_reader(input.as_bytes(), ParseMode::Lenient)
+}
+
+/// Parses a CNF line by line without holding the whole input in memory. Comments may appear
+/// anywhere, clauses may span several lines and a line starting with `%` ends the input, as in
+/// the SATLIB benchmarks. The number of variables is the maximum of the declared and the used
+/// ones. QDIMACS quantifier lines like `a 1 2 0` and `e 3 0` are accepted before the clauses.
+pub fn clauses_from_dimacs_reader(mut reader: impl BufRead, mode: ParseMode) -> Result<DimacsFile> {
+    let mut warnings = vec![];
+    let mut tolerate = |error: Error| match mode {
+        ParseMode::Strict => Err(error),
+        ParseMode::Lenient => {
+            warnings.push(error);
+            Ok(())
+        }
+    };
+
+    let mut header: Option<(usize, usize)> = None;
+    let mut missing_header_reported = false;
+    let mut projection: Option<Vec<Var>> = None;
+    // largest projection variable with its line, checked against the number of variables
+    let mut max_projection_var: Option<(Var, usize)> = None;
+    let mut quantifiers: Vec<(Quantifier, Vec<Var>)> = vec![];
+    let mut symbols = SymbolTable::new();
+    let mut objective: Option<Vec<(i64, Literal)>> = None;
+    let mut a_clauses: Option<Vec<usize>> = None;
+    let mut is_part_a = true;
+    let mut clauses = vec![];
+    let mut literals = vec![];
+    let mut num_vars = 0;
 
-    if file_content.last() == Some(&"0".to_string()) {
-        file_content.pop();
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let content = line.trim();
+
+        if let Some(vars) = content.strip_prefix("c p show") {
+            for var in vars.split_whitespace() {
+                let var = var.parse::<Var>().map_err(|_| Error::Syntax {
+                    line: line_number,
+                    message: format!("Invalid projection variable \"{}\"", var),
+                })?;
+                if var.index() != 0 {
+                    projection.get_or_insert_with(Vec::new).push(var);
+                    if max_projection_var.is_none_or(|(max_var, _)| var > max_var) {
+                        max_projection_var = Some((var, line_number));
+                    }
+                }
+            }
+            continue;
+        }
+        if let Some(declaration) = content.strip_prefix("c var ") {
+            let (var, name) = declaration
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or((declaration, ""));
+            let var = var
+                .parse::<Var>()
+                .ok()
+                .filter(|var| var.index() != 0)
+                .ok_or(Error::Syntax {
+                    line: line_number,
+                    message: format!("Invalid named variable \"{}\"", var),
+                })?;
+            // names that can't be used are dropped, the formula stays the same
+            if let Err(error) = symbols.insert(var, name.trim()) {
+                tolerate(Error::Syntax {
+                    line: line_number,
+                    message: error.to_string(),
+                })?;
+            } else {
+                num_vars = num_vars.max(var.index());
+            }
+            continue;
+        }
+        if let Some(terms) = content.strip_prefix("c min ") {
+            let invalid_objective = || Error::Syntax {
+                line: line_number,
+                message: format!("Invalid objective \"{}\"", content),
+            };
+            let tokens = terms.split_whitespace().collect_vec();
+            if objective.is_some() || tokens.last() != Some(&"0") || tokens.len() % 2 != 1 {
+                return Err(invalid_objective());
+            }
+            let terms = tokens[..tokens.len() - 1]
+                .chunks(2)
+                .map(|term| {
+                    let weight = term[0].parse::<i64>().ok()?;
+                    let lit = term[1].parse::<Literal>().ok()?;
+                    (lit.var().index() != 0).then_some((weight, lit))
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid_objective)?;
+            num_vars = terms
+                .iter()
+                .map(|(_, lit)| lit.var().index())
+                .fold(num_vars, usize::max);
+            objective = Some(terms);
+            continue;
+        }
+        if let Some(part) = content.strip_prefix("c part ") {
+            is_part_a = match part.trim() {
+                "A" => true,
+                "B" => false,
+                _ => {
+                    return Err(Error::Syntax {
+                        line: line_number,
+                        message: format!("Invalid part \"{}\"", part.trim()),
+                    })
+                }
+            };
+            // clauses before the first part line belong to A
+            a_clauses.get_or_insert_with(|| (0..clauses.len()).collect());
+            continue;
+        }
+        if content.is_empty() || content.starts_with('c') {
+            continue;
+        }
+        if content.starts_with('%') {
+            break;
+        }
+        if content.starts_with('p') {
+            if header.is_some() {
+                return Err(Error::Syntax {
+                    line: line_number,
+                    message: "Duplicate header".to_string(),
+                });
+            }
+            header = Some(parse_header(content)?);
+            continue;
+        }
+        let quantifier = match content.chars().next() {
+            Some('a') => Some(Quantifier::Forall),
+            Some('e') => Some(Quantifier::Exists),
+            _ => None,
+        };
+        if let Some(quantifier) = quantifier {
+            if !clauses.is_empty() || !literals.is_empty() {
+                return Err(Error::Syntax {
+                    line: line_number,
+                    message: "Quantifiers must precede the clauses".to_string(),
+                });
+            }
+            let vars = content[1..]
+                .split_whitespace()
+                .map(|var| var.parse::<Var>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .ok()
+                .filter(|vars| vars.last() == Some(&Var::default()))
+                .ok_or(Error::Syntax {
+                    line: line_number,
+                    message: format!("Invalid quantifier block \"{}\"", content),
+                })?;
+            let vars = &vars[..vars.len() - 1];
+            num_vars = vars.iter().map(Var::index).fold(num_vars, usize::max);
+            match quantifiers.last_mut() {
+                Some((last, block)) if *last == quantifier => block.extend(vars),
+                _ => quantifiers.push((quantifier, vars.to_vec())),
+            }
+            continue;
+        }
+        if header.is_none() && !missing_header_reported {
+            missing_header_reported = true;
+            tolerate(Error::Syntax {
+                line: line_number,
+                message: "Clause before the header".to_string(),
+            })?;
+        }
+
+        for token in content.split_whitespace() {
+            let lit = token.parse::<Literal>().map_err(|_| Error::Syntax {
+                line: line_number,
+                message: format!("Invalid literal \"{}\"", token),
+            })?;
+            if lit.var().index() == 0 {
+                if let (true, Some(a_clauses)) = (is_part_a, &mut a_clauses) {
+                    a_clauses.push(clauses.len());
+                }
+                clauses.push(normalize(std::mem::take(&mut literals)));
+            } else {
+                num_vars = num_vars.max(lit.var().index());
+                literals.push(lit);
+            }
+        }
     }
 
-    // parse header
-    let header = file_content
-        .first()
-        .ok_or("File was empty")?
-        .split_whitespace()
-        .collect::<Vec<&str>>();
+    if !literals.is_empty() {
+        tolerate(Error::Syntax {
+            line: line_number,
+            message: "Last clause must end with 0".to_string(),
+        })?;
+        if let (true, Some(a_clauses)) = (is_part_a, &mut a_clauses) {
+            a_clauses.push(clauses.len());
+        }
+        clauses.push(normalize(literals));
+    }
 
-    if header.len() != 4 || header[0] != "p" || header[1] != "cnf" {
-        return Err("Invalid DIMACS header".to_string());
+    if let Some((declared_vars, declared_clauses)) = header {
+        if num_vars > declared_vars {
+            tolerate(Error::VariableCountMismatch {
+                expected: declared_vars,
+                found: num_vars,
+            })?;
+        }
+        num_vars = num_vars.max(declared_vars);
+        if clauses.len() != declared_clauses {
+            tolerate(Error::ClauseCountMismatch {
+                expected: declared_clauses,
+                found: clauses.len(),
+            })?;
+        }
+    } else if !missing_header_reported {
+        tolerate(Error::Syntax {
+            line: line_number.max(1),
+            message: "Missing header".to_string(),
+        })?;
     }
-    let num_vars = header[2].parse::<usize>().map_err(|err| err.to_string())?;
-    let num_clauses = header[3].parse::<usize>().map_err(|err| err.to_string())?;
 
-    let mut clauses: Vec<Vec<Literal>> = file_content
-        .iter()
-        .filter(|line| !line.starts_with('p')) // filter header
-        .join(" ")
-        .split_whitespace()
-        .map(|lit| lit.parse::<Literal>())
-        .collect::<Result<Vec<_>, _>>()
-        .map(|lits| {
-            lits.split(|lit| (*lit).id() == 0)
-                .map(|clause| clause.to_vec())
-                .collect_vec()
-        })
-        .unwrap_or(Vec::new());
-
-    if !clauses.is_empty() && !clauses.last().unwrap().is_empty() {
-        return Err("Last clause must end with 0".to_string());
-    }
-    clauses.pop();
-
-    if clauses.len() != num_clauses {
-        return Err(format!(
-            "Expected {} clauses, got {}",
-            num_clauses,
-            clauses.len()
-        ));
+    if let Some((var, line)) = max_projection_var.filter(|(var, _)| var.index() > num_vars) {
+        return Err(Error::Syntax {
+            line,
+            message: format!(
+                "Projection variable {} exceeds the number of variables {}",
+                var, num_vars
+            ),
+        });
     }
+    let projection = projection.map(|vars| vars.into_iter().unique().collect_vec());
+
+    Ok(DimacsFile {
+        num_vars,
+        clauses,
+        projection,
+        quantifiers,
+        symbols,
+        objective,
+        a_clauses,
+        warnings,
+    })
+}
 
-    // Normalize CNF: sort clauses and remove duplicate literals
-    for clause in clauses.iter_mut() {
-        clause.sort();
-        clause.dedup();
+fn parse_header(line: &str) -> Result<(usize, usize)> {
+    let invalid_header = || Error::InvalidHeader(line.to_string());
+    let header = line.split_whitespace().collect_vec();
+    if header.len() != 4 || header[0] != "p" || header[1] != "cnf" {
+        return Err(invalid_header());
     }
+    let num_vars = header[2].parse::<usize>().map_err(|_| invalid_header())?;
+    let num_clauses = header[3].parse::<usize>().map_err(|_| invalid_header())?;
+    Ok((num_vars, num_clauses))
+}
+
+/// Sorts the literals and removes duplicates
+fn normalize(mut literals: Vec<Literal>) -> Clause {
+    literals.sort();
+    literals.dedup();
+    Clause::from(literals)
+}
+
+/// Parses both the classic `p wcnf <vars> <clauses> <top>` format, where every clause starts
+/// with its weight and weights of at least `top` mark hard clauses, and the format of the
+/// MaxSAT Evaluations since 2022, where hard clauses start with `h`.
+pub fn wcnf_from_string(input: String) -> Result<WcnfFile> {
+    let mut top = None;
+    let mut hard_clauses = vec![];
+    let mut soft_clauses = vec![];
 
-    let var_count_in_clauses = clauses
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let first = tokens.next().unwrap();
+        if first == "p" {
+            let header = tokens.collect_vec();
+            if header.len() < 3 || header[0] != "wcnf" {
+                return Err(Error::InvalidHeader(line.to_string()));
+            }
+            top = match header.get(3) {
+                Some(top) => Some(
+                    top.parse::<u64>()
+                        .map_err(|_| Error::InvalidHeader(line.to_string()))?,
+                ),
+                None => Some(u64::MAX),
+            };
+            continue;
+        }
+
+        let literals = tokens
+            .map(|lit| lit.parse::<Literal>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::Parse(format!("Invalid literal in line \"{}\"", line)))?;
+        if literals.last().map(|lit| lit.var().index()) != Some(0) {
+            return Err(Error::Parse(format!("Clause \"{}\" must end with 0", line)));
+        }
+        let mut literals = literals[..literals.len() - 1].to_vec();
+        literals.sort();
+        literals.dedup();
+        let clause = Clause::from(literals);
+
+        if first == "h" {
+            hard_clauses.push(clause);
+            continue;
+        }
+        let weight = first
+            .parse::<u64>()
+            .map_err(|_| Error::Parse(format!("Invalid weight in line \"{}\"", line)))?;
+        if top.is_some_and(|top| weight >= top) {
+            hard_clauses.push(clause);
+        } else {
+            soft_clauses.push((weight, clause));
+        }
+    }
+
+    let num_vars = hard_clauses
         .iter()
-        .map(|clause| clause.iter().map(|lit| lit.id()).max().unwrap_or(0))
+        .chain(soft_clauses.iter().map(|(_, clause)| clause))
+        .flat_map(|clause| clause.literals.iter().map(|lit| lit.var().index()))
         .max()
         .unwrap_or(0);
 
-    if var_count_in_clauses != num_vars {
-        return Err(format!(
-            "Expected {} variables, got {}",
-            num_vars, var_count_in_clauses
-        ));
+    Ok(WcnfFile {
+        num_vars,
+        hard_clauses,
+        soft_clauses,
+    })
+}
+
+/// Parses the header `p gcnf <vars> <clauses> <groups>` and clauses that start with their group
+/// in braces, e.g. `{2} 1 -3 0`
+pub fn gcnf_from_string(input: String) -> Result<GcnfFile> {
+    let mut header: Option<(usize, usize)> = None;
+    let mut background = vec![];
+    let mut groups: Vec<Vec<Clause>> = vec![];
+    let mut num_vars = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('c') {
+            continue;
+        }
+        if content.starts_with('p') {
+            let invalid_header = || Error::InvalidHeader(content.to_string());
+            let tokens = content.split_whitespace().collect_vec();
+            if tokens.len() != 5 || tokens[1] != "gcnf" {
+                return Err(invalid_header());
+            }
+            let numbers = tokens[2..]
+                .iter()
+                .map(|token| token.parse::<usize>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| invalid_header())?;
+            num_vars = numbers[0];
+            header = Some((numbers[1], numbers[2]));
+            groups = vec![vec![]; numbers[2]];
+            continue;
+        }
+        let syntax_error = |message: String| Error::Syntax {
+            line: line_number,
+            message,
+        };
+        let Some((_, num_groups)) = header else {
+            return Err(syntax_error("Clause before the header".to_string()));
+        };
+        let (group, literals) = content
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .ok_or_else(|| syntax_error(format!("Missing group of clause \"{}\"", content)))?;
+        let group = group
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&group| group <= num_groups)
+            .ok_or_else(|| syntax_error(format!("Invalid group \"{}\"", group)))?;
+        let literals = literals
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .parse::<Literal>()
+                    .map_err(|_| syntax_error(format!("Invalid literal \"{}\"", token)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let Some((last, literals)) = literals.split_last() else {
+            return Err(syntax_error("Empty clause without 0".to_string()));
+        };
+        if last.var().index() != 0 || literals.iter().any(|lit| lit.var().index() == 0) {
+            return Err(syntax_error("Clauses must end with 0".to_string()));
+        }
+        num_vars = literals
+            .iter()
+            .map(|lit| lit.var().index())
+            .fold(num_vars, usize::max);
+        let clause = normalize(literals.to_vec());
+        match group {
+            0 => background.push(clause),
+            group => groups[group - 1].push(clause),
+        }
     }
 
-    let clauses = clauses
-        .iter()
-        .map(|clause| Clause::from(clause.clone()))
-        .collect_vec();
+    let Some((num_clauses, _)) = header else {
+        return Err(Error::InvalidHeader(String::new()));
+    };
+    let found = background.len() + groups.iter().map(Vec::len).sum::<usize>();
+    if found != num_clauses {
+        return Err(Error::ClauseCountMismatch {
+            expected: num_clauses,
+            found,
+        });
+    }
+    Ok(GcnfFile {
+        num_vars,
+        background,
+        groups,
+    })
+}
 
-    Ok(DimacsFile { clauses, num_vars })
+pub fn clauses_to_dimacs(clauses: &[Clause], num_vars: usize) -> String {
+    let mut dimacs = format!("p cnf {} {}\n", num_vars, clauses.len());
+    for clause in clauses {
+        for lit in &clause.literals {
+            dimacs.push_str(format!("{} ", lit).as_str());
+        }
+        dimacs.push_str("0\n");
+    }
+    dimacs
 }
 
-pub fn solution_to_dimacs(solution: Option<HashMap<VarId, bool>>) -> String {
-    let mut dimacs = String::new();
-    if solution.is_none() {
-        return String::from("s UNSATISFIABLE");
-    } else {
-        dimacs.push_str("s SATISFIABLE\n")
+/// The status line of the SAT competition output format
+pub fn status_to_dimacs(result: &SolveResult) -> &'static str {
+    match result {
+        SolveResult::Sat(_) => "s SATISFIABLE",
+        SolveResult::Unsat => "s UNSATISFIABLE",
+        SolveResult::Unknown(_) => "s UNKNOWN",
     }
-    let assignment = solution.unwrap();
-    dimacs.push_str("v ");
-    let sorted_vars = assignment
-        .iter()
-        .sorted_by_key(|(var_id, _)| *var_id)
-        .map(|(id, value)| (id, *value))
-        .collect_vec();
-    for (var_id, value) in sorted_vars.iter() {
-        dimacs.push_str(format!("{}{} ", if *value { "" } else { "-" }, var_id).as_str());
-        dimacs.push(' ');
+}
+
+/// The status line followed by the model as a `v` line terminated by 0
+pub fn solution_to_dimacs(result: &SolveResult) -> String {
+    let status = status_to_dimacs(result);
+    match result {
+        SolveResult::Sat(model) => format!("{}\n{}", status, values_to_dimacs(model.iter())),
+        _ => status.to_string(),
+    }
+}
+
+/// The `v` line of the values, terminated by 0, which is `v 0` for the empty formula
+pub fn values_to_dimacs<T: core::fmt::Display>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .chain(["0".to_string()])
+        .fold("v".to_string(), |line, value| line + " " + &value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CNF: &str = "c comment\np cnf 2 3\n1 -2 0\nc comment between clauses\n2 3\n-1 0 1 0\n";
+
+    #[test]
+    fn lenient_parsing() {
+        let dimacs = clauses_from_dimacs_reader(CNF.as_bytes(), ParseMode::Lenient).unwrap();
+        assert_eq!(dimacs.num_vars, 3);
+        assert_eq!(
+            dimacs.clauses,
+            vec![
+                Clause::from("1 -2"),
+                Clause::from("-1 2 3"),
+                Clause::from("1")
+            ]
+        );
+        assert!(matches!(
+            dimacs.warnings[..],
+            [Error::VariableCountMismatch {
+                expected: 2,
+                found: 3
+            }]
+        ));
+
+        let dimacs = clauses_from_dimacs("1 2 0\n-1 0\n%\n0\n".to_string()).unwrap();
+        assert_eq!(dimacs.clauses.len(), 2);
+        assert_eq!(dimacs.warnings.len(), 1);
+    }
+
+    #[test]
+    fn strict_parsing() {
+        assert!(matches!(
+            clauses_from_dimacs_reader(CNF.as_bytes(), ParseMode::Strict),
+            Err(Error::VariableCountMismatch { .. })
+        ));
+        assert!(matches!(
+            clauses_from_dimacs_reader("p cnf 2 2\n1 2 0\n".as_bytes(), ParseMode::Strict),
+            Err(Error::ClauseCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            clauses_from_dimacs_reader("p cnf 2 1\n1 2\n".as_bytes(), ParseMode::Strict),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn trivial_inputs() {
+        for input in ["", "c only comments\n", "p cnf 0 0\n"] {
+            let dimacs = clauses_from_dimacs(input.to_string()).unwrap();
+            assert_eq!((dimacs.num_vars, dimacs.clauses.len()), (0, 0));
+        }
+        assert!(matches!(
+            clauses_from_dimacs_reader("".as_bytes(), ParseMode::Strict),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+
+        // unused variables are kept, duplicate literals are removed and empty clauses stay
+        let input = "p cnf 4 3\n1 1 0\n0\n-2 1 -2 0\n";
+        let dimacs = clauses_from_dimacs_reader(input.as_bytes(), ParseMode::Strict).unwrap();
+        assert_eq!(dimacs.num_vars, 4);
+        assert_eq!(dimacs.clauses, ["1", "", "1 -2"].map(Clause::from).to_vec());
+
+        let empty_model = SolveResult::Sat(crate::cnf::Model::new(vec![]));
+        assert_eq!(solution_to_dimacs(&empty_model), "s SATISFIABLE\nv 0");
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn compressed_files() {
+        let directory = std::env::temp_dir().join(format!("utopia-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let compressed: [(&str, Vec<u8>); 4] = [
+            ("cnf.gz", {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                std::io::Write::write_all(&mut encoder, CNF.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+            ("cnf.xz", {
+                let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+                std::io::Write::write_all(&mut encoder, CNF.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+            ("cnf.zst", zstd::encode_all(CNF.as_bytes(), 0).unwrap()),
+            // detected by the magic bytes
+            ("bz2.cnf", {
+                let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::best());
+                std::io::Write::write_all(&mut encoder, CNF.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+        ];
+
+        for (name, bytes) in compressed {
+            let path = directory.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            let dimacs = clauses_from_dimacs_file(path.to_str().unwrap()).unwrap();
+            assert_eq!(dimacs.clauses.len(), 3, "{}", name);
+        }
+        std::fs::remove_dir_all(directory).unwrap();
+        assert_eq!(without_compression_extension("a.wcnf.xz"), "a.wcnf");
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(matches!(
+            clauses_from_dimacs("p cnf 2 1\nc\n1 x 0\n".to_string()),
+            Err(Error::Syntax { line: 3, .. })
+        ));
+        assert!(matches!(
+            clauses_from_dimacs("p dnf 2 1\n".to_string()),
+            Err(Error::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn variable_names() {
+        let cnf = "c var 1 a\nc var 2 b.c\nc var 3 a\np cnf 2 1\n1 -2 0\n";
+        let dimacs = clauses_from_dimacs(cnf.to_string()).unwrap();
+        assert_eq!(dimacs.symbols.var("b.c"), Some(Var::new(2)));
+        assert_eq!(dimacs.symbols.name(Var::new(3)), None);
+        assert!(matches!(
+            dimacs.warnings[..],
+            [Error::Syntax { line: 3, .. }]
+        ));
+        assert!(matches!(
+            clauses_from_dimacs("c var x a\n".to_string()),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn objective() {
+        let dimacs = clauses_from_dimacs("c min 3 1 -2 -4 0\np cnf 4 1\n1 2 0\n".to_string());
+        assert_eq!(
+            dimacs.unwrap().objective,
+            Some(vec![(3, Literal::new(1)), (-2, Literal::new(-4))])
+        );
+        assert!(matches!(
+            clauses_from_dimacs("c min 3 1 2 0\np cnf 2 1\n1 2 0\n".to_string()),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn projection() {
+        let cnf = "p cnf 3 1\nc p show 1 3 0\nc p show 3 2 0\n1 2 0\n";
+        let dimacs = clauses_from_dimacs(cnf.to_string()).unwrap();
+        assert_eq!(
+            dimacs.projection,
+            Some(vec![Var::new(1), Var::new(3), Var::new(2)])
+        );
+        assert!(matches!(
+            clauses_from_dimacs("p cnf 2 1\nc p show 1 5 0\n1 2 0\n".to_string()),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn interpolation_partition() {
+        let cnf = "p cnf 3 4\n1 0\nc part B\n-1 2 0\nc part A\n-2\n3 0\nc part B\n-3 0\n";
+        let dimacs = clauses_from_dimacs(cnf.to_string()).unwrap();
+        assert_eq!(dimacs.a_clauses, Some(vec![0, 2]));
+        assert_eq!(
+            clauses_from_dimacs("1 0\n".to_string()).unwrap().a_clauses,
+            None
+        );
+        assert!(matches!(
+            clauses_from_dimacs("c part C\n".to_string()),
+            Err(Error::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn group_cnf() {
+        let gcnf = "c groups\np gcnf 3 4 2\n{0} 1 2 0\n{2} -1 0\n{1} -2 3 0\n{2} -3 0\n";
+        let gcnf = gcnf_from_string(gcnf.to_string()).unwrap();
+        assert_eq!(gcnf.num_vars, 3);
+        assert_eq!(gcnf.background, vec![Clause::from("1 2")]);
+        assert_eq!(
+            gcnf.groups,
+            vec![
+                vec![Clause::from("-2 3")],
+                vec![Clause::from("-1"), Clause::from("-3")]
+            ]
+        );
+        assert!(matches!(
+            gcnf_from_string("p gcnf 2 1 1\n{2} 1 0\n".to_string()),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            gcnf_from_string("p gcnf 2 1 1\n1 0\n".to_string()),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn competition_output() {
+        let model = crate::cnf::Model::new(vec![true, false, true]);
+        assert_eq!(
+            solution_to_dimacs(&SolveResult::Sat(model)),
+            "s SATISFIABLE\nv 1 -2 3 0"
+        );
+        assert_eq!(solution_to_dimacs(&SolveResult::Unsat), "s UNSATISFIABLE");
     }
-    dimacs
 }
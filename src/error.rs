@@ -0,0 +1,116 @@
+use crate::cnf::{UnknownReason, Var};
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Output file that couldn't be written completely
+    #[cfg(feature = "std")]
+    Write {
+        path: String,
+        source: std::io::Error,
+    },
+    FileNotFound(String),
+    /// Missing or malformed `p` line
+    InvalidHeader(String),
+    /// Any other malformed input, e.g. tokens that aren't numbers
+    Parse(String),
+    /// Malformed input at the given line
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    ClauseCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    VariableCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// Literals must not be 0 or `i32::MIN`, which has no negation
+    InvalidLiteral(i32),
+    VariableOutOfRange {
+        var_id: Var,
+        num_vars: usize,
+    },
+    /// Variable removed from the formula by inprocessing, which can't occur in new clauses
+    EliminatedVariable(Var),
+    /// Checkpoint that can't be written or read
+    Checkpoint(String),
+    /// Search stopped by a limit or the terminate flag before the result was complete, e.g.
+    /// while counting models
+    Stopped(UnknownReason),
+    /// Valid input that can't be handled, e.g. QBFs with more than two quantifier blocks
+    Unsupported(String),
+    /// Variable name that is empty or contains whitespace or `=`
+    InvalidName(String),
+    /// Variable name that is already given to another variable
+    DuplicateName(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "{}", err),
+            #[cfg(feature = "std")]
+            Error::Write { path, source } => write!(f, "Could not write {}: {}", path, source),
+            Error::FileNotFound(path) => write!(f, "File {} not found", path),
+            Error::InvalidHeader(header) => write!(f, "Invalid header \"{}\"", header),
+            Error::Parse(message) => write!(f, "{}", message),
+            Error::Syntax { line, message } => write!(f, "Line {}: {}", line, message),
+            Error::ClauseCountMismatch { expected, found } => {
+                write!(
+                    f,
+                    "The header declares {} clauses, but {} were found",
+                    expected, found
+                )
+            }
+            Error::VariableCountMismatch { expected, found } => {
+                write!(
+                    f,
+                    "The header declares {} variables, but variable {} is used",
+                    expected, found
+                )
+            }
+            Error::InvalidLiteral(literal) => write!(f, "Invalid literal {}", literal),
+            Error::VariableOutOfRange { var_id, num_vars } => write!(
+                f,
+                "Variable {} exceeds the number of variables {}",
+                var_id, num_vars
+            ),
+            Error::EliminatedVariable(var_id) => {
+                write!(f, "Variable {} was eliminated by inprocessing", var_id)
+            }
+            Error::Checkpoint(message) => write!(f, "Invalid checkpoint: {}", message),
+            Error::Stopped(reason) => write!(f, "The search was stopped: {:?}", reason),
+            Error::Unsupported(message) => write!(f, "{}", message),
+            Error::InvalidName(name) => write!(f, "Invalid variable name \"{}\"", name),
+            Error::DuplicateName(name) => write!(f, "Variable name \"{}\" is already used", name),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            #[cfg(feature = "std")]
+            Error::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,504 @@
+use crate::cnf::{Clause, Literal, Model, Var};
+use crate::dimacs::read_file;
+use crate::error::{Error, Result};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Boolean formula over named variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Const(bool),
+    Var(String),
+    Not(Box<Formula>),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+    Xor(Box<Formula>, Box<Formula>),
+    Iff(Box<Formula>, Box<Formula>),
+    /// if-then-else
+    Ite(Box<Formula>, Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn var(name: &str) -> Self {
+        Formula::Var(name.to_string())
+    }
+
+    pub fn implies(self, other: Formula) -> Self {
+        Formula::Or(vec![Formula::Not(Box::new(self)), other])
+    }
+}
+
+pub fn formula_from_file(path: &str) -> Result<Formula> {
+    formula_from_string(&read_file(path)?)
+}
+
+/// Parses formulas like `(a & !b) | ite(c, d, e) -> x1 <-> true`. The operators in order of
+/// decreasing precedence are `!`, `&`, `^`, `|`, `->` and `<->`, where `->` is right
+/// associative. `~` is accepted for negation. `#` starts a comment until the end of the line.
+pub fn formula_from_string(input: &str) -> Result<Formula> {
+    let mut parser = Parser {
+        chars: input.chars().peekable(),
+        line: 1,
+    };
+    let formula = parser.parse_iff()?;
+    match parser.next_token()? {
+        None => Ok(formula),
+        Some(token) => Err(parser.error(format!("Unexpected \"{}\"", token))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Operator(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Operator(operator) => write!(f, "{}", operator),
+        }
+    }
+}
+
+const OPERATORS: [&str; 10] = ["<->", "->", "!", "~", "&", "^", "|", "(", ")", ","];
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: String) -> Error {
+        Error::Syntax {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.chars.peek().copied() {
+            if c == '#' {
+                while self.chars.next_if(|c| *c != '\n').is_some() {}
+            } else if c.is_whitespace() {
+                if c == '\n' {
+                    self.line += 1;
+                }
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek_token(&mut self) -> Result<Option<Token>> {
+        self.skip_whitespace_and_comments();
+        if let Some(operator) = OPERATORS.iter().find(|operator| {
+            let mut chars = self.chars.clone();
+            operator.chars().all(|c| chars.next() == Some(c))
+        }) {
+            return Ok(Some(Token::Operator(operator)));
+        }
+        let identifier = self
+            .chars
+            .clone()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect::<String>();
+        match (identifier.is_empty(), self.chars.peek().copied()) {
+            (_, None) => Ok(None),
+            (false, _) => Ok(Some(Token::Identifier(identifier))),
+            (true, Some(c)) => Err(self.error(format!("Unexpected character '{}'", c))),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        let token = self.peek_token()?;
+        let length = match &token {
+            Some(Token::Identifier(name)) => name.chars().count(),
+            Some(Token::Operator(operator)) => operator.len(),
+            None => 0,
+        };
+        for _ in 0..length {
+            self.chars.next();
+        }
+        Ok(token)
+    }
+
+    fn accept(&mut self, operator: &str) -> Result<bool> {
+        if matches!(self.peek_token()?, Some(Token::Operator(op)) if op == operator) {
+            self.next_token()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<()> {
+        if !self.accept(operator)? {
+            return Err(self.error(format!("Expected \"{}\"", operator)));
+        }
+        Ok(())
+    }
+
+    fn parse_iff(&mut self) -> Result<Formula> {
+        let mut formula = self.parse_implication()?;
+        while self.accept("<->")? {
+            formula = Formula::Iff(Box::new(formula), Box::new(self.parse_implication()?));
+        }
+        Ok(formula)
+    }
+
+    fn parse_implication(&mut self) -> Result<Formula> {
+        let formula = self.parse_or()?;
+        if self.accept("->")? {
+            return Ok(formula.implies(self.parse_implication()?));
+        }
+        Ok(formula)
+    }
+
+    fn parse_or(&mut self) -> Result<Formula> {
+        let mut children = vec![self.parse_xor()?];
+        while self.accept("|")? {
+            children.push(self.parse_xor()?);
+        }
+        Ok(match children.len() {
+            1 => children.pop().unwrap(),
+            _ => Formula::Or(children),
+        })
+    }
+
+    fn parse_xor(&mut self) -> Result<Formula> {
+        let mut formula = self.parse_and()?;
+        while self.accept("^")? {
+            formula = Formula::Xor(Box::new(formula), Box::new(self.parse_and()?));
+        }
+        Ok(formula)
+    }
+
+    fn parse_and(&mut self) -> Result<Formula> {
+        let mut children = vec![self.parse_unary()?];
+        while self.accept("&")? {
+            children.push(self.parse_unary()?);
+        }
+        Ok(match children.len() {
+            1 => children.pop().unwrap(),
+            _ => Formula::And(children),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Formula> {
+        if self.accept("!")? || self.accept("~")? {
+            return Ok(Formula::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.accept("(")? {
+            let formula = self.parse_iff()?;
+            self.expect(")")?;
+            return Ok(formula);
+        }
+
+        match self.next_token()? {
+            Some(Token::Identifier(name)) => Ok(match name.as_str() {
+                "true" => Formula::Const(true),
+                "false" => Formula::Const(false),
+                "ite" => {
+                    self.expect("(")?;
+                    let condition = self.parse_iff()?;
+                    self.expect(",")?;
+                    let then = self.parse_iff()?;
+                    self.expect(",")?;
+                    let otherwise = self.parse_iff()?;
+                    self.expect(")")?;
+                    Formula::Ite(Box::new(condition), Box::new(then), Box::new(otherwise))
+                }
+                _ => Formula::Var(name),
+            }),
+            Some(token) => Err(self.error(format!("Unexpected \"{}\"", token))),
+            None => Err(self.error("Unexpected end of formula".to_string())),
+        }
+    }
+}
+
+/// Whether a subformula occurs positively, negatively or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Polarity {
+    Positive,
+    Negative,
+    Both,
+}
+
+impl Polarity {
+    fn flip(self) -> Self {
+        match self {
+            Polarity::Positive => Polarity::Negative,
+            Polarity::Negative => Polarity::Positive,
+            Polarity::Both => Polarity::Both,
+        }
+    }
+
+    fn positive(self) -> bool {
+        self != Polarity::Negative
+    }
+
+    fn negative(self) -> bool {
+        self != Polarity::Positive
+    }
+}
+
+/// Encodes formulas into CNF by introducing a variable for every subformula, as described in
+/// G. S. Tseitin, “On the Complexity of Derivation in Propositional Calculus,” in Automation
+/// of Reasoning, Springer, 1983, pp. 466–483. With `plaisted_greenbaum`, only the implications
+/// in the direction of the polarity of the subformula are added, following D. A. Plaisted and
+/// S. Greenbaum, “A Structure-preserving Clause Form Translation,” Journal of Symbolic
+/// Computation, vol. 2, no. 3, pp. 293–304, 1986. This preserves satisfiability, but auxiliary
+/// variables aren't functionally defined anymore, so model counts differ.
+#[derive(Debug, Clone, Default)]
+pub struct TseitinEncoder {
+    pub num_vars: usize,
+    pub clauses: Vec<Clause>,
+    /// Variables of the named variables of the formulas, in order of their first occurrence
+    pub names: Vec<(String, Var)>,
+    var_of_name: HashMap<String, Var>,
+    true_var: Option<Var>,
+    plaisted_greenbaum: bool,
+}
+
+impl TseitinEncoder {
+    pub fn new(plaisted_greenbaum: bool) -> Self {
+        TseitinEncoder {
+            plaisted_greenbaum,
+            ..Default::default()
+        }
+    }
+
+    /// Adds clauses that are satisfiable iff the formula is. Top-level conjunctions and
+    /// disjunctions are added without auxiliary variables.
+    pub fn assert(&mut self, formula: &Formula) {
+        match formula {
+            Formula::And(children) => children.iter().for_each(|child| self.assert(child)),
+            Formula::Or(children) => {
+                let literals = children
+                    .iter()
+                    .map(|child| self.encode(child, Polarity::Positive))
+                    .collect_vec();
+                self.add_clause(literals);
+            }
+            _ => {
+                let literal = self.encode(formula, Polarity::Positive);
+                self.add_clause(vec![literal]);
+            }
+        }
+    }
+
+    /// The values of the named variables, in order of their first occurrence
+    pub fn named_values<'a>(&'a self, model: &'a Model) -> impl Iterator<Item = (&'a str, bool)> {
+        self.names
+            .iter()
+            .map(|(name, var_id)| (name.as_str(), model[*var_id]))
+    }
+
+    fn new_var(&mut self) -> Var {
+        self.num_vars += 1;
+        Var::new(self.num_vars)
+    }
+
+    fn encode(&mut self, formula: &Formula, polarity: Polarity) -> Literal {
+        let polarity = match self.plaisted_greenbaum {
+            true => polarity,
+            false => Polarity::Both,
+        };
+        match formula {
+            Formula::Const(value) => {
+                let true_var = match self.true_var {
+                    Some(true_var) => true_var,
+                    None => {
+                        let true_var = self.new_var();
+                        self.true_var = Some(true_var);
+                        self.add_clause(vec![Literal::from_value(true_var, true)]);
+                        true_var
+                    }
+                };
+                Literal::from_value(true_var, *value)
+            }
+            Formula::Var(name) => {
+                let var_id = match self.var_of_name.get(name) {
+                    Some(var_id) => *var_id,
+                    None => {
+                        let var_id = self.new_var();
+                        self.var_of_name.insert(name.clone(), var_id);
+                        self.names.push((name.clone(), var_id));
+                        var_id
+                    }
+                };
+                Literal::from_value(var_id, true)
+            }
+            Formula::Not(child) => -self.encode(child, polarity.flip()),
+            Formula::And(children) | Formula::Or(children) => {
+                let children = children
+                    .iter()
+                    .map(|child| self.encode(child, polarity))
+                    .collect_vec();
+                let x = Literal::from_value(self.new_var(), true);
+                // an And is an Or with negated inputs and output
+                let (x, children, polarity) = match formula {
+                    Formula::And(_) => (
+                        -x,
+                        children.into_iter().map(|child| -child).collect_vec(),
+                        polarity.flip(),
+                    ),
+                    _ => (x, children, polarity),
+                };
+                if polarity.positive() {
+                    self.add_clause([vec![-x], children.clone()].concat());
+                }
+                if polarity.negative() {
+                    for child in children {
+                        self.add_clause(vec![x, -child]);
+                    }
+                }
+                match formula {
+                    Formula::And(_) => -x,
+                    _ => x,
+                }
+            }
+            Formula::Xor(a, b) | Formula::Iff(a, b) => {
+                let a = self.encode(a, Polarity::Both);
+                let b = self.encode(b, Polarity::Both);
+                let x = Literal::from_value(self.new_var(), true);
+                // an Iff is a negated Xor
+                let (x, polarity) = match formula {
+                    Formula::Iff(_, _) => (-x, polarity.flip()),
+                    _ => (x, polarity),
+                };
+                if polarity.positive() {
+                    self.add_clause(vec![-x, a, b]);
+                    self.add_clause(vec![-x, -a, -b]);
+                }
+                if polarity.negative() {
+                    self.add_clause(vec![x, -a, b]);
+                    self.add_clause(vec![x, a, -b]);
+                }
+                match formula {
+                    Formula::Iff(_, _) => -x,
+                    _ => x,
+                }
+            }
+            Formula::Ite(condition, then, otherwise) => {
+                let condition = self.encode(condition, Polarity::Both);
+                let then = self.encode(then, polarity);
+                let otherwise = self.encode(otherwise, polarity);
+                let x = Literal::from_value(self.new_var(), true);
+                if polarity.positive() {
+                    self.add_clause(vec![-x, -condition, then]);
+                    self.add_clause(vec![-x, condition, otherwise]);
+                }
+                if polarity.negative() {
+                    self.add_clause(vec![x, -condition, -then]);
+                    self.add_clause(vec![x, condition, -otherwise]);
+                }
+                x
+            }
+        }
+    }
+
+    fn add_clause(&mut self, mut literals: Vec<Literal>) {
+        literals.sort();
+        literals.dedup();
+        if literals.iter().any(|lit| literals.contains(&-*lit)) {
+            return;
+        }
+        self.clauses.push(Clause::from(literals));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+
+    fn evaluate(formula: &Formula, values: &HashMap<&str, bool>) -> bool {
+        match formula {
+            Formula::Const(value) => *value,
+            Formula::Var(name) => values[name.as_str()],
+            Formula::Not(child) => !evaluate(child, values),
+            Formula::And(children) => children.iter().all(|child| evaluate(child, values)),
+            Formula::Or(children) => children.iter().any(|child| evaluate(child, values)),
+            Formula::Xor(a, b) => evaluate(a, values) ^ evaluate(b, values),
+            Formula::Iff(a, b) => evaluate(a, values) == evaluate(b, values),
+            Formula::Ite(condition, then, otherwise) => match evaluate(condition, values) {
+                true => evaluate(then, values),
+                false => evaluate(otherwise, values),
+            },
+        }
+    }
+
+    #[test]
+    fn parse_formula() {
+        let formula = formula_from_string("!a & b | c -> d <-> ite(e, true, ~f) ^ g # comment");
+        let var = |name| Box::new(Formula::var(name));
+        let expected = Formula::Iff(
+            Box::new(
+                Formula::Or(vec![
+                    Formula::And(vec![Formula::Not(var("a")), Formula::var("b")]),
+                    Formula::var("c"),
+                ])
+                .implies(Formula::var("d")),
+            ),
+            Box::new(Formula::Xor(
+                Box::new(Formula::Ite(
+                    var("e"),
+                    Box::new(Formula::Const(true)),
+                    Box::new(Formula::Not(var("f"))),
+                )),
+                var("g"),
+            )),
+        );
+        assert_eq!(formula.unwrap(), expected);
+        assert!(matches!(
+            formula_from_string("a &\n(b | )"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+        assert!(formula_from_string("a b").is_err());
+    }
+
+    #[test]
+    fn encodings_preserve_satisfiability() {
+        let formulas = [
+            "(a ^ b) & (a <-> b)",
+            "ite(a, b, !b) & !(a | b) & (c -> a)",
+            "!(a -> (b -> a))",
+            "(a ^ b ^ c) <-> !(ite(a, b & c, false) | d)",
+            "false | (x & !x) | (y <-> !y)",
+            "!((a & b) | (!a & c)) ^ (b <-> c)",
+        ];
+        for input in formulas {
+            let formula = formula_from_string(input).unwrap();
+            let names = ["a", "b", "c", "d", "x", "y"];
+            let satisfiable = (0..1 << names.len()).any(|bits: usize| {
+                let values = names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| (*name, bits >> i & 1 == 1))
+                    .collect();
+                evaluate(&formula, &values)
+            });
+
+            for plaisted_greenbaum in [false, true] {
+                let mut encoder = TseitinEncoder::new(plaisted_greenbaum);
+                encoder.assert(&formula);
+                let config = Config::default();
+                let mut solver = Solver::new(encoder.clauses.clone(), encoder.num_vars, config);
+                let result = solver.solve();
+                assert_eq!(result.is_sat(), satisfiable, "{}", input);
+                if let Some(model) = result.model() {
+                    let mut values: HashMap<&str, bool> =
+                        names.iter().map(|name| (*name, false)).collect();
+                    values.extend(encoder.named_values(model));
+                    assert!(evaluate(&formula, &values), "{}", input);
+                }
+            }
+        }
+    }
+}
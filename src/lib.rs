@@ -1,3 +1,35 @@
+//! Without the default `std` feature, only the solver and its data structures are available,
+//! built with `no_std` and `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod aiger;
 pub mod cnf;
+#[cfg(feature = "std")]
 pub mod dimacs;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod formula;
+#[cfg(feature = "std")]
+pub mod maxsat;
+#[cfg(feature = "std")]
+pub mod mus;
+#[cfg(feature = "std")]
+pub mod optimize;
+#[cfg(feature = "std")]
+pub mod pb;
+pub mod presimplify;
+#[cfg(feature = "std")]
+pub mod proof;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+pub mod qbf;
 pub mod solver;
+pub mod symbols;
+#[cfg(feature = "std")]
+pub mod tester;
+#[cfg(feature = "wasm")]
+pub mod wasm;
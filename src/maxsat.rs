@@ -0,0 +1,154 @@
+use crate::cnf::{Clause, Literal, Model, SolveResult, UnknownReason, Var};
+use crate::dimacs::WcnfFile;
+use crate::pb::{PbEncoder, PbEncoding};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+
+pub enum MaxSatResult {
+    Optimum {
+        cost: u64,
+        model: Model,
+    },
+    /// The search was stopped after finding a model, which is the best one so far
+    Stopped {
+        cost: u64,
+        model: Model,
+        reason: UnknownReason,
+    },
+    Unsat,
+    Unknown(UnknownReason),
+}
+
+/// Linear SAT-UNSAT search for weighted partial MaxSAT. Every soft clause C is relaxed to
+/// C ∨ b with a fresh variable b. After an initial solution, the weighted sum of the relaxation
+/// variables is encoded once as a generalized totalizer, clipped at the initial cost.
+/// Each improved solution then only requires adding unit clauses to the incremental solver
+/// forbidding all sums of at least the best cost so far.
+/// `on_improvement` is called with the cost of every improved solution. Once a SAT call is
+/// stopped by a limit or the terminate flag, the best solution so far is returned.
+pub fn solve_maxsat(
+    wcnf: &WcnfFile,
+    config: &Config,
+    mut on_improvement: impl FnMut(u64),
+) -> MaxSatResult {
+    // eliminating the relaxation or totalizer variables would make the bounds unsound
+    let mut config = config.clone();
+    config.inprocessing = false;
+
+    let mut num_vars = wcnf.num_vars;
+    let mut clauses = wcnf.hard_clauses.clone();
+    let mut relaxation_literals = vec![];
+    for (weight, clause) in wcnf.soft_clauses.iter().filter(|(w, _)| *w > 0) {
+        num_vars += 1;
+        let relaxation_literal = Literal::from_value(Var::new(num_vars), true);
+        let mut literals = clause.literals.clone();
+        literals.push(relaxation_literal);
+        clauses.push(Clause::from(literals));
+        relaxation_literals.push((*weight, relaxation_literal));
+    }
+
+    let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+    let mut best_model = match solver.solve() {
+        SolveResult::Sat(model) => model,
+        SolveResult::Unsat => return MaxSatResult::Unsat,
+        SolveResult::Unknown(reason) => return MaxSatResult::Unknown(reason),
+    };
+    let mut best_cost = cost(wcnf, &best_model);
+    on_improvement(best_cost);
+
+    if best_cost > 0 {
+        let mut encoder = PbEncoder::new(num_vars, PbEncoding::Totalizer);
+        let sum_outputs = encoder.totalizer(&relaxation_literals, best_cost);
+        clauses.extend(encoder.clauses);
+        let mut solver = Solver::new(clauses, encoder.next_var, config);
+
+        while best_cost > 0 {
+            for (_, output) in sum_outputs.range(best_cost..) {
+                solver.add_clause_at_level_zero(vec![-*output]);
+            }
+
+            let model = match solver.solve() {
+                SolveResult::Sat(model) => model,
+                SolveResult::Unsat => break,
+                SolveResult::Unknown(reason) => {
+                    best_model.truncate(wcnf.num_vars);
+                    return MaxSatResult::Stopped {
+                        cost: best_cost,
+                        model: best_model,
+                        reason,
+                    };
+                }
+            };
+            let model_cost = cost(wcnf, &model);
+            debug_assert!(model_cost < best_cost);
+            best_cost = model_cost;
+            best_model = model;
+            on_improvement(best_cost);
+        }
+    }
+
+    best_model.truncate(wcnf.num_vars);
+    MaxSatResult::Optimum {
+        cost: best_cost,
+        model: best_model,
+    }
+}
+
+/// Sum of the weights of all soft clauses falsified by the model
+pub fn cost(wcnf: &WcnfFile, model: &Model) -> u64 {
+    wcnf.soft_clauses
+        .iter()
+        .filter(|(_, clause)| !clause.literals.iter().any(|lit| model.value(*lit)))
+        .map(|(weight, _)| weight)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimacs::wcnf_from_string;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn optimum(wcnf: &str) -> Option<u64> {
+        let wcnf = wcnf_from_string(wcnf.to_string()).unwrap();
+        let config = Config::default();
+        match solve_maxsat(&wcnf, &config, |_| {}) {
+            MaxSatResult::Optimum { cost, model } => {
+                assert_eq!(super::cost(&wcnf, &model), cost);
+                Some(cost)
+            }
+            MaxSatResult::Unsat => None,
+            MaxSatResult::Stopped { .. } | MaxSatResult::Unknown(_) => {
+                unreachable!("the search has no limits")
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_partial_maxsat() {
+        let classic = "p wcnf 3 6 100\n100 1 2 0\n100 -1 -2 0\n5 1 0\n3 2 0\n4 -2 3 0\n2 -3 0\n";
+        assert_eq!(optimum(classic), Some(3));
+        let new_format = "h 1 2 0\nh -1 -2 0\n5 1 0\n3 2 0\n4 -2 3 0\n2 -3 0\n";
+        assert_eq!(optimum(new_format), Some(3));
+    }
+
+    #[test]
+    fn unsatisfiable_hard_clauses() {
+        assert_eq!(optimum("h 1 0\nh -1 0\n1 2 0\n"), None);
+        assert_eq!(optimum("h 1 0\n1 -1 0\n"), Some(1));
+    }
+
+    #[test]
+    fn stopped_by_the_shared_terminate_flag() {
+        let wcnf = wcnf_from_string("h 1 2 0\n5 -1 0\n3 -2 0\n".to_string()).unwrap();
+        let config = Config {
+            terminate: Some(Arc::new(AtomicBool::new(true))),
+            ..Default::default()
+        };
+        assert!(matches!(
+            solve_maxsat(&wcnf, &config, |_| {}),
+            MaxSatResult::Unknown(UnknownReason::Interrupted)
+        ));
+    }
+}
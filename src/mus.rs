@@ -0,0 +1,164 @@
+use crate::cnf::{Clause, Literal, SolveResult, UnknownReason, Var};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use itertools::Itertools;
+
+pub enum MusResult {
+    /// Indices of the clauses or groups of a minimal unsatisfiable subset, in ascending order
+    Mus(Vec<usize>),
+    Sat,
+    Unknown(UnknownReason),
+}
+
+/// Deletion-based MUS extraction as described in J. Marques-Silva, “Minimal Unsatisfiability:
+/// Models, Algorithms and Applications,” in 40th IEEE International Symposium on Multiple-Valued
+/// Logic, 2010, pp. 9–14. Every clause C is relaxed to C ∨ s with a fresh selector variable s,
+/// which is enabled by assuming ¬s. One candidate after the other is left out of the
+/// assumptions: if the formula stays unsatisfiable, the clause is removed for good by adding the
+/// unit s to the incremental solver, otherwise it is part of the MUS.
+pub fn minimal_unsatisfiable_subset(
+    clauses: &[Clause],
+    num_vars: usize,
+    config: &Config,
+) -> MusResult {
+    let groups = clauses
+        .iter()
+        .map(|clause| vec![clause.clone()])
+        .collect_vec();
+    minimal_unsatisfiable_group_subset(&[], &groups, num_vars, config)
+}
+
+/// Group MUS as defined in A. Liffiton and K. Sakallah, “Algorithms for Computing Minimal
+/// Unsatisfiable Subsets of Constraints,” Journal of Automated Reasoning, vol. 40, no. 1,
+/// pp. 1–33, 2008: the smallest unit that is left out is a whole group, which shares one
+/// selector among its clauses. The background clauses belong to every subset, so if they are
+/// unsatisfiable on their own, the MUS is empty.
+pub fn minimal_unsatisfiable_group_subset(
+    background: &[Clause],
+    groups: &[Vec<Clause>],
+    num_vars: usize,
+    config: &Config,
+) -> MusResult {
+    // selectors are assumed by later calls, so they must not be eliminated
+    let mut config = config.clone();
+    config.inprocessing = false;
+
+    let selector = |index: usize| Literal::from_value(Var::new(num_vars + index + 1), true);
+    let relaxed = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(index, group)| {
+            group.iter().map(move |clause| {
+                let mut literals = clause.literals.clone();
+                literals.push(selector(index));
+                Clause::from(literals)
+            })
+        })
+        .chain(background.iter().cloned())
+        .collect_vec();
+    let mut solver = Solver::new(relaxed, num_vars + groups.len(), config);
+
+    let mut candidates = (0..groups.len()).collect_vec();
+    let mut mus = vec![];
+    let all = candidates
+        .iter()
+        .map(|&index| -selector(index))
+        .collect_vec();
+    // groups whose selectors didn't fail aren't needed for the unsatisfiability, which removes
+    // most of the candidates at once
+    let refine = |solver: &mut Solver, candidates: &mut Vec<usize>| {
+        candidates.retain(|&index| {
+            let is_needed = solver.failed(-selector(index));
+            if !is_needed {
+                solver.add_clause_at_level_zero(vec![selector(index)]);
+            }
+            is_needed
+        });
+    };
+    // the selectors are in range and can't be eliminated
+    match solver.solve_with_assumptions(&all).unwrap() {
+        SolveResult::Sat(_) => return MusResult::Sat,
+        SolveResult::Unknown(reason) => return MusResult::Unknown(reason),
+        SolveResult::Unsat => refine(&mut solver, &mut candidates),
+    }
+
+    while let Some(index) = candidates.pop() {
+        let assumptions = mus
+            .iter()
+            .chain(&candidates)
+            .map(|&index| -selector(index))
+            .collect_vec();
+        match solver.solve_with_assumptions(&assumptions).unwrap() {
+            SolveResult::Sat(_) => mus.push(index),
+            SolveResult::Unsat => {
+                solver.add_clause_at_level_zero(vec![selector(index)]);
+                refine(&mut solver, &mut candidates);
+            }
+            SolveResult::Unknown(reason) => return MusResult::Unknown(reason),
+        }
+    }
+    mus.sort_unstable();
+    MusResult::Mus(mus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tester::{brute_force_sat, random_cnf};
+
+    #[test]
+    fn finds_the_minimal_subset() {
+        let clauses = ["1 2", "-1", "3 4", "-2", "-3 5", "2 -5"].map(Clause::from);
+        let MusResult::Mus(mus) = minimal_unsatisfiable_subset(&clauses, 5, &Config::default())
+        else {
+            panic!("the formula is unsatisfiable");
+        };
+        assert_eq!(mus, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn finds_the_minimal_group_subset() {
+        let background = [Clause::from("1 2")];
+        let groups = [
+            vec![Clause::from("-1"), Clause::from("3")],
+            vec![Clause::from("-3 4")],
+            vec![Clause::from("-2"), Clause::from("-4")],
+            vec![Clause::from("-1 -2")],
+        ];
+        let MusResult::Mus(mus) =
+            minimal_unsatisfiable_group_subset(&background, &groups, 4, &Config::default())
+        else {
+            panic!("the formula is unsatisfiable");
+        };
+        assert_eq!(mus, vec![0, 2]);
+
+        let unsat_background = [Clause::from("1"), Clause::from("-1")];
+        assert!(matches!(
+            minimal_unsatisfiable_group_subset(&unsat_background, &groups, 4, &Config::default()),
+            MusResult::Mus(mus) if mus.is_empty()
+        ));
+    }
+
+    #[test]
+    fn random_muses_are_minimal() {
+        for seed in 0..20 {
+            let clauses = random_cnf(10, 6.0, 3, seed);
+            let MusResult::Mus(mus) =
+                minimal_unsatisfiable_subset(&clauses, 10, &Config::default())
+            else {
+                assert!(brute_force_sat(&clauses, 10), "seed {}", seed);
+                continue;
+            };
+            let subset = |skip: Option<usize>| {
+                mus.iter()
+                    .filter(|&&index| Some(index) != skip)
+                    .map(|&index| clauses[index].clone())
+                    .collect_vec()
+            };
+            assert!(!brute_force_sat(&subset(None), 10), "seed {}", seed);
+            for &index in &mus {
+                assert!(brute_force_sat(&subset(Some(index)), 10), "seed {}", seed);
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::cnf::{Literal, Model, SolveResult, UnknownReason};
+use crate::pb::PbEncoder;
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use std::time::Instant;
+
+pub enum OptimizationResult {
+    /// The model minimizes the objective
+    Optimum {
+        value: i64,
+        model: Model,
+    },
+    /// The search was stopped after finding a model, which is the best one so far
+    Stopped {
+        value: i64,
+        model: Model,
+        reason: UnknownReason,
+    },
+    Unsat,
+    Unknown(UnknownReason),
+}
+
+/// Minimizes the linear objective Σ w_i l_i by model-improving linear search: after every
+/// model, the objective is forced below its value and the formula is solved again, until it
+/// becomes unsatisfiable. Negative weights are moved to the negated literals, so only sums of
+/// non-negative weights are bounded. Like in `solve_maxsat`, the sum is encoded once as a
+/// generalized totalizer, clipped at the value of the first model, and every improvement only
+/// adds unit clauses to the incremental solver. The clauses, auxiliary variables and native
+/// cardinality constraints of the formula are taken from the encoder. The time limit of the
+/// configuration applies to the whole search, and `on_improvement` is called with the value of
+/// every improved model.
+pub fn minimize(
+    mut encoder: PbEncoder,
+    objective: &[(i64, Literal)],
+    config: &Config,
+    mut on_improvement: impl FnMut(i64),
+) -> OptimizationResult {
+    // eliminating the objective or totalizer variables would make the bounds unsound
+    let mut config = config.clone();
+    config.inprocessing = false;
+    let start_time = Instant::now();
+    let num_vars = encoder.next_var;
+
+    let (offset, terms) = normalize(objective);
+    let new_solver = |encoder: &PbEncoder, config: Config| {
+        let mut solver = Solver::new(encoder.clauses.clone(), encoder.next_var, config);
+        for (literals, bound) in &encoder.cardinality_constraints {
+            solver.add_cardinality_constraint(literals, *bound);
+        }
+        solver
+    };
+    let mut solver = new_solver(&encoder, config.clone());
+    let mut best_model = match solver.solve() {
+        SolveResult::Sat(model) => model,
+        SolveResult::Unsat => return OptimizationResult::Unsat,
+        SolveResult::Unknown(reason) => return OptimizationResult::Unknown(reason),
+    };
+    let cost = |model: &Model| -> u64 {
+        terms
+            .iter()
+            .filter(|(_, lit)| model.value(*lit))
+            .map(|(weight, _)| weight)
+            .sum()
+    };
+    let mut best_cost = cost(&best_model);
+    on_improvement(offset + best_cost as i64);
+
+    if best_cost > 0 {
+        let sum_outputs = encoder.totalizer(&terms, best_cost);
+        let mut solver = new_solver(&encoder, config.clone());
+
+        while best_cost > 0 {
+            for (_, output) in sum_outputs.range(best_cost..) {
+                solver.add_clause_at_level_zero(vec![-*output]);
+            }
+            if let Some(limit) = config.time_limit {
+                solver.set_time_limit(Some(limit.saturating_sub(start_time.elapsed())));
+            }
+
+            let model = match solver.solve() {
+                SolveResult::Sat(model) => model,
+                SolveResult::Unsat => break,
+                SolveResult::Unknown(reason) => {
+                    best_model.truncate(num_vars);
+                    return OptimizationResult::Stopped {
+                        value: offset + best_cost as i64,
+                        model: best_model,
+                        reason,
+                    };
+                }
+            };
+            let model_cost = cost(&model);
+            debug_assert!(model_cost < best_cost);
+            best_cost = model_cost;
+            best_model = model;
+            on_improvement(offset + best_cost as i64);
+        }
+    }
+
+    best_model.truncate(num_vars);
+    OptimizationResult::Optimum {
+        value: offset + best_cost as i64,
+        model: best_model,
+    }
+}
+
+/// The constant and the terms with positive weights of the objective, as w l = w + |w| ¬l for
+/// negative weights w
+fn normalize(objective: &[(i64, Literal)]) -> (i64, Vec<(u64, Literal)>) {
+    let mut offset = 0;
+    let mut terms = vec![];
+    for &(weight, lit) in objective {
+        match weight {
+            0 => {}
+            weight if weight > 0 => terms.push((weight as u64, lit)),
+            weight => {
+                offset += weight;
+                terms.push((weight.unsigned_abs(), -lit));
+            }
+        }
+    }
+    (offset, terms)
+}
+
+/// Value of the objective for the model
+pub fn objective_value(objective: &[(i64, Literal)], model: &Model) -> i64 {
+    objective
+        .iter()
+        .filter(|(_, lit)| model.value(*lit))
+        .map(|(weight, _)| weight)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{Clause, Var};
+    use crate::pb::{opb_from_string, PbEncoding};
+    use crate::tester::random_cnf;
+
+    fn optimum(opb: &str, encoding: PbEncoding) -> Option<i64> {
+        let opb = opb_from_string(opb).unwrap();
+        let mut encoder = PbEncoder::new(opb.num_vars, encoding);
+        for constraint in &opb.constraints {
+            encoder.encode(constraint);
+        }
+        let objective = opb.objective.unwrap();
+        let mut values = vec![];
+        match minimize(encoder, &objective, &Config::default(), |value| {
+            values.push(value)
+        }) {
+            OptimizationResult::Optimum { value, model } => {
+                assert_eq!(objective_value(&objective, &model), value);
+                assert_eq!(values.last(), Some(&value));
+                assert!(values.is_sorted_by(|a, b| a > b));
+                Some(value)
+            }
+            OptimizationResult::Unsat => None,
+            _ => panic!("the search has no limits"),
+        }
+    }
+
+    #[test]
+    fn minimizes_pseudo_boolean_objectives() {
+        let opb = "min: +3 x1 +2 x2 +4 x3 -1 x4 ;\n+1 x1 +1 x2 +1 x3 >= 2 ;\n+1 x3 +1 x4 <= 1 ;\n";
+        for encoding in [PbEncoding::Auto, PbEncoding::Native] {
+            assert_eq!(optimum(opb, encoding), Some(4));
+        }
+        let maximize = "max: +1 x1 +1 x2 +1 x3 ;\n+1 x1 +1 x2 +1 x3 <= 2 ;\n";
+        assert_eq!(optimum(maximize, PbEncoding::Auto), Some(-2));
+        let unsat = "min: +1 x1 ;\n+1 x1 >= 1 ;\n+1 ~x1 >= 1 ;\n";
+        assert_eq!(optimum(unsat, PbEncoding::Auto), None);
+    }
+
+    #[test]
+    fn random_optima_match_brute_force() {
+        for seed in 0..10 {
+            let num_vars = 10;
+            let clauses = random_cnf(num_vars, 3.0, 3, seed);
+            let objective = Var::range(num_vars)
+                .map(|var| {
+                    let weight = (var.index() as i64 * 7 + seed as i64) % 9 - 4;
+                    (weight, Literal::from_value(var, true))
+                })
+                .collect::<Vec<_>>();
+            let brute_force = (0..1 << num_vars)
+                .map(|bits: usize| Model::new((0..num_vars).map(|i| bits >> i & 1 == 1).collect()))
+                .filter(|model| {
+                    clauses
+                        .iter()
+                        .all(|clause: &Clause| clause.literals.iter().any(|lit| model.value(*lit)))
+                })
+                .map(|model| objective_value(&objective, &model))
+                .min();
+
+            let mut encoder = PbEncoder::new(num_vars, PbEncoding::Totalizer);
+            encoder.clauses = clauses;
+            let value = match minimize(encoder, &objective, &Config::default(), |_| {}) {
+                OptimizationResult::Optimum { value, .. } => Some(value),
+                OptimizationResult::Unsat => None,
+                _ => panic!("the search has no limits"),
+            };
+            assert_eq!(value, brute_force, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,592 @@
+use crate::cnf::{Clause, Literal, Model, Var};
+use crate::dimacs::read_file;
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparator {
+    AtMost,
+    AtLeast,
+    Equal,
+}
+
+/// Linear pseudo-Boolean constraint Σ w_i l_i ⋈ bound
+#[derive(Debug, Clone, PartialEq)]
+pub struct PbConstraint {
+    pub terms: Vec<(i64, Literal)>,
+    pub comparator: Comparator,
+    pub bound: i64,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum, Eq, PartialEq)]
+pub enum PbEncoding {
+    /// Totalizer for cardinality constraints and the adder network otherwise
+    #[clap(name = "auto")]
+    Auto,
+    #[clap(name = "totalizer")]
+    Totalizer,
+    #[clap(name = "sequential-counter")]
+    SequentialCounter,
+    #[clap(name = "adder")]
+    Adder,
+    /// Cardinality constraints are propagated natively by the solver, others use the adder
+    #[clap(name = "native")]
+    Native,
+}
+
+pub struct OpbFile {
+    pub num_vars: usize,
+    pub constraints: Vec<PbConstraint>,
+    /// Linear objective to be minimized
+    pub objective: Option<Vec<(i64, Literal)>>,
+}
+
+impl PbConstraint {
+    pub fn new(terms: Vec<(i64, Literal)>, comparator: Comparator, bound: i64) -> Self {
+        PbConstraint {
+            terms,
+            comparator,
+            bound,
+        }
+    }
+
+    pub fn at_most(literals: &[Literal], k: usize) -> Self {
+        Self::cardinality(literals, Comparator::AtMost, k)
+    }
+
+    pub fn at_least(literals: &[Literal], k: usize) -> Self {
+        Self::cardinality(literals, Comparator::AtLeast, k)
+    }
+
+    pub fn exactly(literals: &[Literal], k: usize) -> Self {
+        Self::cardinality(literals, Comparator::Equal, k)
+    }
+
+    fn cardinality(literals: &[Literal], comparator: Comparator, k: usize) -> Self {
+        let terms = literals.iter().map(|lit| (1, *lit)).collect();
+        PbConstraint::new(terms, comparator, k as i64)
+    }
+
+    pub fn is_cardinality(&self) -> bool {
+        self.terms.iter().all(|(weight, _)| weight.abs() == 1)
+    }
+
+    pub fn is_satisfied(&self, model: &Model) -> bool {
+        let sum: i64 = self
+            .terms
+            .iter()
+            .filter(|(_, lit)| model.value(*lit))
+            .map(|(weight, _)| weight)
+            .sum();
+        match self.comparator {
+            Comparator::AtMost => sum <= self.bound,
+            Comparator::AtLeast => sum >= self.bound,
+            Comparator::Equal => sum == self.bound,
+        }
+    }
+
+    /// Equivalent constraints of the form Σ w_i l_i ≤ k with positive weights. Negative weights
+    /// are removed using w·l = w + |w|·¬l.
+    fn normalized(&self) -> Vec<(Vec<(u64, Literal)>, i64)> {
+        let at_most = |terms: &[(i64, Literal)], mut bound: i64| {
+            let mut normalized = vec![];
+            for (weight, lit) in terms {
+                if *weight < 0 {
+                    bound -= weight;
+                    normalized.push((weight.unsigned_abs(), -*lit));
+                } else if *weight > 0 {
+                    normalized.push((*weight as u64, *lit));
+                }
+            }
+            (normalized, bound)
+        };
+        let negated = self.terms.iter().map(|(w, lit)| (-w, *lit)).collect_vec();
+
+        match self.comparator {
+            Comparator::AtMost => vec![at_most(&self.terms, self.bound)],
+            Comparator::AtLeast => vec![at_most(&negated, -self.bound)],
+            Comparator::Equal => vec![
+                at_most(&self.terms, self.bound),
+                at_most(&negated, -self.bound),
+            ],
+        }
+    }
+}
+
+/// Translates pseudo-Boolean constraints into clauses, introducing auxiliary variables
+/// after `next_var`.
+pub struct PbEncoder {
+    pub next_var: usize,
+    pub clauses: Vec<Clause>,
+    /// At-most-k constraints left to the solver with the native encoding
+    pub cardinality_constraints: Vec<(Vec<Literal>, usize)>,
+    encoding: PbEncoding,
+}
+
+impl PbEncoder {
+    pub fn new(num_vars: usize, encoding: PbEncoding) -> Self {
+        PbEncoder {
+            next_var: num_vars,
+            clauses: vec![],
+            cardinality_constraints: vec![],
+            encoding,
+        }
+    }
+
+    pub fn encode(&mut self, constraint: &PbConstraint) {
+        let encoding = match self.encoding {
+            PbEncoding::Auto if constraint.is_cardinality() => PbEncoding::Totalizer,
+            PbEncoding::Auto => PbEncoding::Adder,
+            encoding => encoding,
+        };
+
+        for (terms, bound) in constraint.normalized() {
+            self.encode_at_most(terms, bound, encoding);
+        }
+    }
+
+    fn encode_at_most(&mut self, terms: Vec<(u64, Literal)>, bound: i64, encoding: PbEncoding) {
+        if bound < 0 {
+            self.add_clause(vec![]);
+            return;
+        }
+        let bound = bound as u64;
+
+        // literals heavier than the bound can never be true
+        let (too_heavy, terms): (Vec<_>, Vec<_>) =
+            terms.into_iter().partition(|(weight, _)| *weight > bound);
+        for (_, lit) in too_heavy {
+            self.add_clause(vec![-lit]);
+        }
+        if terms.iter().map(|(weight, _)| weight).sum::<u64>() <= bound {
+            return;
+        }
+
+        match encoding {
+            PbEncoding::Totalizer | PbEncoding::Auto => {
+                let outputs = self.totalizer(&terms, bound + 1);
+                self.add_clause(vec![-outputs[&(bound + 1)]]);
+            }
+            PbEncoding::SequentialCounter => self.sequential_counter(&terms, bound),
+            PbEncoding::Adder => self.adder(&terms, bound),
+            PbEncoding::Native => {
+                let literals = terms.iter().map(|(_, lit)| *lit).collect_vec();
+                let is_cardinality = terms.iter().all(|(weight, _)| *weight == 1)
+                    && literals.iter().map(|lit| lit.var()).all_unique();
+                if is_cardinality {
+                    self.cardinality_constraints
+                        .push((literals, bound as usize));
+                } else {
+                    self.adder(&terms, bound);
+                }
+            }
+        }
+    }
+
+    /// Generalized totalizer as described in S. Joshi, R. Martins, and V. Manquinho,
+    /// “Generalized Totalizer Encoding for Pseudo-Boolean Constraints,” in Principles and
+    /// Practice of Constraint Programming, 2015, pp. 200–209. Returns an output literal for every
+    /// reachable sum, which is implied by the inputs. All sums of at least `bound` share one
+    /// output. Only the implications from inputs to outputs are encoded, suited for upper bounds.
+    pub fn totalizer(&mut self, inputs: &[(u64, Literal)], bound: u64) -> BTreeMap<u64, Literal> {
+        if inputs.len() == 1 {
+            let (weight, literal) = inputs[0];
+            return BTreeMap::from([(weight.min(bound), literal)]);
+        }
+        if inputs.is_empty() {
+            return BTreeMap::new();
+        }
+
+        let (left, right) = inputs.split_at(inputs.len() / 2);
+        let left = self.totalizer(left, bound);
+        let right = self.totalizer(right, bound);
+
+        let mut outputs = BTreeMap::new();
+        let left_values = left.iter().map(|(v, l)| (*v, Some(*l))).chain([(0, None)]);
+        let right_values = right.iter().map(|(v, l)| (*v, Some(*l))).chain([(0, None)]);
+        for ((left_value, left_literal), (right_value, right_literal)) in
+            left_values.cartesian_product(right_values.collect_vec())
+        {
+            let value = (left_value + right_value).min(bound);
+            if value == 0 {
+                continue;
+            }
+            let output = match outputs.get(&value) {
+                Some(output) => *output,
+                None => {
+                    let output = self.new_literal();
+                    outputs.insert(value, output);
+                    output
+                }
+            };
+
+            let mut clause = vec![output];
+            clause.extend(left_literal.map(|lit| -lit));
+            clause.extend(right_literal.map(|lit| -lit));
+            self.add_clause(clause);
+        }
+        outputs
+    }
+
+    /// Sequential weight counter as described in S. Hölldobler, N. Manthey, and P. Steinke,
+    /// “A Compact Encoding of Pseudo-Boolean Constraints into SAT,” in KI 2012, pp. 107–118.
+    /// For unit weights, this is the sequential counter by C. Sinz (CP 2005).
+    /// The auxiliary variable s_i_j is true if the sum of the first i terms is at least j.
+    fn sequential_counter(&mut self, terms: &[(u64, Literal)], bound: u64) {
+        let k = bound as usize;
+        let mut previous: Vec<Literal> = vec![];
+
+        for (i, (weight, x)) in terms.iter().enumerate() {
+            let weight = *weight as usize;
+            if !previous.is_empty() {
+                // overflow: the sum would exceed the bound
+                self.add_clause(vec![-previous[k - weight], -*x]);
+            }
+            if i == terms.len() - 1 {
+                break;
+            }
+
+            let current = (0..k).map(|_| self.new_literal()).collect_vec();
+            for j in 1..=k {
+                if !previous.is_empty() {
+                    self.add_clause(vec![-previous[j - 1], current[j - 1]]);
+                    if j + weight <= k {
+                        self.add_clause(vec![-previous[j - 1], -*x, current[j + weight - 1]]);
+                    }
+                }
+                if j <= weight {
+                    self.add_clause(vec![-*x, current[j - 1]]);
+                }
+            }
+            previous = current;
+        }
+    }
+
+    /// Adder network as described in N. Eén and N. Sörensson, “Translating Pseudo-Boolean
+    /// Constraints into SAT,” JSAT, vol. 2, pp. 1–26, 2006. The literals are summed up bitwise
+    /// with full and half adders and the resulting binary number is compared to the bound.
+    fn adder(&mut self, terms: &[(u64, Literal)], bound: u64) {
+        let mut buckets: Vec<Vec<Literal>> = vec![];
+        for (weight, lit) in terms {
+            for bit in 0..64 {
+                if weight >> bit & 1 == 1 {
+                    if buckets.len() <= bit {
+                        buckets.resize(bit + 1, vec![]);
+                    }
+                    buckets[bit].push(*lit);
+                }
+            }
+        }
+
+        let mut sum_bits: Vec<Option<Literal>> = vec![];
+        let mut bit = 0;
+        while bit < buckets.len() {
+            while buckets[bit].len() >= 2 {
+                let inputs = if buckets[bit].len() >= 3 {
+                    buckets[bit].drain(..3).collect_vec()
+                } else {
+                    buckets[bit].drain(..2).collect_vec()
+                };
+                let (sum, carry) = self.adder_cell(&inputs);
+                buckets[bit].push(sum);
+                if buckets.len() <= bit + 1 {
+                    buckets.push(vec![]);
+                }
+                buckets[bit + 1].push(carry);
+            }
+            sum_bits.push(buckets[bit].pop());
+            bit += 1;
+        }
+
+        // The sum is larger than the bound iff at the highest differing bit i, the sum is 1
+        // and the bound is 0. Forbid this for every i where the bound is 0.
+        let bound_bit = |i: usize| i < 64 && bound >> i & 1 == 1;
+        for (i, sum_bit) in sum_bits.iter().enumerate() {
+            let Some(sum_bit) = sum_bit else { continue };
+            if bound_bit(i) {
+                continue;
+            }
+
+            let higher_bits = (i + 1..sum_bits.len())
+                .filter(|j| bound_bit(*j))
+                .collect_vec();
+            // a higher bit which is set in the bound but constant 0 in the sum
+            if higher_bits.iter().any(|j| sum_bits[*j].is_none()) {
+                continue;
+            }
+            let mut clause = vec![-*sum_bit];
+            clause.extend(higher_bits.iter().map(|j| -sum_bits[*j].unwrap()));
+            self.add_clause(clause);
+        }
+    }
+
+    /// Full adder for three and half adder for two inputs, returning (sum, carry)
+    fn adder_cell(&mut self, inputs: &[Literal]) -> (Literal, Literal) {
+        let sum = self.new_literal();
+        let carry = self.new_literal();
+
+        // sum ⇔ x ⊕ y (⊕ z), one clause for every assignment of the inputs
+        for assignment in 0..(1 << inputs.len()) {
+            let mut clause = vec![];
+            let mut parity = false;
+            for (i, input) in inputs.iter().enumerate() {
+                let value = assignment >> i & 1 == 1;
+                parity ^= value;
+                clause.push(if value { -*input } else { *input });
+            }
+            clause.push(if parity { sum } else { -sum });
+            self.add_clause(clause);
+        }
+
+        // carry ⇔ at least two inputs are true
+        for pair in inputs.iter().combinations(2) {
+            self.add_clause(vec![-*pair[0], -*pair[1], carry]);
+        }
+        if inputs.len() == 3 {
+            for pair in inputs.iter().combinations(2) {
+                self.add_clause(vec![*pair[0], *pair[1], -carry]);
+            }
+        } else {
+            self.add_clause(vec![inputs[0], -carry]);
+            self.add_clause(vec![inputs[1], -carry]);
+        }
+
+        (sum, carry)
+    }
+
+    fn new_literal(&mut self) -> Literal {
+        self.next_var += 1;
+        Literal::from_value(Var::new(self.next_var), true)
+    }
+
+    /// Adds a normalized clause, skipping tautologies
+    fn add_clause(&mut self, mut literals: Vec<Literal>) {
+        literals.sort();
+        literals.dedup();
+        if literals.iter().any(|lit| literals.contains(&-*lit)) {
+            return;
+        }
+        self.clauses.push(Clause::from(literals));
+    }
+}
+
+pub fn opb_from_file(path: &str) -> Result<OpbFile> {
+    opb_from_string(&read_file(path)?)
+}
+
+/// Parses linear OPB files as used in the Pseudo-Boolean Competitions, e.g.
+/// `min: +1 x1 +2 x2 ;` and `+1 x1 -3 ~x2 >= -1 ;`. Lines starting with `*` are comments.
+pub fn opb_from_string(input: &str) -> Result<OpbFile> {
+    let mut num_vars = 0;
+    let mut constraints = vec![];
+    let mut objective = None;
+
+    let statements = input
+        .lines()
+        .filter(|line| !line.starts_with('*'))
+        .join(" ");
+
+    for statement in statements.split(';').map(str::trim) {
+        if statement.is_empty() {
+            continue;
+        }
+        let tokens = statement.split_whitespace().collect_vec();
+
+        if tokens[0] == "min:" || tokens[0] == "max:" {
+            let mut terms = parse_opb_terms(&tokens[1..])?;
+            if tokens[0] == "max:" {
+                terms.iter_mut().for_each(|(weight, _)| *weight = -*weight);
+            }
+            objective = Some(terms);
+            continue;
+        }
+
+        let comparator_position = tokens
+            .iter()
+            .position(|token| [">=", "<=", "="].contains(token))
+            .ok_or(Error::Parse(format!(
+                "Missing comparator in \"{}\"",
+                statement
+            )))?;
+        let comparator = match tokens[comparator_position] {
+            ">=" => Comparator::AtLeast,
+            "<=" => Comparator::AtMost,
+            _ => Comparator::Equal,
+        };
+        let bound = tokens
+            .get(comparator_position + 1)
+            .ok_or(Error::Parse(format!("Missing bound in \"{}\"", statement)))?;
+        let bound = bound
+            .parse::<i64>()
+            .map_err(|_| Error::Parse(format!("Invalid bound \"{}\"", bound)))?;
+        let terms = parse_opb_terms(&tokens[..comparator_position])?;
+        constraints.push(PbConstraint::new(terms, comparator, bound));
+    }
+
+    for (_, lit) in constraints
+        .iter()
+        .flat_map(|constraint| constraint.terms.iter())
+        .chain(objective.iter().flatten())
+    {
+        num_vars = num_vars.max(lit.var().index());
+    }
+
+    Ok(OpbFile {
+        num_vars,
+        constraints,
+        objective,
+    })
+}
+
+fn parse_opb_terms(tokens: &[&str]) -> Result<Vec<(i64, Literal)>> {
+    if !tokens.len().is_multiple_of(2) {
+        return Err(Error::Parse(format!(
+            "Non-linear or malformed terms \"{}\" are not supported",
+            tokens.join(" ")
+        )));
+    }
+
+    tokens
+        .chunks(2)
+        .map(|term| {
+            let weight = term[0]
+                .parse::<i64>()
+                .map_err(|_| Error::Parse(format!("Invalid coefficient \"{}\"", term[0])))?;
+            let (positive, name) = match term[1].strip_prefix('~') {
+                Some(name) => (false, name),
+                None => (true, term[1]),
+            };
+            let var_id = name
+                .strip_prefix('x')
+                .and_then(|id| id.parse::<usize>().ok())
+                .filter(|id| *id > 0)
+                .ok_or(Error::Parse(format!("Invalid variable \"{}\"", term[1])))?;
+            Ok((weight, Literal::from_value(Var::new(var_id), positive)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn count_models(constraints: &[PbConstraint], num_vars: usize, encoding: PbEncoding) -> u128 {
+        let mut encoder = PbEncoder::new(num_vars, encoding);
+        constraints
+            .iter()
+            .for_each(|constraint| encoder.encode(constraint));
+        let config = Config::default();
+        let mut solver = Solver::new(encoder.clauses, encoder.next_var, config);
+        for (literals, bound) in &encoder.cardinality_constraints {
+            solver.add_cardinality_constraint(literals, *bound);
+        }
+        let projection = Var::range(num_vars).collect_vec();
+        solver
+            .count_projected_models(&projection)
+            .unwrap()
+            .to_u128()
+            .unwrap()
+    }
+
+    fn brute_force(constraints: &[PbConstraint], num_vars: usize) -> u128 {
+        (0..1 << num_vars)
+            .filter(|bits: &usize| {
+                let model = Model::new((0..num_vars).map(|i| bits >> i & 1 == 1).collect());
+                constraints
+                    .iter()
+                    .all(|constraint| constraint.is_satisfied(&model))
+            })
+            .count() as u128
+    }
+
+    fn random_literal(rng: &mut StdRng, num_vars: usize) -> Literal {
+        Literal::from_value(Var::new(rng.gen_range(1..=num_vars)), rng.gen())
+    }
+
+    #[test]
+    fn encodings_preserve_models() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let num_vars = 6;
+        for _ in 0..30 {
+            let terms = (0..rng.gen_range(1..6))
+                .map(|_| (rng.gen_range(-5..=5), random_literal(&mut rng, num_vars)))
+                .collect();
+            let comparator =
+                [Comparator::AtMost, Comparator::AtLeast, Comparator::Equal][rng.gen_range(0..3)];
+            let constraint = [PbConstraint::new(terms, comparator, rng.gen_range(-3..=8))];
+
+            let expected = brute_force(&constraint, num_vars);
+            for encoding in [
+                PbEncoding::Totalizer,
+                PbEncoding::SequentialCounter,
+                PbEncoding::Adder,
+                PbEncoding::Native,
+            ] {
+                assert_eq!(
+                    count_models(&constraint, num_vars, encoding),
+                    expected,
+                    "{:?} with {:?}",
+                    constraint,
+                    encoding
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn native_cardinality_constraints() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let num_vars = 10;
+        for _ in 0..50 {
+            let mut constraints = (0..rng.gen_range(0..8))
+                .map(|_| {
+                    let clause = (0..3)
+                        .map(|_| random_literal(&mut rng, num_vars))
+                        .collect_vec();
+                    PbConstraint::at_least(&clause, 1)
+                })
+                .collect_vec();
+            for _ in 0..rng.gen_range(1..4) {
+                let literals = Var::range(num_vars)
+                    .filter_map(|var_id| {
+                        let lit = Literal::from_value(var_id, rng.gen());
+                        rng.gen_bool(0.6).then_some(lit)
+                    })
+                    .collect_vec();
+                let k = rng.gen_range(0..=literals.len());
+                constraints.push(match rng.gen_range(0..3) {
+                    0 => PbConstraint::at_most(&literals, k),
+                    1 => PbConstraint::at_least(&literals, k),
+                    _ => PbConstraint::exactly(&literals, k),
+                });
+            }
+
+            assert_eq!(
+                count_models(&constraints, num_vars, PbEncoding::Native),
+                brute_force(&constraints, num_vars),
+                "{:?}",
+                constraints
+            );
+        }
+    }
+
+    #[test]
+    fn parse_opb() {
+        let opb = opb_from_string(
+            "* #variable= 3 #constraint= 2\nmin: +1 x1 +2 x3 ;\n+1 x1 +1 ~x2 >= 1 ;\n-2 x1 +3 x3 = 1 ;\n",
+        )
+        .unwrap();
+        assert_eq!(opb.num_vars, 3);
+        assert_eq!(opb.constraints.len(), 2);
+        assert_eq!(opb.constraints[0].terms[1], (1, Literal::new(-2)));
+        assert_eq!(opb.constraints[1].comparator, Comparator::Equal);
+        assert_eq!(opb.objective.unwrap().len(), 2);
+        assert!(opb_from_string("+1 x1 x2 >= 1 ;").is_err());
+    }
+}
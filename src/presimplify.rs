@@ -0,0 +1,108 @@
+use crate::cnf::Clause;
+use alloc::{vec, vec::Vec};
+use hashbrown::HashSet;
+use itertools::Itertools;
+
+/// Subset checks after which the subsumption stops, so large inputs are only simplified as far
+/// as it's cheap
+const MAX_SUBSUMPTION_CHECKS: usize = 50_000_000;
+
+/// Clauses removed by `presimplify`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresimplifyStats {
+    pub num_duplicates: usize,
+    pub num_subsumed: usize,
+}
+
+/// Removes duplicate clauses, regardless of the order of their literals, and clauses subsumed by
+/// another clause, before the solver is created. The remaining clauses keep their order, and
+/// the formula has the same models. Every kept clause is indexed by one of its literals, so
+/// each clause only has to be compared to the clauses indexed by its own literals.
+pub fn presimplify(clauses: &mut Vec<Clause>, num_vars: usize) -> PresimplifyStats {
+    let mut stats = PresimplifyStats::default();
+    let normalized = clauses
+        .iter()
+        .map(|clause| {
+            clause
+                .literals
+                .iter()
+                .copied()
+                .sorted()
+                .dedup()
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let mut removed = vec![false; clauses.len()];
+    let mut seen = HashSet::new();
+    for (index, literals) in normalized.iter().enumerate() {
+        if !seen.insert(literals.as_slice()) {
+            removed[index] = true;
+            stats.num_duplicates += 1;
+        }
+    }
+
+    let mut occurrences = vec![0usize; 2 * (num_vars + 1)];
+    for literals in normalized.iter() {
+        for lit in literals {
+            occurrences[lit.code()] += 1;
+        }
+    }
+    // subsuming clauses are at most as long as the subsumed ones, so they are indexed first
+    let mut index: Vec<Vec<usize>> = vec![vec![]; 2 * (num_vars + 1)];
+    let mut marked = vec![false; 2 * (num_vars + 1)];
+    let mut num_checks = 0;
+    let by_length = (0..clauses.len())
+        .filter(|&clause| !removed[clause])
+        .sorted_by_key(|&clause| normalized[clause].len());
+    for clause in by_length {
+        let literals = &normalized[clause];
+        let Some(&watch) = literals.iter().min_by_key(|lit| occurrences[lit.code()]) else {
+            continue;
+        };
+        if num_checks < MAX_SUBSUMPTION_CHECKS {
+            literals.iter().for_each(|lit| marked[lit.code()] = true);
+            let is_subsumed = literals.iter().any(|lit| {
+                index[lit.code()].iter().any(|&other| {
+                    num_checks += 1;
+                    normalized[other].iter().all(|lit| marked[lit.code()])
+                })
+            });
+            literals.iter().for_each(|lit| marked[lit.code()] = false);
+            if is_subsumed {
+                removed[clause] = true;
+                stats.num_subsumed += 1;
+                continue;
+            }
+        }
+        index[watch.code()].push(clause);
+    }
+
+    let mut removed = removed.into_iter();
+    clauses.retain(|_| !removed.next().unwrap());
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_and_subsumed_clauses() {
+        let mut clauses = ["1 2 3", "-1 2", "2 -1", "2 3 -1 4", "3 2 1", "-3", "1 -2"]
+            .map(Clause::from)
+            .to_vec();
+        let stats = presimplify(&mut clauses, 4);
+        assert_eq!(
+            stats,
+            PresimplifyStats {
+                num_duplicates: 2,
+                num_subsumed: 1
+            }
+        );
+        assert_eq!(
+            clauses,
+            ["1 2 3", "-1 2", "-3", "1 -2"].map(Clause::from).to_vec()
+        );
+    }
+}
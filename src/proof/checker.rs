@@ -0,0 +1,445 @@
+use crate::cnf::{Clause, Literal, PartialAssignment, Var};
+use crate::dimacs::open_file;
+use crate::error::{Error, Result};
+use crate::solver::proof_logger::ProofStep;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckResult {
+    Verified,
+    /// The lemma in the given line of the proof is neither RUP nor RAT
+    NotImplied {
+        line: usize,
+        lemma: Vec<Literal>,
+    },
+    /// All lemmas are implied, but unit propagation on them does not yield a conflict
+    NoConflict,
+}
+
+/// Parses a proof in the textual DRAT format, as written by the solver. Every step is returned
+/// with its line number.
+pub fn proof_from_file(path: &str) -> Result<Vec<(usize, ProofStep)>> {
+    proof_from_reader(open_file(path)?)
+}
+
+pub fn proof_from_reader(reader: impl BufRead) -> Result<Vec<(usize, ProofStep)>> {
+    let mut steps = vec![];
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = line_number + 1;
+        let mut tokens = line.split_whitespace().peekable();
+        let deletion = tokens.next_if_eq(&"d").is_some();
+        if tokens.peek().is_none_or(|token| token.starts_with('c')) {
+            continue;
+        }
+
+        let syntax_error = |message: String| Error::Syntax {
+            line: line_number,
+            message,
+        };
+        let literals = tokens
+            .map(|token| {
+                token
+                    .parse::<i32>()
+                    .map_err(|_| syntax_error(format!("Invalid literal \"{}\"", token)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let Some((0, literals)) = literals.split_last() else {
+            return Err(syntax_error("Lemma does not end with 0".to_string()));
+        };
+        if literals.contains(&0) {
+            return Err(syntax_error("Lemma contains 0".to_string()));
+        }
+
+        let clause = Clause::from(literals.iter().map(|lit| Literal::new(*lit)).collect_vec());
+        steps.push((
+            line_number,
+            match deletion {
+                true => ProofStep::DeleteClause(clause),
+                false => ProofStep::AddClause(clause),
+            },
+        ));
+    }
+    Ok(steps)
+}
+
+/// Forward checking of a DRAT proof, see N. Wetzler, M. J. H. Heule, and W. A. Hunt,
+/// “DRAT-trim: Efficient Checking and Trimming Using Expressive Clausal Proofs,” in Theory and
+/// Applications of Satisfiability Testing – SAT 2014, pp. 422–429.
+/// Every lemma has to be a reverse unit propagation (RUP) of the clauses and lemmas before it,
+/// or a resolution asymmetric tautology (RAT) on its first literal. The proof is valid once unit
+/// propagation yields a conflict. As in DRAT-trim, deletions of unit clauses are ignored.
+pub fn check_proof(
+    clauses: &[Clause],
+    num_vars: usize,
+    proof: &[(usize, ProofStep)],
+) -> CheckResult {
+    Checker::new(clauses, num_vars, proof, false).check(proof)
+}
+
+/// Indices of the clauses that a valid proof depends on, which form an unsatisfiable core.
+/// The clauses used by the unit propagations of every lemma are recorded during checking, the
+/// core contains the original ones reachable from the final conflict.
+pub fn core_from_proof(
+    clauses: &[Clause],
+    num_vars: usize,
+    proof: &[(usize, ProofStep)],
+) -> Option<Vec<usize>> {
+    let mut checker = Checker::new(clauses, num_vars, proof, true);
+    match checker.check(proof) {
+        CheckResult::Verified => Some(checker.core()),
+        _ => None,
+    }
+}
+
+/// Unit propagation with two watched literals, the first two literals of every clause. Unit
+/// clauses are not watched, their literals are assigned for the rest of the proof.
+struct Checker {
+    clauses: Vec<Vec<Literal>>,
+    num_original_clauses: usize,
+    active: Vec<bool>,
+    /// Clauses by their sorted literals to find the deleted ones
+    clause_ids: HashMap<Vec<Literal>, Vec<usize>>,
+    watches: Vec<Vec<usize>>,
+    vars: PartialAssignment,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Literal>,
+    num_propagated: usize,
+    /// Unit propagation on the clauses without assumptions yields a conflict
+    inconsistent: bool,
+    track_dependencies: bool,
+    /// Clauses used to derive every lemma, empty for the original clauses
+    dependencies: Vec<Vec<usize>>,
+    /// Clauses used to derive the final conflict
+    conflict_antecedents: Vec<usize>,
+}
+
+impl Checker {
+    fn new(
+        clauses: &[Clause],
+        num_vars: usize,
+        proof: &[(usize, ProofStep)],
+        track_dependencies: bool,
+    ) -> Self {
+        let max_var = proof
+            .iter()
+            .flat_map(|(_, step)| match step {
+                ProofStep::AddClause(clause) | ProofStep::DeleteClause(clause) => &clause.literals,
+            })
+            .map(|lit| lit.var().index())
+            .max()
+            .unwrap_or(0)
+            .max(num_vars);
+        let mut checker = Checker {
+            clauses: vec![],
+            num_original_clauses: clauses.len(),
+            active: vec![],
+            clause_ids: HashMap::new(),
+            watches: vec![vec![]; 2 * (max_var + 1)],
+            vars: PartialAssignment::new(max_var),
+            reasons: vec![None; max_var + 1],
+            trail: vec![],
+            num_propagated: 0,
+            inconsistent: false,
+            track_dependencies,
+            dependencies: vec![],
+            conflict_antecedents: vec![],
+        };
+        for clause in clauses {
+            checker.add_clause(&clause.literals, vec![]);
+        }
+        checker
+    }
+
+    fn check(&mut self, proof: &[(usize, ProofStep)]) -> CheckResult {
+        for (line, step) in proof {
+            if self.inconsistent {
+                break;
+            }
+            match step {
+                ProofStep::AddClause(clause) => {
+                    let literals = &clause.literals;
+                    let Some(dependencies) = self.rup(literals).or_else(|| self.rat(literals))
+                    else {
+                        return CheckResult::NotImplied {
+                            line: *line,
+                            lemma: literals.clone(),
+                        };
+                    };
+                    self.add_clause(literals, dependencies);
+                }
+                ProofStep::DeleteClause(clause) => self.delete_clause(&clause.literals),
+            }
+        }
+
+        match self.inconsistent {
+            true => CheckResult::Verified,
+            false => CheckResult::NoConflict,
+        }
+    }
+
+    fn add_clause(&mut self, literals: &[Literal], dependencies: Vec<usize>) {
+        let mut literals = literals.iter().copied().unique().collect_vec();
+        let id = self.clauses.len();
+        self.clause_ids
+            .entry(literals.iter().copied().sorted().collect())
+            .or_default()
+            .push(id);
+        if self.track_dependencies {
+            self.dependencies.push(dependencies);
+        }
+
+        // watch non-false literals if possible, the true ones first
+        literals.sort_by_key(|lit| match lit.value(&self.vars) {
+            Some(true) => 0,
+            None => 1,
+            Some(false) => 2,
+        });
+        if literals.len() >= 2 {
+            self.watches[literals[0].code()].push(id);
+            self.watches[literals[1].code()].push(id);
+        }
+        let first = literals.first().copied();
+        let is_unit = literals.get(1).is_none_or(|lit| lit.is_false(&self.vars));
+        self.clauses.push(literals);
+        self.active.push(true);
+
+        let conflict = match first {
+            None => {
+                self.inconsistent = true;
+                self.conflict_antecedents = vec![id];
+                return;
+            }
+            Some(first) if first.is_false(&self.vars) => Some(id),
+            Some(first) if first.is_free(&self.vars) && is_unit => {
+                self.assign(first, Some(id));
+                self.propagate()
+            }
+            _ => None,
+        };
+        if let Some(conflict) = conflict {
+            self.inconsistent = true;
+            self.conflict_antecedents = self.antecedents(Some(conflict), vec![]);
+        }
+    }
+
+    fn delete_clause(&mut self, literals: &[Literal]) {
+        let key = literals.iter().copied().unique().sorted().collect_vec();
+        if key.len() < 2 {
+            return;
+        }
+        if let Some(id) = self.clause_ids.get_mut(&key).and_then(|ids| ids.pop()) {
+            self.active[id] = false;
+        }
+    }
+
+    fn assign(&mut self, lit: Literal, reason: Option<usize>) {
+        self.vars.assign(lit);
+        self.reasons[lit.var()] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Returns the conflicting clause
+    fn propagate(&mut self) -> Option<usize> {
+        while self.num_propagated < self.trail.len() {
+            let false_lit = -self.trail[self.num_propagated];
+            self.num_propagated += 1;
+
+            let index = false_lit.code();
+            let mut watches = std::mem::take(&mut self.watches[index]);
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watches.len() {
+                let id = watches[i];
+                if !self.active[id] {
+                    watches.swap_remove(i);
+                    continue;
+                }
+                let clause = &mut self.clauses[id];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if clause[0].is_true(&self.vars) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) = (2..clause.len()).find(|k| clause[*k].non_false(&self.vars)) {
+                    clause.swap(1, k);
+                    self.watches[clause[1].code()].push(id);
+                    watches.swap_remove(i);
+                    continue;
+                }
+
+                i += 1;
+                let unit = clause[0];
+                if unit.is_false(&self.vars) {
+                    conflict = Some(id);
+                    break;
+                }
+                self.assign(unit, Some(id));
+            }
+            self.watches[index] = watches;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn backtrack(&mut self, trail_length: usize) {
+        for lit in self.trail.drain(trail_length..) {
+            self.vars.unassign(lit.var());
+        }
+        self.num_propagated = trail_length;
+    }
+
+    /// Clauses of the implication graph of the conflict and the given variables, if the
+    /// dependencies are tracked
+    fn antecedents(&self, conflict: Option<usize>, mut vars: Vec<Var>) -> Vec<usize> {
+        if !self.track_dependencies {
+            return vec![];
+        }
+        let mut antecedents = conflict.into_iter().collect_vec();
+        vars.extend(
+            conflict
+                .iter()
+                .flat_map(|id| self.clauses[*id].iter().map(|lit| lit.var())),
+        );
+        let mut seen = HashSet::new();
+        while let Some(var_id) = vars.pop() {
+            if !seen.insert(var_id) {
+                continue;
+            }
+            if let Some(reason) = self.reasons[var_id] {
+                antecedents.push(reason);
+                vars.extend(self.clauses[reason].iter().map(|lit| lit.var()));
+            }
+        }
+        antecedents
+    }
+
+    /// Returns the clauses used if unit propagation on the negated lemma yields a conflict
+    fn rup(&mut self, lemma: &[Literal]) -> Option<Vec<usize>> {
+        let trail_length = self.trail.len();
+        let mut antecedents = None;
+        for lit in lemma {
+            match lit.value(&self.vars) {
+                Some(true) => {
+                    antecedents = Some(self.antecedents(None, vec![lit.var()]));
+                    break;
+                }
+                Some(false) => {}
+                None => self.assign(-*lit, None),
+            }
+        }
+        if antecedents.is_none() {
+            antecedents = self
+                .propagate()
+                .map(|conflict| self.antecedents(Some(conflict), vec![]));
+        }
+        self.backtrack(trail_length);
+        antecedents
+    }
+
+    /// Returns the clauses used if all resolvents on the first literal of the lemma are RUP
+    fn rat(&mut self, lemma: &[Literal]) -> Option<Vec<usize>> {
+        let pivot = *lemma.first()?;
+        let candidates = (0..self.clauses.len())
+            .filter(|id| self.active[*id] && self.clauses[*id].contains(&-pivot))
+            .collect_vec();
+        let mut antecedents = candidates.clone();
+        for id in candidates {
+            let resolvent = lemma
+                .iter()
+                .chain(self.clauses[id].iter().filter(|lit| **lit != -pivot))
+                .copied()
+                .collect_vec();
+            antecedents.extend(self.rup(&resolvent)?);
+        }
+        Some(antecedents)
+    }
+
+    /// Original clauses the final conflict depends on
+    fn core(&self) -> Vec<usize> {
+        let mut seen = vec![false; self.clauses.len()];
+        let mut stack = self.conflict_antecedents.clone();
+        while let Some(id) = stack.pop() {
+            if !std::mem::replace(&mut seen[id], true) {
+                stack.extend_from_slice(&self.dependencies[id]);
+            }
+        }
+        (0..self.num_original_clauses)
+            .filter(|id| seen[*id])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimacs::clauses_from_dimacs;
+
+    fn check(cnf: &str, proof: &str) -> CheckResult {
+        let cnf = clauses_from_dimacs(cnf.to_string()).unwrap();
+        let proof = proof_from_reader(proof.as_bytes()).unwrap();
+        check_proof(&cnf.clauses, cnf.num_vars, &proof)
+    }
+
+    const UNSAT: &str = "p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n";
+
+    #[test]
+    fn rup_proofs() {
+        assert_eq!(check(UNSAT, "2 0\nd 1 2 0\n0\n"), CheckResult::Verified);
+        // the empty clause is not needed if propagation already fails
+        assert_eq!(check(UNSAT, "2 0\n"), CheckResult::Verified);
+        assert_eq!(check(UNSAT, "c comment\n"), CheckResult::NoConflict);
+        assert_eq!(
+            check(UNSAT, "d -1 2 0\nd 1 2 0\n2 0\n"),
+            CheckResult::NotImplied {
+                line: 3,
+                lemma: vec![Literal::new(2)]
+            }
+        );
+    }
+
+    #[test]
+    fn rat_proofs() {
+        // 3 is a fresh variable, so 3 ∨ 1 is RAT on 3
+        let cnf = "p cnf 3 2\n-1 2 0\n-1 -2 0\n";
+        assert_eq!(check(cnf, "3 1 0\n"), CheckResult::NoConflict);
+        assert_eq!(
+            check(cnf, "1 3 0\n"),
+            CheckResult::NotImplied {
+                line: 1,
+                lemma: vec![Literal::new(1), Literal::new(3)]
+            }
+        );
+    }
+
+    #[test]
+    fn unsat_core() {
+        // the last two clauses are not needed
+        let cnf = clauses_from_dimacs(format!("{}3 4 0\n-3 0\n", UNSAT)).unwrap();
+        let proof = proof_from_reader("2 0\n0\n".as_bytes()).unwrap();
+        assert_eq!(
+            core_from_proof(&cnf.clauses, cnf.num_vars, &proof),
+            Some(vec![0, 1, 2, 3])
+        );
+        let cnf = clauses_from_dimacs("p cnf 2 3\n1 0\n-1 2 0\n-2 0\n".to_string()).unwrap();
+        assert_eq!(
+            core_from_proof(&cnf.clauses, cnf.num_vars, &[]),
+            Some(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(matches!(
+            proof_from_reader("1 2 0\n1 x 0\n".as_bytes()),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+        assert!(proof_from_reader("1 2\n".as_bytes()).is_err());
+    }
+}
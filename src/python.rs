@@ -0,0 +1,208 @@
+// the code generated by #[pymethods] converts PyErr into itself
+#![allow(clippy::useless_conversion)]
+
+use crate::cnf::{Clause, Literal, SolveResult};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+fn literal(lit: i32) -> PyResult<Literal> {
+    match lit {
+        0 => Err(PyValueError::new_err("0 is not a literal")),
+        i32::MIN => Err(PyValueError::new_err(format!("{} is not a literal", lit))),
+        _ => Ok(Literal::new(lit)),
+    }
+}
+
+fn parse_clause(literals: Vec<i32>) -> PyResult<Clause> {
+    let literals = literals
+        .into_iter()
+        .map(literal)
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(Clause::from(literals))
+}
+
+/// Incremental solver with the interface of the solvers of python-sat:
+///
+/// ```python
+/// from utopia import Solver
+///
+/// with Solver(bootstrap_with=[[-1, 2], [-2, 3]]) as solver:
+///     solver.add_clause([1])
+///     assert not solver.solve(assumptions=[-3])
+///     assert solver.get_core() == [-3]
+///     assert solver.solve()
+///     print(solver.get_model())
+/// ```
+///
+/// Inprocessing is disabled, as clauses added later and assumptions may use any variable.
+#[pyclass(name = "Solver", unsendable)]
+struct PySolver {
+    clauses: Vec<Clause>,
+    num_vars: usize,
+    /// Created on the first call of `solve` and recreated after new variables were used
+    solver: Option<Solver>,
+    /// Number of clauses already added to the solver
+    num_added_clauses: usize,
+    status: Option<bool>,
+    model: Option<Vec<i32>>,
+    /// Failed assumptions of the last call of `solve`
+    core: Option<Vec<i32>>,
+}
+
+impl PySolver {
+    fn config() -> Config {
+        Config {
+            inprocessing: false,
+            ..Default::default()
+        }
+    }
+
+    fn use_vars(&mut self, literals: &[Literal]) {
+        let max = literals.iter().map(|lit| lit.var().index()).max();
+        if let Some(max) = max.filter(|&max| max > self.num_vars) {
+            self.num_vars = max;
+            self.solver = None;
+        }
+    }
+
+    /// Adds the pending clauses to the solver, or creates a new one with all clauses
+    fn synchronize(&mut self) -> &mut Solver {
+        match &mut self.solver {
+            Some(solver) => {
+                for clause in &self.clauses[self.num_added_clauses..] {
+                    solver.add_clause_at_level_zero(clause.literals.clone());
+                }
+            }
+            None => {
+                let solver = Solver::new(self.clauses.clone(), self.num_vars, Self::config());
+                self.solver = Some(solver);
+            }
+        }
+        self.num_added_clauses = self.clauses.len();
+        self.solver.as_mut().unwrap()
+    }
+}
+
+#[pymethods]
+impl PySolver {
+    #[new]
+    #[pyo3(signature = (bootstrap_with=None))]
+    fn new(bootstrap_with: Option<Vec<Vec<i32>>>) -> PyResult<Self> {
+        let mut solver = PySolver {
+            clauses: vec![],
+            num_vars: 0,
+            solver: None,
+            num_added_clauses: 0,
+            status: None,
+            model: None,
+            core: None,
+        };
+        if let Some(formula) = bootstrap_with {
+            solver.append_formula(formula)?;
+        }
+        Ok(solver)
+    }
+
+    fn add_clause(&mut self, clause: Vec<i32>) -> PyResult<()> {
+        let clause = parse_clause(clause)?;
+        self.use_vars(&clause.literals);
+        self.clauses.push(clause);
+        Ok(())
+    }
+
+    fn append_formula(&mut self, formula: Vec<Vec<i32>>) -> PyResult<()> {
+        formula
+            .into_iter()
+            .try_for_each(|clause| self.add_clause(clause))
+    }
+
+    /// Whether the formula is satisfiable under the assumptions
+    #[pyo3(signature = (assumptions=vec![]))]
+    fn solve(&mut self, assumptions: Vec<i32>) -> PyResult<bool> {
+        let assumptions = assumptions
+            .into_iter()
+            .map(literal)
+            .collect::<PyResult<Vec<_>>>()?;
+        self.use_vars(&assumptions);
+        let result = self
+            .synchronize()
+            .solve_with_assumptions(&assumptions)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        self.model = match &result {
+            SolveResult::Sat(model) => Some(model.iter().map(i32::from).collect()),
+            _ => None,
+        };
+        self.core = result.is_unsat().then(|| {
+            let solver = self.solver.as_ref().unwrap();
+            assumptions
+                .iter()
+                .filter(|lit| solver.failed(**lit))
+                .map(|lit| i32::from(*lit))
+                .collect()
+        });
+        self.status = Some(result.is_sat());
+        Ok(result.is_sat())
+    }
+
+    /// The result of the last call of `solve`
+    fn get_status(&self) -> Option<bool> {
+        self.status
+    }
+
+    /// The model found by the last call of `solve` as a list of literals, if it was satisfiable
+    fn get_model(&self) -> Option<Vec<i32>> {
+        self.model.clone()
+    }
+
+    /// The assumptions the last call of `solve` found inconsistent with the formula, if it was
+    /// unsatisfiable
+    fn get_core(&self) -> Option<Vec<i32>> {
+        self.core.clone()
+    }
+
+    fn nof_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    fn nof_clauses(&self) -> usize {
+        self.clauses.len()
+    }
+
+    /// Statistics of the search, accumulated over all calls of `solve` since the last time new
+    /// variables were used
+    fn accum_stats(&self) -> HashMap<&'static str, usize> {
+        let Some(solver) = &self.solver else {
+            return HashMap::new();
+        };
+        let stats = solver.stats();
+        HashMap::from([
+            ("restarts", stats.num_restarts),
+            ("conflicts", stats.num_conflicts),
+            ("decisions", stats.num_decisions),
+            ("propagations", stats.num_propagations),
+        ])
+    }
+
+    fn delete(&mut self) {
+        self.solver = None;
+        self.num_added_clauses = 0;
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, pyo3::types::PyTuple>) {
+        self.delete();
+    }
+}
+
+/// Python bindings mirroring the interface of python-sat
+#[pymodule]
+fn utopia(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySolver>()
+}
@@ -0,0 +1,207 @@
+use crate::cnf::{Clause, Literal, Model, SolveResult, Var};
+use crate::dimacs::DimacsFile;
+use crate::error::{Error, Result};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    Exists,
+    Forall,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QbfResult {
+    pub is_true: bool,
+    /// Assignment of the outermost block showing the result, i.e. a witness if it is
+    /// existential and the formula is true or a counterexample if it is universal and the
+    /// formula is false
+    pub certificate: Option<Vec<Literal>>,
+}
+
+/// Solves QBFs with at most two quantifier blocks, where free variables are existentially
+/// quantified in the outermost block.
+/// ∃X ∀Y φ is decided by a single SAT call after universal reduction, which removes the
+/// universal literals from all clauses. ∀X ∃Y φ is solved by counterexample-guided abstraction
+/// refinement as described in M. Janota and J. Marques-Silva, “Abstraction-Based Algorithm for
+/// 2QBF,” in Theory and Applications of Satisfiability Testing - SAT 2011, pp. 230–244.
+/// Candidates x for X are taken from an abstraction, which is checked by solving φ(x, Y).
+/// Every solution y refines the abstraction by ¬φ(X, y), as x has to falsify φ with y as well.
+/// If a SAT call is stopped by a limit or the terminate flag, it fails with `Error::Stopped`.
+pub fn solve_2qbf(qbf: &DimacsFile, config: &Config) -> Result<QbfResult> {
+    let quantified = qbf
+        .quantifiers
+        .iter()
+        .flat_map(|(_, vars)| vars.iter().copied())
+        .collect::<HashSet<_>>();
+    let free_vars = qbf
+        .clauses
+        .iter()
+        .flat_map(|clause| clause.literals.iter().map(|lit| lit.var()))
+        .filter(|var_id| !quantified.contains(var_id))
+        .unique()
+        .collect_vec();
+
+    let mut blocks = qbf.quantifiers.clone();
+    if !free_vars.is_empty() {
+        blocks.insert(0, (Quantifier::Exists, free_vars));
+    }
+    blocks.retain(|(_, vars)| !vars.is_empty());
+    blocks.dedup_by(|inner, outer| {
+        let merge = inner.0 == outer.0;
+        if merge {
+            outer.1.append(&mut inner.1);
+        }
+        merge
+    });
+
+    let mut config = config.clone();
+    config.proof_file = None;
+    match blocks.as_slice() {
+        [] | [(Quantifier::Exists, _)] | [(Quantifier::Exists, _), (Quantifier::Forall, _)] => {
+            let outer = blocks.first().map_or(vec![], |(_, vars)| vars.clone());
+            solve_exists_forall(qbf, &outer, config)
+        }
+        [(Quantifier::Forall, outer)] => solve_forall_exists(qbf, outer, config),
+        [(Quantifier::Forall, outer), (Quantifier::Exists, _)] => {
+            solve_forall_exists(qbf, outer, config)
+        }
+        _ => Err(Error::Unsupported(format!(
+            "Only QBFs with two quantifier blocks are supported, got {}",
+            blocks.len()
+        ))),
+    }
+}
+
+fn solve_exists_forall(qbf: &DimacsFile, outer: &[Var], config: Config) -> Result<QbfResult> {
+    let outer_vars = outer.iter().copied().collect::<HashSet<_>>();
+    let reduced_clauses = qbf
+        .clauses
+        .iter()
+        .map(|clause| {
+            Clause::from(
+                clause
+                    .literals
+                    .iter()
+                    .filter(|lit| outer_vars.contains(&lit.var()))
+                    .copied()
+                    .collect_vec(),
+            )
+        })
+        .collect_vec();
+
+    let model = solve(reduced_clauses, qbf.num_vars, config)?;
+    Ok(QbfResult {
+        is_true: model.is_some(),
+        certificate: model.map(|model| assignment(&model, outer)),
+    })
+}
+
+fn solve_forall_exists(qbf: &DimacsFile, outer: &[Var], config: Config) -> Result<QbfResult> {
+    let mut abstraction: Vec<Clause> = vec![];
+    let mut num_vars = qbf.num_vars;
+
+    loop {
+        let Some(candidate) = solve(abstraction.clone(), num_vars, config.clone())? else {
+            return Ok(QbfResult {
+                is_true: true,
+                certificate: None,
+            });
+        };
+        let x = assignment(&candidate, outer);
+
+        let mut clauses = qbf.clauses.clone();
+        clauses.extend(x.iter().map(|lit| Clause::from(vec![*lit])));
+        let Some(y) = solve(clauses, qbf.num_vars, config.clone())? else {
+            return Ok(QbfResult {
+                is_true: false,
+                certificate: Some(x),
+            });
+        };
+
+        // ¬φ(X, y): one of the clauses not satisfied by y has all its X literals false
+        let mut refinement = vec![];
+        for clause in &qbf.clauses {
+            let satisfied_by_y = clause
+                .literals
+                .iter()
+                .any(|lit| !outer.contains(&lit.var()) && y.value(*lit));
+            if satisfied_by_y {
+                continue;
+            }
+            num_vars += 1;
+            let falsified = Literal::from_value(Var::new(num_vars), true);
+            refinement.push(falsified);
+            for lit in clause
+                .literals
+                .iter()
+                .filter(|lit| outer.contains(&lit.var()))
+            {
+                abstraction.push(Clause::from(vec![-falsified, -*lit]));
+            }
+        }
+        refinement.sort();
+        abstraction.push(Clause::from(refinement));
+    }
+}
+
+/// A model of the clauses, or none if they are unsatisfiable
+fn solve(clauses: Vec<Clause>, num_vars: usize, config: Config) -> Result<Option<Model>> {
+    match Solver::new(clauses, num_vars, config).solve() {
+        SolveResult::Sat(model) => Ok(Some(model)),
+        SolveResult::Unsat => Ok(None),
+        SolveResult::Unknown(reason) => Err(Error::Stopped(reason)),
+    }
+}
+
+fn assignment(model: &Model, vars: &[Var]) -> Vec<Literal> {
+    vars.iter()
+        .map(|var_id| Literal::from_value(*var_id, model[*var_id]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimacs::clauses_from_dimacs;
+
+    fn solve(qdimacs: &str) -> QbfResult {
+        let config = Config::default();
+        let qbf = clauses_from_dimacs(qdimacs.to_string()).unwrap();
+        solve_2qbf(&qbf, &config).unwrap()
+    }
+
+    #[test]
+    fn forall_exists() {
+        // ∀x ∃y x ↔ y
+        let result = solve("p cnf 2 2\na 1 0\ne 2 0\n-1 2 0\n1 -2 0\n");
+        assert!(result.is_true);
+        // ∀x1 x2 ∃y (x1 ∨ y) ∧ (x2 ∨ ¬y) is false for x1 = x2 = false
+        let result = solve("p cnf 3 2\na 1 2 0\ne 3 0\n1 3 0\n2 -3 0\n");
+        assert_eq!(
+            result,
+            QbfResult {
+                is_true: false,
+                certificate: Some(vec![Literal::new(-1), Literal::new(-2)])
+            }
+        );
+    }
+
+    #[test]
+    fn exists_forall() {
+        // ∃x ∀y (x ∨ y) ∧ (x ∨ ¬y)
+        let result = solve("p cnf 2 2\ne 1 0\na 2 0\n1 2 0\n1 -2 0\n");
+        assert_eq!(result.certificate, Some(vec![Literal::new(1)]));
+        // ∃x ∀y x ↔ y
+        assert!(!solve("p cnf 2 2\ne 1 0\na 2 0\n-1 2 0\n1 -2 0\n").is_true);
+        // the free variable 3 is existential, ∃z ∀x ∃y is unsupported
+        let config = Config::default();
+        let qbf = clauses_from_dimacs("p cnf 3 1\na 1 0\ne 2 0\n1 2 3 0\n".to_string()).unwrap();
+        assert!(matches!(
+            solve_2qbf(&qbf, &config),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::cnf::UnknownReason;
+use crate::solver::clock::Instant;
+use crate::solver::config::Config;
+use crate::solver::statistics::resident_memory_kib;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Reading the memory usage is comparatively expensive, so it is only checked every this many
+/// conflicts
+const MEMORY_CHECK_INTERVAL: usize = 1000;
+
+/// Decisions and restarts between two checks of the clock and the memory usage, which are also
+/// checked without conflicts
+const DECISION_CHECK_INTERVAL: usize = 1000;
+
+/// Time, conflict and memory limits of a single call to `Solver::solve`, which returns
+/// `SolveResult::Unknown` once one of them is exceeded or the terminate flag is set. The limits
+/// are checked after conflicts and, as the search may go on for long without conflicts, the
+/// terminate flag before every decision and restart and the time and memory limits before every
+/// `DECISION_CHECK_INTERVAL`-th one.
+pub struct Budget {
+    terminate: Arc<AtomicBool>,
+    decisions_since_check: usize,
+    start_time: Instant,
+    start_conflicts: usize,
+    time_limit: Option<core::time::Duration>,
+    conflict_limit: Option<usize>,
+    memory_limit_kib: Option<usize>,
+}
+
+impl Budget {
+    pub fn start(config: &Config, num_conflicts: usize, terminate: Arc<AtomicBool>) -> Self {
+        Budget {
+            terminate,
+            decisions_since_check: 0,
+            start_time: Instant::now(),
+            start_conflicts: num_conflicts,
+            time_limit: config.time_limit,
+            conflict_limit: config.conflict_limit,
+            memory_limit_kib: config.memory_limit.map(|mib| mib * 1024),
+        }
+    }
+
+    pub fn exceeded(&self, num_conflicts: usize) -> Option<UnknownReason> {
+        if self.terminate.load(Ordering::Relaxed) {
+            return Some(UnknownReason::Interrupted);
+        }
+        let conflicts = num_conflicts - self.start_conflicts;
+        if self.conflict_limit.is_some_and(|limit| conflicts >= limit) {
+            return Some(UnknownReason::ConflictLimit);
+        }
+        if self.time_exceeded() {
+            return Some(UnknownReason::TimeLimit);
+        }
+        if conflicts.is_multiple_of(MEMORY_CHECK_INTERVAL) && self.memory_exceeded() {
+            return Some(UnknownReason::MemoryLimit);
+        }
+        None
+    }
+
+    /// Checked before decisions and restarts
+    pub fn exceeded_without_conflict(&mut self) -> Option<UnknownReason> {
+        if self.terminate.load(Ordering::Relaxed) {
+            return Some(UnknownReason::Interrupted);
+        }
+        self.decisions_since_check += 1;
+        if self.decisions_since_check < DECISION_CHECK_INTERVAL {
+            return None;
+        }
+        self.decisions_since_check = 0;
+        if self.time_exceeded() {
+            return Some(UnknownReason::TimeLimit);
+        }
+        if self.memory_exceeded() {
+            return Some(UnknownReason::MemoryLimit);
+        }
+        None
+    }
+
+    fn time_exceeded(&self) -> bool {
+        self.time_limit
+            .is_some_and(|limit| self.start_time.elapsed() >= limit)
+    }
+
+    fn memory_exceeded(&self) -> bool {
+        self.memory_limit_kib
+            .is_some_and(|limit| resident_memory_kib().is_some_and(|memory| memory > limit))
+    }
+}
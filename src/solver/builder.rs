@@ -0,0 +1,153 @@
+use crate::cnf::{Clause, Literal, Var};
+use crate::error::{Error, Result};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use alloc::vec::Vec;
+use itertools::Itertools;
+
+/// Builds a solver from clauses in the DIMACS convention, where variables are numbered from 1
+/// and negative numbers denote negated literals.
+/// ```
+/// use utopia::cnf::Var;
+/// use utopia::solver::builder::SolverBuilder;
+///
+/// let mut solver = SolverBuilder::new()
+///     .add_clause(&[1, -2])
+///     .add_clause(&[2])
+///     .build()
+///     .unwrap();
+/// assert!(solver.solve().model().unwrap()[Var::new(1)]);
+/// ```
+#[derive(Default)]
+pub struct SolverBuilder {
+    clauses: Vec<Clause>,
+    /// Largest variable of all clauses, including the dropped tautologies
+    max_var: usize,
+    num_vars: Option<usize>,
+    config: Config,
+    /// first invalid input, reported by `build`
+    error: Option<Error>,
+}
+
+impl SolverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a clause, which is normalized by removing duplicate literals. Tautologies are
+    /// dropped.
+    pub fn add_clause(mut self, literals: &[i32]) -> Self {
+        if let Some(invalid) = literals.iter().find(|lit| matches!(**lit, 0 | i32::MIN)) {
+            self.error.get_or_insert(Error::InvalidLiteral(*invalid));
+            return self;
+        }
+        let literals = literals
+            .iter()
+            .map(|lit| Literal::new(*lit))
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let max_var = literals.iter().map(|lit| lit.var().index()).max();
+        self.max_var = self.max_var.max(max_var.unwrap_or(0));
+        if literals.iter().any(|lit| literals.contains(&-*lit)) {
+            return self;
+        }
+        self.clauses.push(Clause::from(literals));
+        self
+    }
+
+    pub fn add_clauses<'a>(self, clauses: impl IntoIterator<Item = &'a [i32]>) -> Self {
+        clauses
+            .into_iter()
+            .fold(self, |builder, clause| builder.add_clause(clause))
+    }
+
+    /// Defaults to the largest variable in the clauses. Variables that occur in no clause are
+    /// part of the model nonetheless.
+    pub fn num_vars(mut self, num_vars: usize) -> Self {
+        self.num_vars = Some(num_vars);
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn build(self) -> Result<Solver> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let num_vars = self.num_vars.unwrap_or(self.max_var);
+        if self.max_var > num_vars {
+            return Err(Error::VariableOutOfRange {
+                var_id: Var::new(self.max_var),
+                num_vars,
+            });
+        }
+
+        Ok(Solver::new(self.clauses, num_vars, self.config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_and_solve() {
+        let clauses: [&[i32]; 4] = [&[1, 2, 2], &[-1, 2], &[-2, 3], &[-3, 1, 3]];
+        let mut solver = SolverBuilder::new()
+            .config(Config::default())
+            .add_clauses(clauses)
+            .num_vars(4)
+            .build()
+            .unwrap();
+        let model = solver.solve().into_model().unwrap();
+        assert!(model[Var::new(2)] && model[Var::new(3)]);
+
+        let mut solver = SolverBuilder::new()
+            .config(Config::default())
+            .add_clause(&[1])
+            .add_clause(&[-1])
+            .build()
+            .unwrap();
+        assert!(solver.solve().is_unsat());
+    }
+
+    #[test]
+    fn trivial_formulas() {
+        let model = |builder: SolverBuilder| {
+            let mut solver = builder.config(Config::default()).build().unwrap();
+            solver.solve().into_model().map(|model| model.num_vars())
+        };
+        assert_eq!(model(SolverBuilder::new()), Some(0));
+        // unused variables are part of the model
+        assert_eq!(model(SolverBuilder::new().num_vars(3)), Some(3));
+        assert_eq!(model(SolverBuilder::new().add_clause(&[2, -2])), Some(2));
+        assert_eq!(model(SolverBuilder::new().add_clause(&[])), None);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(matches!(
+            SolverBuilder::new().add_clause(&[1, 0]).build(),
+            Err(Error::InvalidLiteral(0))
+        ));
+        assert!(matches!(
+            SolverBuilder::new().add_clause(&[i32::MIN]).build(),
+            Err(Error::InvalidLiteral(i32::MIN))
+        ));
+        assert!(matches!(
+            SolverBuilder::new()
+                .add_clause(&[1, -3])
+                .num_vars(2)
+                .build(),
+            Err(Error::VariableOutOfRange {
+                var_id,
+                num_vars: 2
+            }) if var_id == Var::new(3)
+        ));
+    }
+}
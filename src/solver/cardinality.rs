@@ -0,0 +1,155 @@
+use crate::cnf::{Literal, PartialAssignment};
+use crate::solver::trail::{ConstraintRef, Trail};
+use alloc::{vec, vec::Vec};
+
+pub type CardinalityId = usize;
+
+/// At most `bound` of the literals may be true. The literals have distinct variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardinalityConstraint {
+    pub literals: Vec<Literal>,
+    pub bound: usize,
+}
+
+/// Native propagation of at-most-k constraints. A constraint over n literals is equivalent to
+/// at least n - k of them being false. Generalizing the two watched literals of clauses, the
+/// first n - k + 1 literals are watched and kept non-true. Once a watched literal becomes true
+/// and no non-true literal can replace it, the k - 1 unwatched literals are true as well and all
+/// other watched literals have to be false.
+#[derive(Debug, Clone)]
+pub struct CardinalityPropagator {
+    pub constraints: Vec<CardinalityConstraint>,
+    /// constraints watching a literal, indexed by `Literal::code`
+    watches: Vec<Vec<CardinalityId>>,
+}
+
+impl CardinalityPropagator {
+    pub fn new(num_vars: usize) -> Self {
+        CardinalityPropagator {
+            constraints: vec![],
+            watches: vec![vec![]; 2 * (num_vars + 1)],
+        }
+    }
+
+    /// Adds a constraint over unassigned literals with 0 < bound < number of literals
+    pub fn add_constraint(&mut self, constraint: CardinalityConstraint) -> CardinalityId {
+        debug_assert!(0 < constraint.bound && constraint.bound < constraint.literals.len());
+        let id = self.constraints.len();
+        let num_watches = constraint.literals.len() - constraint.bound + 1;
+        for lit in &constraint.literals[..num_watches] {
+            self.watches_mut(*lit).push(id);
+        }
+        self.constraints.push(constraint);
+        id
+    }
+
+    /// Adds a constraint with bound 0, whose literals are all false. It is only used as the
+    /// reason of these assignments and doesn't need to be watched.
+    pub fn add_unit_constraint(&mut self, literals: Vec<Literal>) -> CardinalityId {
+        self.constraints
+            .push(CardinalityConstraint { literals, bound: 0 });
+        self.constraints.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Updates the watches of all constraints watching the literal that just became true,
+    /// collecting the forced literals in `units`. Returns a constraint with more than k true literals, if any.
+    pub fn propagate(
+        &mut self,
+        literal: Literal,
+        vars: &PartialAssignment,
+        units: &mut Vec<(Literal, ConstraintRef)>,
+    ) -> Option<CardinalityId> {
+        let watching = core::mem::take(self.watches_mut(literal));
+        let mut remaining = Vec::with_capacity(watching.len());
+        let mut conflict = None;
+
+        for (i, id) in watching.iter().copied().enumerate() {
+            if conflict.is_some() {
+                remaining.extend_from_slice(&watching[i..]);
+                break;
+            }
+
+            let constraint = &mut self.constraints[id];
+            let num_watches = constraint.literals.len() - constraint.bound + 1;
+            let position = constraint.literals[..num_watches]
+                .iter()
+                .position(|lit| *lit == literal)
+                .unwrap();
+
+            let replacement = (num_watches..constraint.literals.len())
+                .find(|j| !constraint.literals[*j].is_true(vars));
+            if let Some(replacement) = replacement {
+                constraint.literals.swap(position, replacement);
+                let new_watch = constraint.literals[position];
+                self.watches_mut(new_watch).push(id);
+                continue;
+            }
+
+            remaining.push(id);
+            for lit in &constraint.literals[..num_watches] {
+                if *lit == literal {
+                    continue;
+                }
+                if lit.is_true(vars) {
+                    conflict = Some(id);
+                    break;
+                }
+                if lit.is_free(vars) {
+                    units.push((-*lit, ConstraintRef::Cardinality(id)));
+                }
+            }
+        }
+
+        *self.watches_mut(literal) = remaining;
+        conflict
+    }
+
+    /// Clause-like reason for an assignment forced by the constraint: the implied literal
+    /// together with the negations of the literals that were true before it. For conflicts,
+    /// `implied` is `None` and all true literals are used.
+    pub fn explain(
+        &self,
+        id: CardinalityId,
+        implied: Option<Literal>,
+        trail: &Trail,
+    ) -> Vec<Literal> {
+        let limit = implied.map_or(usize::MAX, |lit| trail.var_assignment_pos[lit.var()]);
+        let is_true_before = |lit: &Literal| {
+            let pos = trail.var_assignment_pos[lit.var()];
+            pos < limit
+                && pos < trail.assignment_stack.len()
+                && trail.assignment_stack[pos].literal == *lit
+        };
+
+        let mut reason = implied.into_iter().collect::<Vec<_>>();
+        reason.extend(
+            self.constraints[id]
+                .literals
+                .iter()
+                .filter(|lit| is_true_before(lit))
+                .map(|lit| -*lit),
+        );
+        reason
+    }
+
+    /// A constraint is satisfied by every extension of the assignment, if at most k of its
+    /// literals are non-false.
+    pub fn all_satisfied(&self, vars: &PartialAssignment) -> bool {
+        self.constraints.iter().all(|constraint| {
+            constraint
+                .literals
+                .iter()
+                .filter(|lit| lit.non_false(vars))
+                .count()
+                <= constraint.bound
+        })
+    }
+
+    fn watches_mut(&mut self, lit: Literal) -> &mut Vec<CardinalityId> {
+        &mut self.watches[lit.code()]
+    }
+}
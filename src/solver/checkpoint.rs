@@ -0,0 +1,42 @@
+use crate::cnf::{Clause, Literal};
+#[cfg(feature = "std")]
+use crate::error::{Error, Result};
+use crate::solver::statistics::StateStatistics;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Solver state at decision level 0 to continue a long search later, written by
+/// `Solver::checkpoint` and restored by `Solver::resume`. Clauses are stored instead of the
+/// database layout, so the restored database is compact. Statistics continue from the saved
+/// values, except for the time which starts again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub num_vars: usize,
+    /// Clauses of the formula before any simplification, to validate models
+    pub original_clauses: Vec<Clause>,
+    /// Irredundant clauses after inprocessing
+    pub clauses: Vec<Clause>,
+    pub learned_clauses: Vec<Clause>,
+    pub fixed_literals: Vec<Literal>,
+    /// Witnesses and clauses of the reconstruction stack in the order of removal
+    pub removed_clauses: Vec<(Literal, Clause)>,
+    /// Saved phases of the variables 0..=num_vars
+    pub phases: Vec<bool>,
+    /// Scores of the decision heuristic, if it has any
+    pub scores: Option<Vec<f64>>,
+    pub stats: StateStatistics,
+}
+
+#[cfg(feature = "std")]
+impl Checkpoint {
+    pub fn write(&self, path: &str) -> Result<()> {
+        let bytes = bincode::serialize(self).map_err(|err| Error::Checkpoint(err.to_string()))?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn read(path: &str) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        bincode::deserialize(&bytes).map_err(|err| Error::Checkpoint(err.to_string()))
+    }
+}
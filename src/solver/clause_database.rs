@@ -1,24 +1,89 @@
-use crate::cnf::{Clause, ClauseId};
+use crate::cnf::{Clause, ClauseId, Literal, PartialAssignment, Var};
 use crate::solver::literal_watching::LiteralWatcher;
+use crate::solver::params::Params;
 use crate::solver::proof_logger::ProofLogger;
-use crate::solver::trail::{AssignmentReason, Trail};
+use crate::solver::trail::{ConstraintRef, Trail};
+use alloc::{vec, vec::Vec};
+use core::cmp::max;
+use core::fmt::{Debug, Formatter};
+use core::ops::Index;
+use core::ops::IndexMut;
+use hashbrown::HashSet;
 use itertools::Itertools;
-use std::cmp::max;
-use std::fmt::{Debug, Formatter};
-use std::ops::Index;
-use std::ops::IndexMut;
 
+/// The ids of deleted clauses are reused by later clauses. This is safe as deleting a clause
+/// removes its watches, and reason clauses, the conflict clause and unit clauses are never
+/// deleted, so no id of a deleted clause is referenced anywhere. Debug builds assert this on
+/// every access and reuse.
+///
+/// Reason clauses are recognized by counting how many assignments on the trail they are the
+/// reason of, which the trail updates on every assignment and backtrack.
+///
+/// The metadata the search updates is kept in headers parallel to the clauses, so propagation
+/// checks the blocking literal without loading the literals, and the clauses stay plain
+/// literals with an LBD.
 #[derive(Clone)]
 pub struct ClauseDatabase {
     clauses: Vec<Clause>,
+    headers: Vec<ClauseHeader>,
+    /// Whether the clause with the id is deleted
+    deleted: Vec<bool>,
+    /// Ids of deleted clauses in the order of deletion, the last one is reused first
     free_clause_ids: Vec<ClauseId>,
     num_deletions: usize,
     pub(crate) proof_logger: ProofLogger,
     conflicts_since_last_deletion: usize,
+    num_learned_clauses: usize,
+    /// Variables of irredundant clauses added, deleted or changed since the last call to
+    /// `take_touched_vars`
+    touched_vars: HashSet<Var>,
+    /// See `Fragmentation::wasted_literal_capacity`
+    wasted_literal_capacity: usize,
+}
+
+/// Ids of deleted clauses waiting for reuse, see `ClauseDatabase::fragmentation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fragmentation {
+    pub num_free_ids: usize,
+    /// Number of ids, used or free
+    pub num_ids: usize,
+    /// Literals allocated for the deleted clauses, which are only freed by reuse or compaction
+    pub wasted_literal_capacity: usize,
+}
+
+impl Fragmentation {
+    /// Share of the ids that are free
+    pub fn ratio(&self) -> f64 {
+        match self.num_ids {
+            0 => 0.0,
+            num_ids => self.num_free_ids as f64 / num_ids as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClauseHeader {
+    /// A literal of the clause, which satisfies it if true
+    blocking_literal: Literal,
+    /// Number of assignments on the trail the clause is the reason of
+    reason_count: u32,
+    /// Whether the learned clause took part in a conflict analysis since the last reduction of
+    /// the clause database, which keeps it for one more round
+    used: bool,
+}
+
+impl ClauseHeader {
+    fn new(clause: &Clause) -> Self {
+        ClauseHeader {
+            blocking_literal: *clause.literals.first().unwrap_or(&Literal::new(0)),
+            reason_count: 0,
+            used: false,
+        }
+    }
 }
 
 impl Debug for ClauseDatabase {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         writeln!(f, "ClauseDatabase:")?;
         for clause in &self.clauses {
             writeln!(f, "LBD: {:?} {:?} ", clause.lbd, clause.literals)?;
@@ -31,8 +96,6 @@ pub struct Iter<'a> {
     pos: i32,
     clause_database: &'a ClauseDatabase,
     necessary_clauses_only: bool,
-    next_hole: Option<ClauseId>,
-    next_hole_position: usize,
 }
 
 impl<'a> Iterator for Iter<'a> {
@@ -45,13 +108,7 @@ impl<'a> Iterator for Iter<'a> {
                 return None;
             }
 
-            if Some(self.pos as ClauseId) == self.next_hole {
-                self.next_hole_position += 1;
-                self.next_hole = self
-                    .clause_database
-                    .free_clause_ids
-                    .get(self.next_hole_position)
-                    .copied();
+            if self.clause_database.deleted[self.pos as usize] {
                 continue;
             }
 
@@ -69,90 +126,224 @@ impl<'a> Iterator for Iter<'a> {
     }
 }
 impl ClauseDatabase {
-    pub fn init(clauses: &[Clause], proof_logging: bool) -> Self {
+    pub fn init(clauses: Vec<Clause>, proof_logging: bool) -> Self {
         ClauseDatabase {
             free_clause_ids: Vec::new(),
-            clauses: clauses.to_vec(),
+            deleted: vec![false; clauses.len()],
+            headers: clauses.iter().map(ClauseHeader::new).collect(),
+            clauses,
             num_deletions: 0,
             conflicts_since_last_deletion: 0,
             proof_logger: ProofLogger::new(proof_logging),
+            num_learned_clauses: 0,
+            touched_vars: HashSet::new(),
+            wasted_literal_capacity: 0,
         }
     }
 
-    pub fn add_clause(&mut self, clause: Clause, literal_watcher: &mut LiteralWatcher) -> ClauseId {
+    pub fn add_clause(
+        &mut self,
+        clause: Clause,
+        literal_watcher: &mut LiteralWatcher,
+        vars: &PartialAssignment,
+        trail: &Trail,
+    ) -> ClauseId {
+        if clause.lbd.is_some() {
+            self.num_learned_clauses += 1;
+        }
+        Self::touch(&mut self.touched_vars, &clause);
         let id = if !self.free_clause_ids.is_empty() {
             let id = self.free_clause_ids.pop().unwrap();
+            debug_assert_eq!(
+                self.headers[id].reason_count, 0,
+                "Reusing the id of a reason clause"
+            );
+            self.headers[id] = ClauseHeader::new(&clause);
+            self.wasted_literal_capacity -= self.clauses[id].literals.capacity();
             self.clauses[id] = clause;
+            self.deleted[id] = false;
             id
         } else {
+            self.headers.push(ClauseHeader::new(&clause));
+            self.deleted.push(false);
             self.clauses.push(clause);
             self.clauses.len() - 1
         };
 
-        self.proof_logger.log(&self.clauses[id]);
-        literal_watcher.add_clause(&self.clauses[id], id);
+        self.proof_logger.log_clause(id, &self.clauses[id]);
+        literal_watcher.add_clause(&self.clauses[id], id, vars, trail);
 
         id
     }
 
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             pos: -1,
             clause_database: self,
             necessary_clauses_only: false,
-            next_hole: self.free_clause_ids.first().copied(),
-            next_hole_position: 0,
         }
     }
-    pub fn necessary_clauses_iter(&self) -> Iter {
+    pub fn necessary_clauses_iter(&self) -> Iter<'_> {
         Iter {
             pos: -1,
             clause_database: self,
             necessary_clauses_only: true,
-            next_hole: self.free_clause_ids.first().copied(),
-            next_hole_position: 0,
         }
     }
 
+    /// Records that the clause became the reason of an assignment on the trail
+    pub fn lock(&mut self, clause_id: ClauseId) {
+        self.headers[clause_id].reason_count += 1;
+    }
+
+    /// Records that an assignment the clause is the reason of was removed from the trail
+    pub fn unlock(&mut self, clause_id: ClauseId) {
+        debug_assert!(self.headers[clause_id].reason_count > 0);
+        self.headers[clause_id].reason_count -= 1;
+    }
+
+    /// Whether the clause is the reason of an assignment on the trail
+    pub fn is_reason(&self, clause_id: ClauseId) -> bool {
+        self.headers[clause_id].reason_count > 0
+    }
+
+    pub fn blocking_literal(&self, clause_id: ClauseId) -> Literal {
+        self.headers[clause_id].blocking_literal
+    }
+
+    pub fn set_blocking_literal(&mut self, clause_id: ClauseId, literal: Literal) {
+        self.headers[clause_id].blocking_literal = literal;
+    }
+
+    /// Recomputes the LBD of a learned clause taking part in a conflict analysis and marks it
+    /// as used
+    pub fn update_lbd(&mut self, clause_id: ClauseId, trail: &mut Trail) {
+        if self.clauses[clause_id].lbd.is_some() {
+            self.clauses[clause_id].update_lbd(trail);
+            self.mark_used(clause_id);
+        }
+    }
+
+    /// Keeps the learned clause in the next reduction of the clause database
+    pub fn mark_used(&mut self, clause_id: ClauseId) {
+        self.headers[clause_id].used = true;
+    }
+
+    /// Deletes the clause unless it is a reason or unit clause, returns whether it was deleted
     pub fn delete_clause_if_allowed(
         &mut self,
         clause_id: ClauseId,
         literal_watcher: &mut LiteralWatcher,
-        trail: &Trail,
-    ) {
+    ) -> bool {
         // Clauses that are currently reason clauses may NOT be removed
-        let is_reason = trail
-            .assignment_stack
-            .iter()
-            .any(|assignment| assignment.reason == AssignmentReason::Forced(clause_id));
-        if is_reason {
-            return;
+        if self.is_reason(clause_id) {
+            return false;
         }
 
-        if self.free_clause_ids.binary_search(&clause_id).is_ok() {
+        if self.deleted[clause_id] {
             panic!("Trying to delete already deleted clause");
         }
 
         // don't delete unit clauses
         if self.clauses[clause_id].literals.len() < 2 {
-            return;
+            return false;
         }
 
-        self.proof_logger.delete(&self.clauses[clause_id]);
+        self.proof_logger
+            .delete_clause(clause_id, &self.clauses[clause_id]);
         literal_watcher.delete_clause(&self.clauses[clause_id], clause_id);
+        if self.clauses[clause_id].lbd.is_some() {
+            self.num_learned_clauses -= 1;
+        }
+        Self::touch(&mut self.touched_vars, &self.clauses[clause_id]);
+        self.wasted_literal_capacity += self.clauses[clause_id].literals.capacity();
         self.free_clause_ids.push(clause_id);
-        self.free_clause_ids.sort_unstable();
+        self.deleted[clause_id] = true;
+        true
     }
 
-    pub fn delete_clauses_if_necessary(
+    /// Removes a literal from a clause and re-establishes its watches
+    pub fn strengthen_clause(
         &mut self,
-        conflict_clause_id: ClauseId,
+        clause_id: ClauseId,
+        literal: Literal,
         literal_watcher: &mut LiteralWatcher,
+        vars: &PartialAssignment,
         trail: &Trail,
     ) {
-        if self.conflicts_since_last_deletion < 2000 + 300 * self.num_deletions {
+        literal_watcher.delete_clause(&self.clauses[clause_id], clause_id);
+        let old_clause = self.clauses[clause_id].clone();
+        Self::touch(&mut self.touched_vars, &old_clause);
+
+        let clause = &mut self.clauses[clause_id];
+        clause.literals.retain(|lit| *lit != literal);
+        self.headers[clause_id].blocking_literal = clause.literals[0];
+
+        self.proof_logger
+            .log_replacement(clause_id, &self.clauses[clause_id], &old_clause);
+        literal_watcher.add_clause(&self.clauses[clause_id], clause_id, vars, trail);
+    }
+
+    /// Replaces the literals of a clause implied by the other clauses, keeping its id and LBD, and
+    /// re-establishes its watches
+    pub fn replace_literals(
+        &mut self,
+        clause_id: ClauseId,
+        literals: Vec<Literal>,
+        literal_watcher: &mut LiteralWatcher,
+        vars: &PartialAssignment,
+        trail: &Trail,
+    ) {
+        literal_watcher.delete_clause(&self.clauses[clause_id], clause_id);
+        let old_clause = self.clauses[clause_id].clone();
+        Self::touch(&mut self.touched_vars, &old_clause);
+
+        self.headers[clause_id].blocking_literal = literals[0];
+        self.clauses[clause_id].literals = literals;
+        Self::touch(&mut self.touched_vars, &self.clauses[clause_id]);
+
+        self.proof_logger
+            .log_replacement(clause_id, &self.clauses[clause_id], &old_clause);
+        literal_watcher.add_clause(&self.clauses[clause_id], clause_id, vars, trail);
+    }
+
+    /// Records the variables of an irredundant clause as touched, learned clauses don't change
+    /// the formula
+    fn touch(touched_vars: &mut HashSet<Var>, clause: &Clause) {
+        if clause.lbd.is_none() {
+            touched_vars.extend(clause.literals.iter().map(|lit| lit.var()));
+        }
+    }
+
+    /// Variables of the irredundant clauses added, deleted or changed since the last call
+    pub fn take_touched_vars(&mut self) -> HashSet<Var> {
+        core::mem::take(&mut self.touched_vars)
+    }
+
+    pub fn touched_vars(&self) -> &HashSet<Var> {
+        &self.touched_vars
+    }
+
+    /// Logs the literals fixed at level 0 since the last call as unit lemmas, see
+    /// `ProofLogger::log_units`
+    pub fn log_units(&mut self, trail: &Trail) {
+        self.proof_logger.log_units(trail, &self.clauses);
+    }
+
+    /// Deletes the learned clauses with an LBD above the median after a growing number of
+    /// conflicts, returns whether the database was reduced. Clauses used in a conflict analysis
+    /// since the last reduction survive this one, as their LBD was recomputed on use.
+    pub fn delete_clauses_if_necessary(
+        &mut self,
+        conflict: ConstraintRef,
+        literal_watcher: &mut LiteralWatcher,
+        params: &Params,
+    ) -> bool {
+        let interval =
+            params.reduce_interval + params.reduce_interval_increment * self.num_deletions;
+        if self.conflicts_since_last_deletion < interval {
             self.conflicts_since_last_deletion += 1;
-            return;
+            return false;
         }
 
         self.conflicts_since_last_deletion = 0;
@@ -170,42 +361,81 @@ impl ClauseDatabase {
 
         for clause_id in self.iter().collect_vec() {
             if let Some(lbd) = self.clauses[clause_id].lbd {
-                if lbd <= threshold {
+                let used = core::mem::take(&mut self.headers[clause_id].used);
+                if lbd <= threshold || used {
                     continue;
                 }
-                if clause_id == conflict_clause_id {
+                if conflict == ConstraintRef::Clause(clause_id) {
                     // As clause deletion gets called right after a conflict,
                     // we have to ensure we don't delete the conflict clause
                     continue;
                 }
-                self.delete_clause_if_allowed(clause_id, literal_watcher, trail);
+                self.delete_clause_if_allowed(clause_id, literal_watcher);
             }
         }
+        true
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        Fragmentation {
+            num_free_ids: self.free_clause_ids.len(),
+            num_ids: self.clauses.len(),
+            wasted_literal_capacity: self.wasted_literal_capacity,
+        }
+    }
+
+    /// Moves the clauses to the lowest ids keeping their order, drops the deleted ones and
+    /// renumbers the watches and the proof. Returns the new id of each old id, `None` for the
+    /// free ones, which the caller applies to the reasons on the trail.
+    pub fn compact(&mut self, literal_watcher: &mut LiteralWatcher) -> Vec<Option<ClauseId>> {
+        let mut num_used = 0;
+        let mapping = self
+            .deleted
+            .iter()
+            .map(|free| {
+                (!free).then(|| {
+                    num_used += 1;
+                    num_used - 1
+                })
+            })
+            .collect_vec();
+
+        let mut deleted = self.deleted.iter();
+        self.clauses.retain(|_| !deleted.next().unwrap());
+        let mut deleted = self.deleted.iter();
+        self.headers.retain(|_| !deleted.next().unwrap());
+        self.clauses.shrink_to_fit();
+        self.headers.shrink_to_fit();
+        self.deleted = vec![false; self.clauses.len()];
+        self.free_clause_ids.clear();
+        self.wasted_literal_capacity = 0;
+
+        literal_watcher.renumber(&mapping);
+        self.proof_logger.renumber(&mapping);
+        mapping
     }
 
     pub fn num_clauses(&self) -> usize {
         self.clauses.len() - self.free_clause_ids.len()
     }
+
+    pub fn num_learned_clauses(&self) -> usize {
+        self.num_learned_clauses
+    }
 }
 
 impl Index<ClauseId> for ClauseDatabase {
     type Output = Clause;
 
     fn index(&self, index: ClauseId) -> &Self::Output {
-        /* debug_assert!(
-            self.free_clause_ids.binary_search(&index).is_err(),
-            "Accessing deleted clause"
-        );*/
+        debug_assert!(!self.deleted[index], "Accessing deleted clause");
         &self.clauses[index]
     }
 }
 
 impl IndexMut<ClauseId> for ClauseDatabase {
     fn index_mut(&mut self, index: ClauseId) -> &mut Self::Output {
-        /* debug_assert!(
-            self.free_clause_ids.binary_search(&index).is_err(),
-            "Accessing deleted clause"
-        );*/
+        debug_assert!(!self.deleted[index], "Accessing deleted clause");
         &mut self.clauses[index]
     }
 }
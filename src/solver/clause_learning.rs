@@ -1,48 +1,181 @@
-use crate::cnf::{Clause, ClauseId, Literal, VarId};
+use crate::cnf::{Clause, ClauseId, Literal, PartialAssignment, Var};
 use crate::solver::clause_database::ClauseDatabase;
-use crate::solver::trail::{AssignmentReason, Trail};
+use crate::solver::literal_watching::LiteralWatcher;
+use crate::solver::proof_logger::Antecedents;
+use crate::solver::resolution::ProofClause;
+use crate::solver::state::State;
+use crate::solver::trail::{AssignmentReason, ConstraintRef, Trail};
+use alloc::{collections::BinaryHeap, vec, vec::Vec};
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+use hashbrown::HashSet;
 use itertools::Itertools;
-use std::collections::HashSet;
 
+use core::hash::BuildHasherDefault;
 use fnv::FnvHasher;
-use std::hash::BuildHasherDefault;
 
 type FastHasher = BuildHasherDefault<FnvHasher>;
 
+/// Status of a variable during the minimization of a learned clause
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+enum Minimization {
+    #[default]
+    Unknown,
+    /// Implied by literals of the clause
+    Removable,
+    /// Depends on a decision whose literal isn't in the clause
+    Poison,
+}
+
+/// Bit of the decision level in a set of levels, which may contain further levels
+fn abstract_level(level: usize) -> u64 {
+    1 << (level % 64)
+}
+
+/// Which implication point of the conflict level ends the resolution of the conflict
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum LearningScheme {
+    /// Stop at the first unique implication point
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "first-uip"))]
+    FirstUip,
+    /// Resolve further up to the last unique implication point, usually the decision, as in the
+    /// decision scheme of L. Zhang, C. F. Madigan, M. H. Moskewicz, and S. Malik, “Efficient
+    /// Conflict Driven Learning in a Boolean Satisfiability Solver,” ICCAD 2001, pp. 279–285.
+    #[cfg_attr(feature = "std", clap(name = "last-uip"))]
+    LastUip,
+    /// First UIP, then the literals of each lower level are replaced by the UIP of that level if
+    /// this shrinks the clause
+    #[cfg_attr(feature = "std", clap(name = "all-uip"))]
+    AllUip,
+}
+
+/// Clause learned from each conflict in addition to the asserting one
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum AdditionalClause {
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "none"))]
+    None,
+    /// The clause of the last UIP, usually the decision, if it differs from the learned clause
+    #[cfg_attr(feature = "std", clap(name = "last-uip"))]
+    LastUip,
+    /// The last resolvent before the first UIP with exactly two literals of the conflict level,
+    /// as proposed in K. Pipatsrisawat and A. Darwiche, “A New Clause Learning Scheme for
+    /// Efficient Unsatisfiability Proofs,” in Proceedings of the 23rd AAAI Conference on
+    /// Artificial Intelligence, 2008, pp. 1481–1484. It is not unit after backtracking.
+    #[cfg_attr(feature = "std", clap(name = "bi-asserting"))]
+    BiAsserting,
+}
+
 #[derive(Debug, Default, Clone)]
-pub struct ClauseLearner {}
+pub struct ClauseLearner {
+    /// Antecedent clauses subsumed by an intermediate resolvent during the last analysis,
+    /// together with the pivot literal that can be removed from them.
+    /// Each comes with the resolution chain of the strengthened clause, if it's tracked.
+    strengthening_candidates: Vec<(ClauseId, Literal, Option<Vec<ProofClause>>)>,
+    learning_scheme: LearningScheme,
+    additional_clause: AdditionalClause,
+    /// Derived by the last analysis with its chain, see `take_additional_clause`
+    additional_learned_clause: Option<(Clause, Option<Vec<ProofClause>>)>,
+    /// Collect the antecedents of the learned clauses, see `track_chains`
+    tracking: bool,
+    /// Resolution chain of the clause learned by the last analysis, if it's tracked
+    learned_chain: Option<Vec<ProofClause>>,
+    /// Status of each variable in the minimization of the current clause, reset through the
+    /// visited variables. Both are kept across conflicts to avoid allocations.
+    minimization: Vec<Minimization>,
+    minimization_visited: Vec<Var>,
+}
+
+/// Literals of a resolvent below the conflict level, the trail literal of the UIP, the
+/// variables seen up to then and the antecedents of the resolvent
+type UipResolvent = (
+    Vec<Literal>,
+    Literal,
+    HashSet<Var, FastHasher>,
+    Option<Antecedents>,
+);
 
 impl ClauseLearner {
+    pub fn new(learning_scheme: LearningScheme, additional_clause: AdditionalClause) -> Self {
+        ClauseLearner {
+            strengthening_candidates: vec![],
+            learning_scheme,
+            additional_clause,
+            additional_learned_clause: None,
+            tracking: false,
+            learned_chain: None,
+            minimization: vec![],
+            minimization_visited: vec![],
+        }
+    }
+
+    /// Collects the resolution chains of the learned and strengthened clauses from now on, see
+    /// `learned_chain`. Requires the proof logger to track them.
+    pub fn track_chains(&mut self) {
+        self.tracking = true;
+    }
+
+    /// Resolution chain of the clause learned by the last analysis, which refers to the clauses
+    /// before any of them were strengthened
+    pub fn learned_chain(&self) -> Option<Vec<ProofClause>> {
+        self.learned_chain.clone()
+    }
+
+    /// The additional clause of the last conflict with its chain, if one was configured and
+    /// differs from the learned clause. Has to be added before backtracking, as the watches are
+    /// chosen by decision level, and may be unit after backtracking.
+    pub fn take_additional_clause(&mut self) -> Option<(Clause, Option<Vec<ProofClause>>)> {
+        self.additional_learned_clause.take()
+    }
+
     /// Assumes that the current state is in conflict
     pub fn analyse_conflict(
         &mut self,
         trail: &mut Trail,
-        clause_database: &mut ClauseDatabase,
-        conflict_clause_id: ClauseId,
+        state: &mut State,
+        conflict: ConstraintRef,
     ) -> (Clause, usize) {
         let mut learned_clause = vec![];
 
         // find learned clause
         let mut count = 0;
         let mut current_literal: Option<Literal> = None;
-        let mut current_reason_clause_id: ClauseId = conflict_clause_id;
+        let mut current_reason = conflict;
         let mut trail_position = trail.assignment_stack.len() - 1;
-        let mut seen: HashSet<VarId, FastHasher> = HashSet::with_hasher(FastHasher::default());
+        let mut seen: HashSet<Var, FastHasher> = HashSet::with_hasher(FastHasher::default());
+        let mut first_uip: Option<UipResolvent> = None;
+        let mut bi_asserting = None;
+        self.strengthening_candidates.clear();
+        self.additional_learned_clause = None;
+        // the reasons in the order they are resolved, which is kept exact so the chains of the
+        // learned clauses and of all intermediate resolvents are prefixes of it
+        let mut chain = self.tracking.then(Antecedents::default);
+        let mut candidates = vec![];
 
         loop {
-            let conflict_clause = &mut clause_database[current_reason_clause_id];
-            conflict_clause.update_lbd(trail);
+            if let ConstraintRef::Clause(clause_id) = current_reason {
+                state.clause_database.update_lbd(clause_id, trail);
+            }
+            let reason_literals = state
+                .reason_literals(current_reason, current_literal, trail)
+                .into_owned();
+            if let Some(chain) = &mut chain {
+                chain.add(current_reason, &reason_literals, trail);
+            }
 
-            for lit in conflict_clause.literals.clone() {
-                if current_literal.is_some() && lit.id() == current_literal.unwrap().id() {
+            for lit in reason_literals {
+                if current_literal.is_some() && lit.var() == current_literal.unwrap().var() {
                     continue; // current literal is not part of the reason clause
                 }
 
-                if !seen.contains(&lit.id()) && trail.var_decision_level[lit.id()] > 0 {
-                    seen.insert(lit.id());
+                if !seen.contains(&lit.var()) && trail.var_decision_level[lit.var()] > 0 {
+                    seen.insert(lit.var());
 
-                    assert!(trail.var_decision_level[lit.id()] <= trail.decision_level);
-                    if trail.var_decision_level[lit.id()] == trail.decision_level {
+                    assert!(trail.var_decision_level[lit.var()] <= trail.decision_level);
+                    if trail.var_decision_level[lit.var()] == trail.decision_level {
                         count += 1;
                     } else {
                         learned_clause.push(lit);
@@ -50,45 +183,129 @@ impl ClauseLearner {
                 }
             }
 
+            if let (Some(pivot), ConstraintRef::Clause(clause_id)) =
+                (current_literal, current_reason)
+            {
+                if Self::is_subsumed_on_the_fly(
+                    &state.clause_database[clause_id],
+                    learned_clause.len() + count,
+                    count,
+                    trail,
+                ) {
+                    candidates.push((clause_id, pivot, chain.clone()));
+                }
+            }
+
+            // the conflict clause itself is already in the database
+            if self.additional_clause == AdditionalClause::BiAsserting
+                && count == 2
+                && current_literal.is_some()
+            {
+                bi_asserting = Some((
+                    Self::resolvent(&learned_clause, &seen, trail_position, trail),
+                    chain.clone(),
+                ));
+            }
+
             // find next literal
-            while !seen.contains(&trail.assignment_stack[trail_position].literal.id()) {
+            while !seen.contains(&trail.assignment_stack[trail_position].literal.var()) {
                 trail_position -= 1;
             }
             current_literal = Some(trail.assignment_stack[trail_position].literal);
 
-            seen.remove(&current_literal.unwrap().id());
+            seen.remove(&current_literal.unwrap().var());
             count -= 1;
             if count == 0 {
-                break;
+                let resolve_further = (self.learning_scheme == LearningScheme::LastUip
+                    || self.additional_clause == AdditionalClause::LastUip)
+                    && Self::implied_at_current_level(
+                        state,
+                        trail,
+                        &trail.assignment_stack[trail_position].reason,
+                        current_literal.unwrap(),
+                    );
+                if !resolve_further {
+                    break;
+                }
+                if self.learning_scheme != LearningScheme::LastUip && first_uip.is_none() {
+                    first_uip = Some((
+                        learned_clause.clone(),
+                        current_literal.unwrap(),
+                        seen.clone(),
+                        chain.clone(),
+                    ));
+                }
             }
 
-            current_reason_clause_id = match trail.assignment_stack[trail_position].reason {
+            current_reason = match trail.assignment_stack[trail_position].reason {
                 AssignmentReason::Forced(reason) => reason,
-                AssignmentReason::Heuristic =>
+                AssignmentReason::Heuristic | AssignmentReason::Assumption =>
                     panic!("Search should be completed by now. Trying to resolve with branching assignment"),
             }
         }
 
+        let last_uip = (learned_clause, current_literal.unwrap(), seen, chain);
+        let (learned, additional) = match first_uip {
+            Some(first_uip) => (first_uip, Some(last_uip)),
+            None => (last_uip, None),
+        };
+        let proof_logger = &state.clause_database.proof_logger;
+        let resolve =
+            |chain: Option<Antecedents>| chain.and_then(|chain| proof_logger.chain(&chain));
+        if let Some((literals, uip, seen, mut chain)) = additional {
+            let (clause, _) =
+                self.finish_uip_clause(literals, uip, &seen, state, trail, &mut chain);
+            self.additional_learned_clause = Some((clause, resolve(chain)));
+        } else if let Some((literals, chain)) = bi_asserting {
+            let lbd = Self::lbd(&literals, trail);
+            self.additional_learned_clause =
+                Some((Clause::from_literals_and_lbd(literals, lbd), resolve(chain)));
+        }
+        self.strengthening_candidates = candidates
+            .into_iter()
+            .map(|(clause_id, pivot, chain)| (clause_id, pivot, resolve(chain)))
+            .collect();
+        let (literals, uip, seen, mut chain) = learned;
+        let learned = self.finish_uip_clause(literals, uip, &seen, state, trail, &mut chain);
+        self.learned_chain = resolve(chain);
+        learned
+    }
+
+    /// Adds the UIP to the literals of lower levels and minimizes the clause, extending the
+    /// chain by the resolutions of the minimization. Returns it with its assertion level.
+    fn finish_uip_clause(
+        &mut self,
+        mut learned_clause: Vec<Literal>,
+        uip: Literal,
+        seen: &HashSet<Var, FastHasher>,
+        state: &State,
+        trail: &Trail,
+        chain: &mut Option<Antecedents>,
+    ) -> (Clause, usize) {
         // add the UIP
-        learned_clause.push(-current_literal.unwrap());
+        learned_clause.push(-uip);
 
-        // The UIP has to be one of the watched literals. As the watches are initialized as 0 and 1
-        // we swap the UIP into the first position.
+        // The UIP is moved into the first position, where it is assigned after backtracking.
+        // The watches are chosen by decision level when the clause is added.
 
         let learned_clause_len = learned_clause.len();
         learned_clause.swap(0, learned_clause_len - 1);
         debug_assert_eq!(
-            trail.var_decision_level[learned_clause[0].id()],
+            trail.var_decision_level[learned_clause[0].var()],
             trail.decision_level
         );
 
-        self.conflict_clause_minimization(&mut learned_clause, clause_database, trail, &seen);
+        self.conflict_clause_minimization(&mut learned_clause, state, trail, seen, chain);
+        if self.learning_scheme == LearningScheme::AllUip {
+            Self::all_uip_shrinking(&mut learned_clause, state, trail, chain);
+        }
+        Self::binary_clause_minimization(&mut learned_clause, state, chain);
 
         // learned clause is UIP
         debug_assert_eq!(
             learned_clause
                 .iter()
-                .filter(|lit| trail.var_decision_level[lit.id()] == trail.decision_level)
+                .filter(|lit| trail.var_decision_level[lit.var()] == trail.decision_level)
                 .count(),
             1
         );
@@ -99,81 +316,396 @@ impl ClauseLearner {
         let assertion_level = learned_clause
             .clone()
             .iter()
-            .map(|lit| trail.var_decision_level[lit.id()])
+            .map(|lit| trail.var_decision_level[lit.var()])
             .sorted()
             .rev()
             .nth(1)
             .unwrap_or(0);
 
-        // The second watch has to be the asserting literal, otherwise the watched literals will
-        // become invalid after backtracking. In unit clauses, there is no asserting literal and
-        // this doesn't apply.
-        if let Some(assert_lit_idx) = learned_clause
-            .iter()
-            .position(|lit| trail.var_decision_level[lit.id()] == assertion_level)
-        {
-            learned_clause.swap(1, assert_lit_idx);
-        }
-
         assert!(assertion_level < trail.decision_level);
 
-        // calculate lbd
-        let lbd = learned_clause
-            .iter()
-            .map(|lit| trail.var_decision_level[lit.id()])
-            .collect::<HashSet<_>>()
-            .len();
+        let lbd = Self::lbd(&learned_clause, trail);
         (
             Clause::from_literals_and_lbd(learned_clause, lbd),
             assertion_level,
         )
     }
 
-    /// Conflict clause minimization based on Minisat v. 1.13
+    fn lbd(literals: &[Literal], trail: &Trail) -> usize {
+        literals
+            .iter()
+            .map(|lit| trail.var_decision_level[lit.var()])
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// The current resolvent, the literals of the conflict level are the seen ones still on the
+    /// trail below the current position and come first
+    fn resolvent(
+        lower_literals: &[Literal],
+        seen: &HashSet<Var, FastHasher>,
+        trail_position: usize,
+        trail: &Trail,
+    ) -> Vec<Literal> {
+        let current_level_literals = trail.assignment_stack[..=trail_position]
+            .iter()
+            .rev()
+            .take_while(|assignment| assignment.decision_level == trail.decision_level)
+            .filter(|assignment| seen.contains(&assignment.literal.var()))
+            .map(|assignment| -assignment.literal);
+        current_level_literals
+            .chain(lower_literals.iter().copied())
+            .collect()
+    }
+
+    /// Whether the UIP was forced by another literal of the current decision level, so the
+    /// resolution can go on to an earlier UIP
+    fn implied_at_current_level(
+        state: &State,
+        trail: &Trail,
+        reason: &AssignmentReason,
+        uip: Literal,
+    ) -> bool {
+        let AssignmentReason::Forced(reason) = reason else {
+            return false;
+        };
+        state
+            .reason_literals(*reason, Some(uip), trail)
+            .iter()
+            .any(|lit| {
+                lit.var() != uip.var()
+                    && trail.var_decision_level[lit.var()] == trail.decision_level
+            })
+    }
+
+    /// On-the-fly subsumption as described in H. Han and F. Somenzi, “On-the-Fly Clause
+    /// Improvement,” in Theory and Applications of Satisfiability Testing - SAT 2009, pp. 209–222.
+    /// If the current resolvent is exactly the antecedent without the pivot, the antecedent is
+    /// subsumed by the resolvent and can be strengthened by removing the pivot.
+    /// Only resolvents with at least two literals at the conflict level are considered, as
+    /// these literals become unassigned after backtracking and can be watched safely.
+    fn is_subsumed_on_the_fly(
+        antecedent: &Clause,
+        resolvent_len: usize,
+        current_level_literals: usize,
+        trail: &Trail,
+    ) -> bool {
+        // literals falsified at level 0 are not part of the resolvent
+        current_level_literals >= 2
+            && resolvent_len + 1 == antecedent.literals.len()
+            && antecedent
+                .literals
+                .iter()
+                .all(|lit| trail.var_decision_level[lit.var()] > 0)
+    }
+
+    /// Strengthens all antecedents found to be subsumed during the last conflict analysis.
+    /// Has to be called before backtracking, as the new watches are chosen by decision level.
+    pub fn strengthen_antecedents(
+        &mut self,
+        clause_database: &mut ClauseDatabase,
+        literal_watcher: &mut LiteralWatcher,
+        vars: &PartialAssignment,
+        trail: &Trail,
+    ) -> usize {
+        let num_strengthened = self.strengthening_candidates.len();
+        for (clause_id, literal, chain) in self.strengthening_candidates.drain(..) {
+            clause_database.proof_logger.set_chain(chain);
+            clause_database.strengthen_clause(clause_id, literal, literal_watcher, vars, trail);
+        }
+        num_strengthened
+    }
+
+    /// Recursive conflict clause minimization as in Minisat v. 2.2, with the caching of
+    /// A. Van Gelder, “Improved Conflict-Clause Minimization Leads to Improved Propositional
+    /// Proof Traces,” in Theory and Applications of Satisfiability Testing – SAT 2009,
+    /// pp. 141–146. A literal is removed if each literal of its reason is in the clause, fixed
+    /// at level 0, or removable itself. The removed literals and the ones they depend on are
+    /// resolved with their reasons in reverse trail order, so each resolution only introduces
+    /// literals still in the resolvent.
     fn conflict_clause_minimization(
-        &self,
+        &mut self,
         clause: &mut Vec<Literal>,
-        clause_database: &ClauseDatabase,
+        state: &State,
         trail: &Trail,
-        seen: &HashSet<VarId, FastHasher>,
+        seen: &HashSet<Var, FastHasher>,
+        chain: &mut Option<Antecedents>,
     ) {
+        let num_vars = trail.var_decision_level.len();
+        if self.minimization.len() < num_vars {
+            self.minimization.resize(num_vars, Minimization::Unknown);
+        }
+        // a literal of a level without literals in the clause depends on the decision of it
+        let levels = clause[1..].iter().fold(0u64, |levels, lit| {
+            levels | abstract_level(trail.var_decision_level[lit.var()])
+        });
         let mut minimized_clause = vec![clause[0]]; // keep the uip
-
+        let mut removed = vec![];
         for literal in clause.iter().skip(1) {
-            let reason_clause = match trail.get_reason(*literal) {
-                AssignmentReason::Forced(reason_clause) => {
-                    Some(&clause_database[*reason_clause].literals)
+            if self.is_redundant(*literal, levels, state, trail, seen) {
+                removed.push(*literal);
+            } else {
+                minimized_clause.push(*literal);
+            }
+        }
+        if let Some(chain) = chain {
+            self.add_minimization_chain(&removed, state, trail, seen, chain);
+        }
+        for var in self.minimization_visited.drain(..) {
+            self.minimization[var] = Minimization::Unknown;
+        }
+        *clause = minimized_clause;
+    }
+
+    /// Whether the literal of the learned clause is implied by the other literals, searched
+    /// depth-first through the reasons. The status of the visited variables is cached until the
+    /// clause is minimized.
+    fn is_redundant(
+        &mut self,
+        literal: Literal,
+        levels: u64,
+        state: &State,
+        trail: &Trail,
+        seen: &HashSet<Var, FastHasher>,
+    ) -> bool {
+        let AssignmentReason::Forced(reason) = trail.get_reason(literal) else {
+            return false;
+        };
+        let mut stack = vec![(
+            literal,
+            state.reason_literals(*reason, Some(-literal), trail),
+            0,
+        )];
+        while let Some((current, reason_literals, index)) = stack.last_mut() {
+            let Some(&lit) = reason_literals.get(*index) else {
+                let var = current.var();
+                stack.pop();
+                self.mark(var, Minimization::Removable);
+                continue;
+            };
+            *index += 1;
+            let var = lit.var();
+            if var == current.var()
+                || seen.contains(&var)
+                || trail.var_decision_level[var] == 0
+                || self.minimization[var] == Minimization::Removable
+            {
+                continue;
+            }
+            match trail.get_reason(lit) {
+                AssignmentReason::Forced(reason)
+                    if self.minimization[var] == Minimization::Unknown
+                        && levels & abstract_level(trail.var_decision_level[var]) != 0 =>
+                {
+                    let reason_literals = state.reason_literals(*reason, Some(-lit), trail);
+                    stack.push((lit, reason_literals, 0));
+                }
+                _ => {
+                    self.mark(var, Minimization::Poison);
+                    for (lit, _, _) in stack {
+                        self.mark(lit.var(), Minimization::Poison);
+                    }
+                    return false;
                 }
-                AssignmentReason::Heuristic => None,
+            }
+        }
+        true
+    }
+
+    fn mark(&mut self, var: Var, status: Minimization) {
+        if self.minimization[var] == Minimization::Unknown {
+            self.minimization_visited.push(var);
+        }
+        self.minimization[var] = status;
+    }
+
+    /// Extends the chain by the reasons of the removed literals and of the removable literals
+    /// their reasons introduced, in reverse trail order
+    fn add_minimization_chain(
+        &self,
+        removed: &[Literal],
+        state: &State,
+        trail: &Trail,
+        seen: &HashSet<Var, FastHasher>,
+        chain: &mut Antecedents,
+    ) {
+        let mut resolved: HashSet<Var, FastHasher> = removed.iter().map(|lit| lit.var()).collect();
+        let mut pending = removed.to_vec();
+        while let Some(literal) = pending.pop() {
+            let AssignmentReason::Forced(reason) = trail.get_reason(literal) else {
+                unreachable!("removed literals are implied");
             };
+            for lit in state.reason_literals(*reason, Some(-literal), trail).iter() {
+                if !seen.contains(&lit.var())
+                    && self.minimization[lit.var()] == Minimization::Removable
+                    && resolved.insert(lit.var())
+                {
+                    pending.push(*lit);
+                }
+            }
+        }
+        let positions = resolved
+            .into_iter()
+            .map(|var| trail.var_assignment_pos[var])
+            .sorted_unstable_by_key(|position| core::cmp::Reverse(*position));
+        for position in positions {
+            let assignment = &trail.assignment_stack[position];
+            let AssignmentReason::Forced(reason) = assignment.reason else {
+                unreachable!("removed literals are implied");
+            };
+            let reason_literals = state.reason_literals(reason, Some(assignment.literal), trail);
+            chain.add(reason, &reason_literals, trail);
+        }
+    }
 
-            if let Some(reason_clause) = reason_clause {
-                for reason_literal in reason_clause.iter() {
-                    if reason_literal.id() == literal.id() {
-                        continue;
-                    }
-                    if !seen.contains(&reason_literal.id())
-                        && trail.var_decision_level[reason_literal.id()] > 0
-                    {
-                        minimized_clause.push(*literal);
+    /// All-UIP shrinking as described in N. Feng and F. Bacchus, “Clause Size Reduction with
+    /// all-UIP Learning,” in Theory and Applications of Satisfiability Testing – SAT 2020,
+    /// pp. 28–45. From the highest level down, the literals of each lower decision level are
+    /// resolved with their reasons up to the UIP of that level. The UIP replaces them if only
+    /// literals of other levels already in the clause were introduced and the clause shrinks,
+    /// so the LBD never grows. The UIP has to be the first literal.
+    fn all_uip_shrinking(
+        clause: &mut Vec<Literal>,
+        state: &State,
+        trail: &Trail,
+        chain: &mut Option<Antecedents>,
+    ) {
+        let level = |lit: &Literal| trail.var_decision_level[lit.var()];
+        let mut levels = clause[1..].iter().map(level).collect_vec();
+        levels.sort_unstable_by(|a, b| b.cmp(a));
+        levels.dedup();
 
-                        break;
-                    }
+        for &current_level in &levels {
+            let level_literals = clause[1..]
+                .iter()
+                .filter(|lit| level(lit) == current_level)
+                .copied()
+                .collect_vec();
+            if level_literals.len() < 2 {
+                continue;
+            }
+            let mut level_chain = chain.clone();
+            let Some((uip, introduced)) = Self::level_uip(
+                clause,
+                &level_literals,
+                current_level,
+                &levels,
+                state,
+                trail,
+                &mut level_chain,
+            ) else {
+                continue;
+            };
+            if 1 + introduced.len() >= level_literals.len() {
+                continue;
+            }
+            *chain = level_chain;
+            // the first literal is of the conflict level
+            clause.retain(|lit| level(lit) != current_level);
+            clause.push(uip);
+            clause.extend(introduced);
+        }
+    }
+
+    /// Resolves the literals of the level with their reasons in reverse trail order until one is
+    /// left, the UIP of the level. Returns it with the literals of lower levels that were
+    /// introduced, or `None` if a decision or a literal of a level not in `levels` was reached.
+    fn level_uip(
+        clause: &[Literal],
+        level_literals: &[Literal],
+        level: usize,
+        levels: &[usize],
+        state: &State,
+        trail: &Trail,
+        chain: &mut Option<Antecedents>,
+    ) -> Option<(Literal, Vec<Literal>)> {
+        let mut seen: HashSet<Var, FastHasher> =
+            level_literals.iter().map(|lit| lit.var()).collect();
+        let mut positions: BinaryHeap<usize> = level_literals
+            .iter()
+            .map(|lit| trail.var_assignment_pos[lit.var()])
+            .collect();
+        let mut introduced = vec![];
+        while let Some(position) = positions.pop() {
+            let literal = trail.assignment_stack[position].literal;
+            if positions.is_empty() {
+                return Some((-literal, introduced));
+            }
+            let AssignmentReason::Forced(reason) = trail.assignment_stack[position].reason else {
+                return None;
+            };
+            let reason_literals = state.reason_literals(reason, Some(literal), trail);
+            if let Some(chain) = chain {
+                chain.add(reason, &reason_literals, trail);
+            }
+            for &lit in reason_literals.iter() {
+                if lit.var() == literal.var() || !seen.insert(lit.var()) {
+                    continue;
+                }
+                let lit_level = trail.var_decision_level[lit.var()];
+                if lit_level == level {
+                    positions.push(trail.var_assignment_pos[lit.var()]);
+                } else if lit_level == 0 || clause.contains(&lit) {
+                    continue;
+                } else if lit_level < level && levels.contains(&lit_level) {
+                    introduced.push(lit);
+                } else {
+                    return None;
                 }
-            } else {
-                minimized_clause.push(*literal)
             }
         }
-        *clause = minimized_clause;
+        unreachable!("the level has at least two literals")
+    }
+
+    /// Removes every literal `l` for which a binary clause `(uip ∨ ¬l)` exists, as resolving
+    /// with it yields the clause without `l`, as in G. Audemard and L. Simon, “Refining
+    /// Restarts Strategies for SAT and UNSAT,” in Principles and Practice of Constraint
+    /// Programming, 2012, pp. 118–126, and MapleSAT. The UIP has to be the first literal.
+    /// Clauses longer than `Params::binary_minimization_max_len` aren't shrunk, as in Glucose.
+    fn binary_clause_minimization(
+        clause: &mut Vec<Literal>,
+        state: &State,
+        chain: &mut Option<Antecedents>,
+    ) {
+        if clause.len() > state.params.binary_minimization_max_len {
+            return;
+        }
+        let uip = clause[0];
+        // both literals of binary clauses are watched
+        let binary_clauses = state
+            .literal_watcher
+            .watches(uip)
+            .iter()
+            .filter_map(
+                |clause_id| match state.clause_database[*clause_id].literals[..] {
+                    [first, second] => {
+                        Some((if first == uip { second } else { first }, *clause_id))
+                    }
+                    _ => None,
+                },
+            );
+        if let Some(chain) = chain {
+            let mut resolved = HashSet::with_hasher(FastHasher::default());
+            chain.clauses.extend(
+                binary_clauses
+                    .clone()
+                    .filter(|(implied, _)| clause.contains(&-*implied) && resolved.insert(*implied))
+                    .map(|(_, clause_id)| clause_id),
+            );
+        }
+        let implied = binary_clauses
+            .map(|(implied, _)| implied)
+            .collect::<HashSet<_, FastHasher>>();
+        if !implied.is_empty() {
+            clause.retain(|lit| *lit == uip || !implied.contains(&-*lit));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::solver::state::State;
     use crate::solver::trail::{Assignment, AssignmentReason};
-    use crate::solver::unit_propagation::UnitPropagator;
 
     #[test]
     fn test_learn_clause() {
@@ -190,21 +722,15 @@ mod tests {
             Clause::from("10 -11"),    // 9
             Clause::from("-12 13"),    // 10
         ];
-        let mut state = State::init(cnf.clone());
+        let mut state = State::init(cnf.clone(), 13, false);
         let mut clause_learner = ClauseLearner::default();
         let mut brancher = Trail::new(13);
-        let mut unit_propagator = UnitPropagator::default();
 
         let assigments = vec![-9, -10, 12, 1];
         // unit: 11
         for assignment in assigments {
-            brancher.assign(
-                &mut state,
-                &mut unit_propagator,
-                assignment.into(),
-                AssignmentReason::Heuristic,
-            );
-            unit_propagator.propagate(&mut state, &mut brancher);
+            brancher.assign(&mut state, assignment.into(), AssignmentReason::Heuristic);
+            brancher.propagate(&mut state);
         }
         //state.verify_watches();
         assert_eq!(
@@ -217,16 +743,13 @@ mod tests {
         );
         assert_eq!(
             brancher.assignment_stack[10],
-            Assignment::forced(6.into(), 4, 4)
+            Assignment::forced(6.into(), 4, ConstraintRef::Clause(4))
         );
-        assert!(state.conflict_clause_id.is_some());
+        assert!(state.conflict.is_some());
         // clause learning begins
         println!("{:?}", brancher.assignment_stack);
-        let clause = clause_learner.analyse_conflict(
-            &mut brancher,
-            &state.clause_database,
-            state.conflict_clause_id.clone().unwrap(),
-        );
+        let conflict = state.conflict.unwrap();
+        let clause = clause_learner.analyse_conflict(&mut brancher, &mut state, conflict);
         println!("learned clause {:?}", clause);
         println!("{}", brancher.implication_graph(&state));
     }
@@ -242,30 +765,21 @@ mod tests {
             Clause::from("-1 4 -6"),  // 5
             Clause::from("-1 -5 -6"), // 6
         ];
-        let mut state = State::init(cnf.clone());
+        let mut state = State::init(cnf.clone(), 6, false);
         let mut clause_learner = ClauseLearner::default();
         let mut trail = Trail::new(state.num_vars);
-        let mut unit_propagator = UnitPropagator::default();
         let assignments = vec![1, 2, 3, 4];
         for assignment in assignments {
-            trail.assign(
-                &mut state,
-                &mut unit_propagator,
-                assignment.into(),
-                AssignmentReason::Heuristic,
-            );
-            unit_propagator.propagate(&mut state, &mut trail);
+            trail.assign(&mut state, assignment.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
         }
         // state.verify_watches();
         println!("{}", trail.implication_graph(&state));
-        assert!(state.conflict_clause_id.is_some());
-        println!("{:?}", state.conflict_clause_id);
+        assert!(state.conflict.is_some());
+        println!("{:?}", state.conflict);
         println!("{:#?}", trail.assignment_stack);
-        let learned_clause = clause_learner.analyse_conflict(
-            &mut trail,
-            &state.clause_database,
-            state.conflict_clause_id.unwrap(),
-        );
+        let conflict = state.conflict.unwrap();
+        let learned_clause = clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
         println!("{:?}", learned_clause);
     }
 
@@ -279,26 +793,188 @@ mod tests {
             Clause::from("-6 -8"),    // 4
             Clause::from("7 8"),      // 5
         ];
-        let mut state = State::init(cnf.clone());
-        let mut unit_propagator = UnitPropagator::default();
+        let mut state = State::init(cnf.clone(), 8, false);
         let mut trail = Trail::new(state.num_vars);
         let mut clause_learner = ClauseLearner::default();
         let assignments = vec![1, 2, 4];
         for assignment in assignments {
-            trail.assign(
-                &mut state,
-                &mut unit_propagator,
-                assignment.into(),
-                AssignmentReason::Heuristic,
-            );
-            unit_propagator.propagate(&mut state, &mut trail);
+            trail.assign(&mut state, assignment.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
         }
         println!("{}", trail.implication_graph(&state));
-        let learned_clause = clause_learner.analyse_conflict(
-            &mut trail,
-            &state.clause_database,
-            state.conflict_clause_id.unwrap(),
-        );
+        let conflict = state.conflict.unwrap();
+        let learned_clause = clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
         println!("{:?}", learned_clause);
     }
+
+    #[test]
+    fn on_the_fly_subsumption() {
+        let cnf = vec![
+            Clause::from("-1 2"),     // 0
+            Clause::from("-1 3"),     // 1
+            Clause::from("-2 -3 4"),  // 2
+            Clause::from("-2 -3 -4"), // 3
+        ];
+        let mut state = State::init(cnf.clone(), 4, false);
+        let mut trail = Trail::new(state.num_vars);
+        let mut clause_learner = ClauseLearner::default();
+        trail.assign(&mut state, 1.into(), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
+        assert_eq!(state.conflict, Some(ConstraintRef::Clause(3)));
+
+        let conflict = state.conflict.unwrap();
+        let (learned_clause, _) = clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
+        assert_eq!(learned_clause.literals, vec![(-1).into()]);
+
+        // the resolvent of 2 and 3 subsumes 2
+        let num_strengthened = clause_learner.strengthen_antecedents(
+            &mut state.clause_database,
+            &mut state.literal_watcher,
+            &state.vars,
+            &trail,
+        );
+        assert_eq!(num_strengthened, 1);
+        let mut strengthened = state.clause_database[2].literals.clone();
+        strengthened.sort();
+        assert_eq!(strengthened, vec![(-2).into(), (-3).into()]);
+        state.verify_watches();
+    }
+
+    #[test]
+    fn recursive_minimization() {
+        let cnf = vec![
+            Clause::from("-1 2"),     // 0
+            Clause::from("-2 3"),     // 1
+            Clause::from("-4 -3 5"),  // 2
+            Clause::from("-4 -1 -5"), // 3
+        ];
+        let mut state = State::init(cnf.clone(), 5, false);
+        let mut trail = Trail::new(state.num_vars);
+        let mut clause_learner = ClauseLearner::default();
+        for assignment in [1, 4] {
+            trail.assign(&mut state, assignment.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+        }
+        assert_eq!(state.conflict, Some(ConstraintRef::Clause(3)));
+
+        // -3 is removed as 3 is implied by 1 through 2, which isn't in the clause
+        let conflict = state.conflict.unwrap();
+        let (learned_clause, _) = clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
+        assert_eq!(learned_clause.literals, vec![(-4).into(), (-1).into()]);
+    }
+
+    #[test]
+    fn binary_clause_minimization() {
+        let cnf = vec![
+            Clause::from("-2 3"),        // 0
+            Clause::from("-1 -2 -3 4"),  // 1
+            Clause::from("-1 -2 -3 -4"), // 2
+        ];
+        let mut state = State::init(cnf.clone(), 4, false);
+        let mut trail = Trail::new(state.num_vars);
+        let mut clause_learner = ClauseLearner::default();
+        for assignment in [1, 3, 2] {
+            trail.assign(&mut state, assignment.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+        }
+        assert_eq!(state.conflict, Some(ConstraintRef::Clause(2)));
+
+        // resolving (-2 -1 -3) with (-2 3) removes -3
+        let conflict = state.conflict.unwrap();
+        let (learned_clause, assertion_level) =
+            clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
+        assert_eq!(learned_clause.literals, vec![(-2).into(), (-1).into()]);
+        assert_eq!(assertion_level, 1);
+    }
+
+    fn learn(
+        cnf: &[Clause],
+        num_vars: usize,
+        decisions: &[i32],
+        learning_scheme: LearningScheme,
+    ) -> Vec<Literal> {
+        let mut state = State::init(cnf.to_vec(), num_vars, false);
+        let mut trail = Trail::new(state.num_vars);
+        let mut clause_learner = ClauseLearner::new(learning_scheme, AdditionalClause::None);
+        for &decision in decisions {
+            trail.assign(&mut state, decision.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+        }
+        let conflict = state.conflict.unwrap();
+        let (learned_clause, _) = clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
+        learned_clause.literals
+    }
+
+    #[test]
+    fn last_uip() {
+        let cnf = [
+            Clause::from("-1 2"),
+            Clause::from("-2 3"),
+            Clause::from("-2 4"),
+            Clause::from("-3 -4 5"),
+            Clause::from("-3 -4 -5"),
+        ];
+        // 2 is the first UIP, the decision 1 the last one
+        assert_eq!(
+            learn(&cnf, 5, &[1], LearningScheme::FirstUip),
+            vec![(-2).into()]
+        );
+        assert_eq!(
+            learn(&cnf, 5, &[1], LearningScheme::LastUip),
+            vec![(-1).into()]
+        );
+    }
+
+    #[test]
+    fn additional_clauses() {
+        let cnf = [
+            Clause::from("-1 2"),
+            Clause::from("-2 3"),
+            Clause::from("-2 4"),
+            Clause::from("-3 -4 5"),
+            Clause::from("-3 -4 -5"),
+        ];
+        for (additional_clause, expected) in [
+            (AdditionalClause::None, None),
+            (AdditionalClause::LastUip, Some(vec![-1])),
+            (AdditionalClause::BiAsserting, Some(vec![-3, -2])),
+        ] {
+            let mut state = State::init(cnf.to_vec(), 5, false);
+            let mut trail = Trail::new(state.num_vars);
+            let mut clause_learner =
+                ClauseLearner::new(LearningScheme::FirstUip, additional_clause);
+            trail.assign(&mut state, 1.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+            let conflict = state.conflict.unwrap();
+            let (learned_clause, _) =
+                clause_learner.analyse_conflict(&mut trail, &mut state, conflict);
+            assert_eq!(learned_clause.literals, vec![(-2).into()]);
+            let expected = expected.map(|lits| lits.into_iter().map(Literal::from).collect_vec());
+            assert_eq!(
+                clause_learner
+                    .take_additional_clause()
+                    .map(|(clause, _)| clause.literals),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn all_uip_shrinking() {
+        let cnf = [
+            Clause::from("-1 2"),
+            Clause::from("-1 3"),
+            Clause::from("-2 -3 -4 5"),
+            Clause::from("-2 -3 -4 -5"),
+        ];
+        assert_eq!(
+            learn(&cnf, 5, &[1, 4], LearningScheme::FirstUip),
+            vec![(-4).into(), (-3).into(), (-2).into()]
+        );
+        // 2 and 3 of the first level are replaced by their UIP 1
+        assert_eq!(
+            learn(&cnf, 5, &[1, 4], LearningScheme::AllUip),
+            vec![(-4).into(), (-1).into()]
+        );
+    }
 }
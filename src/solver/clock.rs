@@ -0,0 +1,24 @@
+//! Monotonic clock for time limits and statistics. `std::time::Instant::now` panics on
+//! `wasm32-unknown-unknown`, where the clock of the JavaScript host is used instead.
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use std::time::Instant;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub use web_time::Instant;
+
+/// Without `std` there is no clock and time doesn't pass, so time limits never apply and the
+/// inprocessing effort is measured deterministically
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Instant;
+
+#[cfg(not(feature = "std"))]
+impl Instant {
+    pub fn now() -> Self {
+        Instant
+    }
+
+    pub fn elapsed(&self) -> core::time::Duration {
+        core::time::Duration::ZERO
+    }
+}
@@ -1,13 +1,103 @@
-use crate::solver::heuristic::HeuristicType;
-use crate::solver::progress::ProgressPrintingInterval;
+use crate::cnf::Var;
+use crate::solver::clause_learning::{AdditionalClause, LearningScheme};
+use crate::solver::heuristic::{HeuristicType, ScoreInit};
+use crate::solver::listener::Listener;
+use crate::solver::log::Verbosity;
+use crate::solver::params::Params;
+use crate::solver::phases::PhaseMode;
+use crate::solver::progress::{ProgressFormat, ProgressPrintingInterval};
 use crate::solver::restarts::RestartPolicy;
+use crate::solver::trail::{BacktrackingMode, ReuseStrategy};
+use alloc::sync::Arc;
+use alloc::{string::String, vec, vec::Vec};
+use core::sync::atomic::AtomicBool;
 
+/// Order in which variables are tried for bounded variable elimination, cheapest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BveScoring {
+    /// Product of the positive and negative occurrences, the number of possible resolvents
+    Product,
+    /// Sum of the positive and negative occurrences, the number of removed clauses
+    Sum,
+}
+
+#[derive(Clone)]
 pub struct Config {
     pub heuristic: HeuristicType,
     pub restart_policy: RestartPolicy,
     pub proof_file: Option<String>,
     pub inprocessing: bool,
+    /// The progress table is only printed with at least `Verbosity::Normal`
     pub progress_printing_interval: ProgressPrintingInterval,
+    pub progress_format: ProgressFormat,
+    pub verbosity: Verbosity,
+    pub backtracking_mode: BacktrackingMode,
+    /// Conflicts between consecutive restarts for `RestartPolicy::Custom`, the last one repeats.
+    /// An interval of 0 restarts after every conflict.
+    pub restart_schedule: Vec<usize>,
+    /// Detect XOR constraints in the clauses and propagate them with Gaussian elimination. The
+    /// propagations and conflicts of the XORs aren't logged, so proofs, LRAT files, cores and
+    /// interpolants of such searches don't check, and the CLI rejects the combination.
+    pub gaussian_elimination: bool,
+    /// Log the proof in memory even without a proof file, so cores can be extracted
+    pub core_extraction: bool,
+    /// Track the antecedents of the lemmas during the search and remove the ones the empty
+    /// clause doesn't depend on from the proof, see `ProofLogger::trim`
+    pub proof_trimming: bool,
+    /// Record the resolution chain of every lemma in memory, so the proof is available as a
+    /// `ResolutionProof` and cores are taken from it without checking the proof
+    pub resolution_chains: bool,
+    /// File the resolution proof is written to in LRAT format, which records the chains
+    pub lrat_file: Option<String>,
+    pub bve_scoring: BveScoring,
+    /// Base the inprocessing budget and the progress printing on the number of conflicts
+    /// instead of the wall-clock time, so runs are reproducible
+    pub deterministic: bool,
+    /// Limits of a single call to `Solver::solve`, see `Budget`
+    pub time_limit: Option<core::time::Duration>,
+    pub conflict_limit: Option<usize>,
+    /// Resident memory in MiB, only checked on Linux
+    pub memory_limit: Option<usize>,
+    pub phase_mode: PhaseMode,
+    /// Initial scores of the heuristic and initial phases, computed from the input clauses
+    pub score_init: ScoreInit,
+    /// Restart partially, keeping the decision levels the heuristic would recreate, unless
+    /// inprocessing runs afterwards, see `Trail::restart_reusing_trail`
+    pub trail_reuse: bool,
+    /// Which decision levels `trail_reuse` keeps
+    pub reuse_strategy: ReuseStrategy,
+    /// Save the assignments undone by backjumps and assign their implications again once their
+    /// decisions hold again, see `Trail::backjump_saving`
+    pub trail_saving: bool,
+    /// Alternate between the focused and the stable mode, see `Stabilizer`
+    pub stabilization: bool,
+    /// Heuristic of the stable mode, the configured one is used in the focused mode
+    pub stable_heuristic: HeuristicType,
+    /// Where the resolution of a conflict stops, see `LearningScheme`
+    pub learning_scheme: LearningScheme,
+    /// Second clause learned from each conflict, to evaluate learning multiple clauses
+    pub additional_clause: AdditionalClause,
+    /// Threads updating the watches of the clauses in parallel, ignored without the `parallel`
+    /// feature, see `parallel_propagation`
+    pub propagation_threads: usize,
+    /// Set the saved phases by local search every few restarts, see `Walker`
+    pub walk: bool,
+    /// Variables whose assignments, reasons and unassignments are written to `trace_file`, or
+    /// to stderr without one, see `VarTracer`
+    pub trace_vars: Vec<Var>,
+    pub trace_file: Option<String>,
+    /// Directory the implication graphs of the first `num_conflict_graphs` conflicts are
+    /// written to, see `ConflictGraph`
+    pub conflict_graph_dir: Option<String>,
+    pub num_conflict_graphs: usize,
+    /// Notified of conflicts, restarts, reductions and inprocessing, see `SearchListener`
+    pub listeners: Vec<Listener>,
+    /// Terminate flag shared by all solvers created with this configuration, e.g. the
+    /// incremental solvers of MaxSAT and MUS extraction, see `Solver::terminate_flag`. Each
+    /// solver has its own flag without it.
+    pub terminate: Option<Arc<AtomicBool>>,
+    /// Numeric constants of the search, e.g. for automated tuning
+    pub params: Params,
 }
 
 impl Config {
@@ -17,6 +107,7 @@ impl Config {
         restart_policy: RestartPolicy,
         inprocessing: bool,
         progress_printing_interval: ProgressPrintingInterval,
+        backtracking_mode: BacktrackingMode,
     ) -> Self {
         Config {
             heuristic,
@@ -24,6 +115,8 @@ impl Config {
             restart_policy,
             inprocessing,
             progress_printing_interval,
+            backtracking_mode,
+            ..Default::default()
         }
     }
 }
@@ -37,6 +130,38 @@ impl Default for Config {
             restart_policy: RestartPolicy::GlucoseEma,
             inprocessing: true,
             progress_printing_interval: ProgressPrintingInterval::Medium,
+            progress_format: ProgressFormat::Table,
+            verbosity: Verbosity::Quiet,
+            backtracking_mode: BacktrackingMode::NonChronological,
+            restart_schedule: vec![],
+            gaussian_elimination: false,
+            core_extraction: false,
+            proof_trimming: false,
+            resolution_chains: false,
+            lrat_file: None,
+            bve_scoring: BveScoring::Product,
+            deterministic: false,
+            time_limit: None,
+            conflict_limit: None,
+            memory_limit: None,
+            phase_mode: PhaseMode::Saved,
+            score_init: ScoreInit::Default,
+            trail_reuse: true,
+            reuse_strategy: ReuseStrategy::Preferred,
+            trail_saving: false,
+            stabilization: false,
+            stable_heuristic: HeuristicType::VMTF,
+            learning_scheme: LearningScheme::FirstUip,
+            additional_clause: AdditionalClause::None,
+            propagation_threads: 1,
+            walk: true,
+            trace_vars: vec![],
+            trace_file: None,
+            conflict_graph_dir: None,
+            num_conflict_graphs: 10,
+            listeners: vec![],
+            terminate: None,
+            params: Params::default(),
         }
     }
 }
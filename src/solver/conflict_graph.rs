@@ -0,0 +1,216 @@
+use crate::cnf::Literal;
+use crate::solver::state::State;
+use crate::solver::trail::{AssignmentReason, ConstraintRef, Trail};
+use alloc::{format, string::String, vec, vec::Vec};
+use itertools::Itertools;
+
+/// Implication graph of the assignments a conflict depends on, or of the whole trail without a
+/// conflict. Each assigned literal is a node, with an edge from the negation of every other
+/// literal of its reason. The learned clause can be added once the conflict is analysed.
+#[derive(Debug, Clone)]
+pub struct ConflictGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    /// The conflict with its literals and their decision levels
+    conflict: Option<(ConstraintRef, Vec<(Literal, usize)>)>,
+    pub learned_clause: Option<Vec<Literal>>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    literal: Literal,
+    decision_level: usize,
+    reason: Option<ConstraintRef>,
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    from: (Literal, usize),
+    to: (Literal, usize),
+    reason: ConstraintRef,
+}
+
+impl ConflictGraph {
+    pub fn new(trail: &Trail, state: &State) -> Self {
+        let conflict = state.conflict.map(|conflict| {
+            let literals = state.reason_literals(conflict, None, trail);
+            let literals = literals
+                .iter()
+                .map(|lit| (*lit, trail.var_decision_level[lit.var()]))
+                .collect_vec();
+            (conflict, literals)
+        });
+
+        // walk the trail backwards from the conflict to find the assignments it depends on
+        let mut relevant = vec![conflict.is_none(); trail.assignment_stack.len()];
+        if let Some((_, literals)) = &conflict {
+            for (lit, _) in literals {
+                relevant[trail.var_assignment_pos[lit.var()]] = true;
+            }
+        }
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        for (position, assignment) in trail.assignment_stack.iter().enumerate().rev() {
+            if !relevant[position] {
+                continue;
+            }
+            let reason = match assignment.reason {
+                AssignmentReason::Heuristic | AssignmentReason::Assumption => None,
+                AssignmentReason::Forced(reason) => Some(reason),
+            };
+            nodes.push(Node {
+                literal: assignment.literal,
+                decision_level: assignment.decision_level,
+                reason,
+            });
+            let Some(reason) = reason else {
+                continue;
+            };
+            let reason_literals = state.reason_literals(reason, Some(assignment.literal), trail);
+            for lit in reason_literals
+                .iter()
+                .filter(|lit| **lit != assignment.literal)
+            {
+                relevant[trail.var_assignment_pos[lit.var()]] = true;
+                edges.push(Edge {
+                    from: (-*lit, trail.var_decision_level[lit.var()]),
+                    to: (assignment.literal, assignment.decision_level),
+                    reason,
+                });
+            }
+        }
+        nodes.reverse();
+        edges.reverse();
+
+        ConflictGraph {
+            nodes,
+            edges,
+            conflict,
+            learned_clause: None,
+        }
+    }
+
+    /// The graph in the DOT format of Graphviz, with decisions as boxes
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph G {\n");
+        if let Some(learned_clause) = &self.learned_clause {
+            out.push_str(&format!(
+                "label=\"learned: {}\";\n",
+                learned_clause.iter().join(" ")
+            ));
+        }
+        for node in &self.nodes {
+            let shape = match node.reason {
+                None => "box",
+                Some(_) => "ellipse",
+            };
+            out.push_str(&format!(
+                "{} [shape={}];\n",
+                node_name(node.literal, node.decision_level),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "{} -> {} [label=\"{}\"];\n",
+                node_name(edge.from.0, edge.from.1),
+                node_name(edge.to.0, edge.to.1),
+                edge.reason
+            ));
+        }
+        if let Some((conflict, literals)) = &self.conflict {
+            out.push_str(&format!("C [color=red, label=\"{}\"];\n", conflict));
+            for (lit, decision_level) in literals {
+                out.push_str(&format!(
+                    "{} -> C [color=red];\n",
+                    node_name(-*lit, *decision_level)
+                ));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let literals = |literals: &[Literal]| format!("[{}]", literals.iter().join(", "));
+        let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+        let nodes = self.nodes.iter().map(|node| {
+            format!(
+                "    {{\"literal\": {}, \"level\": {}, \"reason\": {}}}",
+                node.literal,
+                node.decision_level,
+                optional(node.reason.map(|reason| format!("\"{}\"", reason)))
+            )
+        });
+        let edges = self.edges.iter().map(|edge| {
+            format!(
+                "    {{\"from\": {}, \"to\": {}, \"reason\": \"{}\"}}",
+                edge.from.0, edge.to.0, edge.reason
+            )
+        });
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ],\n  \"conflict\": {},\n  \
+             \"conflict_literals\": {},\n  \"learned_clause\": {}\n}}\n",
+            nodes.format(",\n"),
+            edges.format(",\n"),
+            optional(self.conflict.as_ref().map(|(c, _)| format!("\"{}\"", c))),
+            optional(self.conflict.as_ref().map(|(_, literals)| {
+                format!("[{}]", literals.iter().map(|(lit, _)| lit).join(", "))
+            })),
+            optional(self.learned_clause.as_deref().map(literals)),
+        )
+    }
+}
+
+fn node_name(literal: Literal, decision_level: usize) -> String {
+    format!("\"{}@{}\"", literal, decision_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::Clause;
+    use crate::solver::clause_learning::ClauseLearner;
+
+    #[test]
+    fn conflict_graph() {
+        let cnf = vec![
+            Clause::from("-1 -2 -3"), // 0
+            Clause::from("-2 -4 -5"), // 1
+            Clause::from("3 5 6"),    // 2
+            Clause::from("-6 -7"),    // 3
+            Clause::from("-6 -8"),    // 4
+            Clause::from("7 8"),      // 5
+            Clause::from("9 10"),     // 6
+        ];
+        let mut state = State::init(cnf, 10, false);
+        let mut trail = Trail::new(state.num_vars);
+        for lit in [9, 1, 2, 4] {
+            trail.assign(&mut state, lit.into(), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+        }
+        let mut graph = ConflictGraph::new(&trail, &state);
+        let conflict = state.conflict.unwrap();
+        let (learned_clause, _) =
+            ClauseLearner::default().analyse_conflict(&mut trail, &mut state, conflict);
+        graph.learned_clause = Some(learned_clause.literals.clone());
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"1@2\" [shape=box];"));
+        assert!(dot.contains("\"1@2\" -> \"-3@3\" [label=\"clause 0\"];"));
+        assert!(dot.contains("\"6@4\" -> \"-7@4\" [label=\"clause 3\"];"));
+        assert!(dot.contains("C [color=red, label=\"clause 5\"];"));
+        assert!(dot.contains("\"-8@4\" -> C [color=red];"));
+        // the first decision isn't part of the conflict
+        assert!(!dot.contains("\"9@1\""));
+
+        let json = graph.to_json();
+        assert!(json.contains("{\"literal\": 4, \"level\": 4, \"reason\": null}"));
+        assert!(json.contains("{\"from\": 6, \"to\": -8, \"reason\": \"clause 4\"}"));
+        assert!(json.contains("\"conflict\": \"clause 5\""));
+        assert!(json.contains(&format!(
+            "\"learned_clause\": [{}]",
+            learned_clause.literals.iter().join(", ")
+        )));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::cnf::{Literal, SolveResult, Var};
+use crate::error::{self, Error};
+use crate::solver::Solver;
+use alloc::{vec, vec::Vec};
+use core::fmt::{Display, Formatter};
+use itertools::Itertools;
+
+/// Arbitrary precision model count, stored as little-endian 64 bit limbs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelCount {
+    limbs: Vec<u64>,
+}
+
+impl ModelCount {
+    pub fn add_power_of_two(&mut self, exponent: usize) {
+        let mut limb = exponent / 64;
+        let mut carry = 1u64 << (exponent % 64);
+        while carry != 0 {
+            if limb >= self.limbs.len() {
+                self.limbs.resize(limb + 1, 0);
+            }
+            let (sum, overflow) = self.limbs[limb].overflowing_add(carry);
+            self.limbs[limb] = sum;
+            carry = overflow as u64;
+            limb += 1;
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|limb| *limb == 0)
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.iter().skip(2).any(|limb| *limb != 0) {
+            return None;
+        }
+        let low = *self.limbs.first().unwrap_or(&0) as u128;
+        let high = *self.limbs.get(1).unwrap_or(&0) as u128;
+        Some(high << 64 | low)
+    }
+}
+
+impl Display for ModelCount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // repeatedly divide by 10^19, the largest power of ten fitting into a limb
+        const CHUNK: u128 = 10_000_000_000_000_000_000;
+        let mut limbs = self.limbs.clone();
+        let mut chunks = vec![];
+        while limbs.iter().any(|limb| *limb != 0) {
+            let mut remainder: u128 = 0;
+            for limb in limbs.iter_mut().rev() {
+                let current = remainder << 64 | *limb as u128;
+                *limb = (current / CHUNK) as u64;
+                remainder = current % CHUNK;
+            }
+            chunks.push(remainder as u64);
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:019}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Solver {
+    /// Counts all models of the formula exactly. Like enumeration, counting fails with
+    /// `Error::EliminatedVariable` after variables were eliminated by `solve` or `simplify`.
+    pub fn count_models(&mut self) -> error::Result<ModelCount> {
+        let projection = Var::range(self.state.num_vars).collect_vec();
+        self.count_projected_models(&projection)
+    }
+
+    /// Counts the assignments to the projection variables that can be extended to a model.
+    /// Every solution found is generalized to the cube of assigned projection variables,
+    /// as all extensions of the partial assignment are models. The cube is then blocked and
+    /// accounts for 2^k models, where k is the number of unassigned projection variables.
+    /// Like model enumeration, this disables inprocessing and requires the projection variables
+    /// not to be eliminated, which would be counted as free. A search that is stopped by a limit
+    /// or the terminate flag fails with `Error::Stopped`, as the count is incomplete.
+    /// The solver is consumed for further use: the blocking clauses stay in the formula, which is
+    /// unsatisfiable after a complete count, and inprocessing stays disabled. Later calls to
+    /// `solve`, `validate_model` or `core_clauses` need a fresh solver for the original formula.
+    pub fn count_projected_models(&mut self, projection: &[Var]) -> error::Result<ModelCount> {
+        self.check_vars(projection)?;
+        self.config.inprocessing = false;
+        let mut count = ModelCount::default();
+
+        loop {
+            match self.solve() {
+                SolveResult::Sat(_) => {}
+                SolveResult::Unsat => break,
+                SolveResult::Unknown(reason) => return Err(Error::Stopped(reason)),
+            }
+            let cube = projection
+                .iter()
+                .filter_map(|var_id| self.state.vars.value(*var_id).map(|value| (*var_id, value)))
+                .collect_vec();
+            count.add_power_of_two(projection.len() - cube.len());
+
+            let blocking_clause = cube
+                .iter()
+                .map(|(var_id, value)| Literal::from_value(*var_id, !*value))
+                .collect_vec();
+            self.add_clause_at_level_zero(blocking_clause);
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{Clause, Model, UnknownReason};
+    use crate::solver::config::Config;
+    use crate::tester::random_cnf;
+
+    fn count(clauses: &[&str], num_vars: usize, projection: Option<&[Var]>) -> ModelCount {
+        let config = Config::default();
+        let clauses = clauses.iter().map(|clause| Clause::from(*clause)).collect();
+        let mut solver = Solver::new(clauses, num_vars, config);
+        match projection {
+            Some(projection) => solver.count_projected_models(projection),
+            None => solver.count_models(),
+        }
+        .unwrap()
+    }
+
+    #[test]
+    fn count_models() {
+        assert_eq!(count(&["1 2", "-1 -2 3"], 3, None).to_u128(), Some(5));
+        assert_eq!(
+            count(&["1 2", "-1 -2 3"], 3, Some(&[Var::new(1), Var::new(2)])).to_u128(),
+            Some(3)
+        );
+        assert_eq!(count(&["1", "-1"], 1, None).to_u128(), Some(0));
+        assert_eq!(
+            count(&["1 -1 2"], 100, None).to_string(),
+            "1267650600228229401496703205376"
+        );
+    }
+
+    #[test]
+    fn display_large_count() {
+        let mut count = ModelCount::default();
+        count.add_power_of_two(200);
+        count.add_power_of_two(0);
+        assert_eq!(
+            count.to_string(),
+            "1606938044258990275541962092341162602522202993782792835301377"
+        );
+    }
+
+    #[test]
+    fn counting_blocks_all_models() {
+        let clauses = ["1 2", "-1 -2 3"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 3, Config::default());
+        let model = Model::new(vec![true, true, true]);
+        assert!(solver.validate_model(&model).is_ok());
+        assert_eq!(solver.count_models().unwrap().to_u128(), Some(5));
+        assert!(!solver.config.inprocessing);
+        assert!(solver.solve().is_unsat());
+        assert!(solver.validate_model(&model).is_err());
+    }
+
+    #[test]
+    fn count_after_simplification() {
+        let clauses = ["1 2", "-1 3", "2 3 4"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 4, Config::default());
+        solver.simplify();
+        assert!(matches!(
+            solver.count_models(),
+            Err(Error::EliminatedVariable(_))
+        ));
+
+        let projection = Var::range(4).collect_vec();
+        for seed in 0..20 {
+            let clauses = random_cnf(10, 3.0, 3, seed);
+            let mut expected = Solver::new(clauses.clone(), 10, Config::default());
+            let expected = expected.count_projected_models(&projection).unwrap();
+            let mut solver = Solver::new(clauses, 10, Config::default());
+            for var_id in &projection {
+                solver.freeze(*var_id);
+            }
+            solver.simplify();
+            let count = solver.count_projected_models(&projection).unwrap();
+            assert_eq!(count, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn stopped_count_is_an_error() {
+        let clauses = ["1 2", "-1 3"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 3, Config::default());
+        solver
+            .terminate_flag()
+            .store(true, core::sync::atomic::Ordering::Relaxed);
+        assert!(matches!(
+            solver.count_models(),
+            Err(Error::Stopped(UnknownReason::Interrupted))
+        ));
+    }
+}
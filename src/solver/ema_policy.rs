@@ -1,12 +1,10 @@
-const LBD_EMA_SHORT_TERM_ALPHA: f64 = 2.0 / 51.0; // Window size of 50
-
-// As in Biere & Fröhlich, should be between 2e-12 and 2e-18
-const LBD_EMA_LONG_TERM_ALPHA: f64 = 2e-6;
-const ASSIGNMENT_EMA_SHORT_TERM_ALPHA: f64 = 2.0 / 51.0; // Window size of 50
-const ASSIGNMENT_EMA_LONG_TERM_ALPHA: f64 = 2e-6;
-
-const MARGIN_RATIO_FORCING_RESTART: f64 = 1.15;
-const MARGIN_RATIO_BLOCKING_RESTART: f64 = 1.4;
+use crate::solver::clock::Instant;
+use crate::solver::config::Config;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(not(feature = "std"))]
+use num_traits::float::FloatCore;
 
 #[derive(Debug, Clone)]
 pub struct EMAPolicy {
@@ -14,19 +12,64 @@ pub struct EMAPolicy {
     lbd_long_term: ExponentialMovingAverage,
     assignments_short_term: ExponentialMovingAverage,
     assignments_long_term: ExponentialMovingAverage,
+    margin_ratio_forcing_restart: f64,
+    margin_ratio_blocking_restart: f64,
+    trail_size_percentile: Option<f64>,
+    /// Number of recent trail sizes considered for the percentile based restart blocking
+    trail_size_window: usize,
+    trail_size_percentile_interval: usize,
+    min_conflicts: usize,
+    /// Blocking is lifted once the last restart is too long ago
+    max_time_restart_blocked: Option<Duration>,
+    /// Replaces the time limit in deterministic mode
+    max_conflicts_restart_blocked: Option<usize>,
+    last_restart: Instant,
+    recent_trail_sizes: VecDeque<usize>,
+    trail_size_percentile_value: usize,
+    last_trail_size: usize,
+    conflicts_since_percentile_update: usize,
 }
 
 impl EMAPolicy {
-    pub fn init() -> Self {
+    pub fn init(config: &Config) -> Self {
+        let params = &config.params;
+        // without a clock, the time since the last restart can't be measured
+        let deterministic = config.deterministic || cfg!(not(feature = "std"));
         EMAPolicy {
-            lbd_short_term: ExponentialMovingAverage::init(LBD_EMA_SHORT_TERM_ALPHA),
-            lbd_long_term: ExponentialMovingAverage::init(LBD_EMA_LONG_TERM_ALPHA),
-            assignments_short_term: ExponentialMovingAverage::init(ASSIGNMENT_EMA_SHORT_TERM_ALPHA),
-            assignments_long_term: ExponentialMovingAverage::init(ASSIGNMENT_EMA_LONG_TERM_ALPHA),
+            lbd_short_term: ExponentialMovingAverage::init(params.lbd_ema_short_term_alpha),
+            lbd_long_term: ExponentialMovingAverage::init(params.lbd_ema_long_term_alpha),
+            assignments_short_term: ExponentialMovingAverage::init(
+                params.assignment_ema_short_term_alpha,
+            ),
+            assignments_long_term: ExponentialMovingAverage::init(
+                params.assignment_ema_long_term_alpha,
+            ),
+            margin_ratio_forcing_restart: params.margin_ratio_forcing_restart,
+            margin_ratio_blocking_restart: params.margin_ratio_blocking_restart,
+            trail_size_percentile: (params.restart_blocking_trail_percentile > 0.0)
+                .then_some(params.restart_blocking_trail_percentile),
+            trail_size_window: params.trail_size_window,
+            trail_size_percentile_interval: params.trail_size_percentile_interval,
+            min_conflicts: params.restart_min_conflicts,
+            max_time_restart_blocked: (!deterministic && params.max_time_restart_blocked > 0.0)
+                .then(|| Duration::from_secs_f64(params.max_time_restart_blocked)),
+            max_conflicts_restart_blocked: (deterministic
+                && params.max_conflicts_restart_blocked > 0)
+                .then_some(params.max_conflicts_restart_blocked),
+            last_restart: Instant::now(),
+            recent_trail_sizes: VecDeque::new(),
+            trail_size_percentile_value: usize::MAX,
+            last_trail_size: 0,
+            conflicts_since_percentile_update: 0,
         }
     }
 
     pub fn conflict(&mut self, learned_clause_lbd: usize, num_current_assignments: usize) {
+        if self.trail_size_percentile.is_some() {
+            self.update_trail_size_percentile(num_current_assignments);
+        }
+        self.last_trail_size = num_current_assignments;
+
         let learned_clause_lbd = learned_clause_lbd as f64;
         self.lbd_long_term.update(learned_clause_lbd);
         self.lbd_short_term.update(learned_clause_lbd);
@@ -54,16 +97,57 @@ impl EMAPolicy {
     pub fn check_if_restart_necessary(&self, conflicts_since_last_restart: usize) -> bool {
         // open log.csv
 
-        conflicts_since_last_restart >= 50 && self.restart_necessary() && !self.restart_blocked()
+        conflicts_since_last_restart >= self.min_conflicts
+            && self.restart_necessary()
+            && !self.restart_blocked(conflicts_since_last_restart)
+    }
+
+    /// Starts the time limit of restart blocking anew
+    pub fn restarted(&mut self) {
+        self.last_restart = Instant::now();
     }
 
     fn restart_necessary(&self) -> bool {
-        self.lbd_short_term.value > MARGIN_RATIO_FORCING_RESTART * self.lbd_long_term.value
+        self.lbd_short_term.value > self.margin_ratio_forcing_restart * self.lbd_long_term.value
     }
 
-    fn restart_blocked(&self) -> bool {
+    fn restart_blocked(&self, conflicts_since_last_restart: usize) -> bool {
+        // blocking is lifted once the last restart is too long ago
+        if let Some(max_time) = self.max_time_restart_blocked {
+            if self.last_restart.elapsed() >= max_time {
+                return false;
+            }
+        }
+        if let Some(max_conflicts) = self.max_conflicts_restart_blocked {
+            if conflicts_since_last_restart >= max_conflicts {
+                return false;
+            }
+        }
+
         self.assignments_short_term.value
-            > MARGIN_RATIO_BLOCKING_RESTART * self.assignments_long_term.value
+            > self.margin_ratio_blocking_restart * self.assignments_long_term.value
+            || self.last_trail_size > self.trail_size_percentile_value
+    }
+
+    /// Keeps a window of recent trail sizes and periodically recomputes the configured percentile.
+    /// Restarts are blocked while the trail is larger than this percentile.
+    fn update_trail_size_percentile(&mut self, num_current_assignments: usize) {
+        self.recent_trail_sizes.push_back(num_current_assignments);
+        if self.recent_trail_sizes.len() > self.trail_size_window {
+            self.recent_trail_sizes.pop_front();
+        }
+
+        self.conflicts_since_percentile_update += 1;
+        if self.conflicts_since_percentile_update < self.trail_size_percentile_interval {
+            return;
+        }
+        self.conflicts_since_percentile_update = 0;
+
+        let percentile = self.trail_size_percentile.unwrap();
+        let mut sizes = self.recent_trail_sizes.iter().copied().collect::<Vec<_>>();
+        let index = ((sizes.len() - 1) as f64 * percentile).round() as usize;
+        let (_, value, _) = sizes.select_nth_unstable(index);
+        self.trail_size_percentile_value = *value;
     }
 }
 
@@ -0,0 +1,154 @@
+use crate::cnf::{Literal, Model, Var};
+use crate::error;
+use crate::solver::Solver;
+use alloc::vec::Vec;
+use itertools::Itertools;
+
+/// Iterator over the models of a formula. Every model found is blocked by adding a clause over
+/// the projection variables before the search continues, so the models differ on them.
+pub struct ModelIterator<'a> {
+    solver: &'a mut Solver,
+    projection: Vec<Var>,
+    remaining: Option<usize>,
+}
+
+impl Iterator for ModelIterator<'_> {
+    type Item = Model;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        let model = self.solver.solve().into_model()?;
+        let blocking_clause = self
+            .projection
+            .iter()
+            .map(|var_id| Literal::from_value(*var_id, !model[*var_id]))
+            .collect_vec();
+        self.solver.add_clause_at_level_zero(blocking_clause);
+
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        Some(model)
+    }
+}
+
+impl Solver {
+    /// Enumerates up to `limit` models of the formula (all models if `None`).
+    /// Inprocessing is disabled for the solver, as eliminated variables can't be blocked.
+    /// Enumeration should therefore start before the first call to `solve` or `simplify`,
+    /// otherwise it fails with `Error::EliminatedVariable`.
+    pub fn enumerate_models(&mut self, limit: Option<usize>) -> error::Result<ModelIterator<'_>> {
+        let projection = Var::range(self.state.num_vars).collect_vec();
+        self.enumerate_projected_models(limit, &projection)
+    }
+
+    /// Enumerates up to `limit` models projected onto the given variables, i.e. each
+    /// assignment to the projection variables that can be extended to a model is returned once.
+    /// Only the projection variables have to be kept from elimination by earlier calls, e.g. by
+    /// freezing them, see `Solver::freeze`.
+    /// The solver is consumed for further use: the blocking clauses are added to the formula and
+    /// inprocessing stays disabled, so later calls to `solve`, `validate_model` or `core_clauses`
+    /// see the formula without the enumerated models. Use a fresh solver for the original one.
+    pub fn enumerate_projected_models(
+        &mut self,
+        limit: Option<usize>,
+        projection: &[Var],
+    ) -> error::Result<ModelIterator<'_>> {
+        self.check_vars(projection)?;
+        self.config.inprocessing = false;
+        Ok(ModelIterator {
+            solver: self,
+            projection: projection.to_vec(),
+            remaining: limit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cnf::{Clause, Var};
+    use crate::error::Error;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+    use crate::tester::random_cnf;
+    use itertools::Itertools;
+
+    fn create_solver(clauses: &[&str], num_vars: usize) -> Solver {
+        let config = Config::default();
+        let clauses = clauses.iter().map(|clause| Clause::from(*clause)).collect();
+        Solver::new(clauses, num_vars, config)
+    }
+
+    #[test]
+    fn enumerate_all_models() {
+        let mut solver = create_solver(&["1 2", "-1 -2 3"], 3);
+        // 6 assignments satisfy the first clause, only 1 2 -3 violates the second
+        assert_eq!(solver.enumerate_models(None).unwrap().count(), 5);
+    }
+
+    #[test]
+    fn enumerate_with_limit_and_projection() {
+        let mut solver = create_solver(&["1 2", "-1 -2 3"], 3);
+        assert_eq!(solver.enumerate_models(Some(2)).unwrap().count(), 2);
+
+        let mut solver = create_solver(&["1 2", "-1 -2 3"], 3);
+        let models = solver
+            .enumerate_projected_models(None, &[Var::new(1), Var::new(2)])
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(models.len(), 3);
+        assert!(models
+            .iter()
+            .map(|model| (model[Var::new(1)], model[Var::new(2)]))
+            .all_unique());
+    }
+
+    #[test]
+    fn enumerate_unsat() {
+        let mut solver = create_solver(&["1", "-1"], 1);
+        assert_eq!(solver.enumerate_models(None).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn enumeration_blocks_the_models() {
+        let mut solver = create_solver(&["1 2", "-1 -2 3"], 3);
+        assert_eq!(solver.enumerate_models(Some(4)).unwrap().count(), 4);
+        let model = solver.solve().into_model().unwrap();
+        assert!(!solver.config.inprocessing);
+        // the fifth model is only blocked by enumerating it
+        assert_eq!(solver.enumerate_models(None).unwrap().count(), 1);
+        assert!(solver.solve().is_unsat());
+        assert!(solver.validate_model(&model).is_err());
+    }
+
+    #[test]
+    fn enumerate_after_simplification() {
+        let mut solver = create_solver(&["1 2", "-1 3", "2 3 4"], 4);
+        solver.simplify();
+        assert!(matches!(
+            solver.enumerate_models(None),
+            Err(Error::EliminatedVariable(_))
+        ));
+
+        let projection = Var::range(4).collect_vec();
+        for seed in 0..20 {
+            let clauses = random_cnf(10, 3.0, 3, seed);
+            let mut expected = Solver::new(clauses.clone(), 10, Config::default());
+            let expected = expected
+                .enumerate_projected_models(None, &projection)
+                .unwrap()
+                .count();
+            let mut solver = Solver::new(clauses, 10, Config::default());
+            for var_id in &projection {
+                solver.freeze(*var_id);
+            }
+            solver.simplify();
+            let models = solver
+                .enumerate_projected_models(None, &projection)
+                .unwrap()
+                .count();
+            assert_eq!(models, expected, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::cnf::{Literal, Var};
+use alloc::{boxed::Box, vec, vec::Vec};
+use hashbrown::HashMap;
+
+/// User propagator in the style of IPASIR-UP, as described in K. Fazekas, A. Niemetz,
+/// M. Preiner, M. Kirchweger, S. Szeider, and A. Biere, “IPASIR-UP: User Propagators for CDCL,”
+/// in Theory and Applications of Satisfiability Testing – SAT 2023, pp. 8:1–8:13.
+/// The solver notifies the propagator about the assignments of the trail, so a theory can be
+/// checked lazily and propagate literals on top of the clauses.
+pub trait ExternalPropagator {
+    /// New assignments in trail order, all at the current decision level
+    fn notify_assignment(&mut self, literals: &[Literal]);
+
+    /// A decision opens a new level, followed by the notification of the decision literal
+    fn notify_new_decision_level(&mut self) {}
+
+    /// All assignments above the given decision level have been undone
+    fn notify_backtrack(&mut self, decision_level: usize);
+
+    /// Called before every decision of the heuristic. Returning an unassigned literal
+    /// overrides it.
+    fn decide(&mut self) -> Option<Literal> {
+        None
+    }
+
+    /// Called at every propagation fixpoint of the clauses. Returned literals are implied by
+    /// the theory. Implying a false literal is a conflict.
+    fn propagate(&mut self) -> Vec<Literal> {
+        vec![]
+    }
+
+    /// Reason clause of a literal returned by `propagate`, containing the literal itself, all
+    /// other literals must be false. It has to follow from the clauses and the theory.
+    fn add_reason_clause(&mut self, propagated: Literal) -> Vec<Literal>;
+}
+
+pub(crate) struct External {
+    pub propagator: Box<dyn ExternalPropagator>,
+    /// Variables that are always assigned in models found, so the theory can check them
+    pub observed_vars: Vec<Var>,
+}
+
+/// Reason clauses of the external propagations, stored by the implied variable
+#[derive(Debug, Clone, Default)]
+pub struct ExternalReasons {
+    reasons: HashMap<Var, Vec<Literal>>,
+    conflict: Vec<Literal>,
+}
+
+impl ExternalReasons {
+    pub fn add_reason(&mut self, implied: Literal, reason: Vec<Literal>) {
+        self.reasons.insert(implied.var(), reason);
+    }
+
+    pub fn set_conflict(&mut self, conflict: Vec<Literal>) {
+        self.conflict = conflict;
+    }
+
+    /// Reason clause of the implied literal, or the conflict clause for `None`
+    pub fn explain(&self, implied: Option<Literal>) -> &[Literal] {
+        match implied {
+            Some(lit) => &self.reasons[&lit.var()],
+            None => &self.conflict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{check_assignment, Clause};
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+    use itertools::Itertools;
+
+    /// At most one pigeon per hole, checked on the notified assignment
+    struct AtMostOnePropagator {
+        groups: Vec<Vec<Var>>,
+        assigned: Vec<Literal>,
+        level_starts: Vec<usize>,
+    }
+
+    impl ExternalPropagator for AtMostOnePropagator {
+        fn notify_assignment(&mut self, literals: &[Literal]) {
+            self.assigned.extend_from_slice(literals);
+        }
+
+        fn notify_new_decision_level(&mut self) {
+            self.level_starts.push(self.assigned.len());
+        }
+
+        fn notify_backtrack(&mut self, decision_level: usize) {
+            self.assigned.truncate(
+                self.level_starts
+                    .get(decision_level)
+                    .copied()
+                    .unwrap_or(self.assigned.len()),
+            );
+            self.level_starts.truncate(decision_level);
+        }
+
+        fn propagate(&mut self) -> Vec<Literal> {
+            let is_true =
+                |var_id: &Var| self.assigned.contains(&Literal::from_value(*var_id, true));
+            let is_false =
+                |var_id: &Var| self.assigned.contains(&Literal::from_value(*var_id, false));
+            self.groups
+                .iter()
+                .filter(|group| group.iter().any(is_true))
+                .flat_map(|group| {
+                    let true_var = group.iter().find(|var_id| is_true(var_id)).unwrap();
+                    group
+                        .iter()
+                        .filter(move |var_id| *var_id != true_var && !is_false(var_id))
+                        .map(|var_id| Literal::from_value(*var_id, false))
+                })
+                .collect()
+        }
+
+        fn add_reason_clause(&mut self, propagated: Literal) -> Vec<Literal> {
+            let group = self
+                .groups
+                .iter()
+                .find(|group| group.contains(&propagated.var()))
+                .unwrap();
+            let true_var = group
+                .iter()
+                .find(|var_id| {
+                    **var_id != propagated.var()
+                        && self.assigned.contains(&Literal::from_value(**var_id, true))
+                })
+                .unwrap();
+            vec![propagated, Literal::from_value(*true_var, false)]
+        }
+    }
+
+    /// Pigeon p sits in hole h if variable p * holes + h + 1 is true
+    fn solve_pigeons(pigeons: usize, holes: usize) -> Option<bool> {
+        let var = |pigeon: usize, hole: usize| Var::new(pigeon * holes + hole + 1);
+        let clauses = (0..pigeons)
+            .map(|p| {
+                Clause::from(
+                    (0..holes)
+                        .map(|h| Literal::from_value(var(p, h), true))
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+        let groups = (0..holes)
+            .map(|h| (0..pigeons).map(|p| var(p, h)).collect_vec())
+            .collect_vec();
+        let config = Config::default();
+        let num_vars = pigeons * holes;
+        let mut solver = Solver::new(clauses.clone(), num_vars, config);
+        let propagator = AtMostOnePropagator {
+            groups: groups.clone(),
+            assigned: vec![],
+            level_starts: vec![],
+        };
+        solver.connect_external_propagator(propagator, &Var::range(num_vars).collect_vec());
+
+        let model = solver.solve().into_model()?;
+        let at_most_one = groups
+            .iter()
+            .all(|group| group.iter().filter(|var_id| model[**var_id]).count() <= 1);
+        Some(check_assignment(&clauses, &model) && at_most_one)
+    }
+
+    #[test]
+    fn pigeons_with_external_propagator() {
+        assert_eq!(solve_pigeons(5, 5), Some(true));
+        assert_eq!(solve_pigeons(6, 5), None);
+    }
+}
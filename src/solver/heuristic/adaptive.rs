@@ -0,0 +1,122 @@
+use crate::solver::heuristic::HeuristicType;
+use crate::solver::statistics::StateStatistics;
+
+/// Counters at the start of a period, i.e. since the last switch or comparison
+#[derive(Debug, Clone, Default)]
+struct PeriodStart {
+    num_conflicts: usize,
+    num_decisions: usize,
+    lbd_sum: usize,
+    lbd_count: usize,
+}
+
+impl PeriodStart {
+    fn new(stats: &StateStatistics) -> Self {
+        PeriodStart {
+            num_conflicts: stats.num_conflicts,
+            num_decisions: stats.num_decisions,
+            lbd_sum: stats.learned_lbds.sum(),
+            lbd_count: stats.learned_lbds.count(),
+        }
+    }
+}
+
+/// Decides when `--heuristic adaptive` switches between VSIDS and VMTF. The solver asks at
+/// restarts and replaces its heuristic, seeding the new one with the scores of the old one.
+/// After every `heuristic_switch_interval` conflicts, the quality of the period is compared to
+/// the last period of the other heuristic: conflicts per decision divided by the mean LBD of the
+/// learned clauses, as a heuristic that finds conflicts quickly and learns clauses of low LBD
+/// makes progress. The first comparison always tries the other heuristic. Afterwards, the
+/// heuristic changes once its period is worse, e.g. once the LBD trends upwards.
+#[derive(Debug, Clone)]
+pub struct HeuristicSwitcher {
+    current: HeuristicType,
+    interval: usize,
+    period_start: PeriodStart,
+    /// Quality of the last period of the other heuristic
+    other_quality: Option<f64>,
+}
+
+impl HeuristicSwitcher {
+    /// Starts with VSIDS
+    pub fn new(interval: usize) -> Self {
+        HeuristicSwitcher {
+            current: HeuristicType::VSIDS,
+            interval,
+            period_start: PeriodStart::default(),
+            other_quality: None,
+        }
+    }
+
+    pub fn current(&self) -> &HeuristicType {
+        &self.current
+    }
+
+    /// The heuristic to switch to, if the period is over and the other one did better
+    pub fn restart(&mut self, stats: &StateStatistics) -> Option<HeuristicType> {
+        if stats.num_conflicts - self.period_start.num_conflicts < self.interval {
+            return None;
+        }
+        let quality = self.quality(stats);
+        self.period_start = PeriodStart::new(stats);
+        if self.other_quality.is_some_and(|other| quality >= other) {
+            return None;
+        }
+        self.other_quality = Some(quality);
+        self.current = match self.current {
+            HeuristicType::VSIDS => HeuristicType::VMTF,
+            _ => HeuristicType::VSIDS,
+        };
+        Some(self.current.clone())
+    }
+
+    fn quality(&self, stats: &StateStatistics) -> f64 {
+        let start = &self.period_start;
+        let conflicts = (stats.num_conflicts - start.num_conflicts) as f64;
+        let decisions = (stats.num_decisions - start.num_decisions).max(1) as f64;
+        let lbd_count = (stats.learned_lbds.count() - start.lbd_count).max(1) as f64;
+        let mean_lbd = ((stats.learned_lbds.sum() - start.lbd_sum) as f64 / lbd_count).max(1.0);
+        conflicts / decisions / mean_lbd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Statistics after a period of 100 conflicts with the given decisions and LBD
+    fn period(stats: &mut StateStatistics, decisions: usize, lbd: usize) {
+        for _ in 0..100 {
+            stats.learned_lbds.add(lbd);
+        }
+        stats.num_conflicts += 100;
+        stats.num_decisions += decisions;
+    }
+
+    #[test]
+    fn switches_to_the_better_heuristic() {
+        let mut switcher = HeuristicSwitcher::new(100);
+        let mut stats = StateStatistics {
+            num_conflicts: 50,
+            ..Default::default()
+        };
+        assert!(switcher.restart(&stats).is_none());
+
+        // VSIDS: 0.5 conflicts per decision with LBD 5
+        period(&mut stats, 200, 5);
+        assert!(matches!(
+            switcher.restart(&stats),
+            Some(HeuristicType::VMTF)
+        ));
+        // VMTF does better
+        period(&mut stats, 100, 4);
+        assert!(switcher.restart(&stats).is_none());
+        // until its LBD grows
+        period(&mut stats, 100, 12);
+        assert!(matches!(
+            switcher.restart(&stats),
+            Some(HeuristicType::VSIDS)
+        ));
+        assert!(matches!(switcher.current(), HeuristicType::VSIDS));
+    }
+}
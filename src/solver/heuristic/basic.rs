@@ -1,27 +1,31 @@
-use crate::cnf::VarId;
-use crate::solver::heuristic::Heuristic;
+use crate::cnf::{PartialAssignment, Var};
+use crate::solver::heuristic::{Decision, Heuristic};
 use crate::solver::state::State;
 use crate::solver::trail::Assignment;
+use alloc::vec::Vec;
 
+/// Decides the first unassigned variable, trying `true` first unless a value is preferred
 pub struct HeuristicTrue {
-    pub order: Vec<(VarId, bool)>,
+    pub order: Vec<Var>,
 }
 
 impl Heuristic for HeuristicTrue {
     fn init(state: &State) -> Self {
-        let order = (1..=state.num_vars).map(|id| (id, true)).collect();
-        // reverse
+        let order = Var::range(state.num_vars).collect();
         HeuristicTrue { order }
     }
 
     fn unassign(&mut self, assignment: &Assignment) {
-        self.order.push(assignment.literal.id_val());
+        self.order.push(assignment.literal.var());
     }
 
-    fn next(&mut self, vars: &[Option<bool>]) -> VarId {
-        for (id, _) in self.order.iter() {
-            if vars[*id].is_none() {
-                return *id;
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
+        for id in self.order.iter() {
+            if !vars.is_assigned(*id) {
+                return Decision {
+                    var: *id,
+                    phase_hint: Some(true),
+                };
             }
         }
         panic!("No unassigned literal found");
@@ -1,83 +1,146 @@
-use crate::cnf::VarId;
-use crate::solver::heuristic::Heuristic;
+use crate::cnf::{PartialAssignment, Var};
+use crate::solver::heuristic::heap::VarHeap;
+use crate::solver::heuristic::{Decision, Heuristic};
 use crate::solver::state::State;
 use crate::solver::trail::Assignment;
-use itertools::Itertools;
+use alloc::{vec, vec::Vec};
 
-#[derive(Default)]
-pub struct HeuristicDecay {
-    pub order: Vec<(VarId, f64)>,
-
-    pub positions: Vec<usize>,
+/// Values and the increment are scaled down once a value exceeds this limit
+const RESCALE_LIMIT: f64 = 1e100;
 
-    pub branch_count: usize,
+/// Prefers recently unassigned variables: every unassignment adds to the value of the variable
+/// and all values decay every `Params::decay_interval` decisions. Like in EVSIDS, the decay grows
+/// the increment instead of touching all values, so the indexed heap keeps its order and the
+/// next variable is found in logarithmic time.
+pub struct HeuristicDecay {
+    order: VarHeap,
+    values: Vec<f64>,
+    increment: f64,
+    num_decisions: usize,
+    /// See `Params::decay_interval` and `Params::decay_factor`
+    decay_interval: usize,
+    decay_factor: f64,
 }
 
 impl HeuristicDecay {
-    fn recalc_positions(&mut self) {
-        for (i, (var_id, _)) in self.order.iter().enumerate() {
-            self.positions[*var_id] = i;
+    fn rescale(&mut self) {
+        for value in &mut self.values {
+            *value /= RESCALE_LIMIT;
         }
+        self.increment /= RESCALE_LIMIT;
     }
+}
 
-    pub fn initialize(&mut self, state: &State) {
-        // start out with all variables having a heuristic value of 1 and set to true
-        self.order = (1..=state.vars.len()).map(|id| (id, 1.0)).collect_vec();
-
-        self.positions = (0..=state.vars.len()).collect_vec();
+impl Heuristic for HeuristicDecay {
+    fn init(state: &State) -> Self {
+        // start out with all variables having a heuristic value of 1
+        let values = vec![1.0; state.num_vars + 1];
+        let mut order = VarHeap::new(state.num_vars);
+        for var_id in Var::range(state.num_vars) {
+            order.insert(var_id, &values);
+        }
+        HeuristicDecay {
+            order,
+            values,
+            increment: 1.0,
+            num_decisions: 0,
+            decay_interval: state.params.decay_interval,
+            decay_factor: state.params.decay_factor,
+        }
+    }
 
-        self.recalc_positions();
+    fn seed_scores(&mut self, scores: &[f64]) {
+        self.values = scores.iter().map(|score| 1.0 + score).collect();
+        self.increment = 1.0;
+        self.order = VarHeap::new(self.values.len() - 1);
+        for var_id in Var::range(self.values.len() - 1) {
+            self.order.insert(var_id, &self.values);
+        }
     }
 
-    pub fn choose_literal(&mut self, vars: &[Option<bool>]) -> VarId {
-        self.branch_count += 1;
+    fn unassign(&mut self, assignment: &Assignment) {
+        let var_id = assignment.literal.var();
+        self.values[var_id] += self.increment;
+        if self.values[var_id] > RESCALE_LIMIT {
+            self.rescale();
+        }
+        if self.order.contains(var_id) {
+            self.order.increased(var_id, &self.values);
+        } else {
+            self.order.insert(var_id, &self.values);
+        }
+    }
 
-        // decay the heuristic values every 100 branches
-        if self.branch_count > 100 {
-            self.branch_count = 0;
-            self.order = self
-                .order
-                .iter()
-                .map(|(id, heuristic_value)| {
-                    let new_heuristic_value = heuristic_value * 0.95;
-                    (*id, new_heuristic_value)
-                })
-                .collect_vec();
+    fn peek(&mut self, vars: &PartialAssignment) -> Option<Var> {
+        // assigned variables are inserted again when they are unassigned
+        while let Some(var_id) = self.order.top() {
+            if !vars.is_assigned(var_id) {
+                return Some(var_id);
+            }
+            self.order.pop(&self.values);
+        }
+        None
+    }
 
-            // sort the list by the heuristic value
-            self.order
-                .sort_by(|(_, heuristic_value1), (_, heuristic_value2)| {
-                    heuristic_value2.partial_cmp(heuristic_value1).unwrap()
-                });
+    fn prefers(&self, var_id: Var, other: Var) -> bool {
+        self.values[var_id] > self.values[other]
+    }
 
-            self.recalc_positions();
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
+        self.num_decisions += 1;
+        if self.num_decisions.is_multiple_of(self.decay_interval) {
+            self.increment /= self.decay_factor;
+            if self.increment > RESCALE_LIMIT {
+                self.rescale();
+            }
         }
-
-        // return the first element that is not assigned
-        for (var_id, _) in &self.order {
-            if vars[*var_id].is_none() {
-                return *var_id;
+        loop {
+            let var_id = self
+                .order
+                .pop(&self.values)
+                .expect("No unassigned variable found");
+            if !vars.is_assigned(var_id) {
+                return Decision::new(var_id);
             }
         }
-        panic!("No unassigned literal found");
     }
 }
 
-impl Heuristic for HeuristicDecay {
-    fn init(state: &State) -> Self {
-        let mut manager = HeuristicDecay::default();
-        manager.initialize(state);
-        manager
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{Clause, Literal};
+    use crate::solver::trail::AssignmentReason;
 
-    fn unassign(&mut self, assignment: &Assignment) {
-        // increase the key of the var by one
-        let (var_id, heuristic_value) = &mut self.order[self.positions[assignment.literal.id()]];
-        debug_assert_eq!(*var_id, assignment.literal.id());
-        *heuristic_value += 1.0;
-    }
+    #[test]
+    fn prefers_recently_unassigned_variables() {
+        let state = State::init(vec![Clause::from("1 2 3 4")], 4, false);
+        let mut heuristic = HeuristicDecay::init(&state);
+        let mut vars = PartialAssignment::new(4);
+        let unassign = |heuristic: &mut HeuristicDecay, var_id: i32| {
+            heuristic.unassign(&Assignment {
+                literal: Literal::new(var_id),
+                decision_level: 1,
+                reason: AssignmentReason::Heuristic,
+            })
+        };
 
-    fn next(&mut self, vars: &[Option<bool>]) -> VarId {
-        self.choose_literal(vars)
+        for var_id in [3, 2, 3] {
+            unassign(&mut heuristic, var_id);
+        }
+        for _ in 0..heuristic.decay_interval {
+            heuristic.num_decisions += 1;
+            heuristic.increment /= heuristic.decay_factor;
+        }
+        // a single later unassignment outweighs the two earlier ones after the decays
+        unassign(&mut heuristic, 4);
+        let order = (0..4)
+            .map(|_| {
+                let var_id = heuristic.next(&vars).var;
+                vars.assign(Literal::from_value(var_id, true));
+                var_id.index()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, [4, 3, 2, 1]);
     }
 }
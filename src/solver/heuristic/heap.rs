@@ -0,0 +1,127 @@
+use crate::cnf::Var;
+use alloc::{vec, vec::Vec};
+
+const NOT_IN_HEAP: usize = usize::MAX;
+
+/// Binary max-heap of variables ordered by an external activity, with the position of every
+/// variable stored so its key can be increased in place, like the heap of Minisat.
+#[derive(Debug, Clone, Default)]
+pub struct VarHeap {
+    heap: Vec<Var>,
+    positions: Vec<usize>,
+}
+
+impl VarHeap {
+    pub fn new(num_vars: usize) -> Self {
+        VarHeap {
+            heap: Vec::with_capacity(num_vars),
+            positions: vec![NOT_IN_HEAP; num_vars + 1],
+        }
+    }
+
+    pub fn contains(&self, var_id: Var) -> bool {
+        self.positions[var_id] != NOT_IN_HEAP
+    }
+
+    pub fn insert(&mut self, var_id: Var, activity: &[f64]) {
+        if self.contains(var_id) {
+            return;
+        }
+        self.positions[var_id] = self.heap.len();
+        self.heap.push(var_id);
+        self.sift_up(self.heap.len() - 1, activity);
+    }
+
+    /// Restores the heap property after the activity of the variable was increased
+    pub fn increased(&mut self, var_id: Var, activity: &[f64]) {
+        if self.contains(var_id) {
+            self.sift_up(self.positions[var_id], activity);
+        }
+    }
+
+    pub fn top(&self) -> Option<Var> {
+        self.heap.first().copied()
+    }
+
+    pub fn pop(&mut self, activity: &[f64]) -> Option<Var> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+        self.positions[top] = NOT_IN_HEAP;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.positions[last] = 0;
+            self.sift_down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut pos: usize, activity: &[f64]) {
+        let var_id = self.heap[pos];
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if activity[self.heap[parent]] >= activity[var_id] {
+                break;
+            }
+            self.heap[pos] = self.heap[parent];
+            self.positions[self.heap[pos]] = pos;
+            pos = parent;
+        }
+        self.heap[pos] = var_id;
+        self.positions[var_id] = pos;
+    }
+
+    fn sift_down(&mut self, mut pos: usize, activity: &[f64]) {
+        let var_id = self.heap[pos];
+        loop {
+            let left = 2 * pos + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len()
+                && activity[self.heap[right]] > activity[self.heap[left]]
+            {
+                right
+            } else {
+                left
+            };
+            if activity[self.heap[child]] <= activity[var_id] {
+                break;
+            }
+            self.heap[pos] = self.heap[child];
+            self.positions[self.heap[pos]] = pos;
+            pos = child;
+        }
+        self.heap[pos] = var_id;
+        self.positions[var_id] = pos;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn pops_by_decreasing_activity() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let num_vars = 100;
+        let mut activity = (0..=num_vars).map(|_| rng.gen::<f64>()).collect::<Vec<_>>();
+        let mut heap = VarHeap::new(num_vars);
+        for var_id in Var::range(num_vars) {
+            heap.insert(var_id, &activity);
+        }
+        for var_id in Var::range(num_vars).step_by(7) {
+            activity[var_id] += 1.0;
+            heap.increased(var_id, &activity);
+        }
+
+        let mut last = f64::INFINITY;
+        while let Some(var_id) = heap.pop(&activity) {
+            assert!(activity[var_id] <= last);
+            assert!(!heap.contains(var_id));
+            last = activity[var_id];
+        }
+    }
+}
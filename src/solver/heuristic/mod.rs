@@ -1,13 +1,38 @@
+pub mod adaptive;
 pub mod basic;
 pub mod decay;
+mod heap;
+pub mod priority;
+pub mod stabilizing;
 mod vmtf;
 mod vsids;
 
-use crate::cnf::{Clause, VarId};
+use crate::cnf::{Clause, PartialAssignment, Var};
+use crate::solver::phases::jeroslow_wang_scores;
 use crate::solver::state::State;
 use crate::solver::trail::Assignment;
+use alloc::{boxed::Box, vec, vec::Vec};
+#[cfg(feature = "std")]
 use clap::ValueEnum;
 
+/// A variable chosen by the heuristic. Phase-aware heuristics may hint the value to try first,
+/// but the `PhaseSelector` decides the value, see `PhaseSelector::decide`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub var: Var,
+    pub phase_hint: Option<bool>,
+}
+
+impl Decision {
+    /// A decision leaving the value to the phase mode
+    pub fn new(var: Var) -> Self {
+        Decision {
+            var,
+            phase_hint: None,
+        }
+    }
+}
+
 pub trait Heuristic {
     fn init(state: &State) -> Self
     where
@@ -18,19 +43,56 @@ pub trait Heuristic {
         // by default, do nothing
     }
 
-    fn next(&mut self, vars: &[Option<bool>]) -> VarId;
+    fn next(&mut self, vars: &PartialAssignment) -> Decision;
+
+    /// The variable `next` would return, without deciding it. Heuristics returning `None` don't
+    /// support reusing the trail on restarts.
+    fn peek(&mut self, _vars: &PartialAssignment) -> Option<Var> {
+        None
+    }
+
+    /// Whether the variable would be decided before the other one if both were unassigned
+    fn prefers(&self, _var_id: Var, _other: Var) -> bool {
+        false
+    }
+
+    /// Scores of the variables 0..=num_vars for checkpoints, if the heuristic has any
+    fn scores(&self) -> Option<Vec<f64>> {
+        None
+    }
+
+    /// Continues with the scores of a checkpoint
+    fn restore_scores(&mut self, _scores: &[f64]) {
+        // by default, there are no scores to restore
+    }
+
+    /// Initial scores of the variables 0..=num_vars from `Config::score_init`, at most 1 and
+    /// higher for the variables to decide first
+    fn seed_scores(&mut self, _scores: &[f64]) {
+        // by default, the heuristic keeps its own initial order
+    }
+
+    /// Switches between the focused and the stable mode of the `Stabilizer`
+    fn stabilize(&mut self, _stable: bool) {
+        // by default, both modes decide the same way
+    }
 }
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
 pub enum HeuristicType {
-    #[clap(name = "decay")]
+    #[cfg_attr(feature = "std", clap(name = "decay"))]
     Decay,
-    #[clap(name = "true-first")]
+    #[cfg_attr(feature = "std", clap(name = "true-first"))]
     TrueFirst,
-    #[clap(name = "vmtf")]
+    #[cfg_attr(feature = "std", clap(name = "vmtf"))]
     VMTF,
-    #[clap(name = "vsids")]
+    #[cfg_attr(feature = "std", clap(name = "vsids"))]
     VSIDS,
+    /// Starts with VSIDS and switches between VSIDS and VMTF at restarts, whichever found more
+    /// conflicts per decision with learned clauses of lower LBD
+    #[cfg_attr(feature = "std", clap(name = "adaptive"))]
+    Adaptive,
 }
 
 impl HeuristicType {
@@ -39,7 +101,68 @@ impl HeuristicType {
             HeuristicType::Decay => Box::new(decay::HeuristicDecay::init(state)),
             HeuristicType::TrueFirst => Box::new(basic::HeuristicTrue::init(state)),
             HeuristicType::VMTF => Box::new(vmtf::HeuristicVMTF::init(state)),
-            HeuristicType::VSIDS => Box::new(vsids::HeuristicVSIDS::init(state)),
+            HeuristicType::VSIDS | HeuristicType::Adaptive => {
+                Box::new(vsids::HeuristicVSIDS::init(state))
+            }
         }
     }
 }
+
+/// Scores the heuristic and the phases start with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum ScoreInit {
+    /// Every heuristic starts with its own order, VSIDS by the number of occurrences, and the
+    /// phases with `true`, unless the phase mode says otherwise
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "default"))]
+    Default,
+    /// The number of occurrences of the literals
+    #[cfg_attr(feature = "std", clap(name = "literal-count"))]
+    LiteralCount,
+    /// The sum of 2^-|C| over the clauses C containing the literal, see R. G. Jeroslow and
+    /// J. Wang, “Solving propositional satisfiability problems,” Annals of Mathematics and
+    /// Artificial Intelligence, vol. 1, pp. 167–187, 1990.
+    #[cfg_attr(feature = "std", clap(name = "jeroslow-wang"))]
+    JeroslowWang,
+}
+
+impl ScoreInit {
+    /// Scores of the negative and the positive literal of the variables 0..=num_vars, `None`
+    /// for the default initialization. A variable scores the sum of its literals and starts with
+    /// the phase of the higher one.
+    pub fn literal_scores(&self, state: &State) -> Option<Vec<[f64; 2]>> {
+        match self {
+            ScoreInit::Default => None,
+            ScoreInit::LiteralCount => {
+                let mut counts = vec![[0.0; 2]; state.num_vars + 1];
+                for clause_id in state.clause_database.iter() {
+                    for lit in &state.clause_database[clause_id].literals {
+                        counts[lit.var()][lit.positive() as usize] += 1.0;
+                    }
+                }
+                Some(counts)
+            }
+            ScoreInit::JeroslowWang => Some(jeroslow_wang_scores(state)),
+        }
+    }
+}
+
+/// Sums of the literal scores, scaled to at most 1
+pub fn var_scores(literal_scores: &[[f64; 2]]) -> Vec<f64> {
+    normalize_scores(
+        literal_scores
+            .iter()
+            .map(|[negative, positive]| negative + positive)
+            .collect(),
+    )
+}
+
+/// Scales non-negative scores to at most 1, as expected by `Heuristic::seed_scores`
+pub fn normalize_scores(scores: Vec<f64>) -> Vec<f64> {
+    let max = scores.iter().copied().fold(0.0, f64::max);
+    match max > 0.0 {
+        true => scores.iter().map(|score| score / max).collect(),
+        false => scores,
+    }
+}
@@ -0,0 +1,117 @@
+use crate::cnf::{Clause, PartialAssignment, Var};
+use crate::solver::heuristic::heap::VarHeap;
+use crate::solver::heuristic::{Decision, Heuristic, HeuristicType};
+use crate::solver::state::State;
+use crate::solver::trail::Assignment;
+use alloc::{boxed::Box, vec, vec::Vec};
+
+/// Decides the variables with a positive priority before all others, by decreasing priority,
+/// and the remaining ones with the wrapped heuristic. The solver always decides through it, see
+/// `Solver::set_decision_priority`, so priorities survive when `--heuristic adaptive` replaces
+/// the wrapped heuristic.
+pub struct HeuristicPriority {
+    inner: Box<dyn Heuristic>,
+    priorities: Vec<f64>,
+    /// Variables with a positive priority, assigned ones are removed lazily
+    order: VarHeap,
+}
+
+impl HeuristicPriority {
+    pub fn new(inner: Box<dyn Heuristic>, num_vars: usize) -> Self {
+        HeuristicPriority {
+            inner,
+            priorities: vec![0.0; num_vars + 1],
+            order: VarHeap::new(num_vars),
+        }
+    }
+
+    /// Sets the priority of `Solver::set_decision_priority`
+    pub fn set_priority(&mut self, var_id: Var, priority: f64) {
+        self.priorities[var_id] = priority;
+        // the heap can't lower priorities in place, and priorities are rarely changed
+        self.order = VarHeap::new(self.priorities.len() - 1);
+        for var_id in Var::range(self.priorities.len() - 1) {
+            if self.priority(var_id) > 0.0 {
+                self.order.insert(var_id, &self.priorities);
+            }
+        }
+    }
+
+    /// Decides the variables without a positive priority with another heuristic from now on
+    pub fn replace_inner(&mut self, inner: Box<dyn Heuristic>) {
+        self.inner = inner;
+    }
+
+    fn priority(&self, var_id: Var) -> f64 {
+        self.priorities[var_id]
+    }
+
+    /// The unassigned variable with the highest positive priority
+    fn peek_prioritized(&mut self, vars: &PartialAssignment) -> Option<Var> {
+        while let Some(var_id) = self.order.top() {
+            if !vars.is_assigned(var_id) {
+                return Some(var_id);
+            }
+            self.order.pop(&self.priorities);
+        }
+        None
+    }
+}
+
+impl Heuristic for HeuristicPriority {
+    fn init(state: &State) -> Self {
+        HeuristicPriority::new(HeuristicType::VSIDS.create(state), state.num_vars)
+    }
+
+    fn unassign(&mut self, assignment: &Assignment) {
+        let var_id = assignment.literal.var();
+        if self.priority(var_id) > 0.0 {
+            self.order.insert(var_id, &self.priorities);
+        }
+        self.inner.unassign(assignment);
+    }
+
+    fn conflict(&mut self, clause: &Clause) {
+        self.inner.conflict(clause);
+    }
+
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
+        match self.peek_prioritized(vars) {
+            Some(var_id) => {
+                self.order.pop(&self.priorities);
+                Decision::new(var_id)
+            }
+            None => self.inner.next(vars),
+        }
+    }
+
+    fn peek(&mut self, vars: &PartialAssignment) -> Option<Var> {
+        match self.peek_prioritized(vars) {
+            Some(var_id) => Some(var_id),
+            None => self.inner.peek(vars),
+        }
+    }
+
+    fn prefers(&self, var_id: Var, other: Var) -> bool {
+        match self.priority(var_id) == self.priority(other) {
+            true => self.inner.prefers(var_id, other),
+            false => self.priority(var_id) > self.priority(other),
+        }
+    }
+
+    fn scores(&self) -> Option<Vec<f64>> {
+        self.inner.scores()
+    }
+
+    fn restore_scores(&mut self, scores: &[f64]) {
+        self.inner.restore_scores(scores);
+    }
+
+    fn seed_scores(&mut self, scores: &[f64]) {
+        self.inner.seed_scores(scores);
+    }
+
+    fn stabilize(&mut self, stable: bool) {
+        self.inner.stabilize(stable);
+    }
+}
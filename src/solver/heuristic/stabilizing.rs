@@ -0,0 +1,81 @@
+use crate::cnf::{Clause, PartialAssignment, Var};
+use crate::solver::heuristic::{Decision, Heuristic, HeuristicType};
+use crate::solver::state::State;
+use crate::solver::trail::Assignment;
+use alloc::{boxed::Box, vec::Vec};
+
+/// Decides with the focused or the stable heuristic depending on the mode of the
+/// `Stabilizer`. Both are kept up to date, so each can take over at any time.
+pub struct HeuristicStabilizing {
+    focused: Box<dyn Heuristic>,
+    stable: Box<dyn Heuristic>,
+    is_stable: bool,
+}
+
+impl HeuristicStabilizing {
+    pub fn new(focused: Box<dyn Heuristic>, stable: Box<dyn Heuristic>) -> Self {
+        HeuristicStabilizing {
+            focused,
+            stable,
+            is_stable: false,
+        }
+    }
+
+    fn active(&mut self) -> &mut dyn Heuristic {
+        match self.is_stable {
+            true => self.stable.as_mut(),
+            false => self.focused.as_mut(),
+        }
+    }
+}
+
+impl Heuristic for HeuristicStabilizing {
+    fn init(state: &State) -> Self {
+        HeuristicStabilizing::new(
+            HeuristicType::VSIDS.create(state),
+            HeuristicType::VMTF.create(state),
+        )
+    }
+
+    fn unassign(&mut self, assignment: &Assignment) {
+        self.focused.unassign(assignment);
+        self.stable.unassign(assignment);
+    }
+
+    fn conflict(&mut self, clause: &Clause) {
+        self.focused.conflict(clause);
+        self.stable.conflict(clause);
+    }
+
+    fn scores(&self) -> Option<Vec<f64>> {
+        self.focused.scores()
+    }
+
+    fn restore_scores(&mut self, scores: &[f64]) {
+        self.focused.restore_scores(scores);
+    }
+
+    fn seed_scores(&mut self, scores: &[f64]) {
+        self.focused.seed_scores(scores);
+        self.stable.seed_scores(scores);
+    }
+
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
+        self.active().next(vars)
+    }
+
+    fn peek(&mut self, vars: &PartialAssignment) -> Option<Var> {
+        self.active().peek(vars)
+    }
+
+    fn prefers(&self, var_id: Var, other: Var) -> bool {
+        match self.is_stable {
+            true => self.stable.prefers(var_id, other),
+            false => self.focused.prefers(var_id, other),
+        }
+    }
+
+    fn stabilize(&mut self, stable: bool) {
+        self.is_stable = stable;
+    }
+}
@@ -1,21 +1,41 @@
-use crate::cnf::{Clause, VarId};
-use crate::solver::heuristic::Heuristic;
+use crate::cnf::{Clause, PartialAssignment, Var};
+use crate::solver::heuristic::{Decision, Heuristic};
 use crate::solver::state::State;
 use crate::solver::trail::Assignment;
+use alloc::{vec, vec::Vec};
 
 #[derive(Default)]
 pub struct HeuristicVMTF {
-    pub order: Vec<VarId>,
+    pub order: Vec<Var>,
 }
 
 impl Heuristic for HeuristicVMTF {
     fn init(state: &State) -> Self {
         // start out with all variables having a heuristic value of 1 and set to true
         HeuristicVMTF {
-            order: (1..=state.vars.len()).collect(),
+            order: Var::range(state.num_vars).collect(),
         }
     }
 
+    /// Scores by position, from 1 for the front of the queue down to 0
+    fn scores(&self) -> Option<Vec<f64>> {
+        let mut scores = vec![0.0; self.order.len() + 1];
+        for (position, var_id) in self.order.iter().enumerate() {
+            scores[*var_id] = 1.0 - position as f64 / self.order.len() as f64;
+        }
+        Some(scores)
+    }
+
+    fn restore_scores(&mut self, scores: &[f64]) {
+        self.seed_scores(scores);
+    }
+
+    fn seed_scores(&mut self, scores: &[f64]) {
+        // stable, so variables of equal score stay in the order of their indices
+        self.order
+            .sort_by(|var_id, other| scores[*other].total_cmp(&scores[*var_id]));
+    }
+
     fn unassign(&mut self, _assignment: &Assignment) {
         // no need to replay unassignments with VMTF
     }
@@ -23,24 +43,24 @@ impl Heuristic for HeuristicVMTF {
     fn conflict(&mut self, clause: &Clause) {
         // remove the variables
         self.order
-            .retain(|var_id| clause.literals.iter().all(|lit| lit.id() != *var_id));
+            .retain(|var_id| clause.literals.iter().all(|lit| lit.var() != *var_id));
 
         // add them to the front
-        let var_ids = clause.literals.iter().map(|lit| lit.id());
+        let var_ids = clause.literals.iter().map(|lit| lit.var());
         self.order = var_ids.chain(self.order.iter().cloned()).collect();
     }
 
-    fn next(&mut self, vars: &[Option<bool>]) -> VarId {
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
         // find the first variable in the order that is not assigned
         let mut unassigned_pos = None;
 
         for var_id in &self.order {
-            if vars[*var_id].is_none() {
+            if !vars.is_assigned(*var_id) {
                 unassigned_pos = Some(*var_id);
                 break;
             }
         }
 
-        unassigned_pos.expect("No unassigned variable found")
+        Decision::new(unassigned_pos.expect("No unassigned variable found"))
     }
 }
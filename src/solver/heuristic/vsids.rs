@@ -1,123 +1,130 @@
-use ordered_float::NotNan;
-use priority_queue::PriorityQueue;
-
-use crate::cnf::{Clause, VarId};
-use crate::solver::heuristic::Heuristic;
+use crate::cnf::{Clause, PartialAssignment, Var};
+use crate::solver::heuristic::heap::VarHeap;
+use crate::solver::heuristic::{Decision, Heuristic};
 use crate::solver::state::State;
 use crate::solver::trail::Assignment;
+use alloc::{vec, vec::Vec};
 
-use fnv::FnvHasher;
 use itertools::Itertools;
-use std::hash::BuildHasherDefault;
-
-type FastHasher = BuildHasherDefault<FnvHasher>;
 
-#[derive(Default)]
+/// Activities and the increment are scaled down once an activity exceeds this limit
+const RESCALE_LIMIT: f64 = 1e100;
+/// Initial activity per occurrence of a variable
+const OCCURRENCE_ACTIVITY: f64 = 1e-3;
+
+/// Exponential VSIDS as described in N. Eén and N. Sörensson, “An Extensible SAT-solver,” in
+/// Theory and Applications of Satisfiability Testing, vol. 2919, E. Giunchiglia and
+/// A. Tacchella, Eds., Berlin, Heidelberg: Springer Berlin Heidelberg, 2004, pp. 502–518.
+/// doi: 10.1007/978-3-540-24605-3_37.
+/// Instead of decaying all activities after a conflict, the bump increment is increased.
+/// Rescaling multiplies all activities by the same factor, so the heap order is unaffected.
 pub struct HeuristicVSIDS {
-    pub order: PriorityQueue<VarId, NotNan<f64>, FastHasher>,
-    priorities: Vec<NotNan<f64>>,
-    conflict_index: f64,
+    order: VarHeap,
+    activity: Vec<f64>,
+    var_inc: f64,
+    /// The increment grows by `1 / var_decay` after every conflict, which decays all activities
+    var_decay: f64,
 }
 
-const BUMP_BASIS: f64 = 1.1;
-
 impl HeuristicVSIDS {
-    fn rescale(&mut self, factor: f64) {
-        // divide everything by factor
-        for priority in &mut self.priorities {
-            *priority = NotNan::new(priority.into_inner() / factor).unwrap();
-            if *priority == 0.0 {
-                *priority = NotNan::new(f64::MIN_POSITIVE).unwrap();
+    fn bump(&mut self, var_id: Var) {
+        self.activity[var_id] += self.var_inc;
+        if self.activity[var_id] > RESCALE_LIMIT {
+            for activity in &mut self.activity {
+                *activity /= RESCALE_LIMIT;
             }
+            self.var_inc /= RESCALE_LIMIT;
         }
-        // change conflict index, such that BUMP_FACTOR^conflict_index gets divided by factor
-        // g^i_new = g^i_old / factor
-
-        self.conflict_index -= factor.ln() / BUMP_BASIS.ln();
-
-        let mut new_order: PriorityQueue<VarId, NotNan<f64>, FastHasher> =
-            PriorityQueue::with_capacity_and_hasher(self.order.len(), FastHasher::default());
-
-        // rescale the priorities in the queue
-        for (var_id, _) in self.order.clone() {
-            new_order.push(var_id, self.priorities[var_id]);
-        }
-        self.order = new_order;
+        self.order.increased(var_id, &self.activity);
     }
 }
 
 impl Heuristic for HeuristicVSIDS {
     fn init(state: &State) -> Self {
-        let var_counts = state
-            .clause_database
-            .iter()
-            .flat_map(|clause| {
-                state.clause_database[clause]
-                    .literals
-                    .iter()
-                    .map(|lit| lit.id())
-            })
-            .counts();
+        let mut var_counts = vec![0; state.num_vars + 1];
+        for clause_id in state.clause_database.iter() {
+            for lit in &state.clause_database[clause_id].literals {
+                var_counts[lit.var()] += 1;
+            }
+        }
 
-        let initial_priorities = (0..=state.vars.len())
-            .map(|var| *var_counts.get(&(var as VarId)).unwrap_or(&0) as f64 + 0.1)
+        // variables occurring more often are tried first, but a single bump outweighs that
+        let activity = var_counts
+            .iter()
+            .map(|count| *count as f64 * OCCURRENCE_ACTIVITY)
             .collect_vec();
+        let mut order = VarHeap::new(state.num_vars);
+        for var_id in Var::range(state.num_vars) {
+            order.insert(var_id, &activity);
+        }
 
         HeuristicVSIDS {
-            priorities: initial_priorities
+            order,
+            activity,
+            var_inc: 1.0,
+            var_decay: state.params.vsids_decay,
+        }
+    }
+
+    /// Activities relative to the current increment, so they keep their weight against future
+    /// bumps when restored with the initial increment
+    fn scores(&self) -> Option<Vec<f64>> {
+        Some(
+            self.activity
                 .iter()
-                .map(|&p| NotNan::new(p).unwrap())
-                .collect(),
-            order: (1..state.vars.len())
-                .sorted_by_key(|id| NotNan::new(initial_priorities[*id]).unwrap())
-                .map(|id| (id, NotNan::new(1.0).unwrap()))
+                .map(|activity| activity / self.var_inc)
                 .collect(),
-            conflict_index: 0.0,
+        )
+    }
+
+    fn restore_scores(&mut self, scores: &[f64]) {
+        self.activity = scores.to_vec();
+        self.var_inc = 1.0;
+        self.order = VarHeap::new(self.activity.len() - 1);
+        for var_id in Var::range(self.activity.len() - 1) {
+            self.order.insert(var_id, &self.activity);
         }
     }
 
+    /// The seeds replace the activities by occurrence, the highest one weighs as much as a bump
+    fn seed_scores(&mut self, scores: &[f64]) {
+        self.restore_scores(scores);
+    }
+
     fn unassign(&mut self, assignment: &Assignment) {
-        let (var_id, _) = assignment.literal.id_val();
-        self.order.push(var_id, self.priorities[var_id]); // replaces any existing priority
+        self.order.insert(assignment.literal.var(), &self.activity);
     }
 
     fn conflict(&mut self, clause: &Clause) {
-        self.conflict_index += 1.0;
-
-        // bump the priority of the variables in the clause
         for lit in &clause.literals {
-            let (var_id, _) = lit.id_val();
+            self.bump(lit.var());
+        }
+        self.var_inc /= self.var_decay;
+    }
 
-            let mut increase = BUMP_BASIS.powi(self.conflict_index as i32);
-            let new_priority = self.priorities[var_id].into_inner() + increase;
-            if new_priority > 10000000.0 {
-                self.rescale(10000000.0);
-                increase = BUMP_BASIS.powi(self.conflict_index as i32);
+    fn peek(&mut self, vars: &PartialAssignment) -> Option<Var> {
+        // assigned variables are inserted again when they are unassigned
+        while let Some(var_id) = self.order.top() {
+            if !vars.is_assigned(var_id) {
+                return Some(var_id);
             }
-
-            self.priorities[var_id] =
-                NotNan::new(self.priorities[var_id].into_inner() + increase).unwrap();
-            self.order.change_priority(&var_id, self.priorities[var_id]);
+            self.order.pop(&self.activity);
         }
+        None
     }
 
-    fn next(&mut self, vars: &[Option<bool>]) -> VarId {
+    fn prefers(&self, var_id: Var, other: Var) -> bool {
+        self.activity[var_id] > self.activity[other]
+    }
+
+    fn next(&mut self, vars: &PartialAssignment) -> Decision {
         loop {
-            if self.order.is_empty() {
-                // Oh no...
-                println!("No unassigned variable found");
-                // check if the heuristic is in sync with the var state
-                println!("Checking sync of vars and heuristic");
-                for (var_id, value) in vars.iter().enumerate() {
-                    if value.is_none() && var_id != 0 {
-                        println!("Var {} is unassigned, but not in the heuristic", var_id);
-                    }
-                }
-                panic!("");
-            }
-            let (var_id, _) = self.order.pop().expect("No unassigned variable found");
-            if vars[var_id].is_none() {
-                return var_id;
+            let var_id = self
+                .order
+                .pop(&self.activity)
+                .expect("No unassigned variable found");
+            if !vars.is_assigned(var_id) {
+                return Decision::new(var_id);
             }
         }
     }
@@ -1,59 +1,167 @@
-use crate::cnf::{Clause, ClauseId, Literal, VarId};
+use crate::cnf::{Clause, ClauseId, Literal, Var};
+use crate::solver::clause_database::ClauseDatabase;
+use crate::solver::clock::Instant;
+use crate::solver::config::{BveScoring, Config};
 use crate::solver::heuristic::Heuristic;
+use crate::solver::proof_logger::Antecedents;
 use crate::solver::state::State;
-use crate::solver::trail::AssignmentReason;
 use crate::solver::trail::Trail;
-use crate::solver::unit_propagation::UnitPropagator;
+use crate::solver::trail::{AssignmentReason, ConstraintRef};
+use alloc::collections::VecDeque;
+use alloc::{vec, vec::Vec};
+use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
-use std::collections::{HashMap, VecDeque};
 
-const INPROCESSING_RATIO: f64 = 0.10;
+/// Simplification technique of the inprocessing rotation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Technique {
+    /// Subsumption and self-subsuming resolution, see `Inprocessor::subsume`
+    Subsumption,
+    /// Failed literal probing, see `Inprocessor::probe`
+    Probing,
+    /// Clause vivification, see `Inprocessor::vivify`
+    Vivification,
+    /// Blocked clause elimination, see `Inprocessor::eliminate_blocked_clauses`
+    BlockedClauses,
+    /// Equivalent literal substitution, autarkies and bounded variable elimination
+    Elimination,
+}
+
+impl Technique {
+    const ALL: [Technique; 5] = [
+        Technique::Subsumption,
+        Technique::Probing,
+        Technique::Vivification,
+        Technique::BlockedClauses,
+        Technique::Elimination,
+    ];
+
+    /// Share of the budget of a round the technique may use, relative to the other techniques
+    /// that haven't reached a fixed point
+    fn weight(self) -> f64 {
+        match self {
+            Technique::Subsumption | Technique::Probing | Technique::BlockedClauses => 1.0,
+            Technique::Vivification => 2.0,
+            Technique::Elimination => 5.0,
+        }
+    }
+}
 
+/// Changes of the formula made by inprocessing, the number of irredundant clauses and the
+/// number of literals fixed at level 0. A technique that changed nothing in a pass over the
+/// formula has reached a fixed point until the revision changes.
+type Revision = (usize, usize, usize);
+
+/// Progress of a technique over the rounds of inprocessing
+#[derive(Debug, Clone, Default)]
+struct Schedule {
+    /// Work done so far: resolutions, assignments or visited literals
+    effort: usize,
+    /// Position in the clauses or variables where an interrupted pass continues
+    cursor: usize,
+    /// Revision at the start of the current pass
+    pass_start: Revision,
+    /// Revision that a complete pass didn't change
+    fixed_point: Option<Revision>,
+}
+
+/// Runs the simplification techniques at level 0 in rounds. Each round starts with the technique
+/// after the one the previous round started with and gives each technique that hasn't reached a
+/// fixed point its share of the budget. Interrupted passes continue in the next round.
 pub struct Inprocessor {
-    bve_reconstruction_data: Vec<(Literal, Clause)>,
-    initialization_time: std::time::Instant,
-    pub total_inprocessing_time: std::time::Duration,
-    current_inprocessing_start: std::time::Instant,
-    bve_queue: VecDeque<VarId>,
-    pub resolved_vars: usize,
+    initialization_time: Instant,
+    total_inprocessing_time: core::time::Duration,
+    current_inprocessing_start: Instant,
+    bve_queue: VecDeque<Var>,
+    inprocessing_ratio: f64,
+    /// Effort per conflict the search is worth in deterministic mode
+    resolutions_per_conflict: f64,
+    /// Inprocessing only starts if at least this much budget is available, in seconds or in
+    /// resolutions in deterministic mode
+    min_budget: f64,
+    clause_growth: usize,
+    max_resolvent_length: Option<usize>,
+    deterministic: bool,
+    /// Progress of each technique in the order of `Technique::ALL`
+    schedules: [Schedule; Technique::ALL.len()],
+    /// Technique the next round starts with
+    next_technique: usize,
+    /// Changes of the formula made by inprocessing, see `Revision`
+    num_changes: usize,
+    /// Effort at which the running technique is interrupted, `None` if it runs to completion
+    effort_limit: Option<f64>,
+    /// Variables occurring in other constraints than clauses or in assumptions
+    excluded_vars: HashSet<Var>,
+    /// How often each variable was frozen by the user and not melted again
+    frozen_vars: HashMap<Var, usize>,
+    /// Frozen variables removed from the elimination queue, which are queued again once melted
+    unqueued_frozen_vars: HashSet<Var>,
+    /// Variables whose elimination was given up, which are queued again once an irredundant
+    /// clause containing them is added, deleted or changed
+    skipped_vars: HashSet<Var>,
 }
 
 impl Inprocessor {
-    pub fn init(cnf: &[Clause]) -> Self {
-        let lit_occurrences = cnf
-            .iter()
-            .flat_map(|clause| clause.literals.iter())
-            .counts();
+    /// Orders the variables of the irredundant clauses of the database for elimination
+    pub fn init(clause_database: &ClauseDatabase, config: &Config) -> Self {
+        let mut lit_occurrences = HashMap::<Literal, usize>::new();
+        for lit in clause_database
+            .necessary_clauses_iter()
+            .flat_map(|clause_id| clause_database[clause_id].literals.iter())
+        {
+            *lit_occurrences.entry(*lit).or_default() += 1;
+        }
 
         let vars = lit_occurrences
             .keys()
-            .map(|lit| lit.id())
-            .unique()
+            .map(|lit| lit.var())
+            .sorted_unstable()
+            .dedup()
             .collect_vec();
 
+        let occurrences = |var_id: Var, positive: bool| {
+            *lit_occurrences
+                .get(&Literal::from_value(var_id, positive))
+                .unwrap_or(&0)
+        };
         let vars_ordered_by_occurrences = vars
             .iter()
             .sorted_by_cached_key(|var_id| {
-                (
-                    lit_occurrences
-                        .get(&Literal::from_value(**var_id, true))
-                        .unwrap_or(&0)
-                        * lit_occurrences
-                            .get(&Literal::from_value(**var_id, true))
-                            .unwrap_or(&0),
-                    **var_id,
-                )
+                let (pos, neg) = (occurrences(**var_id, true), occurrences(**var_id, false));
+                let score = match config.bve_scoring {
+                    BveScoring::Product => pos * neg,
+                    BveScoring::Sum => pos + neg,
+                };
+                (score, **var_id)
             })
             .copied()
-            .collect::<VecDeque<VarId>>();
+            .collect::<VecDeque<Var>>();
 
+        // without a clock, the time spent can't be measured
+        let deterministic = config.deterministic || cfg!(not(feature = "std"));
         Inprocessor {
-            bve_reconstruction_data: vec![],
-            initialization_time: std::time::Instant::now(),
-            total_inprocessing_time: std::time::Duration::from_secs(0),
-            current_inprocessing_start: std::time::Instant::now(),
+            initialization_time: Instant::now(),
+            total_inprocessing_time: core::time::Duration::from_secs(0),
+            current_inprocessing_start: Instant::now(),
             bve_queue: vars_ordered_by_occurrences,
-            resolved_vars: 0,
+            inprocessing_ratio: config.params.inprocessing_ratio,
+            resolutions_per_conflict: config.params.resolutions_per_conflict,
+            clause_growth: config.params.bve_clause_growth,
+            max_resolvent_length: (config.params.bve_max_resolvent_length > 0)
+                .then_some(config.params.bve_max_resolvent_length),
+            min_budget: match deterministic {
+                true => config.params.min_inprocessing_resolutions,
+                false => config.params.min_inprocessing_time,
+            },
+            deterministic,
+            schedules: Default::default(),
+            next_technique: 0,
+            num_changes: 0,
+            effort_limit: None,
+            excluded_vars: HashSet::new(),
+            frozen_vars: HashMap::new(),
+            unqueued_frozen_vars: HashSet::new(),
+            skipped_vars: HashSet::new(),
         }
     }
 
@@ -62,9 +170,7 @@ impl Inprocessor {
         trail: &mut Trail,
         state: &mut State,
         heuristic: &mut dyn Heuristic,
-    ) -> Vec<(Literal, ClauseId)> {
-        self.current_inprocessing_start = std::time::Instant::now();
-
+    ) -> Vec<(Literal, ConstraintRef)> {
         assert_eq!(
             trail.decision_level, 0,
             "Inprocessing called at decision level != 0"
@@ -75,11 +181,11 @@ impl Inprocessor {
             .iter()
             .map(|x| {
                 // check preconditions for inprocessing
-                if let AssignmentReason::Forced(clause_id) = x.reason {
+                if let AssignmentReason::Forced(reason) = x.reason {
                     if x.decision_level != 0 {
                         panic!("Inprocessing called at decision level != 0");
                     }
-                    (x.literal, clause_id)
+                    (x.literal, reason)
                 } else {
                     panic!("Inprocessing called at decision level != 0");
                 }
@@ -87,77 +193,321 @@ impl Inprocessor {
             .collect::<Vec<_>>();
         trail.backtrack_completely(state, heuristic);
 
-        for (lit, _) in units.iter() {
-            state.unassign(*lit);
-        }
-
         units
     }
 
     pub fn end_inprocessing(
         &mut self,
-        units: Vec<(Literal, ClauseId)>,
-        unit_propagator: &mut UnitPropagator,
+        units: Vec<(Literal, ConstraintRef)>,
+        state: &mut State,
+        trail: &mut Trail,
     ) {
-        // enqueue all units again
-        for (unit_literal, conflict_clause_id) in units {
-            unit_propagator.enqueue(unit_literal, conflict_clause_id);
+        // assign all units again, they are propagated by the next call to `Trail::propagate`
+        for (unit_literal, reason) in units {
+            trail.enqueue(state, unit_literal, reason);
         }
+    }
 
-        self.total_inprocessing_time += self.current_inprocessing_start.elapsed();
+    /// Excludes variables occurring in other constraints than clauses from elimination
+    pub fn exclude_vars(&mut self, var_ids: &[Var]) {
+        self.bve_queue.retain(|var_id| !var_ids.contains(var_id));
+        self.skipped_vars.retain(|var_id| !var_ids.contains(var_id));
+        self.excluded_vars.extend(var_ids);
+    }
+
+    /// Excludes the variable from elimination until it is melted as often as it was frozen
+    pub fn freeze(&mut self, var_id: Var) {
+        *self.frozen_vars.entry(var_id).or_default() += 1;
+        if let Some(position) = self.bve_queue.iter().position(|queued| *queued == var_id) {
+            self.bve_queue.remove(position);
+            self.unqueued_frozen_vars.insert(var_id);
+        }
+    }
+
+    /// Undoes one call to `freeze`, returns false if the variable wasn't frozen
+    pub fn melt(&mut self, var_id: Var) -> bool {
+        let Some(count) = self.frozen_vars.get_mut(&var_id) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.frozen_vars.remove(&var_id);
+            if self.unqueued_frozen_vars.remove(&var_id) && !self.excluded_vars.contains(&var_id) {
+                self.bve_queue.push_back(var_id);
+            }
+        }
+        true
+    }
+
+    /// Queues the skipped variables whose clauses changed since the last call, frozen ones once
+    /// they are melted
+    fn requeue_touched_vars(&mut self, state: &mut State) {
+        for var_id in state.clause_database.take_touched_vars() {
+            if !self.skipped_vars.remove(&var_id) {
+                continue;
+            }
+            if self.is_frozen(var_id) {
+                self.unqueued_frozen_vars.insert(var_id);
+            } else {
+                self.bve_queue.push_back(var_id);
+            }
+        }
+    }
+
+    pub fn is_frozen(&self, var_id: Var) -> bool {
+        self.frozen_vars.contains_key(&var_id)
+    }
+
+    /// Whether the variable must not be removed from the formula
+    fn is_excluded(&self, var_id: Var) -> bool {
+        self.excluded_vars.contains(&var_id) || self.is_frozen(var_id)
+    }
+
+    /// Effort spent on inprocessing and the share of the total effort it may take, measured
+    /// in seconds or in the work of the techniques and conflicts in deterministic mode
+    fn effort_and_budget(&self, num_conflicts: usize) -> (f64, f64) {
+        match self.deterministic {
+            true => (
+                self.total_effort() as f64,
+                num_conflicts as f64 * self.resolutions_per_conflict * self.inprocessing_ratio,
+            ),
+            false => (
+                self.total_inprocessing_time.as_secs_f64(),
+                self.initialization_time.elapsed().as_secs_f64() * self.inprocessing_ratio,
+            ),
+        }
+    }
+
+    fn total_effort(&self) -> usize {
+        self.schedules.iter().map(|schedule| schedule.effort).sum()
+    }
+
+    /// Effort including the running round
+    fn current_effort(&self) -> f64 {
+        match self.deterministic {
+            true => self.total_effort() as f64,
+            false => (self.total_inprocessing_time + self.current_inprocessing_start.elapsed())
+                .as_secs_f64(),
+        }
     }
 
-    pub fn should_interrupt(&self) -> bool {
-        (self.total_inprocessing_time + self.current_inprocessing_start.elapsed()).as_secs_f64()
-            > self.initialization_time.elapsed().as_secs_f64() * INPROCESSING_RATIO
+    pub fn should_start_inprocessing(&self, num_conflicts: usize) -> bool {
+        let (effort, budget) = self.effort_and_budget(num_conflicts);
+        effort + self.min_budget < budget
     }
 
-    pub fn should_start_inprocessing(&self) -> bool {
-        self.total_inprocessing_time.as_secs_f64() + 0.1
-            < self.initialization_time.elapsed().as_secs_f64() * INPROCESSING_RATIO
+    /// Whether the running technique used up its share of the round
+    fn is_exhausted(&self) -> bool {
+        self.effort_limit
+            .is_some_and(|limit| self.current_effort() > limit)
     }
 
+    fn revision(&self, state: &State, trail: &Trail) -> Revision {
+        let database = &state.clause_database;
+        let num_fixed = trail
+            .assignment_stack
+            .iter()
+            .take_while(|assignment| assignment.decision_level == 0)
+            .count();
+        (
+            self.num_changes,
+            database.num_clauses() - database.num_learned_clauses(),
+            num_fixed,
+        )
+    }
+
+    /// Whether running the technique can't change the formula. Probing and vivification derive
+    /// clauses by propagation without resolution chains, so they are skipped if chains are
+    /// tracked.
+    fn is_at_fixed_point(&self, technique: Technique, state: &State, trail: &Trail) -> bool {
+        match technique {
+            Technique::Elimination => {
+                self.bve_queue.is_empty()
+                    && state
+                        .clause_database
+                        .touched_vars()
+                        .iter()
+                        .all(|var_id| !self.skipped_vars.contains(var_id))
+            }
+            Technique::Probing | Technique::Vivification
+                if state.clause_database.proof_logger.is_tracking() =>
+            {
+                true
+            }
+            _ => {
+                self.schedules[technique as usize].fixed_point == Some(self.revision(state, trail))
+            }
+        }
+    }
+
+    /// Whether all techniques reached a fixed point
+    pub fn is_complete(&self, state: &State, trail: &Trail) -> bool {
+        Technique::ALL
+            .iter()
+            .all(|technique| self.is_at_fixed_point(*technique, state, trail))
+    }
+
+    /// Whether the next call to `inprocess` would simplify the formula
+    pub fn is_due(&self, state: &State, trail: &Trail) -> bool {
+        self.should_start_inprocessing(state.stats.num_conflicts) && !self.is_complete(state, trail)
+    }
+
+    /// Runs a round of the techniques within the budget, at level 0
     pub fn inprocess(
         &mut self,
-        unit_propagator: &mut UnitPropagator,
         heuristic: &mut dyn Heuristic,
         state: &mut State,
         trail: &mut Trail,
     ) {
-        if self.bve_queue.is_empty() || !self.should_start_inprocessing() {
-            return;
+        if self.is_due(state, trail) {
+            self.run_round(heuristic, state, trail, true);
+        }
+    }
+
+    /// Runs the techniques regardless of the budget until all reached a fixed point or the
+    /// formula turned out to be unsatisfiable
+    pub fn inprocess_completely(
+        &mut self,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+    ) {
+        loop {
+            trail.propagate(state);
+            if state.conflict.is_some() || self.is_complete(state, trail) {
+                break;
+            }
+            self.run_round(heuristic, state, trail, false);
+        }
+    }
+
+    fn run_round(
+        &mut self,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+        budgeted: bool,
+    ) {
+        self.current_inprocessing_start = Instant::now();
+        self.requeue_touched_vars(state);
+        let (effort, budget) = self.effort_and_budget(state.stats.num_conflicts);
+        let total_weight: f64 = Technique::ALL
+            .iter()
+            .filter(|technique| !self.is_at_fixed_point(**technique, state, trail))
+            .map(|technique| technique.weight())
+            .sum();
+        let first = self.next_technique;
+        self.next_technique = (first + 1) % Technique::ALL.len();
+        for offset in 0..Technique::ALL.len() {
+            let technique = Technique::ALL[(first + offset) % Technique::ALL.len()];
+            if state.conflict.is_some() {
+                break;
+            }
+            if self.is_at_fixed_point(technique, state, trail) {
+                continue;
+            }
+            self.effort_limit = budgeted.then(|| {
+                self.current_effort() + (budget - effort) * technique.weight() / total_weight
+            });
+            self.run_technique(technique, heuristic, state, trail);
+        }
+        self.effort_limit = None;
+
+        self.total_inprocessing_time += self.current_inprocessing_start.elapsed();
+        state.stats.num_inprocessings += 1;
+        state.stats.inprocessing_time = self.total_inprocessing_time;
+    }
+
+    /// Continues the pass of the technique until it is complete or exhausted and records a fixed
+    /// point if a complete pass changed nothing
+    fn run_technique(
+        &mut self,
+        technique: Technique,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+    ) {
+        let index = technique as usize;
+        if self.schedules[index].cursor == 0 {
+            self.schedules[index].pass_start = self.revision(state, trail);
         }
+        let complete = match technique {
+            Technique::Subsumption => self.subsume(state, trail),
+            Technique::Probing => self.probe(heuristic, state, trail),
+            Technique::Vivification => self.vivify(heuristic, state, trail),
+            Technique::BlockedClauses => self.eliminate_blocked_clauses(state, trail),
+            Technique::Elimination => self.eliminate(heuristic, state, trail),
+        };
+        if complete {
+            let revision = self.revision(state, trail);
+            let schedule = &mut self.schedules[index];
+            schedule.cursor = 0;
+            if revision == schedule.pass_start {
+                schedule.fixed_point = Some(revision);
+            }
+        }
+    }
 
+    /// Substitutes equivalent literals, removes autarkies and eliminates the queued variables,
+    /// returns whether the queue is empty
+    fn eliminate(
+        &mut self,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+    ) -> bool {
         // remove all unit-assignments from the trail. This makes adding arbitrary clauses much
         // easier, as we can re-initalize the trail with the new clauses.
-        let units = self.start_inprocessing(trail, state, heuristic);
+        let mut units = self.start_inprocessing(trail, state, heuristic);
+
+        self.substitute_equivalent_literals(trail, &mut units, state);
+        self.eliminate_autarky(&units, state);
 
-        while let Some(var) = self.bve_queue.pop_front() {
-            self.bounded_variable_elimination(var, trail, unit_propagator, state);
+        self.eliminate_queued_vars(trail, &mut units, state);
+
+        self.end_inprocessing(units, state, trail);
+        self.bve_queue.is_empty()
+    }
 
-            if self.should_interrupt() {
+    /// Eliminates the queued variables until the budget is exhausted, the ones not worth
+    /// eliminating are skipped until their clauses change
+    fn eliminate_queued_vars(
+        &mut self,
+        trail: &Trail,
+        units: &mut Vec<(Literal, ConstraintRef)>,
+        state: &mut State,
+    ) {
+        while !self.is_exhausted() {
+            let Some(var) = self.bve_queue.pop_front() else {
                 break;
+            };
+            if !self.bounded_variable_elimination(var, trail, units, state) {
+                self.skipped_vars.insert(var);
             }
         }
-
-        self.end_inprocessing(units, unit_propagator);
-        /*
-        if self.bve_queue.is_empty() {
-            println!("c Inprocessing completed")
-        }*/
     }
 
     /// Reconstruction as described in M. Järvisalo, M. J. H. Heule, and A. Biere,
     /// “Inprocessing Rules,” in Automated Reasoning, vol. 7364, B. Gramlich, D. Miller,
     /// and U. Sattler, Eds., Berlin, Heidelberg: Springer Berlin Heidelberg, 2012, pp. 355–370.
     /// doi: 10.1007/978-3-642-31365-3_28.
+    /// The resolvents are logged before the clauses of the variable are deleted, so they are
+    /// RUP and the proof needs no RAT steps. Returns whether the variable was eliminated.
+    ///
+    /// Only the irredundant clauses are resolved and count towards the bound. The learned
+    /// clauses of the variable are deleted with them: they are implied by the original clauses
+    /// but not by the resolvents, and the reconstruction may flip the variable against them.
+    /// Variables fixed at level 0 are left out, their unit and reason clauses must stay.
     fn bounded_variable_elimination(
         &mut self,
-        var_id: VarId,
+        var_id: Var,
         trail: &Trail,
-        unit_propagator: &mut UnitPropagator,
+        units: &mut Vec<(Literal, ConstraintRef)>,
         state: &mut State,
-    ) {
+    ) -> bool {
+        if units.iter().any(|(lit, _)| lit.var() == var_id) {
+            return false;
+        }
         let mut resolution_clauses = vec![];
 
         // find all pos_occ and neg_occ
@@ -167,7 +517,7 @@ impl Inprocessor {
         for clause_id in state.clause_database.iter() {
             let clause = &state.clause_database[clause_id];
             for lit in &clause.literals {
-                if lit.id() == var_id {
+                if lit.var() == var_id {
                     if lit.positive() {
                         pos_occ.push(clause_id);
                     } else {
@@ -177,93 +527,1107 @@ impl Inprocessor {
             }
         }
 
-        let num_clauses_before = pos_occ.len() + neg_occ.len();
-
         // do resolution with the non-learned clauses
-        let pairs = pos_occ
+        let irredundant = |occ: &[ClauseId]| {
+            occ.iter()
+                .copied()
+                .filter(|clause_id| state.clause_database[*clause_id].lbd.is_none())
+                .collect_vec()
+        };
+        let (pos_irredundant, neg_irredundant) = (irredundant(&pos_occ), irredundant(&neg_occ));
+        let num_clauses_before = pos_irredundant.len() + neg_irredundant.len();
+
+        // resolvents of two gate clauses are tautologies and those of two non-gate clauses are
+        // implied by the remaining ones, so only gate clauses are resolved with non-gate clauses
+        let gate = Self::find_gate(var_id, &pos_irredundant, &neg_irredundant, state);
+        let pairs = pos_irredundant
             .iter()
-            .filter(|clause_id| state.clause_database[**clause_id].lbd.is_none())
-            .cartesian_product(
-                neg_occ
-                    .iter()
-                    .filter(|clause_id| state.clause_database[**clause_id].lbd.is_none()),
-            );
+            .cartesian_product(&neg_irredundant)
+            .filter(|(clause_1, clause_2)| {
+                gate.as_ref()
+                    .is_none_or(|gate| gate.contains(clause_1) != gate.contains(clause_2))
+            });
 
         for (clause_1, clause_2) in pairs {
+            self.schedules[Technique::Elimination as usize].effort += 1;
             let c1_iter = state.clause_database[*clause_1].literals.iter();
             let c2_iter = state.clause_database[*clause_2].literals.iter();
 
-            let resolution_clause = c1_iter.chain(c2_iter).filter(|lit| lit.id() != var_id);
+            let resolution_clause = c1_iter.chain(c2_iter).filter(|lit| lit.var() != var_id);
 
-            // deduplicate new_clause
-            let unique = resolution_clause.unique().collect_vec();
+            // deduplicate new_clause, complementary literals are adjacent after sorting
+            let unique = resolution_clause.sorted_unstable().dedup().collect_vec();
 
             // check for tautology
-            if unique.len() == unique.iter().map(|lit| lit.id()).unique().count() {
-                resolution_clauses.push(Clause::from(unique.iter().map(|lit| **lit).collect_vec()));
+            if unique.windows(2).all(|pair| pair[0].var() != pair[1].var()) {
+                if self
+                    .max_resolvent_length
+                    .is_some_and(|max_length| unique.len() > max_length)
+                {
+                    return false;
+                }
+                let resolvent = Clause::from(unique.iter().map(|lit| **lit).collect_vec());
+                resolution_clauses.push((resolvent, [*clause_1, *clause_2]));
             }
 
-            if resolution_clauses.len() >= num_clauses_before {
-                return; // This won't be worthwhile. Abort and don't execute resolution.
+            if resolution_clauses.len() >= num_clauses_before + self.clause_growth {
+                return false; // This won't be worthwhile. Abort and don't execute resolution.
             }
         }
 
-        self.resolved_vars += 1;
+        state.stats.num_eliminated_vars += 1;
+        self.num_changes += 1;
+        if gate.is_some() {
+            state.stats.num_gate_eliminations += 1;
+        }
+        state.stats.num_resolvents += resolution_clauses.len();
 
         // add clauses as required clauses
-        for clause in &resolution_clauses {
-            let clause_id = state
+        for (clause, antecedents) in &resolution_clauses {
+            state
                 .clause_database
-                .add_clause(clause.clone(), &mut state.literal_watcher);
+                .proof_logger
+                .set_antecedents(&Antecedents {
+                    clauses: antecedents.to_vec(),
+                    ..Default::default()
+                });
+            let clause_id = state.clause_database.add_clause(
+                clause.clone(),
+                &mut state.literal_watcher,
+                &state.vars,
+                trail,
+            );
 
-            // newly found units have to be enqueued
+            // newly found units are assigned after the inprocessing
             if clause.literals.len() == 1 {
-                unit_propagator.enqueue(clause.literals[0], clause_id);
+                units.push((clause.literals[0], ConstraintRef::Clause(clause_id)));
             }
         }
 
         // delete old clauses
         for (any_occ, polarity_in_clause) in [(pos_occ, true), (neg_occ, false)] {
             for clause_id in any_occ.iter() {
-                // if clause is required then add to bve_reconstruction_data
+                // required clauses are needed to reconstruct the models
                 if state.clause_database[*clause_id].lbd.is_none() {
-                    self.bve_reconstruction_data.push((
+                    state.reconstruction_stack.push(
                         Literal::from_value(var_id, polarity_in_clause),
                         state.clause_database[*clause_id].clone(),
-                    ));
+                    );
                 }
 
-                state.clause_database.delete_clause_if_allowed(
-                    *clause_id,
-                    &mut state.literal_watcher,
-                    trail,
-                );
+                // without the units on the trail, no clause is a reason, and the clauses of
+                // variables without units are not unit clauses
+                let deleted = state
+                    .clause_database
+                    .delete_clause_if_allowed(*clause_id, &mut state.literal_watcher);
+                debug_assert!(deleted, "Clause of eliminated variable {} kept", var_id);
+                state.stats.num_inprocessing_deleted_clauses += 1;
             }
         }
 
         let num_added_clauses = resolution_clauses.len();
 
-        assert!(num_added_clauses <= num_clauses_before);
+        assert!(num_added_clauses <= num_clauses_before + self.clause_growth);
+        true
     }
 
-    /// Reconstruction as described in M. Järvisalo, M. J. H. Heule, and A. Biere,
-    /// “Inprocessing Rules,” in Automated Reasoning, vol. 7364, B. Gramlich, D. Miller,
-    /// and U. Sattler, Eds., Berlin, Heidelberg: Springer Berlin Heidelberg, 2012, pp. 355–370.
-    /// doi: 10.1007/978-3-642-31365-3_28.
-    pub fn reconstruct_solution(&mut self, solution: &mut HashMap<VarId, bool>) {
-        while let Some((literal, clause)) = self.bve_reconstruction_data.pop() {
-            let clause_is_sat = clause
+    /// Clauses defining the variable as an AND, OR, XOR or ITE gate of other literals, following
+    /// N. Eén and A. Biere, “Effective Preprocessing in SAT Through Variable and Clause
+    /// Elimination,” in Theory and Applications of Satisfiability Testing – SAT 2005,
+    /// pp. 61–75. Only the irredundant occurrences are given, returns None if no gate is found.
+    fn find_gate(
+        var_id: Var,
+        pos_occ: &[ClauseId],
+        neg_occ: &[ClauseId],
+        state: &State,
+    ) -> Option<Vec<ClauseId>> {
+        let database = &state.clause_database;
+        let x = Literal::from_value(var_id, true);
+
+        // p = l_1 ∧ … ∧ l_k given by (¬p ∨ l_i) for all i and (p ∨ ¬l_1 ∨ … ∨ ¬l_k),
+        // OR gates are AND gates of ¬x
+        for (p, long, binary) in [(x, pos_occ, neg_occ), (-x, neg_occ, pos_occ)] {
+            let inputs: HashMap<Literal, ClauseId> = binary
+                .iter()
+                .filter_map(|clause_id| match database[*clause_id].literals[..] {
+                    [a, b] if a == -p => Some((b, *clause_id)),
+                    [a, b] if b == -p => Some((a, *clause_id)),
+                    _ => None,
+                })
+                .collect();
+            for clause_id in long {
+                let gate = database[*clause_id]
+                    .literals
+                    .iter()
+                    .filter(|lit| **lit != p)
+                    .map(|lit| inputs.get(&-*lit).copied())
+                    .collect::<Option<Vec<_>>>();
+                if let Some(mut gate) = gate {
+                    gate.push(*clause_id);
+                    return Some(gate);
+                }
+            }
+        }
+
+        let ternary: HashMap<Vec<Literal>, ClauseId> = pos_occ
+            .iter()
+            .chain(neg_occ)
+            .filter(|clause_id| database[**clause_id].literals.len() == 3)
+            .map(|clause_id| {
+                let literals = database[*clause_id].literals.iter().copied();
+                (literals.sorted_unstable().collect_vec(), *clause_id)
+            })
+            .collect();
+        let find = |literals: [Literal; 3]| {
+            ternary
+                .get(&literals.into_iter().sorted_unstable().collect_vec())
+                .copied()
+        };
+        // the literals besides ¬x of the ternary clauses containing ¬x
+        let negative = neg_occ
+            .iter()
+            .filter_map(|clause_id| match database[*clause_id].literals[..] {
+                [a, b, c] if a == -x => Some((*clause_id, b, c)),
+                [a, b, c] if b == -x => Some((*clause_id, a, c)),
+                [a, b, c] if c == -x => Some((*clause_id, a, b)),
+                _ => None,
+            })
+            .filter(|(_, a, b)| a.var() != var_id && b.var() != var_id && a.var() != b.var())
+            .collect_vec();
+
+        // x = a ⊕ b given by (¬x ∨ a ∨ b), (¬x ∨ ¬a ∨ ¬b), (x ∨ ¬a ∨ b) and (x ∨ a ∨ ¬b)
+        for &(clause_id, a, b) in &negative {
+            if let (Some(second), Some(third), Some(fourth)) =
+                (find([-x, -a, -b]), find([x, -a, b]), find([x, a, -b]))
+            {
+                return Some(vec![clause_id, second, third, fourth]);
+            }
+        }
+
+        // x = c ? t : e given by (¬x ∨ ¬c ∨ t), (¬x ∨ c ∨ e), (x ∨ ¬c ∨ ¬t) and (x ∨ c ∨ ¬e)
+        let mut by_literal: HashMap<Literal, Vec<(ClauseId, Literal)>> = HashMap::new();
+        for &(clause_id, a, b) in &negative {
+            by_literal.entry(a).or_default().push((clause_id, b));
+            by_literal.entry(b).or_default().push((clause_id, a));
+        }
+        let then_clauses = negative
+            .iter()
+            .flat_map(|&(clause_id, a, b)| [(a, (clause_id, b)), (b, (clause_id, a))]);
+        for (not_c, (then_id, t)) in then_clauses {
+            for &(else_id, e) in by_literal.get(&-not_c).into_iter().flatten() {
+                if let (Some(third), Some(fourth)) = (find([x, not_c, -t]), find([x, -not_c, -e])) {
+                    return Some(vec![then_id, else_id, third, fourth]);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Equivalent literal substitution: the literals of a strongly connected component of the
+    /// binary implication graph are equivalent, see M. J. H. Heule, M. Järvisalo, and A. Biere,
+    /// “Efficient CNF Simplification Based on Binary Implication Graphs,” in Theory and
+    /// Applications of Satisfiability Testing – SAT 2011, pp. 201–215.
+    /// Every variable of a component is replaced by its representative in all clauses. The
+    /// rewritten clauses are RUP as long as the binary clauses of the component are present,
+    /// which become tautologies and are only deleted at the end. Both directions of each
+    /// equivalence are pushed on the reconstruction stack, so the substituted variable gets the
+    /// value of its representative. Variables fixed at level 0 are left out.
+    fn substitute_equivalent_literals(
+        &mut self,
+        trail: &Trail,
+        units: &mut Vec<(Literal, ConstraintRef)>,
+        state: &mut State,
+    ) {
+        let fixed_vars = units
+            .iter()
+            .map(|(lit, _)| lit.var())
+            .collect::<HashSet<_>>();
+        let tracking = state.clause_database.proof_logger.is_tracking();
+        let mut implications = vec![vec![]; 2 * (state.num_vars + 1)];
+        // binary clause of each implication, to resolve along the paths between equivalent
+        // literals if the proof is tracked
+        let mut implication_clauses = HashMap::new();
+        for clause_id in state.clause_database.iter() {
+            if let [first, second] = state.clause_database[clause_id].literals[..] {
+                if !fixed_vars.contains(&first.var()) && !fixed_vars.contains(&second.var()) {
+                    implications[(-first).code()].push(second);
+                    implications[(-second).code()].push(first);
+                    if tracking {
+                        implication_clauses.insert((-first, second), clause_id);
+                        implication_clauses.insert((-second, first), clause_id);
+                    }
+                }
+            }
+        }
+
+        let mut replacements: Vec<Option<Literal>> = vec![None; state.num_vars + 1];
+        let mut handled = vec![false; state.num_vars + 1];
+        for component in strongly_connected_components(&implications) {
+            // the component of the negated literals has the same variables
+            if component.len() < 2 || handled[component[0].var()] {
+                continue;
+            }
+            for lit in &component {
+                handled[lit.var()] = true;
+            }
+            // a literal equivalent to its negation is left to the search
+            let num_vars = component
+                .iter()
+                .map(|lit| lit.var())
+                .sorted_unstable()
+                .dedup()
+                .count();
+            if num_vars < component.len() {
+                continue;
+            }
+            // excluded variables can't be substituted, so they are preferred as representative
+            let representative = *component
+                .iter()
+                .min_by_key(|lit| (!self.is_excluded(lit.var()), lit.var()))
+                .unwrap();
+            for lit in component {
+                if lit != representative && !self.is_excluded(lit.var()) {
+                    replacements[lit.var()] = Some(match lit.positive() {
+                        true => representative,
+                        false => -representative,
+                    });
+                }
+            }
+        }
+
+        let substitute = |lit: Literal| match replacements[lit.var()] {
+            Some(replacement) if lit.positive() => replacement,
+            Some(replacement) => -replacement,
+            None => lit,
+        };
+        let affected = state
+            .clause_database
+            .iter()
+            .filter(|clause_id| {
+                state.clause_database[*clause_id]
+                    .literals
+                    .iter()
+                    .any(|lit| replacements[lit.var()].is_some())
+            })
+            .collect_vec();
+        let mut tautologies = vec![];
+        for clause_id in affected {
+            let literals = state.clause_database[clause_id]
                 .literals
                 .iter()
-                .any(|lit| *solution.get(&(lit.id())).unwrap() == (lit.positive()));
+                .map(|lit| substitute(*lit))
+                .sorted_unstable()
+                .dedup()
+                .collect_vec();
+            // complementary literals are adjacent after sorting
+            if literals
+                .windows(2)
+                .any(|pair| pair[0].var() == pair[1].var())
+            {
+                tautologies.push(clause_id);
+                continue;
+            }
+            if let [unit] = literals[..] {
+                units.push((unit, ConstraintRef::Clause(clause_id)));
+            }
+            if tracking {
+                let antecedents = substitution_antecedents(
+                    &state.clause_database[clause_id].literals,
+                    clause_id,
+                    substitute,
+                    &implications,
+                    &implication_clauses,
+                );
+                state
+                    .clause_database
+                    .proof_logger
+                    .set_antecedents(&antecedents);
+            }
+            state.clause_database.replace_literals(
+                clause_id,
+                literals,
+                &mut state.literal_watcher,
+                &state.vars,
+                trail,
+            );
+        }
+        for clause_id in tautologies {
+            if state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher)
+            {
+                state.stats.num_inprocessing_deleted_clauses += 1;
+            }
+        }
 
-            if !clause_is_sat {
-                if literal.positive() {
-                    solution.insert(literal.id(), true);
-                } else {
-                    solution.insert(literal.id(), false);
+        for var_id in Var::range(state.num_vars) {
+            if let Some(replacement) = replacements[var_id] {
+                let lit = Literal::from_value(var_id, true);
+                state
+                    .reconstruction_stack
+                    .push(lit, Clause::from(vec![lit, -replacement]));
+                state
+                    .reconstruction_stack
+                    .push(-lit, Clause::from(vec![-lit, replacement]));
+                state.stats.num_substituted_vars += 1;
+                self.num_changes += 1;
+            }
+        }
+        self.bve_queue
+            .retain(|var_id| replacements[*var_id].is_none());
+        self.skipped_vars
+            .retain(|var_id| replacements[*var_id].is_none());
+    }
+
+    /// Removes the clauses touched by an autarky, an assignment satisfying every clause it
+    /// touches, see O. Kullmann, “Investigations on autark assignments,” Discrete Applied
+    /// Mathematics, vol. 107, no. 1–3, pp. 99–137, 2000.
+    /// Starting with the polarity occurring more often, the variables of touched clauses without
+    /// a true literal are unassigned until a fixpoint is reached. Variables fixed at level 0 keep
+    /// their value without being part of the autarky, excluded variables are never assigned.
+    /// The autarky literals are pushed as units on the reconstruction stack, setting them
+    /// satisfies all removed clauses.
+    fn eliminate_autarky(&mut self, units: &[(Literal, ConstraintRef)], state: &mut State) {
+        let num_vars = state.num_vars;
+        let mut values: Vec<Option<bool>> = vec![None; num_vars + 1];
+        let mut fixed = vec![false; num_vars + 1];
+        for (lit, _) in units {
+            values[lit.var()] = Some(lit.positive());
+            fixed[lit.var()] = true;
+        }
+
+        let database = &state.clause_database;
+        let clause_ids = database.necessary_clauses_iter().collect_vec();
+        let mut occurrences = vec![vec![]; 2 * (num_vars + 1)];
+        for (index, clause_id) in clause_ids.iter().enumerate() {
+            for lit in &database[*clause_id].literals {
+                occurrences[lit.code()].push(index);
+            }
+        }
+        for var_id in Var::range(num_vars) {
+            if fixed[var_id] || self.is_excluded(var_id) {
+                continue;
+            }
+            let num_positive = occurrences[Literal::from_value(var_id, true).code()].len();
+            let num_negative = occurrences[Literal::from_value(var_id, false).code()].len();
+            if num_positive + num_negative > 0 {
+                values[var_id] = Some(num_positive >= num_negative);
+            }
+        }
+
+        let mut num_true = clause_ids
+            .iter()
+            .map(|clause_id| {
+                database[*clause_id]
+                    .literals
+                    .iter()
+                    .filter(|lit| values[lit.var()] == Some(lit.positive()))
+                    .count()
+            })
+            .collect_vec();
+        let mut unsatisfied = (0..clause_ids.len())
+            .filter(|index| {
+                num_true[*index] == 0
+                    && touches_autarky(&database[clause_ids[*index]], &values, &fixed)
+            })
+            .collect_vec();
+        while let Some(index) = unsatisfied.pop() {
+            for lit in &database[clause_ids[index]].literals {
+                if fixed[lit.var()] || values[lit.var()].is_none() {
+                    continue;
+                }
+                // the literal is false, the clauses of its negation lose a true literal
+                values[lit.var()] = None;
+                for other in &occurrences[(-*lit).code()] {
+                    num_true[*other] -= 1;
+                    if num_true[*other] == 0
+                        && touches_autarky(&database[clause_ids[*other]], &values, &fixed)
+                    {
+                        unsatisfied.push(*other);
+                    }
+                }
+            }
+        }
+
+        let autarky = Var::range(num_vars)
+            .filter(|var_id| !fixed[*var_id])
+            .filter_map(|var_id| values[var_id].map(|value| Literal::from_value(var_id, value)))
+            .collect_vec();
+        if autarky.is_empty() {
+            return;
+        }
+        let touched = database
+            .iter()
+            .filter(|clause_id| touches_autarky(&database[*clause_id], &values, &fixed))
+            .collect_vec();
+        for clause_id in touched {
+            if state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher)
+            {
+                state.stats.num_inprocessing_deleted_clauses += 1;
+            }
+        }
+        for lit in &autarky {
+            state
+                .reconstruction_stack
+                .push(*lit, Clause::from(vec![*lit]));
+        }
+        state.stats.num_autarky_vars += autarky.len();
+        self.num_changes += 1;
+        self.bve_queue
+            .retain(|var_id| values[*var_id].is_none() || fixed[*var_id]);
+        self.skipped_vars
+            .retain(|var_id| values[*var_id].is_none() || fixed[*var_id]);
+    }
+
+    /// Backward subsumption and self-subsuming resolution among the irredundant clauses as in
+    /// N. Eén and A. Biere, “Effective Preprocessing in SAT Through Variable and Clause
+    /// Elimination,” in Theory and Applications of Satisfiability Testing – SAT 2005,
+    /// pp. 61–75. From the shortest clause on, each clause deletes the clauses it subsumes and
+    /// removes the negated literal from the clauses it subsumes with one literal negated, which
+    /// leaves their resolvent. Returns whether all clauses were tried.
+    fn subsume(&mut self, state: &mut State, trail: &mut Trail) -> bool {
+        let index = Technique::Subsumption as usize;
+        let num_literals = 2 * (state.num_vars + 1);
+        let database = &state.clause_database;
+        let clause_ids = database
+            .necessary_clauses_iter()
+            .sorted_by_key(|clause_id| (database[*clause_id].literals.len(), *clause_id))
+            .collect_vec();
+        let mut occurrences = vec![vec![]; num_literals];
+        for clause_id in &clause_ids {
+            for lit in &database[*clause_id].literals {
+                occurrences[lit.code()].push(*clause_id);
+            }
+        }
+        let mut marked = vec![false; num_literals];
+        let mut deleted = HashSet::new();
+
+        let cursor = self.schedules[index].cursor;
+        for (position, &clause_id) in clause_ids.iter().enumerate().skip(cursor) {
+            if self.is_exhausted() || state.conflict.is_some() {
+                self.schedules[index].cursor = position;
+                return state.conflict.is_some();
+            }
+            if deleted.contains(&clause_id) {
+                continue;
+            }
+            let literals = state.clause_database[clause_id].literals.clone();
+            let Some(&pivot) = literals.iter().min_by_key(|lit| {
+                occurrences[lit.code()].len() + occurrences[(-**lit).code()].len()
+            }) else {
+                continue;
+            };
+            literals.iter().for_each(|lit| marked[lit.code()] = true);
+            let candidates = occurrences[pivot.code()]
+                .iter()
+                .chain(&occurrences[(-pivot).code()])
+                .copied()
+                .filter(|other| *other != clause_id && !deleted.contains(other))
+                .collect_vec();
+            for other in candidates {
+                let other_literals = &state.clause_database[other].literals;
+                if other_literals.len() < literals.len() {
+                    continue;
+                }
+                self.schedules[index].effort += other_literals.len();
+                let num_contained = other_literals
+                    .iter()
+                    .filter(|lit| marked[lit.code()])
+                    .count();
+                let mut negated = other_literals.iter().filter(|lit| marked[(-**lit).code()]);
+                let (first_negated, more_negated) =
+                    (negated.next().copied(), negated.next().is_some());
+                if num_contained == literals.len() {
+                    if state
+                        .clause_database
+                        .delete_clause_if_allowed(other, &mut state.literal_watcher)
+                    {
+                        deleted.insert(other);
+                        state.stats.num_subsumed_clauses += 1;
+                        state.stats.num_inprocessing_deleted_clauses += 1;
+                        self.num_changes += 1;
+                    }
+                } else if let (Some(negated), false) = (first_negated, more_negated) {
+                    if num_contained + 1 != literals.len() || state.clause_database.is_reason(other)
+                    {
+                        continue;
+                    }
+                    state
+                        .clause_database
+                        .proof_logger
+                        .set_antecedents(&Antecedents {
+                            clauses: vec![other, clause_id],
+                            ..Default::default()
+                        });
+                    state.clause_database.strengthen_clause(
+                        other,
+                        negated,
+                        &mut state.literal_watcher,
+                        &state.vars,
+                        trail,
+                    );
+                    state.stats.num_self_subsumptions += 1;
+                    self.num_changes += 1;
+                    if let [unit] = state.clause_database[other].literals[..] {
+                        trail.enqueue(state, unit, ConstraintRef::Clause(other));
+                    }
+                }
+            }
+            literals.iter().for_each(|lit| marked[lit.code()] = false);
+        }
+        true
+    }
+
+    /// Failed literal probing: each literal implying another one by a binary clause is decided
+    /// and propagated. If this leads to a conflict, the negated literal is added as a unit
+    /// clause, which is RUP. Returns whether all literals were tried or a conflict at level 0
+    /// was found.
+    fn probe(
+        &mut self,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+    ) -> bool {
+        let index = Technique::Probing as usize;
+        trail.propagate(state);
+        let mut implies = vec![false; 2 * (state.num_vars + 1)];
+        for clause_id in state.clause_database.necessary_clauses_iter() {
+            if let [first, second] = state.clause_database[clause_id].literals[..] {
+                implies[(-first).code()] = true;
+                implies[(-second).code()] = true;
+            }
+        }
+
+        for position in self.schedules[index].cursor..state.num_vars {
+            if self.is_exhausted() || state.conflict.is_some() {
+                self.schedules[index].cursor = position;
+                return state.conflict.is_some();
+            }
+            let var_id = Var::new(position + 1);
+            for lit in [true, false].map(|value| Literal::from_value(var_id, value)) {
+                if !implies[lit.code()]
+                    || state.vars.is_assigned(var_id)
+                    || state.conflict.is_some()
+                {
+                    continue;
+                }
+                let (num_assignments, num_conflicts) =
+                    (state.stats.num_assignments, state.stats.num_conflicts);
+                trail.assign(state, lit, AssignmentReason::Heuristic);
+                trail.propagate(state);
+                let failed = state.conflict.is_some();
+                trail.backtrack(state, heuristic, 0);
+                // the conflicts of probes aren't conflicts of the search
+                state.stats.num_conflicts = num_conflicts;
+                self.schedules[index].effort += state.stats.num_assignments - num_assignments;
+                if failed {
+                    let clause_id = state.clause_database.add_clause(
+                        Clause::from(vec![-lit]),
+                        &mut state.literal_watcher,
+                        &state.vars,
+                        trail,
+                    );
+                    trail.enqueue(state, -lit, ConstraintRef::Clause(clause_id));
+                    trail.propagate(state);
+                    state.stats.num_failed_literals += 1;
+                    self.num_changes += 1;
                 }
             }
         }
+        true
+    }
+
+    /// Clause vivification as described in C. Piette, Y. Hamadi, and L. Saïs, “Vivifying
+    /// Propositional Clausal Formulae,” in ECAI 2008, pp. 525–529. The negated literals of an
+    /// irredundant clause are decided and propagated one after another. Literals falsified on
+    /// the way are removed, and once a literal is satisfied or a conflict is found, the clause
+    /// is cut off after it. The shortened clause is RUP. Returns whether all clauses were tried
+    /// or a conflict at level 0 was found.
+    fn vivify(
+        &mut self,
+        heuristic: &mut dyn Heuristic,
+        state: &mut State,
+        trail: &mut Trail,
+    ) -> bool {
+        let index = Technique::Vivification as usize;
+        trail.propagate(state);
+        let clause_ids = state.clause_database.necessary_clauses_iter().collect_vec();
+
+        let cursor = self.schedules[index].cursor;
+        for (position, &clause_id) in clause_ids.iter().enumerate().skip(cursor) {
+            if self.is_exhausted() || state.conflict.is_some() {
+                self.schedules[index].cursor = position;
+                return state.conflict.is_some();
+            }
+            let literals = state.clause_database[clause_id].literals.clone();
+            if literals.len() < 2
+                || state.clause_database.is_reason(clause_id)
+                || literals.iter().any(|lit| lit.is_true(&state.vars))
+            {
+                continue;
+            }
+            let (num_assignments, num_conflicts) =
+                (state.stats.num_assignments, state.stats.num_conflicts);
+            let mut vivified = vec![];
+            for &lit in &literals {
+                match lit.value(&state.vars) {
+                    Some(false) => continue,
+                    Some(true) => {
+                        vivified.push(lit);
+                        break;
+                    }
+                    None => {
+                        vivified.push(lit);
+                        trail.assign(state, -lit, AssignmentReason::Heuristic);
+                        trail.propagate(state);
+                        if state.conflict.is_some() {
+                            break;
+                        }
+                    }
+                }
+            }
+            trail.backtrack(state, heuristic, 0);
+            state.stats.num_conflicts = num_conflicts;
+            self.schedules[index].effort += state.stats.num_assignments - num_assignments;
+            if vivified.is_empty() || vivified.len() == literals.len() {
+                continue;
+            }
+            let unit = (vivified.len() == 1).then(|| vivified[0]);
+            state.clause_database.replace_literals(
+                clause_id,
+                vivified,
+                &mut state.literal_watcher,
+                &state.vars,
+                trail,
+            );
+            state.stats.num_vivified_clauses += 1;
+            self.num_changes += 1;
+            if let Some(unit) = unit {
+                trail.enqueue(state, unit, ConstraintRef::Clause(clause_id));
+                trail.propagate(state);
+            }
+        }
+        true
+    }
+
+    /// Blocked clause elimination as described in M. Järvisalo, A. Biere, and M. J. H. Heule,
+    /// “Blocked Clause Elimination,” in Tools and Algorithms for the Construction and Analysis
+    /// of Systems – TACAS 2010, pp. 129–144. An irredundant clause is blocked on a literal if
+    /// all its resolvents on it with the irredundant clauses are tautologies. It is deleted and
+    /// pushed on the reconstruction stack with the literal as witness. Excluded variables and
+    /// variables fixed at level 0 are never witnesses. Returns whether all variables were tried.
+    fn eliminate_blocked_clauses(&mut self, state: &mut State, trail: &Trail) -> bool {
+        let index = Technique::BlockedClauses as usize;
+        let num_literals = 2 * (state.num_vars + 1);
+        let mut occurrences = vec![vec![]; num_literals];
+        for clause_id in state.clause_database.necessary_clauses_iter() {
+            for lit in &state.clause_database[clause_id].literals {
+                occurrences[lit.code()].push(clause_id);
+            }
+        }
+        let mut marked = vec![false; num_literals];
+        let mut deleted = HashSet::new();
+
+        for position in self.schedules[index].cursor..state.num_vars {
+            if self.is_exhausted() {
+                self.schedules[index].cursor = position;
+                return false;
+            }
+            let var_id = Var::new(position + 1);
+            if self.is_excluded(var_id) || state.vars.is_assigned(var_id) {
+                continue;
+            }
+            for lit in [true, false].map(|value| Literal::from_value(var_id, value)) {
+                for &clause_id in &occurrences[lit.code()] {
+                    if deleted.contains(&clause_id) || state.clause_database.is_reason(clause_id) {
+                        continue;
+                    }
+                    let clause = &state.clause_database[clause_id];
+                    clause
+                        .literals
+                        .iter()
+                        .for_each(|lit| marked[lit.code()] = true);
+                    let is_blocked = occurrences[(-lit).code()]
+                        .iter()
+                        .filter(|other| !deleted.contains(*other))
+                        .all(|other| {
+                            let other_literals = &state.clause_database[*other].literals;
+                            self.schedules[index].effort += other_literals.len();
+                            other_literals
+                                .iter()
+                                .any(|other_lit| *other_lit != -lit && marked[(-*other_lit).code()])
+                        });
+                    clause
+                        .literals
+                        .iter()
+                        .for_each(|lit| marked[lit.code()] = false);
+                    if !is_blocked {
+                        continue;
+                    }
+                    let clause = clause.clone();
+                    if state
+                        .clause_database
+                        .delete_clause_if_allowed(clause_id, &mut state.literal_watcher)
+                    {
+                        state.reconstruction_stack.push(lit, clause);
+                        deleted.insert(clause_id);
+                        state.stats.num_blocked_clauses += 1;
+                        state.stats.num_inprocessing_deleted_clauses += 1;
+                        self.num_changes += 1;
+                    }
+                }
+            }
+        }
+        debug_assert_eq!(trail.decision_level, 0);
+        true
+    }
+}
+
+/// Whether the clause contains a variable of the autarky
+fn touches_autarky(clause: &Clause, values: &[Option<bool>], fixed: &[bool]) -> bool {
+    clause
+        .literals
+        .iter()
+        .any(|lit| values[lit.var()].is_some() && !fixed[lit.var()])
+}
+
+/// Antecedents of the clause with the substituted literals: each literal is resolved with the
+/// binary clauses along a path of the implication graph to its replacement. Incomplete if a
+/// path removes another literal of the clause that is kept.
+fn substitution_antecedents(
+    literals: &[Literal],
+    clause_id: ClauseId,
+    substitute: impl Fn(Literal) -> Literal,
+    implications: &[Vec<Literal>],
+    implication_clauses: &HashMap<(Literal, Literal), ClauseId>,
+) -> Antecedents {
+    let mut antecedents = Antecedents {
+        clauses: vec![clause_id],
+        ..Default::default()
+    };
+    let mut resolvent: HashSet<Literal> = literals.iter().copied().collect();
+    for &lit in literals {
+        let replacement = substitute(lit);
+        if replacement == lit || !resolvent.contains(&lit) {
+            continue;
+        }
+        for step in implication_path(lit, replacement, implications).windows(2) {
+            antecedents
+                .clauses
+                .push(implication_clauses[&(step[0], step[1])]);
+            resolvent.remove(&step[0]);
+            resolvent.insert(step[1]);
+        }
+    }
+    antecedents.incomplete = resolvent != literals.iter().map(|lit| substitute(*lit)).collect();
+    antecedents
+}
+
+/// Shortest path from one literal to another in the graph with the edges `graph[lit.code()]`,
+/// which has to exist
+fn implication_path(from: Literal, to: Literal, graph: &[Vec<Literal>]) -> Vec<Literal> {
+    let mut predecessors = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(lit) = queue.pop_front() {
+        if lit == to {
+            break;
+        }
+        for &next in &graph[lit.code()] {
+            if next != from && !predecessors.contains_key(&next) {
+                predecessors.insert(next, lit);
+                queue.push_back(next);
+            }
+        }
+    }
+    let mut path = vec![to];
+    while let Some(predecessor) = predecessors.get(path.last().unwrap()) {
+        path.push(*predecessor);
+    }
+    path.reverse();
+    path
+}
+
+/// Strongly connected components of the graph with the edges `graph[lit.code()]` from each
+/// literal, by Tarjan's algorithm without recursion
+fn strongly_connected_components(graph: &[Vec<Literal>]) -> Vec<Vec<Literal>> {
+    const UNVISITED: usize = usize::MAX;
+    let literal = |code: usize| Literal::from_value(Var::new(code >> 1), code & 1 == 0);
+    let mut index = vec![UNVISITED; graph.len()];
+    let mut low_link = vec![0; graph.len()];
+    let mut on_stack = vec![false; graph.len()];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut next_index = 0;
+
+    for root in 0..graph.len() {
+        if index[root] != UNVISITED || graph[root].is_empty() {
+            continue;
+        }
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        // nodes of the depth-first search with the position of their next edge
+        let mut path = vec![(root, 0)];
+
+        while let Some(&(node, edge)) = path.last() {
+            if let Some(successor) = graph[node].get(edge).map(|lit| lit.code()) {
+                path.last_mut().unwrap().1 += 1;
+                if index[successor] == UNVISITED {
+                    index[successor] = next_index;
+                    low_link[successor] = next_index;
+                    next_index += 1;
+                    stack.push(successor);
+                    on_stack[successor] = true;
+                    path.push((successor, 0));
+                } else if on_stack[successor] {
+                    low_link[node] = low_link[node].min(index[successor]);
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(parent, _)) = path.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                let mut component = vec![];
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(literal(member));
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{check_assignment, SolveResult};
+    use crate::proof::checker::{check_proof, CheckResult};
+    use crate::solver::statistics::StateStatistics;
+    use crate::solver::Solver;
+    use crate::tester::{brute_force_sat, random_cnf};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn substitution_and_autarkies_preserve_satisfiability() {
+        let config = Config {
+            core_extraction: true,
+            resolution_chains: true,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let num_vars = 12;
+        let (mut substituted_vars, mut autarky_vars) = (0, 0);
+        for seed in 0..200 {
+            let mut clauses = random_cnf(num_vars, 3.0, 3, seed);
+            for _ in 0..3 {
+                let first = Literal::from_value(Var::new(rng.gen_range(1..=num_vars)), rng.gen());
+                let second = Literal::from_value(Var::new(rng.gen_range(1..=num_vars)), rng.gen());
+                if first.var() != second.var() {
+                    clauses.push(Clause::from(vec![-first, second]));
+                    clauses.push(Clause::from(vec![first, -second]));
+                }
+            }
+
+            let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+            solver.simplify();
+            substituted_vars += solver.stats().num_substituted_vars;
+            autarky_vars += solver.stats().num_autarky_vars;
+            match solver.solve() {
+                SolveResult::Sat(model) => assert!(check_assignment(&clauses, &model)),
+                SolveResult::Unsat => {
+                    assert!(!brute_force_sat(&clauses, num_vars), "seed {}", seed);
+                    let proof = solver.state.clause_database.proof_logger.proof.clone();
+                    let proof = proof.into_iter().enumerate().collect_vec();
+                    assert_eq!(
+                        check_proof(&clauses, num_vars, &proof),
+                        CheckResult::Verified,
+                        "seed {}",
+                        seed
+                    );
+                    let resolution_proof = solver.resolution_proof().unwrap();
+                    assert!(resolution_proof.is_valid(&clauses), "seed {}", seed);
+                }
+                SolveResult::Unknown(_) => unreachable!(),
+            }
+        }
+        assert!(substituted_vars > 0);
+        assert!(autarky_vars > 0);
+    }
+
+    #[test]
+    fn techniques_preserve_satisfiability() {
+        // the proof is logged without chains, so all techniques run
+        let config = Config {
+            core_extraction: true,
+            ..Default::default()
+        };
+        let num_vars = 12;
+        let mut stats = StateStatistics::default();
+        for seed in 0..300 {
+            let mut clauses = random_cnf(num_vars, 3.5, 3, seed);
+            clauses.extend(random_cnf(num_vars, 1.0, 2, seed + 1000));
+
+            let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+            if solver.simplify() {
+                assert!(solver.inprocessor.is_complete(&solver.state, &solver.trail));
+            }
+            stats.num_subsumed_clauses += solver.stats().num_subsumed_clauses;
+            stats.num_self_subsumptions += solver.stats().num_self_subsumptions;
+            stats.num_vivified_clauses += solver.stats().num_vivified_clauses;
+            stats.num_failed_literals += solver.stats().num_failed_literals;
+            stats.num_blocked_clauses += solver.stats().num_blocked_clauses;
+            match solver.solve() {
+                SolveResult::Sat(model) => assert!(check_assignment(&clauses, &model)),
+                SolveResult::Unsat => {
+                    assert!(!brute_force_sat(&clauses, num_vars), "seed {}", seed);
+                    let proof = solver.state.clause_database.proof_logger.proof.clone();
+                    let proof = proof.into_iter().enumerate().collect_vec();
+                    assert_eq!(
+                        check_proof(&clauses, num_vars, &proof),
+                        CheckResult::Verified,
+                        "seed {}",
+                        seed
+                    );
+                }
+                SolveResult::Unknown(_) => unreachable!(),
+            }
+        }
+        assert!(stats.num_subsumed_clauses > 0);
+        assert!(stats.num_self_subsumptions > 0);
+        assert!(stats.num_vivified_clauses > 0);
+        assert!(stats.num_failed_literals > 0);
+        assert!(stats.num_blocked_clauses > 0);
+    }
+
+    #[test]
+    fn gate_definitions_preserve_satisfiability() {
+        let config = Config::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let (num_inputs, num_vars) = (10, 14);
+        let mut num_gates = 0;
+        for seed in 0..100 {
+            let mut clauses = random_cnf(num_inputs, 2.0, 3, seed);
+            let mut input =
+                || Literal::from_value(Var::new(rng.gen_range(1..=num_inputs)), rng.gen::<bool>());
+            let (a, b, c) = (input(), input(), input());
+            if a.var() == b.var() || a.var() == c.var() || b.var() == c.var() {
+                continue;
+            }
+            let x = |index: usize| Literal::from_value(Var::new(num_inputs + index), true);
+            // x_1 = a ∧ b, x_2 = a ∨ c, x_3 = a ⊕ b and x_4 = a ? b : c
+            clauses.extend(
+                [
+                    vec![-x(1), a],
+                    vec![-x(1), b],
+                    vec![x(1), -a, -b],
+                    vec![x(2), -a],
+                    vec![x(2), -c],
+                    vec![-x(2), a, c],
+                    vec![-x(3), a, b],
+                    vec![-x(3), -a, -b],
+                    vec![x(3), -a, b],
+                    vec![x(3), a, -b],
+                    vec![-x(4), -a, b],
+                    vec![-x(4), a, c],
+                    vec![x(4), -a, -b],
+                    vec![x(4), a, -c],
+                    vec![x(1), x(2), -x(3)],
+                    vec![-x(2), x(4), -c],
+                    vec![x(3), -x(4), b],
+                ]
+                .map(Clause::from),
+            );
+
+            let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+            solver.simplify();
+            num_gates += solver.stats().num_gate_eliminations;
+            match solver.solve() {
+                SolveResult::Sat(model) => assert!(check_assignment(&clauses, &model)),
+                SolveResult::Unsat => {
+                    assert!(!brute_force_sat(&clauses, num_vars), "seed {}", seed)
+                }
+                SolveResult::Unknown(_) => unreachable!(),
+            }
+        }
+        assert!(num_gates > 0);
+    }
+
+    #[test]
+    fn skipped_vars_are_queued_again_when_touched() {
+        let clauses = ["1 2", "1 3", "1 4", "-1 5", "-1 6", "-1 7"].map(Clause::from);
+        let mut state = State::init(clauses.to_vec(), 7, false);
+        let trail = Trail::new(7);
+        let mut inprocessor = Inprocessor::init(&state.clause_database, &Config::default());
+        let var_id = Var::new(1);
+        inprocessor.bve_queue = VecDeque::from([var_id]);
+
+        // 9 resolvents replacing 6 clauses exceed the clause growth of 0
+        inprocessor.eliminate_queued_vars(&trail, &mut vec![], &mut state);
+        assert!(inprocessor.skipped_vars.contains(&var_id));
+        assert!(inprocessor.is_at_fixed_point(Technique::Elimination, &state, &trail));
+
+        for clause_id in [0, 1] {
+            state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher);
+        }
+        assert!(!inprocessor.is_at_fixed_point(Technique::Elimination, &state, &trail));
+        inprocessor.requeue_touched_vars(&mut state);
+        assert_eq!(inprocessor.bve_queue, [var_id]);
+
+        // 3 resolvents replace the remaining 4 clauses
+        inprocessor.eliminate_queued_vars(&trail, &mut vec![], &mut state);
+        assert!(inprocessor.skipped_vars.is_empty());
+        assert_eq!(state.stats.num_eliminated_vars, 1);
+    }
+
+    #[test]
+    fn learned_clauses_of_eliminated_vars_are_deleted() {
+        let clauses = ["1 2", "1 3", "-1 4", "2 3 4 5"].map(Clause::from);
+        let mut state = State::init(clauses.to_vec(), 5, false);
+        let trail = Trail::new(5);
+        for (literals, lbd) in [("1 5", 2), ("-1 -5", 2), ("-1 2 5", 3)] {
+            let literals = Clause::from(literals).literals;
+            state.clause_database.add_clause(
+                Clause::from_literals_and_lbd(literals, lbd),
+                &mut state.literal_watcher,
+                &state.vars,
+                &trail,
+            );
+        }
+        let mut inprocessor = Inprocessor::init(&state.clause_database, &Config::default());
+        let var_id = Var::new(1);
+
+        // a variable fixed at level 0 is left out
+        let mut units = vec![(Literal::new(1), ConstraintRef::Clause(0))];
+        inprocessor.bve_queue = VecDeque::from([var_id]);
+        inprocessor.eliminate_queued_vars(&trail, &mut units, &mut state);
+        assert_eq!(state.stats.num_eliminated_vars, 0);
+
+        // 2 resolvents replace the 3 irredundant clauses, the learned ones don't count
+        inprocessor.bve_queue = VecDeque::from([var_id]);
+        inprocessor.eliminate_queued_vars(&trail, &mut vec![], &mut state);
+        assert_eq!(state.stats.num_eliminated_vars, 1);
+        assert_eq!(state.clause_database.num_learned_clauses(), 0);
+        let database = &state.clause_database;
+        assert!(database.iter().all(|clause_id| database[clause_id]
+            .literals
+            .iter()
+            .all(|lit| lit.var() != var_id)));
     }
 }
@@ -0,0 +1,280 @@
+use crate::cnf::{Clause, Literal, Model, Var};
+use crate::solver::resolution::{ProofClause, ResolutionProof};
+use alloc::{vec, vec::Vec};
+use hashbrown::HashMap;
+
+/// Node of an `Interpolant`, the operands of gates are indices of earlier nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InterpolantNode {
+    Const(bool),
+    Literal(Literal),
+    And(usize, usize),
+    Or(usize, usize),
+}
+
+/// Craig interpolant of a partition of an unsatisfiable formula into the clauses A and B: a
+/// formula over the variables occurring in both, implied by A and inconsistent with B. It is
+/// stored as a circuit, as partial interpolants are shared by all lemmas derived from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpolant {
+    /// Nodes in topological order, starting with the constants `false` and `true`
+    pub nodes: Vec<InterpolantNode>,
+    pub root: usize,
+}
+
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+impl Interpolant {
+    /// Computes the interpolant from a resolution refutation with the system of K. L. McMillan,
+    /// “Interpolation and SAT-Based Model Checking,” in Computer Aided Verification, 2003,
+    /// pp. 1–13. The partial interpolant of a clause of A is the disjunction of its literals
+    /// occurring in B, the one of a clause of B is `true`. Resolving on a variable of A only
+    /// disjoins the partial interpolants, resolving on any other variable conjoins them.
+    /// Returns `None` if a chain isn't a sequence of resolutions.
+    pub fn from_proof(
+        proof: &ResolutionProof,
+        original_clauses: &[Clause],
+        num_vars: usize,
+        a_clauses: &[usize],
+    ) -> Option<Self> {
+        let mut is_a = vec![false; original_clauses.len()];
+        for &index in a_clauses {
+            if let Some(is_a) = is_a.get_mut(index) {
+                *is_a = true;
+            }
+        }
+        let mut is_shared = vec![false; num_vars + 1];
+        for (index, clause) in original_clauses.iter().enumerate() {
+            if !is_a[index] {
+                for lit in &clause.literals {
+                    is_shared[lit.var()] = true;
+                }
+            }
+        }
+
+        let mut builder = Builder::default();
+        let mut original_nodes = HashMap::new();
+        let mut lemma_nodes: Vec<usize> = Vec::with_capacity(proof.lemmas.len());
+        for (index, lemma) in proof.lemmas.iter().enumerate() {
+            let pivots = proof.pivots(index, original_clauses)?;
+            let antecedents = lemma
+                .chain
+                .iter()
+                .map(|clause| match *clause {
+                    ProofClause::Original(index) if is_a[index] => {
+                        *original_nodes.entry(index).or_insert_with(|| {
+                            original_clauses[index]
+                                .literals
+                                .iter()
+                                .filter(|lit| is_shared[lit.var()])
+                                .fold(FALSE, |node, lit| {
+                                    let lit = builder.literal(*lit);
+                                    builder.or(node, lit)
+                                })
+                        })
+                    }
+                    ProofClause::Original(_) => TRUE,
+                    ProofClause::Lemma(index) => lemma_nodes[index],
+                })
+                .collect::<Vec<_>>();
+            let mut resolvent = antecedents[0];
+            for (antecedent, pivot) in antecedents[1..].iter().zip(pivots) {
+                resolvent = match is_shared[pivot] {
+                    true => builder.and(resolvent, *antecedent),
+                    false => builder.or(resolvent, *antecedent),
+                };
+            }
+            lemma_nodes.push(resolvent);
+        }
+        Some(Interpolant {
+            nodes: builder.nodes,
+            root: *lemma_nodes.last()?,
+        })
+    }
+
+    /// Value of the interpolant under the model
+    pub fn evaluate(&self, model: &Model) -> bool {
+        let mut values = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match *node {
+                InterpolantNode::Const(value) => value,
+                InterpolantNode::Literal(lit) => model.value(lit),
+                InterpolantNode::And(left, right) => values[left] && values[right],
+                InterpolantNode::Or(left, right) => values[left] || values[right],
+            };
+            values.push(value);
+        }
+        values[self.root]
+    }
+
+    /// Variables the interpolant depends on, in ascending order
+    pub fn vars(&self) -> Vec<Var> {
+        let mut is_used = vec![false; self.nodes.len()];
+        is_used[self.root] = true;
+        let mut vars = vec![];
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            match (is_used[index], *node) {
+                (true, InterpolantNode::Literal(lit)) => vars.push(lit.var()),
+                (true, InterpolantNode::And(left, right) | InterpolantNode::Or(left, right)) => {
+                    is_used[left] = true;
+                    is_used[right] = true;
+                }
+                _ => {}
+            }
+        }
+        vars.sort_unstable();
+        vars.dedup();
+        vars
+    }
+}
+
+/// Creates the nodes of an interpolant, folding constants and sharing equal nodes
+struct Builder {
+    nodes: Vec<InterpolantNode>,
+    indices: HashMap<InterpolantNode, usize>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        let mut builder = Builder {
+            nodes: vec![],
+            indices: HashMap::new(),
+        };
+        builder.add(InterpolantNode::Const(false));
+        builder.add(InterpolantNode::Const(true));
+        builder
+    }
+}
+
+impl Builder {
+    fn add(&mut self, node: InterpolantNode) -> usize {
+        if let Some(index) = self.indices.get(&node) {
+            return *index;
+        }
+        self.nodes.push(node);
+        self.indices.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn literal(&mut self, lit: Literal) -> usize {
+        self.add(InterpolantNode::Literal(lit))
+    }
+
+    fn and(&mut self, left: usize, right: usize) -> usize {
+        match (left, right) {
+            (FALSE, _) | (_, FALSE) => FALSE,
+            (TRUE, other) | (other, TRUE) => other,
+            _ if left == right => left,
+            _ => self.add(InterpolantNode::And(left.min(right), left.max(right))),
+        }
+    }
+
+    fn or(&mut self, left: usize, right: usize) -> usize {
+        match (left, right) {
+            (TRUE, _) | (_, TRUE) => TRUE,
+            (FALSE, other) | (other, FALSE) => other,
+            _ if left == right => left,
+            _ => self.add(InterpolantNode::Or(left.min(right), left.max(right))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+
+    fn config() -> Config {
+        Config {
+            resolution_chains: true,
+            ..Default::default()
+        }
+    }
+
+    /// Tseitin encoding of the interpolant, where node i is variable num_vars + 1 + i
+    fn encode(interpolant: &Interpolant, num_vars: usize) -> (Literal, Vec<Clause>) {
+        let node_literal = |node: usize| Literal::from_value(Var::new(num_vars + 1 + node), true);
+        let mut clauses = vec![];
+        for (index, node) in interpolant.nodes.iter().enumerate() {
+            let output = node_literal(index);
+            match *node {
+                InterpolantNode::Const(value) => {
+                    clauses.push(vec![if value { output } else { -output }]);
+                }
+                InterpolantNode::Literal(lit) => {
+                    clauses.extend([vec![-output, lit], vec![output, -lit]]);
+                }
+                InterpolantNode::And(left, right) => {
+                    let (left, right) = (node_literal(left), node_literal(right));
+                    clauses.extend([
+                        vec![-output, left],
+                        vec![-output, right],
+                        vec![output, -left, -right],
+                    ]);
+                }
+                InterpolantNode::Or(left, right) => {
+                    let (left, right) = (node_literal(left), node_literal(right));
+                    clauses.extend([
+                        vec![output, -left],
+                        vec![output, -right],
+                        vec![-output, left, right],
+                    ]);
+                }
+            }
+        }
+        let clauses = clauses.into_iter().map(Clause::from).collect();
+        (node_literal(interpolant.root), clauses)
+    }
+
+    #[test]
+    fn interpolant_of_a_chain_of_implications() {
+        let clauses = ["1", "-1 2", "-2 3", "-3"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 3, config());
+        assert!(solver.solve().is_unsat());
+        let interpolant = solver.interpolant(&[0, 1]).unwrap();
+        assert_eq!(interpolant.vars(), [Var::new(2)]);
+        assert!(interpolant.evaluate(&Model::new(vec![false, true, false])));
+        assert!(!interpolant.evaluate(&Model::new(vec![true, false, true])));
+        // A alone is unsatisfiable
+        let interpolant = solver.interpolant(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(
+            interpolant.nodes[interpolant.root],
+            InterpolantNode::Const(false)
+        );
+    }
+
+    #[test]
+    fn interpolants_separate_the_partition() {
+        for file in ["hole6.cnf", "pret60_25.cnf", "aim-50-1_6-no-1.cnf"] {
+            let path = format!("testfiles/lecture_testfiles/unsat/{}", file);
+            let dimacs = clauses_from_dimacs_file(&path).unwrap();
+            let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config());
+            assert!(solver.solve().is_unsat());
+            let a_clauses = (0..dimacs.clauses.len() / 2).collect::<Vec<_>>();
+            let interpolant = solver.interpolant(&a_clauses).unwrap();
+            let (a, b) = dimacs.clauses.split_at(a_clauses.len());
+
+            let shared = |var: &Var| {
+                let occurs = |clauses: &[Clause]| {
+                    clauses
+                        .iter()
+                        .any(|clause| clause.literals.iter().any(|lit| lit.var() == *var))
+                };
+                occurs(a) && occurs(b)
+            };
+            assert!(interpolant.vars().iter().all(shared), "{}", file);
+
+            let (root, encoding) = encode(&interpolant, dimacs.num_vars);
+            let num_vars = dimacs.num_vars + interpolant.nodes.len();
+            for (part, root) in [(a, -root), (b, root)] {
+                let mut clauses = [part, &encoding].concat();
+                clauses.push(Clause::from(vec![root]));
+                let mut solver = Solver::new(clauses, num_vars, config());
+                assert!(solver.solve().is_unsat(), "{}", file);
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::cnf::Literal;
+use crate::solver::statistics::StateStatistics;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+/// Observer of the search, registered in `Config::listeners`, e.g. for visualizers and tuners.
+/// All callbacks do nothing by default and get the statistics at the time of the event. The
+/// number of learned clauses in the statistics is only updated between the events, the
+/// callbacks get the exact counts where they matter.
+pub trait SearchListener {
+    /// A learned clause is added after a conflict, its first literal is asserted next
+    fn on_conflict(&mut self, _stats: &StateStatistics, _learned_clause: &[Literal], _lbd: usize) {}
+
+    /// After backtracking for a restart, keeping the given number of decision levels
+    fn on_restart(&mut self, _stats: &StateStatistics, _reused_levels: usize) {}
+
+    /// The clause database was reduced to the given number of learned clauses
+    fn on_reduce(&mut self, _stats: &StateStatistics, _num_learned_clauses: usize) {}
+
+    /// A round of inprocessing finished, compare with the statistics before it to see what it did
+    fn on_inprocessing(&mut self, _before: &StateStatistics, _after: &StateStatistics) {}
+}
+
+/// The solver only borrows a listener during a callback, so the caller can keep a clone and
+/// read what it collected between calls to `solve`
+pub type Listener = Rc<RefCell<dyn SearchListener>>;
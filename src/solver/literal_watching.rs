@@ -1,99 +1,145 @@
-use crate::cnf::{Clause, ClauseId, Literal};
-use std::ops::Neg;
-
-#[derive(Debug, Default, Clone)]
-pub struct VarWatch {
-    pub pos: Vec<ClauseId>,
-    pub neg: Vec<ClauseId>,
-}
+use crate::cnf::{Clause, ClauseId, Literal, PartialAssignment};
+use crate::solver::trail::Trail;
+use alloc::{vec, vec::Vec};
+use core::cmp::Reverse;
+use core::ops::Neg;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum WatchUpdate {
-    FoundNewWatch,
+    FoundNewWatch(Literal),
     Unit(Literal),
     Conflict,
     Satisfied(Literal),
 }
 
+/// The watched literals are stored separately for each clause instead of being the first two
+/// literals, so the literals of a clause can be reordered or removed without breaking the
+/// watches as long as the watched ones are kept.
 #[derive(Debug, Clone)]
 pub struct LiteralWatcher {
-    pub var_watches: Vec<VarWatch>,
+    /// Clauses watching each literal, indexed by `Literal::code`
+    watches: Vec<Vec<ClauseId>>,
+    /// Watched literals by clause, unused for clauses with less than two literals
+    watched_literals: Vec<[Literal; 2]>,
 }
 
 impl LiteralWatcher {
     pub fn new(clauses: &[Clause], num_vars: usize) -> Self {
-        LiteralWatcher {
-            var_watches: Self::create_watches(clauses, num_vars),
+        let mut literal_watcher = LiteralWatcher {
+            watches: vec![vec![]; 2 * (num_vars + 1)],
+            watched_literals: vec![],
+        };
+        for (clause_id, clause) in clauses.iter().enumerate() {
+            // no literal is assigned yet, so any two literals can be watched
+            literal_watcher.watch(clause, clause_id, |_| 0);
         }
+        literal_watcher
     }
 
-    pub fn add_clause(&mut self, clause: &Clause, clause_id: ClauseId) {
+    /// Watches the two literals becoming unassigned last during backtracking: true and
+    /// unassigned literals, otherwise the false literals with the highest decision levels.
+    /// This keeps the watches valid after backtracking to any level.
+    pub fn add_clause(
+        &mut self,
+        clause: &Clause,
+        clause_id: ClauseId,
+        vars: &PartialAssignment,
+        trail: &Trail,
+    ) {
+        self.watch(clause, clause_id, |lit| match lit.is_false(vars) {
+            true => trail.var_decision_level[lit.var()],
+            false => usize::MAX,
+        });
+    }
+
+    fn watch(&mut self, clause: &Clause, clause_id: ClauseId, priority: impl Fn(Literal) -> usize) {
+        if self.watched_literals.len() <= clause_id {
+            self.watched_literals
+                .resize(clause_id + 1, [Literal::new(0); 2]);
+        }
         // unit clauses don't need watches
         if clause.literals.len() < 2 {
             return;
         }
 
-        for lit in &clause.literals[0..2] {
-            self.add_watch(*lit, clause_id);
+        let first = (0..clause.literals.len())
+            .min_by_key(|i| Reverse(priority(clause.literals[*i])))
+            .unwrap();
+        let second = (0..clause.literals.len())
+            .filter(|i| *i != first)
+            .min_by_key(|i| Reverse(priority(clause.literals[*i])))
+            .unwrap();
+        let watched = [clause.literals[first], clause.literals[second]];
+        for lit in watched {
+            self.add_watch(lit, clause_id);
         }
+        self.watched_literals[clause_id] = watched;
     }
 
     pub fn delete_clause(&mut self, clause: &Clause, clause_id: ClauseId) {
-        for lit in &clause.literals[0..2] {
+        if clause.literals.len() < 2 {
+            return;
+        }
+
+        for lit in self.watched_literals[clause_id] {
             self.affected_clauses(lit.neg())
                 .retain(|&id| id != clause_id);
         }
     }
 
+    pub fn watched_literals(&self, clause_id: ClauseId) -> [Literal; 2] {
+        self.watched_literals[clause_id]
+    }
+
+    /// Clauses watching the literal
+    pub fn watches(&self, lit: Literal) -> &[ClauseId] {
+        &self.watches[lit.code()]
+    }
+
+    /// Clauses watching the negation of the literal, which have to be updated when it is assigned
     pub fn affected_clauses(&mut self, lit: Literal) -> &mut Vec<ClauseId> {
-        if lit.positive() {
-            &mut self.var_watches[lit.id()].neg
-        } else {
-            &mut self.var_watches[lit.id()].pos
-        }
+        &mut self.watches[lit.neg().code()]
     }
 
     pub fn add_watch(&mut self, lit: Literal, clause_id: ClauseId) {
-        if lit.positive() {
-            self.var_watches[lit.id()].pos.push(clause_id);
-        } else {
-            self.var_watches[lit.id()].neg.push(clause_id);
-        }
+        self.watches[lit.code()].push(clause_id);
     }
 
-    /// Sets watched literal to two non-false literals if possible
-    pub fn update_clause(
-        &mut self,
-        clause: &mut Clause,
+    /// Finds a non-false literal to replace the watched literal that just became false, without
+    /// changing the watches, so multiple threads can search at once
+    pub fn next_watch(
+        &self,
+        clause: &Clause,
+        clause_id: ClauseId,
         invalid_literal: Literal,
-        vars: &[Option<bool>],
+        vars: &PartialAssignment,
     ) -> WatchUpdate {
-        // ensure that the first watch is the newly invalid one
-        if clause.literals[0].id() != invalid_literal.id() {
-            clause.literals.swap(0, 1);
-        }
-
-        assert_eq!(clause.literals[0], invalid_literal);
+        let watched = self.watched_literals[clause_id];
+        let position = watched
+            .iter()
+            .position(|lit| *lit == invalid_literal)
+            .expect("the literal is watched");
+        let other = watched[1 - position];
 
         assert!(invalid_literal.is_false(vars));
 
-        // the other literal is also invalid
-        if clause.literals[1].is_false(vars) {
-            return WatchUpdate::Conflict;
-        }
-
-        for i in 0..clause.literals.len() {
-            if clause.literals[i].is_true(vars) {
-                return WatchUpdate::Satisfied(clause.literals[i]);
+        for lit in &clause.literals {
+            if lit.is_true(vars) {
+                return WatchUpdate::Satisfied(*lit);
             }
 
-            // the first two literals can't become new watches as the already are
-            if i > 1 && clause.literals[i].is_free(vars) {
-                // new watch found -- swap it into the watch position
-                clause.literals.swap(0, i);
-                return WatchUpdate::FoundNewWatch;
+            // the watched literals can't become new watches as the already are
+            if !watched.contains(lit) && lit.is_free(vars) {
+                return WatchUpdate::FoundNewWatch(*lit);
             }
         }
+
+        // the other watch may be false without being propagated yet, so the clause is only in
+        // conflict if no new watch exists
+        if other.is_false(vars) {
+            return WatchUpdate::Conflict;
+        }
+
         // verify that the clause is actually unit
         debug_assert_eq!(
             clause
@@ -114,28 +160,39 @@ impl LiteralWatcher {
             0
         );
 
-        WatchUpdate::Unit(clause.literals[1])
+        WatchUpdate::Unit(other)
     }
 
-    fn create_watches(clauses: &[Clause], num_vars: usize) -> Vec<VarWatch> {
-        let mut watches = vec![VarWatch::default(); num_vars + 1];
-        for (clause_id, clause) in clauses.iter().enumerate() {
-            if clause.literals.is_empty() {
-                continue; // skip empty clauses
-            }
-            if clause.literals.len() == 1 {
-                continue; // Don't watch unit clauses, they never change
+    /// Applies new clause ids after `ClauseDatabase::compact`, the watches stay the same
+    pub fn renumber(&mut self, mapping: &[Option<ClauseId>]) {
+        for watches in &mut self.watches {
+            for clause_id in watches.iter_mut() {
+                *clause_id = mapping[*clause_id].expect("deleted clauses aren't watched");
             }
-
-            for lit in &clause.literals[0..2] {
-                let var_id = lit.id();
-                if lit.positive() {
-                    watches[var_id].pos.push(clause_id);
-                } else {
-                    watches[var_id].neg.push(clause_id);
-                }
+        }
+        let mut watched_literals = vec![[Literal::new(0); 2]; mapping.iter().flatten().count()];
+        for (old, new) in mapping.iter().enumerate() {
+            if let (Some(new), Some(watched)) = (new, self.watched_literals.get(old)) {
+                watched_literals[*new] = *watched;
             }
         }
-        watches
+        self.watched_literals = watched_literals;
+    }
+
+    /// Watches `new_watch` instead of `invalid_literal`. The new watch is added, removing the old
+    /// one is left to the caller iterating over them.
+    pub fn replace_watch(
+        &mut self,
+        clause_id: ClauseId,
+        invalid_literal: Literal,
+        new_watch: Literal,
+    ) {
+        let watched = &mut self.watched_literals[clause_id];
+        let position = watched
+            .iter()
+            .position(|lit| *lit == invalid_literal)
+            .expect("the literal is watched");
+        watched[position] = new_watch;
+        self.add_watch(new_watch, clause_id);
     }
 }
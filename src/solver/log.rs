@@ -0,0 +1,56 @@
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+
+/// Amount of comment lines the solver prints to stdout, each level includes the lower ones
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum Verbosity {
+    /// Nothing, the default for library users
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "quiet"))]
+    Quiet,
+    /// The progress table and messages about files that are written
+    #[cfg_attr(feature = "std", clap(name = "normal"))]
+    Normal,
+    /// Clause database reductions, inprocessing and mode switches
+    #[cfg_attr(feature = "std", clap(name = "verbose"))]
+    Verbose,
+    /// Every restart
+    #[cfg_attr(feature = "std", clap(name = "debug"))]
+    Debug,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Logger {
+    verbosity: Verbosity,
+}
+
+impl Logger {
+    pub fn new(verbosity: Verbosity) -> Self {
+        Logger { verbosity }
+    }
+
+    /// Always false without `std`, as there is nowhere to print to
+    pub fn enabled(&self, level: Verbosity) -> bool {
+        cfg!(feature = "std") && level <= self.verbosity
+    }
+}
+
+/// Prints a comment line if the logger is enabled for the level, e.g.
+/// `log!(self.log, Verbose, "Eliminated {} variables", num_vars)`
+#[cfg(feature = "std")]
+macro_rules! log {
+    ($logger:expr, $level:ident, $($arg:tt)*) => {
+        if $logger.enabled($crate::solver::log::Verbosity::$level) {
+            println!("c {}", format_args!($($arg)*));
+        }
+    };
+}
+
+#[cfg(not(feature = "std"))]
+macro_rules! log {
+    ($logger:expr, $level:ident, $($arg:tt)*) => {
+        let _ = (&$logger, format_args!($($arg)*));
+    };
+}
+pub(crate) use log;
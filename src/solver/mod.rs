@@ -1,208 +1,2432 @@
+pub mod budget;
+pub mod builder;
+pub mod cardinality;
+pub mod checkpoint;
 mod clause_database;
-mod clause_learning;
+pub mod clause_learning;
+pub mod clock;
 pub mod config;
+pub mod conflict_graph;
+pub mod counter;
 mod ema_policy;
+pub mod enumeration;
+pub mod external;
 pub mod heuristic;
 mod inprocessor;
+pub mod interpolant;
+pub mod listener;
 mod literal_watching;
+pub mod log;
+mod original_clauses;
+#[cfg(feature = "parallel")]
+mod parallel_propagation;
+pub mod params;
+pub mod phases;
+pub mod presets;
 pub mod progress;
-mod proof_logger;
+pub mod proof_logger;
+pub mod reconstruction;
+pub mod resolution;
 pub mod restarts;
+pub mod sharing;
+pub mod simplified;
+pub mod stabilization;
 pub mod state;
 pub mod statistics;
+pub mod tracer;
 pub mod trail;
-mod unit_propagation;
+#[cfg(feature = "tui")]
+mod tui;
+pub mod walker;
+pub mod xor;
 
-use crate::cnf::{Clause, Literal, Solution, VarId};
+use crate::cnf::{Clause, ClauseId, Literal, Model, SolveResult, UnknownReason, Var};
+use crate::error::{self, Error};
+#[cfg(feature = "std")]
+use crate::proof::checker::core_from_proof;
+use crate::solver::budget::Budget;
+use crate::solver::cardinality::CardinalityConstraint;
+use crate::solver::checkpoint::Checkpoint;
 use crate::solver::clause_learning::ClauseLearner;
 use crate::solver::config::Config;
+#[cfg(feature = "std")]
+use crate::solver::conflict_graph::ConflictGraph;
+use crate::solver::external::{External, ExternalPropagator};
+use crate::solver::heuristic::adaptive::HeuristicSwitcher;
+use crate::solver::heuristic::priority::HeuristicPriority;
+use crate::solver::heuristic::stabilizing::HeuristicStabilizing;
+use crate::solver::heuristic::{normalize_scores, var_scores, Heuristic, HeuristicType};
 use crate::solver::inprocessor::Inprocessor;
-use crate::solver::progress::Progress;
+use crate::solver::interpolant::Interpolant;
+use crate::solver::listener::SearchListener;
+use crate::solver::log::{log, Logger, Verbosity};
+use crate::solver::original_clauses::OriginalClauses;
+use crate::solver::phases::PhaseSelector;
+use crate::solver::progress::{EventKind, Progress, ProgressPrintingInterval};
+use crate::solver::proof_logger::Antecedents;
+use crate::solver::resolution::ResolutionProof;
 use crate::solver::restarts::Restarter;
+use crate::solver::simplified::SimplifiedFormula;
+use crate::solver::stabilization::Stabilizer;
 use crate::solver::state::State;
 use crate::solver::statistics::StateStatistics;
-use crate::solver::trail::{AssignmentReason, Trail};
-use crate::solver::unit_propagation::UnitPropagator;
+#[cfg(feature = "std")]
+use crate::solver::tracer::VarTracer;
+use crate::solver::trail::{AssignmentReason, ConstraintRef, Trail};
+use crate::solver::walker::Walker;
+use crate::solver::xor::{find_xors, XorPropagation, XorPropagator};
+use alloc::sync::Arc;
+use alloc::{boxed::Box, format, vec, vec::Vec};
+use core::sync::atomic::AtomicBool;
+use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
 
 pub struct Solver {
     config: Config,
     state: State,
     clause_learner: ClauseLearner,
+    heuristic: HeuristicPriority,
+    /// Replaces the heuristic at restarts with `--heuristic adaptive`
+    switcher: Option<HeuristicSwitcher>,
+    restarter: Restarter,
+    phases: PhaseSelector,
+    walker: Walker,
+    stabilizer: Stabilizer,
+    log: Logger,
+    trail: Trail,
+    inprocessor: Inprocessor,
+    is_unsat: bool,
+    learn_callback: Option<LearnCallback>,
+    import_callback: Option<ImportCallback>,
+    /// Stops the search once set, see `Solver::terminate_flag`
+    terminate: Arc<AtomicBool>,
+    /// Error of the last attempt to write the proof file, see `Solver::proof_error`
+    proof_error: Option<Error>,
+    /// Error of creating the trace file or writing a conflict graph, see
+    /// `Solver::debug_output_error`
+    debug_output_error: Option<Error>,
+    /// Kept once the formula is found unsatisfiable, see `Solver::resolution_proof`
+    resolution_proof: Option<ResolutionProof>,
+    external: Option<External>,
+    /// Clauses of the formula including the ones added later, before any simplification
+    original_clauses: OriginalClauses,
+    /// Literals decided before all other decisions in the current call of `solve`
+    assumptions: Vec<Literal>,
+    /// Assumptions the last call of `solve` found inconsistent with the formula
+    failed_assumptions: Vec<Literal>,
 }
 
+/// Maximum length of the clauses passed to the callback
+type LearnCallback = (usize, Box<dyn FnMut(&[Literal])>);
+/// Returns the clauses to import at a restart
+type ImportCallback = Box<dyn FnMut() -> Vec<Vec<i32>>>;
+
 impl Solver {
     pub fn new(clauses: Vec<Clause>, n_vars: usize, config: Config) -> Self {
-        let clause_learner = ClauseLearner::default();
+        let mut clause_learner =
+            ClauseLearner::new(config.learning_scheme, config.additional_clause);
+        let resolution_chains = config.resolution_chains || config.lrat_file.is_some();
+        let proof_logging = config.proof_file.is_some() || config.core_extraction;
+        let proof_logging = proof_logging || resolution_chains;
+        // the database takes the clauses, the originals are only kept as flat literals
+        let original_clauses = OriginalClauses::new(&clauses);
+        let formula_indices = (0..clauses.len())
+            .filter(|&index| !clauses[index].is_tautology())
+            .collect_vec();
+        let mut state = State::init(clauses, n_vars, proof_logging);
+        if proof_logging && (config.proof_trimming || resolution_chains) {
+            let proof_logger = &mut state.clause_database.proof_logger;
+            proof_logger.enable_tracking(formula_indices);
+            if config.proof_trimming {
+                proof_logger.enable_trimming();
+            }
+            clause_learner.track_chains();
+        }
+        let mut inprocessor = Inprocessor::init(&state.clause_database, &config);
+        if config.gaussian_elimination {
+            let database = &state.clause_database;
+            let xors = find_xors(
+                database.necessary_clauses_iter().map(|id| &database[id]),
+                config.params.max_xor_size,
+            );
+            state.xor_propagator = XorPropagator::new(&xors);
+            inprocessor.exclude_vars(state.xor_propagator.vars());
+        }
+        state.propagation_threads = config.propagation_threads;
+        state.params = config.params.clone();
+        // the variables aren't traced if the trace file can't be created
+        #[cfg(feature = "std")]
+        let debug_output_error = match Self::create_tracer(&config, n_vars) {
+            Ok(tracer) => {
+                state.tracer = tracer;
+                None
+            }
+            Err(err) => Some(err),
+        };
+        #[cfg(not(feature = "std"))]
+        let debug_output_error = None;
+        let terminate = config.terminate.clone().unwrap_or_default();
 
-        Solver {
-            state: State::init(clauses, n_vars, config.proof_file.is_some()),
+        let mut solver = Solver {
+            heuristic: HeuristicPriority::new(
+                match config.stabilization {
+                    true => Box::new(HeuristicStabilizing::new(
+                        config.heuristic.create(&state),
+                        config.stable_heuristic.create(&state),
+                    )),
+                    false => config.heuristic.create(&state),
+                },
+                state.num_vars,
+            ),
+            switcher: (matches!(config.heuristic, HeuristicType::Adaptive)
+                && !config.stabilization)
+                .then(|| HeuristicSwitcher::new(config.params.heuristic_switch_interval)),
+            stabilizer: Stabilizer::init(&config),
+            log: Logger::new(config.verbosity),
+            restarter: Restarter::init(&config),
+            phases: PhaseSelector::init(config.phase_mode, &mut state),
+            walker: Walker::init(&config),
+            trail: Trail::new(state.num_vars),
+            inprocessor,
+            state,
             clause_learner,
             config,
+            is_unsat: false,
+            learn_callback: None,
+            import_callback: None,
+            terminate,
+            proof_error: None,
+            debug_output_error,
+            resolution_proof: None,
+            external: None,
+            original_clauses,
+            assumptions: vec![],
+            failed_assumptions: vec![],
+        };
+        if let Some(literal_scores) = solver.config.score_init.literal_scores(&solver.state) {
+            solver.heuristic.seed_scores(&var_scores(&literal_scores));
+            for (phase, [negative, positive]) in
+                solver.state.var_phases.iter_mut().zip(literal_scores)
+            {
+                *phase = positive >= negative;
+            }
+        }
+        solver.enqueue_initial_units();
+        solver
+    }
+
+    /// Tracer of `Config::trace_vars`, writing to `Config::trace_file` or stderr
+    #[cfg(feature = "std")]
+    fn create_tracer(config: &Config, num_vars: usize) -> error::Result<VarTracer> {
+        if config.trace_vars.is_empty() {
+            return Ok(VarTracer::default());
         }
+        Ok(match &config.trace_file {
+            Some(path) => {
+                let file = std::fs::File::create(path).map_err(|err| Error::Write {
+                    path: path.clone(),
+                    source: err,
+                })?;
+                VarTracer::new(&config.trace_vars, num_vars, std::io::BufWriter::new(file))
+            }
+            None => VarTracer::new(&config.trace_vars, num_vars, std::io::stderr()),
+        })
+    }
+
+    /// Solves the formula. Can be called repeatedly, e.g. after adding clauses at level 0.
+    pub fn solve(&mut self) -> SolveResult {
+        self.search(&[])
     }
 
-    pub fn solve(&mut self) -> Solution {
+    /// Solves the formula under assumptions, which only hold for this call: `Unsat` means that
+    /// no model satisfies all of them. The assumptions are decided before any other decision,
+    /// like in MiniSat. Assumed variables are excluded from inprocessing, but variables
+    /// eliminated by earlier calls can't be assumed and are rejected with
+    /// `Error::EliminatedVariable`, so they have to be frozen upfront, see `Solver::freeze`.
+    pub fn solve_with_assumptions(
+        &mut self,
+        assumptions: &[Literal],
+    ) -> error::Result<SolveResult> {
+        self.check_vars(&assumptions.iter().map(|lit| lit.var()).collect_vec())?;
+        Ok(self.search(assumptions))
+    }
+
+    fn search(&mut self, assumptions: &[Literal]) -> SolveResult {
+        self.assumptions = assumptions.to_vec();
+        self.failed_assumptions.clear();
+        self.inprocessor
+            .exclude_vars(&assumptions.iter().map(|lit| lit.var()).collect_vec());
         self.state.stats.start_timing();
-        let mut progress = Progress::init(&self.config.progress_printing_interval);
+        let progress_printing_interval = match self.log.enabled(Verbosity::Normal) {
+            true => &self.config.progress_printing_interval,
+            false => &ProgressPrintingInterval::Off,
+        };
+        let mut progress = Progress::init(
+            progress_printing_interval,
+            self.config.progress_format,
+            self.config.deterministic,
+        );
 
-        if self.is_trivially_unsat() {
-            return None;
+        if !self.is_unsat {
+            if let Some(refutation) = self.trivial_refutation() {
+                self.state
+                    .clause_database
+                    .proof_logger
+                    .set_antecedents(&Antecedents {
+                        clauses: refutation,
+                        ..Default::default()
+                    });
+                self.is_unsat = true;
+            }
+        }
+        if self.is_unsat {
+            self.finish_proof();
+            return SolveResult::Unsat;
         }
 
-        let mut heuristic = self.config.heuristic.create(&self.state);
-        let mut restarter = Restarter::init(self.config.restart_policy);
-        let mut unit_propagator = UnitPropagator::default();
-        let mut trail = Trail::new(self.state.num_vars);
-        let mut inprocessor = Inprocessor::init(
-            &self
-                .state
-                .clause_database
-                .necessary_clauses_iter()
-                .map(|clause_id| self.state.clause_database[clause_id].clone())
-                .collect_vec(),
+        // continue from the top level if a previous call found a solution
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        let mut budget = Budget::start(
+            &self.config,
+            self.state.stats.num_conflicts,
+            self.terminate.clone(),
         );
-        self.enqueue_initial_units(&mut unit_propagator);
 
         loop {
-            unit_propagator.propagate(&mut self.state, &mut trail);
+            self.trail.propagate(&mut self.state);
+            if self.propagate_xors() || self.propagate_external() {
+                continue;
+            }
+            if self.state.conflict.is_none() && self.trail.decision_level == 0 {
+                self.state.clause_database.log_units(&self.trail);
+            }
 
-            if let Some(conflict_clause_id) = self.state.conflict_clause_id {
-                if trail.decision_level == 0 {
+            if let Some(conflict) = self.state.conflict {
+                if self.trail.decision_level == 0 {
+                    self.log_final_conflict(conflict);
                     break;
                 }
-                self.state.clause_database.delete_clauses_if_necessary(
-                    conflict_clause_id,
+                #[cfg(feature = "std")]
+                let conflict_graph = (self.config.conflict_graph_dir.is_some()
+                    && self.state.stats.num_conflicts <= self.config.num_conflict_graphs)
+                    .then(|| ConflictGraph::new(&self.trail, &self.state));
+                self.phases.conflict(&self.trail);
+                if self.config.stabilization && self.stabilizer.conflict() {
+                    let stable = self.stabilizer.is_stable();
+                    self.restarter.set_stable(stable);
+                    self.heuristic.stabilize(stable);
+                    self.phases.set_stable(stable);
+                    let mode = if stable { "stable" } else { "focused" };
+                    log!(self.log, Verbose, "Switching to {} mode", mode);
+                }
+                let reduced = self.state.clause_database.delete_clauses_if_necessary(
+                    conflict,
                     &mut self.state.literal_watcher,
-                    &trail,
+                    &self.config.params,
                 );
+                if reduced {
+                    let num_learned_clauses = self.state.clause_database.num_learned_clauses();
+                    self.notify(|listener, stats| listener.on_reduce(stats, num_learned_clauses));
+                    progress.event(EventKind::Reduction, || {
+                        format!(
+                            "reduced the database to {} learned clauses",
+                            num_learned_clauses
+                        )
+                    });
+                    log!(
+                        self.log,
+                        Verbose,
+                        "Reduced the database to {} learned clauses",
+                        self.state.clause_database.num_learned_clauses()
+                    );
+                }
 
                 // find conflict clause
                 let (new_clause, assertion_level) = self.clause_learner.analyse_conflict(
-                    &mut trail,
-                    &mut self.state.clause_database,
-                    conflict_clause_id,
+                    &mut self.trail,
+                    &mut self.state,
+                    conflict,
                 );
+                #[cfg(feature = "std")]
+                if let Some(mut graph) = conflict_graph {
+                    graph.learned_clause = Some(new_clause.literals.clone());
+                    self.write_conflict_graph(&graph);
+                }
+                self.state.stats.num_strengthened_clauses +=
+                    self.clause_learner.strengthen_antecedents(
+                        &mut self.state.clause_database,
+                        &mut self.state.literal_watcher,
+                        &self.state.vars,
+                        &self.trail,
+                    );
 
-                restarter.conflict(new_clause.lbd.unwrap(), trail.assignment_stack.len());
+                self.restarter
+                    .conflict(new_clause.lbd.unwrap(), self.trail.assignment_stack.len());
+                self.state.stats.learned_clause(&new_clause);
+                self.notify(|listener, stats| {
+                    listener.on_conflict(stats, &new_clause.literals, new_clause.lbd.unwrap())
+                });
+                if let Some((max_len, callback)) = self.learn_callback.as_mut() {
+                    if new_clause.literals.len() <= *max_len {
+                        callback(&new_clause.literals);
+                    }
+                }
 
                 // The first literal is always UIP
                 let uip = new_clause.literals[0];
-                let new_clause_id = self
-                    .state
-                    .clause_database
-                    .add_clause(new_clause, &mut self.state.literal_watcher);
+                let proof_logger = &mut self.state.clause_database.proof_logger;
+                proof_logger.set_chain(self.clause_learner.learned_chain());
+                let new_clause_id = self.state.clause_database.add_clause(
+                    new_clause,
+                    &mut self.state.literal_watcher,
+                    &self.state.vars,
+                    &self.trail,
+                );
+                let additional_clause_id =
+                    self.clause_learner
+                        .take_additional_clause()
+                        .map(|(clause, chain)| {
+                            self.state.stats.num_additional_clauses += 1;
+                            self.state.clause_database.proof_logger.set_chain(chain);
+                            self.state.clause_database.add_clause(
+                                clause,
+                                &mut self.state.literal_watcher,
+                                &self.state.vars,
+                                &self.trail,
+                            )
+                        });
 
-                unit_propagator.enqueue(uip, new_clause_id);
+                match conflict {
+                    ConstraintRef::Clause(clause_id) => self
+                        .heuristic
+                        .conflict(&self.state.clause_database[clause_id]),
+                    _ => {
+                        let explanation = self.state.reason_literals(conflict, None, &self.trail);
+                        self.heuristic
+                            .conflict(&Clause::from(explanation.into_owned()));
+                    }
+                }
+                let backtrack_level = self.config.backtracking_mode.backtrack_level(
+                    self.trail.decision_level,
+                    assertion_level,
+                    self.config.params.chronological_backtracking_threshold,
+                );
+                match self.config.trail_saving {
+                    true => self.trail.backjump_saving(
+                        &mut self.state,
+                        &mut self.heuristic,
+                        backtrack_level,
+                    ),
+                    false => {
+                        self.trail
+                            .backtrack(&mut self.state, &mut self.heuristic, backtrack_level)
+                    }
+                }
+                self.trail.assign(
+                    &mut self.state,
+                    uip,
+                    AssignmentReason::Forced(ConstraintRef::Clause(new_clause_id)),
+                );
+                if let Some(clause_id) = additional_clause_id {
+                    self.propagate_if_unit(clause_id);
+                }
 
-                heuristic.conflict(&self.state.clause_database[conflict_clause_id]);
-                trail.backtrack(&mut self.state, heuristic.as_mut(), assertion_level);
-            } else if self.state.check_satisfied_and_update_blocking_literals() {
+                if let Some(reason) = budget.exceeded(self.state.stats.num_conflicts) {
+                    return self.stop_search(&progress, reason);
+                }
+            } else if self.state.check_satisfied_and_update_blocking_literals()
+                && self.observed_vars_assigned()
+                && self.assumptions_hold()
+            {
                 self.state.stats.stop_timing();
+                self.state.update_clause_statistics();
                 progress.close_table();
-                return Some(self.get_solution(&mut inprocessor));
-            } else if restarter.check_if_restart_necessary() {
+                return SolveResult::Sat(self.get_model());
+            } else if let Some(reason) = budget.exceeded_without_conflict() {
+                // decisions and restarts may go on for long without conflicts
+                return self.stop_search(&progress, reason);
+            } else if self.restarter.check_if_restart_necessary() {
                 self.state.stats.num_restarts += 1;
-                trail.restart(&mut self.state, heuristic.as_mut());
-                if self.config.inprocessing {
-                    inprocessor.inprocess(
-                        &mut unit_propagator,
-                        heuristic.as_mut(),
+                self.phases.restart(&self.trail);
+                let inprocess =
+                    self.config.inprocessing && self.inprocessor.is_due(&self.state, &self.trail);
+                let imports = self.import_callback.as_mut().map(|callback| callback());
+                let imports = imports.filter(|clauses| !clauses.is_empty());
+                let walk = self.walker.is_due(self.state.stats.num_conflicts);
+                let switch = self
+                    .switcher
+                    .as_mut()
+                    .and_then(|switcher| switcher.restart(&self.state.stats));
+                // decisions of the external propagator can't be predicted
+                let mut reused_levels = 0;
+                if self.config.trail_reuse
+                    && !inprocess
+                    && !walk
+                    && imports.is_none()
+                    && switch.is_none()
+                    && self.external.is_none()
+                {
+                    reused_levels = self.trail.restart_reusing_trail(
                         &mut self.state,
-                        &mut trail,
+                        &mut self.heuristic,
+                        self.config.reuse_strategy,
+                    );
+                    self.state.stats.num_reused_levels += reused_levels;
+                } else {
+                    self.trail.restart(&mut self.state, &mut self.heuristic);
+                }
+                if let Some(heuristic_type) = switch {
+                    self.switch_heuristic(heuristic_type);
+                }
+                self.notify(|listener, stats| listener.on_restart(stats, reused_levels));
+                let num_restarts = self.state.stats.num_restarts;
+                let num_conflicts = self.state.stats.num_conflicts;
+                progress.event(EventKind::Restart, || {
+                    format!(
+                        "restart {} after {} conflicts, reusing {} decision levels",
+                        num_restarts, num_conflicts, reused_levels
+                    )
+                });
+                log!(
+                    self.log,
+                    Debug,
+                    "Restart {} after {} conflicts, reusing {} decision levels",
+                    self.state.stats.num_restarts,
+                    self.state.stats.num_conflicts,
+                    reused_levels
+                );
+                if let Some(clauses) = imports {
+                    // clauses over eliminated variables or out of range are skipped
+                    let clauses = clauses
+                        .iter()
+                        .filter_map(|clause| self.parse_clause(clause).ok())
+                        .collect_vec();
+                    self.import_literals(clauses);
+                    if self.is_unsat {
+                        break;
+                    }
+                }
+                if inprocess {
+                    let stats = self.state.stats.clone();
+                    self.inprocessor.inprocess(
+                        &mut self.heuristic,
+                        &mut self.state,
+                        &mut self.trail,
+                    );
+                    log!(
+                        self.log,
+                        Verbose,
+                        "Inprocessing eliminated {} variables ({} by gates), substituted {} and \
+                         removed {} of autarkies, subsumed {} clauses, vivified {}, removed {} \
+                         blocked ones and found {} failed literals",
+                        self.state.stats.num_eliminated_vars - stats.num_eliminated_vars,
+                        self.state.stats.num_gate_eliminations - stats.num_gate_eliminations,
+                        self.state.stats.num_substituted_vars - stats.num_substituted_vars,
+                        self.state.stats.num_autarky_vars - stats.num_autarky_vars,
+                        self.state.stats.num_subsumed_clauses - stats.num_subsumed_clauses,
+                        self.state.stats.num_vivified_clauses - stats.num_vivified_clauses,
+                        self.state.stats.num_blocked_clauses - stats.num_blocked_clauses,
+                        self.state.stats.num_failed_literals - stats.num_failed_literals
+                    );
+                    if self.inprocessor.is_complete(&self.state, &self.trail) {
+                        log!(self.log, Verbose, "Inprocessing completed");
+                    }
+                    self.notify(|listener, after| listener.on_inprocessing(&stats, after));
+                }
+                if walk {
+                    let walk = self.walker.walk(&mut self.state);
+                    self.phases.reset_targets();
+                    log!(
+                        self.log,
+                        Verbose,
+                        "Local search flipped {} variables, {} clauses remain falsified",
+                        walk.num_flips,
+                        walk.num_falsified
+                    );
+                }
+                let fragmentation = self.state.clause_database.fragmentation();
+                if fragmentation.ratio() > self.config.params.compaction_threshold {
+                    self.compact();
+                    log!(
+                        self.log,
+                        Verbose,
+                        "Compacted the clause database, freeing {} ids and {} literals",
+                        fragmentation.num_free_ids,
+                        fragmentation.wasted_literal_capacity
                     );
                 }
             } else {
-                let next_var = heuristic.next(&self.state.vars);
-                let next_literal = Literal::from_value(next_var, self.state.var_phases[next_var]);
+                let assumption = match self.next_assumption() {
+                    Ok(assumption) => assumption,
+                    Err(failed) => {
+                        self.state.stats.stop_timing();
+                        self.state.update_clause_statistics();
+                        progress.close_table();
+                        log!(self.log, Verbose, "Assumption {} failed", failed);
+                        self.failed_assumptions = self.analyze_failed_assumption(failed);
+                        return SolveResult::Unsat;
+                    }
+                };
+                let (next_literal, reason) = match assumption {
+                    Some(assumption) => (assumption, AssignmentReason::Assumption),
+                    None => {
+                        let decision = self.external_decision().unwrap_or_else(|| {
+                            let decision = self.heuristic.next(&self.state.vars);
+                            self.phases.decide(decision, &self.state)
+                        });
+                        (decision, AssignmentReason::Heuristic)
+                    }
+                };
 
-                trail.assign(
-                    &mut self.state,
-                    &mut unit_propagator,
-                    next_literal,
-                    AssignmentReason::Heuristic,
-                );
+                self.state.stats.num_decisions += 1;
+                self.trail.assign(&mut self.state, next_literal, reason);
             }
 
+            self.state.update_clause_statistics();
             progress.print_progress_if_necessary(
                 &self.state.stats,
-                trail.assignment_stack.len(),
+                self.trail.assignment_stack.len(),
                 self.state.clause_database.num_clauses(),
-                inprocessor.resolved_vars,
-                inprocessor.total_inprocessing_time.as_millis(),
             );
         }
+        self.is_unsat = true;
         self.state.stats.stop_timing();
+        self.state.update_clause_statistics();
         progress.close_table();
+        self.finish_proof();
+
+        SolveResult::Unsat
+    }
+
+    /// Ends a search that ran out of budget or was interrupted, the proof so far is kept
+    fn stop_search(&mut self, progress: &Progress, reason: UnknownReason) -> SolveResult {
+        self.state.stats.stop_timing();
+        self.state.update_clause_statistics();
+        progress.close_table();
+        log!(self.log, Normal, "Stopping the search: {:?}", reason);
+        self.write_partial_proof();
+        SolveResult::Unknown(reason)
+    }
+
+    /// Logs the units fixed at level 0 and the chain of the empty clause, which follows from
+    /// the conflict at level 0, if the chains are tracked
+    fn log_final_conflict(&mut self, conflict: ConstraintRef) {
+        if !self.state.clause_database.proof_logger.is_tracking() {
+            return;
+        }
+        self.state.clause_database.log_units(&self.trail);
+        let literals = self.state.reason_literals(conflict, None, &self.trail);
+        let mut antecedents = Antecedents::default();
+        antecedents.add(conflict, &literals, &self.trail);
+        let proof_logger = &mut self.state.clause_database.proof_logger;
+        proof_logger.set_antecedents(&antecedents);
+    }
+
+    /// Concludes the proof with the empty clause, keeps its resolution proof if the chains are
+    /// tracked, trims it if configured and writes it to the proof files
+    fn finish_proof(&mut self) {
+        let proof_logger = &mut self.state.clause_database.proof_logger;
+        proof_logger.log_empty_clause();
+        if proof_logger.is_tracking() {
+            self.resolution_proof = proof_logger.resolution_proof();
+        }
+        let num_trimmed = proof_logger.trim();
+        if num_trimmed > 0 {
+            log!(
+                self.log,
+                Normal,
+                "Trimmed {} of {} proof steps",
+                num_trimmed,
+                num_trimmed + proof_logger.proof.len()
+            );
+        }
+        #[cfg(feature = "std")]
         if let Some(proof_file) = self.config.proof_file.as_ref() {
-            println!("c Writing proof to file");
-            self.state
-                .clause_database
-                .proof_logger
-                .write_to_file(proof_file);
+            if proof_file != "-" {
+                log!(self.log, Normal, "Writing proof to file");
+            }
+            self.proof_error = proof_logger.write_to_file(proof_file).err();
+        }
+        #[cfg(feature = "std")]
+        if let Some(lrat_file) = self.config.lrat_file.as_ref() {
+            log!(self.log, Normal, "Writing LRAT proof to file");
+            let written = match &self.resolution_proof {
+                Some(proof) => proof.write_lrat(lrat_file, &self.original_clauses.to_clauses()),
+                None => Err(Error::Unsupported(
+                    "The proof has lemmas without resolution chains, e.g. implied by XOR \
+                     constraints, so it can't be written in LRAT format"
+                        .to_string(),
+                )),
+            };
+            self.proof_error = self.proof_error.take().or(written.err());
         }
+    }
 
-        None
+    /// Writes the clauses learned so far to the proof file when the search stops without a
+    /// result, so they can be checked or reused. Proofs written to stdout are left out, as
+    /// they'd be mixed with the result.
+    fn write_partial_proof(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(proof_file) = self.config.proof_file.as_ref() {
+            if proof_file != "-" {
+                log!(self.log, Normal, "Writing partial proof to file");
+                self.proof_error = self
+                    .state
+                    .clause_database
+                    .proof_logger
+                    .write_to_file(proof_file)
+                    .err();
+            }
+        }
+    }
+
+    /// Replaces the heuristic after a full restart, the new one starts with the order of the
+    /// old one
+    fn switch_heuristic(&mut self, heuristic_type: HeuristicType) {
+        let scores = self.heuristic.scores();
+        let mut heuristic = heuristic_type.create(&self.state);
+        if let Some(scores) = scores {
+            heuristic.seed_scores(&normalize_scores(scores));
+        }
+        self.heuristic.replace_inner(heuristic);
+        log!(
+            self.log,
+            Verbose,
+            "Switching to the {:?} heuristic after {} conflicts",
+            heuristic_type,
+            self.state.stats.num_conflicts
+        );
+    }
+
+    fn notify(&self, callback: impl Fn(&mut dyn SearchListener, &StateStatistics)) {
+        for listener in &self.config.listeners {
+            callback(&mut *listener.borrow_mut(), &self.state.stats);
+        }
+    }
+
+    /// Writes the graph of the current conflict to `conflict_<n>.dot` and `conflict_<n>.json`.
+    /// After the first error, no more graphs are written.
+    #[cfg(feature = "std")]
+    fn write_conflict_graph(&mut self, graph: &ConflictGraph) {
+        let Some(dir) = self.config.conflict_graph_dir.as_ref() else {
+            return;
+        };
+        let dir = std::path::Path::new(dir);
+        let name = format!("conflict_{}", self.state.stats.num_conflicts);
+        let written = std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(dir.join(format!("{}.dot", name)), graph.to_dot()))
+            .and_then(|_| std::fs::write(dir.join(format!("{}.json", name)), graph.to_json()));
+        if let Err(err) = written {
+            self.debug_output_error = Some(Error::Write {
+                path: dir.display().to_string(),
+                source: err,
+            });
+            self.config.conflict_graph_dir = None;
+        }
+    }
+
+    /// Adds a clause between calls to `solve`, keeping the learned clauses, the fixed literals
+    /// and the heuristic scores, e.g. to refine an abstraction in a CEGAR loop. The clause may
+    /// only use the variables the solver was created with. Variables eliminated by inprocessing
+    /// can't be used, so they have to be frozen upfront, see `Solver::freeze`.
+    pub fn add_clause_incremental(&mut self, literals: &[i32]) -> error::Result<()> {
+        let literals = self.parse_clause(literals)?;
+        let is_tautology = literals.iter().any(|lit| literals.contains(&-*lit));
+        if is_tautology {
+            self.original_clauses.push(&literals);
+        } else {
+            self.add_clause_at_level_zero(literals);
+        }
+        Ok(())
+    }
+
+    /// Literals of a clause in the DIMACS convention, which must only contain variables of the
+    /// current formula
+    fn parse_clause(&self, literals: &[i32]) -> error::Result<Vec<Literal>> {
+        let literals = literals
+            .iter()
+            .map(|&lit| match lit {
+                0 | i32::MIN => Err(Error::InvalidLiteral(lit)),
+                _ => Ok(Literal::new(lit)),
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        self.check_vars(&literals.iter().map(|lit| lit.var()).collect_vec())?;
+        Ok(literals)
+    }
+
+    /// Checks that the variables belong to the current formula, i.e. that they are in range and
+    /// weren't eliminated by inprocessing, so they can be used in new clauses and assumptions
+    fn check_vars(&self, vars: &[Var]) -> error::Result<()> {
+        if let Some(&var_id) = vars
+            .iter()
+            .find(|var_id| var_id.index() > self.state.num_vars)
+        {
+            return Err(Error::VariableOutOfRange {
+                var_id,
+                num_vars: self.state.num_vars,
+            });
+        }
+        let vars: HashSet<Var> = vars.iter().copied().collect();
+        let mut witnesses = self.state.reconstruction_stack.entries().iter();
+        if let Some((witness, _)) = witnesses.find(|(witness, _)| vars.contains(&witness.var())) {
+            return Err(Error::EliminatedVariable(witness.var()));
+        }
+        Ok(())
+    }
+
+    /// Keeps inprocessing from eliminating or substituting the variable, like in MiniSat, so it
+    /// can be used in clauses added and assumptions made by later calls. Freezing is counted:
+    /// the variable may be eliminated again once `melt` was called as often as `freeze`.
+    /// Variables that are already eliminated stay eliminated.
+    pub fn freeze(&mut self, var_id: Var) {
+        self.inprocessor.freeze(var_id);
+    }
+
+    /// Undoes one call to `freeze`, returns false if the variable isn't frozen
+    pub fn melt(&mut self, var_id: Var) -> bool {
+        self.inprocessor.melt(var_id)
+    }
+
+    pub fn is_frozen(&self, var_id: Var) -> bool {
+        self.inprocessor.is_frozen(var_id)
+    }
+
+    /// Decides the variable before all variables with a lower priority, e.g. the actions of a
+    /// planner before the fluents. Variables have priority 0 by default and the configured
+    /// heuristic orders variables of equal priority, except for positive ones, which are
+    /// decided by decreasing priority in no particular order among equal ones. Priorities are
+    /// kept when `HeuristicType::Adaptive` switches the heuristic.
+    pub fn set_decision_priority(&mut self, var_id: Var, weight: f64) {
+        self.heuristic.set_priority(var_id, weight);
     }
 
-    fn is_trivially_unsat(&self) -> bool {
-        // contains empty clause
-        if self
+    /// Flag stopping the search with `UnknownReason::Interrupted` at the next conflict, decision
+    /// or restart once it is set, e.g. from a signal handler or another thread. It stays set for
+    /// later calls to `solve` until it is cleared.
+    pub fn terminate_flag(&self) -> Arc<AtomicBool> {
+        self.terminate.clone()
+    }
+
+    /// Error of the last attempt to write the proof file, which leaves a previous file with the
+    /// same name untouched. The result of `solve` is valid regardless.
+    pub fn proof_error(&self) -> Option<&Error> {
+        self.proof_error.as_ref()
+    }
+
+    /// Error of creating the file of `Config::trace_file`, in which case the variables aren't
+    /// traced, or of writing a graph to `Config::conflict_graph_dir`, after which no more graphs
+    /// are written. The search isn't affected.
+    pub fn debug_output_error(&self) -> Option<&Error> {
+        self.debug_output_error.as_ref()
+    }
+
+    /// Whether the assumption is one of the ones the last call of `solve_with_assumptions` found
+    /// inconsistent with the formula, like `ipasir_failed`. The failed assumptions alone
+    /// already make the formula unsatisfiable. None fail if it is unsatisfiable without them.
+    pub fn failed(&self, assumption: Literal) -> bool {
+        self.failed_assumptions.contains(&assumption)
+    }
+
+    /// Resolution refutation after `solve` returned `Unsat`, recorded with
+    /// `Config::resolution_chains`. `None` if a lemma the empty clause depends on wasn't derived
+    /// by resolution, e.g. with Gaussian elimination or imported clauses.
+    pub fn resolution_proof(&self) -> Option<&ResolutionProof> {
+        self.resolution_proof.as_ref()
+    }
+
+    /// Craig interpolant of the clauses of the formula with the given indices, in the order the
+    /// clauses were added, and the remaining clauses, computed from `Solver::resolution_proof`,
+    /// e.g. to over-approximate the reachable states in model checking. `None` if there is no
+    /// resolution proof. Indices of no clause are ignored.
+    pub fn interpolant(&self, a_clauses: &[usize]) -> Option<Interpolant> {
+        let proof = self.resolution_proof.as_ref()?;
+        Interpolant::from_proof(
+            proof,
+            &self.original_clauses.to_clauses(),
+            self.state.num_vars,
+            a_clauses,
+        )
+    }
+
+    /// Replaces the time limit of the configuration for the following calls to `solve`, e.g. to
+    /// share one limit among several calls
+    pub fn set_time_limit(&mut self, time_limit: Option<core::time::Duration>) {
+        self.config.time_limit = time_limit;
+    }
+
+    /// Always decides the variable with this value first instead of following the phase mode
+    pub fn set_preferred_phase(&mut self, var_id: Var, phase: bool) {
+        self.phases.set_preferred_phase(var_id, Some(phase));
+    }
+
+    /// Adds a clause while the solver is at decision level 0. Literals that are already false at
+    /// level 0 are dropped, satisfied clauses are ignored and unit clauses are propagated during
+    /// the next call to `solve`.
+    pub(crate) fn add_clause_at_level_zero(&mut self, literals: Vec<Literal>) {
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        self.original_clauses.push(&literals);
+
+        if literals.iter().any(|lit| lit.is_true(&self.state.vars)) {
+            return;
+        }
+        let (mut literals, fixed): (Vec<_>, Vec<_>) = literals
+            .into_iter()
+            .partition(|lit| !lit.is_false(&self.state.vars));
+        literals.sort_unstable();
+        literals.dedup();
+        let fixed_vars = fixed.iter().map(|lit| lit.var()).collect_vec();
+        let proof_logger = &mut self.state.clause_database.proof_logger;
+        let index = self.original_clauses.len() - 1;
+        proof_logger.set_chain(proof_logger.original_chain(index, &fixed_vars));
+
+        if literals.is_empty() {
+            // the chain is used by the empty clause logged by the next call of `solve`
+            self.is_unsat = true;
+            return;
+        }
+
+        let clause_id = self.state.clause_database.add_clause(
+            Clause::from(literals),
+            &mut self.state.literal_watcher,
+            &self.state.vars,
+            &self.trail,
+        );
+        if let [unit] = self.state.clause_database[clause_id].literals[..] {
+            self.trail
+                .enqueue(&mut self.state, unit, ConstraintRef::Clause(clause_id));
+        }
+    }
+
+    /// Checks the model against the clauses of the formula, including the ones added later,
+    /// before any simplification. Returns the indices of the violated clauses in the order the
+    /// clauses were added.
+    pub fn validate_model(&self, model: &Model) -> Result<(), Vec<ClauseId>> {
+        let violated = self
+            .original_clauses
+            .iter()
+            .positions(|literals| !literals.iter().any(|lit| model.value(*lit)))
+            .collect_vec();
+        match violated.is_empty() {
+            true => Ok(()),
+            false => Err(violated),
+        }
+    }
+
+    /// Shrinks the model found by the last call of `solve` to a prime implicant of the clauses of
+    /// the formula: literals satisfying every clause, none of which can be dropped. Unlike the
+    /// model, which sets variables the search didn't need to `true`, the values of all other
+    /// variables don't matter. Literals are dropped greedily, starting with the variables the
+    /// search left unassigned. Cardinality constraints and external propagators are not taken
+    /// into account. Returns `None` if the current assignment is no model.
+    pub fn minimize_model(&self) -> Option<Vec<Literal>> {
+        let model = self.get_model();
+        if self.validate_model(&model).is_err() {
+            return None;
+        }
+
+        let mut occurrences = vec![vec![]; 2 * (model.num_vars() + 1)];
+        let mut num_true = self
+            .original_clauses
+            .iter()
+            .enumerate()
+            .map(|(index, literals)| {
+                let mut true_literals = literals
+                    .iter()
+                    .filter(|lit| model.value(**lit))
+                    .copied()
+                    .collect_vec();
+                true_literals.sort_unstable();
+                true_literals.dedup();
+                for lit in &true_literals {
+                    occurrences[lit.code()].push(index);
+                }
+                true_literals.len()
+            })
+            .collect_vec();
+
+        let (unassigned, assigned): (Vec<_>, Vec<_>) =
+            model.iter().partition(|lit| lit.is_free(&self.state.vars));
+        let mut implicant = vec![];
+        for lit in unassigned.into_iter().chain(assigned) {
+            let clauses = &occurrences[lit.code()];
+            if clauses.iter().all(|&index| num_true[index] > 1) {
+                clauses.iter().for_each(|&index| num_true[index] -= 1);
+            } else {
+                implicant.push(lit);
+            }
+        }
+        implicant.sort_unstable_by_key(|lit| lit.var());
+        Some(implicant)
+    }
+
+    /// Unsatisfiable core after `solve` returned `Unsat`, i.e. the clauses of the formula used
+    /// by the logged proof. Requires a proof file or `Config::core_extraction`. Taken from the
+    /// resolution proof if there is one, otherwise the proof is checked. Returns `None` if the
+    /// proof is not logged or can't be verified.
+    #[cfg(feature = "std")]
+    pub fn core_clauses(&self) -> Option<Vec<Clause>> {
+        if !self.is_unsat || !self.state.clause_database.proof_logger.active {
+            return None;
+        }
+        if let Some(proof) = &self.resolution_proof {
+            let core = proof.core().into_iter();
+            return Some(
+                core.map(|index| Clause::from(self.original_clauses.get(index).to_vec()))
+                    .collect(),
+            );
+        }
+        let proof = self
             .state
             .clause_database
-            .necessary_clauses_iter()
-            .map(|clause_id| &self.state.clause_database[clause_id])
-            .any(|clause| clause.literals.is_empty())
+            .proof_logger
+            .proof
+            .iter()
+            .cloned()
+            .enumerate()
+            .collect_vec();
+        let original_clauses = self.original_clauses.to_clauses();
+        let core = core_from_proof(&original_clauses, self.state.num_vars, &proof)?;
+        Some(
+            core.into_iter()
+                .map(|id| original_clauses[id].clone())
+                .collect(),
+        )
+    }
+
+    /// Calls `callback` with every learned clause of at most `max_len` literals, like
+    /// `ipasir_set_learn`, e.g. to share clauses with other solvers. Replaces any previous
+    /// callback.
+    pub fn set_learn_callback(
+        &mut self,
+        max_len: usize,
+        callback: impl FnMut(&[Literal]) + 'static,
+    ) {
+        self.learn_callback = Some((max_len, Box::new(callback)));
+    }
+
+    /// Adds the native constraint that at most `bound` of the literals are true, which have to
+    /// belong to distinct variables. The variables are excluded from inprocessing, so the
+    /// constraint has to be added before the first call to `solve` if inprocessing is enabled.
+    /// Conflicts involving the constraint are explained by clauses that are not part of the
+    /// formula, so proofs are only valid for the clauses.
+    pub fn add_cardinality_constraint(&mut self, literals: &[Literal], bound: usize) {
+        debug_assert_eq!(
+            literals
+                .iter()
+                .map(|lit| lit.var())
+                .sorted()
+                .dedup()
+                .count(),
+            literals.len()
+        );
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        self.inprocessor
+            .exclude_vars(&literals.iter().map(|lit| lit.var()).collect_vec());
+
+        let num_true = literals
+            .iter()
+            .filter(|lit| lit.is_true(&self.state.vars))
+            .count();
+        if num_true > bound {
+            self.is_unsat = true;
+            return;
+        }
+        let bound = bound - num_true;
+        let literals = literals
+            .iter()
+            .filter(|lit| lit.is_free(&self.state.vars))
+            .copied()
+            .collect_vec();
+
+        if bound >= literals.len() {
+            return;
+        }
+        let propagator = &mut self.state.cardinality_propagator;
+        if bound == 0 {
+            let id = propagator.add_unit_constraint(literals.clone());
+            for lit in literals {
+                self.trail
+                    .enqueue(&mut self.state, -lit, ConstraintRef::Cardinality(id));
+            }
+        } else {
+            propagator.add_constraint(CardinalityConstraint { literals, bound });
+        }
+    }
+
+    /// Runs the Gaussian elimination of the XOR constraints at the propagation fixpoint.
+    /// Returns whether new units have to be propagated.
+    fn propagate_xors(&mut self) -> bool {
+        if self.state.conflict.is_some() || self.state.xor_propagator.is_empty() {
+            return false;
+        }
+
+        let mut units = vec![];
+        match self
+            .state
+            .xor_propagator
+            .propagate(&self.state.vars, &mut units)
         {
-            return true;
+            XorPropagation::Nothing => false,
+            XorPropagation::Units => {
+                for (unit, reason) in units {
+                    self.trail.enqueue(&mut self.state, unit, reason);
+                }
+                true
+            }
+            XorPropagation::Conflict => {
+                self.set_lazy_conflict(ConstraintRef::Xor);
+                false
+            }
         }
+    }
 
-        let cnf = self.state.clause_database.necessary_clauses_iter();
-        // contains a unit clause and its negation
-        let units = cnf
-            .map(|clause_id| &self.state.clause_database[clause_id])
-            .filter(|clause| clause.literals.len() == 1)
-            .map(|clause| clause.literals[0])
-            .collect_vec();
+    /// Connects an external propagator, replacing any previous one. The observed variables are
+    /// excluded from inprocessing, so they have to be given before the first call to `solve` if
+    /// inprocessing is enabled. They are assigned in every model found.
+    pub fn connect_external_propagator(
+        &mut self,
+        propagator: impl ExternalPropagator + 'static,
+        observed_vars: &[Var],
+    ) {
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        self.inprocessor.exclude_vars(observed_vars);
+        self.trail.num_notified = 0;
+        self.trail.notified_backtrack = false;
+        self.external = Some(External {
+            propagator: Box::new(propagator),
+            observed_vars: observed_vars.to_vec(),
+        });
+    }
+
+    pub fn disconnect_external_propagator(&mut self) -> Option<Box<dyn ExternalPropagator>> {
+        self.external.take().map(|external| external.propagator)
+    }
+
+    /// Notifies the external propagator about backtracking and all new assignments on the trail
+    fn notify_external(&mut self) {
+        let Some(external) = self.external.as_mut() else {
+            return;
+        };
+        let trail = &mut self.trail;
+        if trail.notified_backtrack {
+            trail.notified_backtrack = false;
+            let decision_level = trail
+                .num_notified
+                .checked_sub(1)
+                .map_or(0, |pos| trail.assignment_stack[pos].decision_level);
+            external.propagator.notify_backtrack(decision_level);
+        }
+
+        let len = trail.assignment_stack.len();
+        while trail.num_notified < len {
+            let start = trail.num_notified;
+            let is_decision = |pos: usize| trail.assignment_stack[pos].reason.is_decision();
+            if is_decision(start) {
+                external.propagator.notify_new_decision_level();
+            }
+            let end = (start + 1..len)
+                .find(|pos| is_decision(*pos))
+                .unwrap_or(len);
+            let literals = trail.assignment_stack[start..end]
+                .iter()
+                .map(|assignment| assignment.literal)
+                .collect_vec();
+            external.propagator.notify_assignment(&literals);
+            trail.num_notified = end;
+        }
+    }
+
+    /// Assigns the literals implied by the external propagator at the propagation fixpoint.
+    /// Returns whether new units have to be propagated.
+    fn propagate_external(&mut self) -> bool {
+        if self.state.conflict.is_some() || self.external.is_none() {
+            return false;
+        }
+        self.notify_external();
+
+        let propagator = &mut self.external.as_mut().unwrap().propagator;
+        let mut has_units = false;
+        for lit in propagator.propagate() {
+            if lit.is_true(&self.state.vars) {
+                continue;
+            }
+
+            let mut reason = propagator.add_reason_clause(lit);
+            reason.sort_unstable();
+            reason.dedup();
+            debug_assert!(reason.contains(&lit));
+            debug_assert!(reason
+                .iter()
+                .all(|other| *other == lit || other.is_false(&self.state.vars)));
+
+            if lit.is_false(&self.state.vars) {
+                self.state.external_reasons.set_conflict(reason);
+                self.set_lazy_conflict(ConstraintRef::External);
+                return false;
+            }
+            self.state.external_reasons.add_reason(lit, reason);
+            self.trail
+                .enqueue(&mut self.state, lit, ConstraintRef::External);
+            has_units = true;
+        }
+        has_units
+    }
+
+    fn external_decision(&mut self) -> Option<Literal> {
+        self.external.as_ref()?;
+        self.notify_external();
+        self.external
+            .as_mut()?
+            .propagator
+            .decide()
+            .filter(|lit| lit.is_free(&self.state.vars))
+    }
 
-        let positives: HashSet<VarId> = units
+    /// Assigns the last free literal of a clause added before backtracking if all others are
+    /// false, as such a clause isn't propagated by the watches
+    fn propagate_if_unit(&mut self, clause_id: ClauseId) {
+        let literals = &self.state.clause_database[clause_id].literals;
+        if literals.iter().any(|lit| lit.is_true(&self.state.vars)) {
+            return;
+        }
+        if let Ok(lit) = literals
             .iter()
-            .filter(|lit| lit.positive())
-            .map(|lit| lit.id())
-            .collect();
-        let negatives: HashSet<VarId> = units
+            .filter(|lit| lit.is_free(&self.state.vars))
+            .exactly_one()
+        {
+            let lit = *lit;
+            self.trail.assign(
+                &mut self.state,
+                lit,
+                AssignmentReason::Forced(ConstraintRef::Clause(clause_id)),
+            );
+        }
+    }
+
+    fn assumptions_hold(&self) -> bool {
+        self.assumptions
             .iter()
-            .filter(|lit| !lit.positive())
-            .map(|lit| lit.id())
-            .collect();
+            .all(|lit| lit.is_true(&self.state.vars))
+    }
 
-        positives.intersection(&negatives).count() > 0
+    /// The assumptions the negation of the false assumption is implied by, including it, found
+    /// by following the reasons on the trail back to the assumption decisions like
+    /// `analyzeFinal` in MiniSat. No heuristic decision is made before all assumptions are
+    /// assigned, so every other decision level is an assumption level.
+    fn analyze_failed_assumption(&self, failed: Literal) -> Vec<Literal> {
+        let mut failed_assumptions = vec![failed];
+        let mut seen = vec![false; self.state.num_vars + 1];
+        seen[failed.var()] = true;
+        for assignment in self.trail.assignment_stack.iter().rev() {
+            let var_id = assignment.literal.var();
+            if !seen[var_id] || assignment.decision_level == 0 {
+                continue;
+            }
+            match assignment.reason {
+                AssignmentReason::Assumption => failed_assumptions.push(assignment.literal),
+                AssignmentReason::Forced(reason) => {
+                    let literals =
+                        self.state
+                            .reason_literals(reason, Some(assignment.literal), &self.trail);
+                    for lit in literals.iter() {
+                        seen[lit.var()] = true;
+                    }
+                }
+                AssignmentReason::Heuristic => {
+                    unreachable!("Heuristic decisions are only made after all assumptions")
+                }
+            }
+        }
+        failed_assumptions
     }
 
-    fn enqueue_initial_units(&self, unit_propagator: &mut UnitPropagator) {
-        self.state
+    /// The first assumption that isn't assigned yet, or `Err` with one that is false
+    fn next_assumption(&self) -> Result<Option<Literal>, Literal> {
+        for lit in &self.assumptions {
+            match lit.value(&self.state.vars) {
+                Some(true) => continue,
+                Some(false) => return Err(*lit),
+                None => return Ok(Some(*lit)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn observed_vars_assigned(&self) -> bool {
+        self.external.as_ref().is_none_or(|external| {
+            external
+                .observed_vars
+                .iter()
+                .all(|var_id| self.state.vars.is_assigned(*var_id))
+        })
+    }
+
+    /// Sets a conflict found after the propagation fixpoint. The conflict may only involve
+    /// lower decision levels, in which case the analysis has to start from the highest one.
+    fn set_lazy_conflict(&mut self, conflict: ConstraintRef) {
+        let conflict_level = self
+            .state
+            .reason_literals(conflict, None, &self.trail)
+            .iter()
+            .map(|lit| self.trail.var_decision_level[lit.var()])
+            .max()
+            .unwrap_or(0);
+        if conflict_level < self.trail.decision_level {
+            self.trail
+                .backtrack(&mut self.state, &mut self.heuristic, conflict_level);
+        }
+        self.state.conflict = Some(conflict);
+        self.state.stats.num_conflicts += 1;
+    }
+
+    /// Clauses the empty clause is resolved from directly, if the formula contains it or a unit
+    /// clause and its negation
+    fn trivial_refutation(&self) -> Option<Vec<ClauseId>> {
+        let database = &self.state.clause_database;
+        let mut units = HashMap::new();
+        for clause_id in database.necessary_clauses_iter() {
+            match database[clause_id].literals[..] {
+                [] => return Some(vec![clause_id]),
+                [unit] => {
+                    if let Some(&negation) = units.get(&-unit) {
+                        return Some(vec![clause_id, negation]);
+                    }
+                    units.insert(unit, clause_id);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn enqueue_initial_units(&mut self) {
+        let units = self
+            .state
             .clause_database
             .necessary_clauses_iter()
             .enumerate()
             .filter(|(_, clause)| self.state.clause_database[*clause].literals.len() == 1)
-            .for_each(|(clause_id, clause)| {
-                unit_propagator.enqueue(self.state.clause_database[clause].literals[0], clause_id);
-            })
+            .map(|(clause_id, clause)| (self.state.clause_database[clause].literals[0], clause_id))
+            .collect_vec();
+        for (unit, clause_id) in units {
+            self.trail
+                .enqueue(&mut self.state, unit, ConstraintRef::Clause(clause_id));
+        }
     }
 
-    fn get_solution(&self, inprocessor: &mut Inprocessor) -> HashMap<VarId, bool> {
-        let mut assignment = self.state.get_assignment();
-        for var in 1..=self.state.num_vars {
-            assignment.entry(var).or_insert(true);
-        }
-        if self.config.inprocessing {
-            inprocessor.reconstruct_solution(&mut assignment);
-        }
-        assignment
+    fn get_model(&self) -> Model {
+        let mut model = self.simplified_model();
+        self.state.reconstruction_stack.extend_model(&mut model);
+        self.state.tracer.model(&model);
+        model
+    }
+
+    /// Model of the simplified formula found by the last call to `solve`, before the removed
+    /// clauses are satisfied
+    fn simplified_model(&self) -> Model {
+        Model::new(
+            self.state
+                .vars
+                .values()
+                .map(|value| value.unwrap_or(true))
+                .collect(),
+        )
+    }
+
+    /// Witnesses flipped to extend the model found by the last call to `solve` to the removed
+    /// clauses, each with the clause that required the flip, in the order of the flips. Only
+    /// meaningful after a satisfiable result, to debug the reconstruction of eliminated
+    /// variables.
+    pub fn reconstruction_trace(&self) -> Vec<(Literal, Clause)> {
+        let mut model = self.simplified_model();
+        self.state
+            .reconstruction_stack
+            .extend_model_traced(&mut model)
+            .into_iter()
+            .map(|(witness, clause)| (witness, clause.clone()))
+            .collect()
+    }
+
+    /// Renumbers the clauses without the ids of deleted ones, frees their literals and rebuilds
+    /// the watches, see `ClauseDatabase::compact`. Happens at restarts once
+    /// `Params::compaction_threshold` is exceeded, and can be called between incremental calls
+    /// to `solve`, e.g. after many clauses were deleted by `simplify`.
+    pub fn compact(&mut self) {
+        self.state.compact_clause_database(&mut self.trail);
     }
 
     pub fn stats(&self) -> &StateStatistics {
         &self.state.stats
     }
+
+    /// Simplifies the formula without search, propagating the units at decision level 0 and, if
+    /// inprocessing is enabled, trying to eliminate every variable regardless of the budget.
+    /// Returns `false` if the formula turned out to be unsatisfiable.
+    pub fn simplify(&mut self) -> bool {
+        if self.is_unsat || self.trivial_refutation().is_some() {
+            self.is_unsat = true;
+            return false;
+        }
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        self.trail.propagate(&mut self.state);
+        if self.config.inprocessing && self.state.conflict.is_none() {
+            self.inprocessor.inprocess_completely(
+                &mut self.heuristic,
+                &mut self.state,
+                &mut self.trail,
+            );
+            self.trail.propagate(&mut self.state);
+        }
+        if let Some(conflict) = self.state.conflict {
+            self.log_final_conflict(conflict);
+            self.is_unsat = true;
+        }
+        !self.is_unsat
+    }
+
+    /// The current formula with compact variable numbers, see `SimplifiedFormula`, or the empty
+    /// clause once the formula is known to be unsatisfiable
+    pub fn simplified_formula(&self) -> SimplifiedFormula {
+        if self.is_unsat {
+            return SimplifiedFormula::unsatisfiable();
+        }
+        SimplifiedFormula::new(
+            self.clauses(),
+            self.fixed_literals().collect(),
+            self.state.reconstruction_stack.entries(),
+            self.state.num_vars,
+        )
+    }
+
+    /// Irredundant clauses currently in the database, i.e. the formula after inprocessing,
+    /// without the literals in `fixed_literals`
+    pub fn clauses(&self) -> impl Iterator<Item = &Clause> + '_ {
+        let database = &self.state.clause_database;
+        database
+            .necessary_clauses_iter()
+            .map(move |clause_id| &database[clause_id])
+    }
+
+    /// Learned clauses currently in the database, implied by `clauses`
+    pub fn learned_clauses(&self) -> impl Iterator<Item = &Clause> + '_ {
+        let database = &self.state.clause_database;
+        database
+            .iter()
+            .map(move |clause_id| &database[clause_id])
+            .filter(|clause| clause.lbd.is_some())
+    }
+
+    /// Literals assigned at decision level 0, which hold in every model
+    pub fn fixed_literals(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.trail
+            .assignment_stack
+            .iter()
+            .take_while(|assignment| assignment.decision_level == 0)
+            .map(|assignment| assignment.literal)
+    }
+
+    /// The state at decision level 0 to continue the search later with `Solver::resume`.
+    /// Cardinality constraints and external propagators are not part of it.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            num_vars: self.state.num_vars,
+            original_clauses: self.original_clauses.to_clauses(),
+            clauses: self.clauses().cloned().collect(),
+            learned_clauses: self.learned_clauses().cloned().collect(),
+            fixed_literals: self.fixed_literals().collect(),
+            removed_clauses: self.state.reconstruction_stack.entries().to_vec(),
+            phases: self.state.var_phases.clone(),
+            scores: self.heuristic.scores(),
+            stats: self.state.stats.clone(),
+        }
+    }
+
+    /// Continues from a checkpoint with the given configuration
+    pub fn resume(checkpoint: Checkpoint, config: Config) -> Self {
+        let clauses = checkpoint
+            .fixed_literals
+            .iter()
+            .map(|lit| Clause::from(vec![*lit]))
+            .chain(checkpoint.clauses)
+            .collect_vec();
+        let mut solver = Solver::new(clauses, checkpoint.num_vars, config);
+        // learned units are fixed literals already
+        for clause in checkpoint.learned_clauses {
+            if clause.literals.len() >= 2 {
+                solver.state.clause_database.add_clause(
+                    clause,
+                    &mut solver.state.literal_watcher,
+                    &solver.state.vars,
+                    &solver.trail,
+                );
+            }
+        }
+        for (witness, clause) in checkpoint.removed_clauses {
+            solver.state.reconstruction_stack.push(witness, clause);
+        }
+        if let Some(scores) = &checkpoint.scores {
+            solver.heuristic.restore_scores(scores);
+        }
+        solver.original_clauses = OriginalClauses::new(&checkpoint.original_clauses);
+        solver.state.var_phases = checkpoint.phases;
+        solver.state.stats = checkpoint.stats;
+        solver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{check_assignment, UnknownReason};
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::proof::checker::{check_proof, CheckResult};
+    use crate::solver::clause_learning::{AdditionalClause, LearningScheme};
+    use crate::solver::heuristic::ScoreInit;
+    use crate::solver::params::Params;
+    use crate::solver::phases::PhaseMode;
+    use crate::solver::proof_logger::ProofStep;
+    use crate::solver::restarts::RestartPolicy;
+    use crate::solver::trail::{BacktrackingMode, ReuseStrategy};
+    use crate::tester::{brute_force_sat, cross_check, random_cnf, MAX_BRUTE_FORCE_VARS};
+    use clap::ValueEnum;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn search_listeners() {
+        #[derive(Default)]
+        struct Counter {
+            conflicts: usize,
+            restarts: usize,
+            reductions: usize,
+            inprocessings: usize,
+        }
+        impl SearchListener for Counter {
+            fn on_conflict(&mut self, stats: &StateStatistics, clause: &[Literal], lbd: usize) {
+                assert!(lbd <= clause.len());
+                self.conflicts += 1;
+                assert_eq!(self.conflicts, stats.num_conflicts);
+            }
+            fn on_restart(&mut self, stats: &StateStatistics, _reused_levels: usize) {
+                self.restarts += 1;
+                assert_eq!(self.restarts, stats.num_restarts);
+            }
+            fn on_reduce(&mut self, _stats: &StateStatistics, _num_learned_clauses: usize) {
+                self.reductions += 1;
+            }
+            fn on_inprocessing(&mut self, before: &StateStatistics, after: &StateStatistics) {
+                assert!(after.num_eliminated_vars >= before.num_eliminated_vars);
+                self.inprocessings += 1;
+            }
+        }
+
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole7.cnf").unwrap();
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        let config = Config {
+            deterministic: true,
+            listeners: vec![counter.clone()],
+            ..Default::default()
+        };
+        let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config);
+        assert_eq!(solver.solve(), SolveResult::Unsat);
+        let counter = counter.borrow();
+        let stats = solver.stats();
+        assert!(counter.conflicts > 0);
+        assert!(counter.conflicts <= stats.num_conflicts);
+        assert_eq!(counter.restarts, stats.num_restarts);
+        assert!(counter.reductions > 0);
+        assert_eq!(counter.inprocessings, stats.num_inprocessings);
+        assert!(counter.inprocessings > 0);
+    }
+
+    #[test]
+    fn learn_callback() {
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let config = Config::default();
+        let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+        let learned = Rc::new(RefCell::new(vec![]));
+        let learned_clone = learned.clone();
+        solver.set_learn_callback(3, move |literals| {
+            learned_clone.borrow_mut().push(literals.to_vec())
+        });
+
+        assert!(solver.solve().is_unsat());
+        let learned = learned.borrow();
+        assert!(!learned.is_empty());
+        assert!(learned.iter().all(|clause| clause.len() <= 3));
+    }
+
+    #[test]
+    fn proofs_are_verified() {
+        let config = Config {
+            core_extraction: true,
+            ..Default::default()
+        };
+        for file in ["hole6.cnf", "pret60_25.cnf", "aim-50-1_6-no-1.cnf"] {
+            let path = format!("testfiles/lecture_testfiles/unsat/{}", file);
+            let dimacs = clauses_from_dimacs_file(&path).unwrap();
+            for proof_trimming in [false, true] {
+                let config = Config {
+                    proof_trimming,
+                    ..config.clone()
+                };
+                let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+                assert!(solver.solve().is_unsat());
+
+                let proof = &solver.state.clause_database.proof_logger.proof;
+                assert!(matches!(
+                    proof.last(),
+                    Some(ProofStep::AddClause(clause)) if clause.literals.is_empty()
+                ));
+                let proof = proof.iter().cloned().enumerate().collect_vec();
+                assert_eq!(
+                    check_proof(&dimacs.clauses, dimacs.num_vars, &proof),
+                    CheckResult::Verified,
+                    "{} {}",
+                    file,
+                    proof_trimming
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn trimmed_proofs_are_shorter() {
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/ssa0432-003.cnf").unwrap();
+        let num_steps = [false, true].map(|proof_trimming| {
+            let config = Config {
+                core_extraction: true,
+                proof_trimming,
+                deterministic: true,
+                ..Default::default()
+            };
+            let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+            assert!(solver.solve().is_unsat());
+            solver.state.clause_database.proof_logger.proof.len()
+        });
+        assert!(num_steps[1] < num_steps[0], "{:?}", num_steps);
+    }
+
+    #[test]
+    fn resolution_proofs_are_valid() {
+        for file in ["hole6.cnf", "pret60_25.cnf", "ssa0432-003.cnf"] {
+            let path = format!("testfiles/lecture_testfiles/unsat/{}", file);
+            let dimacs = clauses_from_dimacs_file(&path).unwrap();
+            let schemes = LearningScheme::value_variants()
+                .iter()
+                .map(|&scheme| (scheme, AdditionalClause::None));
+            let additional_clauses = AdditionalClause::value_variants()
+                .iter()
+                .map(|&additional_clause| (LearningScheme::FirstUip, additional_clause));
+            for (learning_scheme, additional_clause) in schemes.chain(additional_clauses) {
+                let config = Config {
+                    resolution_chains: true,
+                    learning_scheme,
+                    additional_clause,
+                    ..Default::default()
+                };
+                let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+                assert!(solver.solve().is_unsat());
+                let proof = solver.resolution_proof().unwrap();
+                assert!(
+                    proof.is_valid(&dimacs.clauses),
+                    "{} {:?} {:?}",
+                    file,
+                    learning_scheme,
+                    additional_clause
+                );
+
+                let core = solver.core_clauses().unwrap();
+                let config = Config::default();
+                assert!(Solver::new(core, dimacs.num_vars, config)
+                    .solve()
+                    .is_unsat());
+            }
+        }
+    }
+
+    #[test]
+    fn core_clauses() {
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/aim-100-1_6-no-1.cnf")
+                .unwrap();
+        let config = Config {
+            inprocessing: false,
+            core_extraction: true,
+            ..Default::default()
+        };
+        let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone());
+        assert_eq!(solver.core_clauses(), None);
+        assert!(solver.solve().is_unsat());
+
+        let core = solver.core_clauses().unwrap();
+        assert!(core.len() < dimacs.clauses.len());
+        assert!(core.iter().all(|clause| dimacs.clauses.contains(clause)));
+        assert!(Solver::new(core, dimacs.num_vars, config)
+            .solve()
+            .is_unsat());
+    }
+
+    #[test]
+    fn phase_modes() {
+        for phase_mode in PhaseMode::value_variants() {
+            let config = Config {
+                phase_mode: *phase_mode,
+                ..Default::default()
+            };
+            for file in ["sat/aim-100-2_0-yes1-1.cnf", "unsat/aim-100-2_0-no-1.cnf"] {
+                let path = format!("testfiles/lecture_testfiles/{}", file);
+                let dimacs = clauses_from_dimacs_file(&path).unwrap();
+                let result =
+                    Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone()).solve();
+                match result.model() {
+                    Some(model) => assert!(check_assignment(&dimacs.clauses, model)),
+                    None => assert!(file.starts_with("unsat")),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn score_initializations() {
+        let clauses = vec![
+            Clause::from("-1 -2"),
+            Clause::from("-1 3"),
+            Clause::from("1 2 3"),
+        ];
+        let config = Config {
+            score_init: ScoreInit::JeroslowWang,
+            ..Default::default()
+        };
+        let solver = Solver::new(clauses, 3, config);
+        assert_eq!(solver.state.var_phases[1..], [false, false, true]);
+
+        for (score_init, heuristic) in ScoreInit::value_variants()
+            .iter()
+            .cartesian_product(HeuristicType::value_variants())
+        {
+            let config = Config {
+                score_init: *score_init,
+                heuristic: heuristic.clone(),
+                ..Default::default()
+            };
+            for file in ["sat/aim-100-2_0-yes1-1.cnf", "unsat/aim-100-2_0-no-1.cnf"] {
+                let path = format!("testfiles/lecture_testfiles/{}", file);
+                let dimacs = clauses_from_dimacs_file(&path).unwrap();
+                let result =
+                    Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone()).solve();
+                match result.model() {
+                    Some(model) => assert!(check_assignment(&dimacs.clauses, model)),
+                    None => assert!(file.starts_with("unsat")),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decision_priorities() {
+        for heuristic in HeuristicType::value_variants() {
+            for (first, second) in [(7, 8), (8, 7)] {
+                let config = Config {
+                    heuristic: heuristic.clone(),
+                    inprocessing: false,
+                    ..Default::default()
+                };
+                let mut solver = Solver::new(vec![Clause::from("-7 -8")], 10, config);
+                for var_id in [7, 8] {
+                    solver.set_preferred_phase(Var::new(var_id), true);
+                }
+                solver.set_decision_priority(Var::new(first), 2.0);
+                solver.set_decision_priority(Var::new(second), 1.0);
+                let model = solver.solve().into_model().unwrap();
+                assert!(model[Var::new(first)], "{:?}", heuristic);
+                assert!(!model[Var::new(second)], "{:?}", heuristic);
+            }
+        }
+    }
+
+    #[test]
+    fn decision_priorities_survive_heuristic_switches() {
+        let config = Config {
+            heuristic: HeuristicType::Adaptive,
+            inprocessing: false,
+            ..Default::default()
+        };
+        let mut solver = Solver::new(vec![Clause::from("-7 -8")], 10, config);
+        for var_id in [7, 8] {
+            solver.set_preferred_phase(Var::new(var_id), true);
+        }
+        solver.set_decision_priority(Var::new(8), 1.0);
+        assert!(solver.switcher.is_some());
+        solver.switch_heuristic(HeuristicType::VMTF);
+        let model = solver.solve().into_model().unwrap();
+        assert!(model[Var::new(8)]);
+        assert!(!model[Var::new(7)]);
+    }
+
+    #[test]
+    fn trace_vars() {
+        let path = std::env::temp_dir().join("utopia_trace_vars_test.txt");
+        let config = Config {
+            inprocessing: false,
+            trace_vars: vec![Var::new(1), Var::new(3)],
+            trace_file: Some(path.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        let clauses = vec![
+            Clause::from("-1 2"),
+            Clause::from("-2 -3"),
+            Clause::from("1 3"),
+        ];
+        let mut solver = Solver::new(clauses, 3, config);
+        let model = solver.solve().into_model().unwrap();
+
+        let trace = std::fs::read_to_string(&path).unwrap();
+        let lines = trace.lines().collect_vec();
+        assert!(lines.iter().all(|line| !line.contains(" 2 level")));
+        assert!(lines.iter().any(|line| line.starts_with("assign")));
+        let expected = [1, 3].map(|var_id| {
+            let lit = Literal::from_value(Var::new(var_id), model[Var::new(var_id)]);
+            format!("model {}", lit)
+        });
+        assert_eq!(lines[lines.len() - 2..], expected);
+        assert!(solver.debug_output_error().is_none());
+        std::fs::remove_file(path).unwrap();
+
+        let config = Config {
+            trace_vars: vec![Var::new(1)],
+            trace_file: Some("/nonexistent/trace.txt".to_string()),
+            ..Default::default()
+        };
+        let mut solver = Solver::new(vec![Clause::from("1 2")], 2, config);
+        assert!(matches!(
+            solver.debug_output_error(),
+            Some(Error::Write { .. })
+        ));
+        assert!(solver.solve().is_sat());
+    }
+
+    #[test]
+    fn stabilization() {
+        let config = Config {
+            stabilization: true,
+            params: Params {
+                stabilization_interval: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for file in ["sat/ii16a1.cnf", "unsat/hole6.cnf"] {
+            let path = format!("testfiles/lecture_testfiles/{}", file);
+            let dimacs = clauses_from_dimacs_file(&path).unwrap();
+            let result =
+                Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone()).solve();
+            match result.model() {
+                Some(model) => assert!(check_assignment(&dimacs.clauses, model)),
+                None => assert!(file.starts_with("unsat")),
+            }
+        }
+    }
+
+    #[test]
+    fn trail_saving() {
+        let config = Config {
+            trail_saving: true,
+            ..Default::default()
+        };
+        for file in ["sat/ii16a1.cnf", "unsat/hole6.cnf"] {
+            let path = format!("testfiles/lecture_testfiles/{}", file);
+            let dimacs = clauses_from_dimacs_file(&path).unwrap();
+            let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone());
+            match solver.solve().model() {
+                Some(model) => assert!(check_assignment(&dimacs.clauses, model)),
+                None => {
+                    assert!(file.starts_with("unsat"));
+                    assert!(solver.stats().num_replayed_assignments > 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reuse_strategies() {
+        for reuse_strategy in ReuseStrategy::value_variants() {
+            let config = Config {
+                reuse_strategy: *reuse_strategy,
+                deterministic: true,
+                ..Default::default()
+            };
+            for file in ["sat/ii16a1.cnf", "unsat/hole6.cnf"] {
+                let path = format!("testfiles/lecture_testfiles/{}", file);
+                let dimacs = clauses_from_dimacs_file(&path).unwrap();
+                let mut solver =
+                    Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone());
+                match solver.solve().model() {
+                    Some(model) => assert!(check_assignment(&dimacs.clauses, model)),
+                    None => {
+                        assert!(file.starts_with("unsat"));
+                        assert!(solver.stats().num_reused_levels > 0, "{:?}", reuse_strategy);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chronological_backtracking() {
+        // a threshold of 0 backtracks chronologically after every conflict
+        let config = Config {
+            backtracking_mode: BacktrackingMode::Chronological,
+            core_extraction: true,
+            params: Params {
+                chronological_backtracking_threshold: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let formulas = (0..100)
+            .map(|seed| (random_cnf(14, 4.26, 3, seed), 14))
+            .chain(["hole6.cnf", "pret60_25.cnf"].map(|file| {
+                let path = format!("testfiles/lecture_testfiles/unsat/{}", file);
+                let dimacs = clauses_from_dimacs_file(&path).unwrap();
+                (dimacs.clauses, dimacs.num_vars)
+            }));
+        for (clauses, num_vars) in formulas {
+            let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+            match solver.solve() {
+                SolveResult::Sat(model) => assert!(check_assignment(&clauses, &model)),
+                SolveResult::Unsat => {
+                    assert!(
+                        num_vars > MAX_BRUTE_FORCE_VARS || !brute_force_sat(&clauses, num_vars)
+                    );
+                    let proof = &solver.state.clause_database.proof_logger.proof;
+                    let proof = proof.iter().cloned().enumerate().collect_vec();
+                    assert_eq!(
+                        check_proof(&clauses, num_vars, &proof),
+                        CheckResult::Verified
+                    );
+                }
+                SolveResult::Unknown(reason) => panic!("unknown result: {:?}", reason),
+            }
+        }
+    }
+
+    #[test]
+    fn conflict_limit() {
+        let config = Config {
+            conflict_limit: Some(100),
+            ..Default::default()
+        };
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config);
+        let mut num_calls = 1;
+        while solver.solve() == SolveResult::Unknown(UnknownReason::ConflictLimit) {
+            assert_eq!(solver.stats().num_conflicts, 100 * num_calls);
+            num_calls += 1;
+        }
+        assert!(num_calls > 1);
+        assert!(solver.solve().is_unsat());
+    }
+
+    #[test]
+    fn time_limit_without_conflicts() {
+        let config = Config {
+            inprocessing: false,
+            time_limit: Some(core::time::Duration::ZERO),
+            ..Default::default()
+        };
+        // every clause takes a decision, but none of them leads to a conflict
+        let clauses = (1..=3000)
+            .map(|i| Clause::from(format!("{} {}", 2 * i - 1, 2 * i).as_str()))
+            .collect_vec();
+        let mut solver = Solver::new(clauses, 6000, config);
+        assert_eq!(
+            solver.solve(),
+            SolveResult::Unknown(UnknownReason::TimeLimit)
+        );
+        assert_eq!(solver.stats().num_conflicts, 0);
+    }
+
+    #[test]
+    fn terminate_flag() {
+        let config = Config::default();
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config);
+        let terminate = solver.terminate_flag();
+        terminate.store(true, core::sync::atomic::Ordering::Relaxed);
+        assert_eq!(
+            solver.solve(),
+            SolveResult::Unknown(UnknownReason::Interrupted)
+        );
+        // the flag is read before the first decision, not only after conflicts
+        assert_eq!(solver.stats().num_decisions, 0);
+        assert_eq!(solver.stats().num_conflicts, 0);
+
+        terminate.store(false, core::sync::atomic::Ordering::Relaxed);
+        assert!(solver.solve().is_unsat());
+    }
+
+    #[test]
+    fn proof_write_errors() {
+        let path = std::env::temp_dir().join(format!("utopia-{}.drup", std::process::id()));
+        let path = path.to_str().unwrap();
+        for (proof_file, written) in [(path, true), ("/nonexistent/proof.drup", false)] {
+            let config = Config {
+                proof_file: Some(proof_file.to_string()),
+                ..Default::default()
+            };
+            let clauses = ["1 2", "-1 2", "1 -2", "-1 -2"].map(Clause::from).to_vec();
+            let mut solver = Solver::new(clauses, 2, config);
+            assert!(solver.solve().is_unsat());
+            assert_eq!(solver.proof_error().is_none(), written);
+            assert_eq!(std::fs::exists(proof_file).unwrap(), written);
+            assert!(!std::fs::exists(format!("{}.tmp", proof_file)).unwrap());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn conflict_graph_write_errors() {
+        let dir = std::env::temp_dir().join(format!("utopia-graphs-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        for (graph_dir, written) in [(dir, true), ("/proc/nope", false)] {
+            let config = Config {
+                inprocessing: false,
+                conflict_graph_dir: Some(graph_dir.to_string()),
+                num_conflict_graphs: 2,
+                ..Default::default()
+            };
+            let dimacs =
+                clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+            let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config);
+            assert!(solver.solve().is_unsat());
+            assert_eq!(solver.debug_output_error().is_none(), written);
+            let graph = std::path::Path::new(graph_dir).join("conflict_1.dot");
+            assert_eq!(std::fs::exists(graph).unwrap_or(false), written);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_and_resume() {
+        let config = Config {
+            conflict_limit: Some(100),
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("utopia-{}.checkpoint", std::process::id()));
+        let path = path.to_str().unwrap();
+        for (file, sat) in [
+            ("sat/aim-200-2_0-yes1-1.cnf", true),
+            ("unsat/hole6.cnf", false),
+        ] {
+            let dimacs =
+                clauses_from_dimacs_file(&format!("testfiles/lecture_testfiles/{}", file)).unwrap();
+            let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config.clone());
+            let mut num_calls = 1;
+            let result = loop {
+                let result = solver.solve();
+                if result != SolveResult::Unknown(UnknownReason::ConflictLimit) {
+                    break result;
+                }
+                assert_eq!(solver.stats().num_conflicts, 100 * num_calls);
+                solver.checkpoint().write(path).unwrap();
+                solver = Solver::resume(Checkpoint::read(path).unwrap(), config.clone());
+                num_calls += 1;
+            };
+            assert!(num_calls > 1, "{}", file);
+            assert_eq!(result.is_sat(), sat, "{}", file);
+            if let SolveResult::Sat(model) = result {
+                assert_eq!(solver.validate_model(&model), Ok(()));
+            }
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validate_model() {
+        let clauses = ["1 2", "-1 3", "-2 -3"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 3, Config::default());
+        let model = solver.solve().into_model().unwrap();
+        assert_eq!(solver.validate_model(&model), Ok(()));
+
+        let model = Model::new(vec![true, true, true]);
+        assert_eq!(solver.validate_model(&model), Err(vec![2]));
+        solver.add_clause_at_level_zero(vec![Literal::new(-1)]);
+        assert_eq!(solver.validate_model(&model), Err(vec![2, 3]));
+    }
+
+    #[test]
+    fn compaction_keeps_proofs_and_incremental_solving() {
+        let config = Config {
+            resolution_chains: true,
+            params: Params {
+                reduce_interval: 50,
+                reduce_interval_increment: 0,
+                compaction_threshold: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone());
+        assert!(solver.solve().is_unsat());
+        assert!(solver.stats().num_compactions > 0);
+        let proof = solver.state.clause_database.proof_logger.proof.clone();
+        let proof = proof.into_iter().enumerate().collect_vec();
+        assert_eq!(
+            check_proof(&dimacs.clauses, dimacs.num_vars, &proof),
+            CheckResult::Verified
+        );
+        assert!(solver.resolution_proof().unwrap().is_valid(&dimacs.clauses));
+
+        for seed in 0..20 {
+            let mut clauses = random_cnf(10, 3.0, 3, seed);
+            let mut solver = Solver::new(clauses.clone(), 10, config.clone());
+            solver.simplify();
+            while let SolveResult::Sat(model) = solver.solve() {
+                assert!(check_assignment(&clauses, &model));
+                solver.compact();
+                assert_eq!(solver.state.clause_database.fragmentation().num_free_ids, 0);
+                solver.state.verify_watches();
+                let blocking = model.iter().map(|lit| -lit).take(4).collect_vec();
+                let literals = blocking.iter().map(|lit| lit.to_i32()).collect_vec();
+                // clauses over eliminated variables are rejected
+                if solver.add_clause_incremental(&literals).is_err() {
+                    break;
+                }
+                clauses.push(Clause::from(blocking));
+            }
+        }
+    }
+
+    #[test]
+    fn add_clause_incremental() {
+        let config = Config {
+            inprocessing: false,
+            resolution_chains: true,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for seed in 0..20 {
+            let mut clauses = random_cnf(10, 3.0, 3, seed);
+            let mut solver = Solver::new(clauses.clone(), 10, config.clone());
+            while let SolveResult::Sat(model) = solver.solve() {
+                assert!(check_assignment(&clauses, &model));
+                // refine by excluding the model on a few variables
+                let refinement = (0..3)
+                    .map(|_| rng.gen_range(1..=10))
+                    .map(|var: i32| match model[Var::new(var as usize)] {
+                        true => -var,
+                        false => var,
+                    })
+                    .collect_vec();
+                solver.add_clause_incremental(&refinement).unwrap();
+                clauses.push(Clause::from(
+                    refinement
+                        .iter()
+                        .map(|&lit| Literal::new(lit))
+                        .collect_vec(),
+                ));
+            }
+            assert!(!brute_force_sat(&clauses, 10), "seed {}", seed);
+            let proof = solver.resolution_proof().unwrap();
+            assert!(proof.is_valid(&clauses), "seed {}", seed);
+        }
+
+        let mut solver = Solver::new(vec![Clause::from("1 2")], 2, config);
+        assert!(matches!(
+            solver.add_clause_incremental(&[1, 0]),
+            Err(Error::InvalidLiteral(0))
+        ));
+        assert!(matches!(
+            solver.add_clause_incremental(&[3]),
+            Err(Error::VariableOutOfRange { .. })
+        ));
+        solver.add_clause_incremental(&[1, -1]).unwrap();
+        solver.add_clause_incremental(&[-1]).unwrap();
+        assert!(solver.solve().into_model().unwrap()[Var::new(2)]);
+        solver.add_clause_incremental(&[-2]).unwrap();
+        assert!(solver.solve().is_unsat());
+        let clauses = ["1 2", "1 -1", "-1", "-2"].map(Clause::from);
+        assert!(solver.resolution_proof().unwrap().is_valid(&clauses));
+    }
+
+    #[test]
+    fn add_clause_incremental_rejects_eliminated_vars() {
+        let config = Config::default();
+        let clauses = ["1 2", "-1 3", "2 3 4"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses, 4, config);
+        solver.simplify();
+        let eliminated = solver.state.reconstruction_stack.entries()[0].0.var();
+        assert!(matches!(
+            solver.add_clause_incremental(&[eliminated.index() as i32]),
+            Err(Error::EliminatedVariable(var_id)) if var_id == eliminated
+        ));
+    }
+
+    #[test]
+    fn frozen_vars_are_not_eliminated() {
+        let config = Config::default();
+        let clauses = ["1 2", "-1 3", "2 3 4", "-2 -4"].map(Clause::from).to_vec();
+        let mut solver = Solver::new(clauses.clone(), 4, config);
+        for var_id in Var::range(4) {
+            solver.freeze(var_id);
+            solver.freeze(var_id);
+        }
+        solver.melt(Var::new(1));
+        solver.simplify();
+        assert!(solver.state.reconstruction_stack.is_empty());
+
+        solver.melt(Var::new(1));
+        assert!(!solver.is_frozen(Var::new(1)));
+        assert!(!solver.melt(Var::new(1)));
+        solver.simplify();
+        let witnesses = solver.state.reconstruction_stack.entries().iter();
+        assert!(witnesses
+            .map(|(witness, _)| witness.var())
+            .all(|var| var == Var::new(1)));
+        assert!(!solver.state.reconstruction_stack.is_empty());
+        solver.add_clause_incremental(&[-2, 3]).unwrap();
+        assert!(check_assignment(
+            &clauses,
+            &solver.solve().into_model().unwrap()
+        ));
+    }
+
+    #[test]
+    fn clause_iteration() {
+        let config = Config {
+            inprocessing: false,
+            ..Default::default()
+        };
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+        assert_eq!(solver.clauses().cloned().collect_vec(), dimacs.clauses);
+        assert_eq!(solver.learned_clauses().count(), 0);
+
+        solver.add_clause_at_level_zero(vec![Literal::new(1)]);
+        assert!(solver.solve().is_unsat());
+        assert_eq!(
+            solver.learned_clauses().count(),
+            solver.stats().num_learned_clauses
+        );
+        assert!(solver.learned_clauses().all(|clause| clause.lbd.is_some()));
+        assert_eq!(solver.clauses().count(), dimacs.clauses.len() + 1);
+    }
+
+    #[test]
+    fn simplify() {
+        let config = Config::default();
+        for file in ["sat/ii8a1.cnf", "sat/ii8b1.cnf"] {
+            let dimacs =
+                clauses_from_dimacs_file(&format!("testfiles/lecture_testfiles/{}", file)).unwrap();
+            let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config.clone());
+            assert!(solver.simplify());
+            let formula = solver.simplified_formula();
+            assert!(formula.num_vars < dimacs.num_vars);
+
+            let mut simplified =
+                Solver::new(formula.clauses.clone(), formula.num_vars, config.clone());
+            let SolveResult::Sat(model) = simplified.solve() else {
+                panic!("the simplified formula of {} is unsatisfiable", file);
+            };
+            let model = formula.extend_model(&model, dimacs.num_vars);
+            assert!(check_assignment(&dimacs.clauses, &model), "{}", file);
+        }
+
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/pret60_25.cnf").unwrap();
+        let mut solver = Solver::new(dimacs.clauses, dimacs.num_vars, config.clone());
+        if solver.simplify() {
+            let formula = solver.simplified_formula();
+            assert!(Solver::new(formula.clauses, formula.num_vars, config)
+                .solve()
+                .is_unsat());
+        }
+    }
+
+    #[test]
+    fn assumptions() {
+        let config = Config::default();
+        let clauses = ["1 2", "-1 3", "-2 3", "-3 4 5", "6 -1"]
+            .map(Clause::from)
+            .to_vec();
+        let mut solver = Solver::new(clauses.clone(), 6, config);
+        let assumptions = [Literal::new(6), Literal::new(-4), Literal::new(-5)];
+        assert!(solver
+            .solve_with_assumptions(&assumptions)
+            .unwrap()
+            .is_unsat());
+        assert!(solver.failed(Literal::new(-4)) && solver.failed(Literal::new(-5)));
+        assert!(!solver.failed(Literal::new(6)));
+        // contradicting assumptions fail together
+        let assumptions = [Literal::new(2), Literal::new(1), Literal::new(-2)];
+        assert!(solver
+            .solve_with_assumptions(&assumptions)
+            .unwrap()
+            .is_unsat());
+        assert!(solver.failed(Literal::new(2)) && solver.failed(Literal::new(-2)));
+        assert!(!solver.failed(Literal::new(1)));
+
+        let assumptions = [Literal::new(-4), Literal::new(1)];
+        let SolveResult::Sat(model) = solver.solve_with_assumptions(&assumptions).unwrap() else {
+            panic!("satisfiable under {:?}", assumptions);
+        };
+        assert!(check_assignment(&clauses, &model));
+        assert!(assumptions.iter().all(|lit| model.value(*lit)));
+
+        // assumptions only hold for a single call
+        assert!(solver.solve().is_sat());
+        solver.add_clause_at_level_zero(vec![Literal::new(-3)]);
+        assert!(solver
+            .solve_with_assumptions(&[Literal::new(4)])
+            .unwrap()
+            .is_unsat());
+        // the formula is unsatisfiable by itself
+        assert!(!solver.failed(Literal::new(4)));
+        assert!(solver.solve().is_unsat());
+    }
+
+    #[test]
+    fn trail_reuse_agrees_with_brute_force() {
+        let mut num_reused_levels = 0;
+        for reuse_strategy in ReuseStrategy::value_variants() {
+            // restarts after every conflict
+            let config = Config {
+                trail_reuse: true,
+                reuse_strategy: *reuse_strategy,
+                restart_policy: RestartPolicy::FixedInterval,
+                params: Params {
+                    restart_fixed_interval: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            for seed in 0..50 {
+                let clauses = random_cnf(12, 4.26, 3, seed);
+                assert_eq!(cross_check(&clauses, 12, &config), None, "seed {}", seed);
+
+                let mut rng = StdRng::seed_from_u64(seed);
+                let assumptions = (1..=3)
+                    .map(|_| Literal::from_value(Var::new(rng.gen_range(1..=12)), rng.gen()))
+                    .collect_vec();
+                let mut solver = Solver::new(clauses.clone(), 12, config.clone());
+                let result = solver.solve_with_assumptions(&assumptions).unwrap();
+                let units = assumptions.iter().map(|lit| Clause::from(vec![*lit]));
+                let expected =
+                    brute_force_sat(&clauses.iter().cloned().chain(units).collect_vec(), 12);
+                match result {
+                    SolveResult::Sat(model) => {
+                        assert!(check_assignment(&clauses, &model), "seed {}", seed);
+                        assert!(
+                            assumptions.iter().all(|lit| model.value(*lit)),
+                            "seed {}",
+                            seed
+                        );
+                    }
+                    SolveResult::Unsat => assert!(!expected, "seed {}", seed),
+                    SolveResult::Unknown(reason) => panic!("unknown result: {:?}", reason),
+                }
+                num_reused_levels += solver.stats().num_reused_levels;
+            }
+        }
+        assert!(num_reused_levels > 0);
+    }
+
+    #[test]
+    fn failed_assumptions_are_inconsistent() {
+        let config = Config::default();
+        for seed in 0..100 {
+            let clauses = random_cnf(12, 3.0, 3, seed);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let assumptions = (1..=12)
+                .filter_map(|var| {
+                    let value = rng.gen_range(0..3);
+                    (value != 0).then(|| Literal::from_value(Var::new(var), value == 1))
+                })
+                .collect_vec();
+            let mut solver = Solver::new(clauses.clone(), 12, config.clone());
+            if !brute_force_sat(&clauses, 12)
+                || !solver
+                    .solve_with_assumptions(&assumptions)
+                    .unwrap()
+                    .is_unsat()
+            {
+                continue;
+            }
+            let failed = assumptions
+                .iter()
+                .filter(|lit| solver.failed(**lit))
+                .map(|lit| Clause::from(vec![*lit]));
+            let clauses = clauses.iter().cloned().chain(failed).collect_vec();
+            assert!(!brute_force_sat(&clauses, 12), "seed {}", seed);
+        }
+    }
+    #[test]
+    fn assumptions_after_simplification() {
+        for seed in 0..50 {
+            let clauses = random_cnf(12, 3.0, 3, seed);
+            let mut solver = Solver::new(clauses.clone(), 12, Config::default());
+            for var_id in Var::range(3) {
+                solver.freeze(var_id);
+            }
+            solver.simplify();
+            let eliminated = solver.state.reconstruction_stack.entries().first();
+            if let Some(&(witness, _)) = eliminated {
+                assert!(matches!(
+                    solver.solve_with_assumptions(&[witness]),
+                    Err(Error::EliminatedVariable(var_id)) if var_id == witness.var()
+                ));
+            }
+            let mut rng = StdRng::seed_from_u64(seed);
+            let assumptions = Var::range(3)
+                .map(|var_id| Literal::from_value(var_id, rng.gen()))
+                .collect_vec();
+            match solver.solve_with_assumptions(&assumptions).unwrap() {
+                SolveResult::Sat(model) => {
+                    assert!(check_assignment(&clauses, &model), "seed {}", seed);
+                    assert!(assumptions.iter().all(|lit| model.value(*lit)));
+                }
+                _ => {
+                    let units = assumptions.iter().map(|lit| Clause::from(vec![*lit]));
+                    let clauses = clauses.iter().cloned().chain(units).collect_vec();
+                    assert!(!brute_force_sat(&clauses, 12), "seed {}", seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn minimize_model() {
+        let config = Config::default();
+        let clauses = ["1 2 3", "-1 2", "2 -3 4", "4 5 6"]
+            .map(Clause::from)
+            .to_vec();
+        let mut solver = Solver::new(clauses.clone(), 7, config);
+        assert!(solver.solve().is_sat());
+
+        let implicant = solver.minimize_model().unwrap();
+        let satisfied = |literals: &[Literal]| {
+            clauses
+                .iter()
+                .all(|clause| clause.literals.iter().any(|lit| literals.contains(lit)))
+        };
+        assert!(satisfied(&implicant));
+        // prime: no literal can be dropped
+        for index in 0..implicant.len() {
+            let mut smaller = implicant.clone();
+            smaller.remove(index);
+            assert!(!satisfied(&smaller));
+        }
+        assert!(implicant.len() <= 3);
+    }
 }
@@ -0,0 +1,54 @@
+use crate::cnf::{Clause, Literal};
+use alloc::vec::Vec;
+
+/// The clauses of the formula before any simplification, including the ones added later. They
+/// are only read to validate models, shrink them to prime implicants, extract cores and write
+/// checkpoints, so they are stored as one array of literals instead of a `Clause` per clause,
+/// which keeps the copy next to the clause database small.
+#[derive(Debug, Clone, Default)]
+pub struct OriginalClauses {
+    literals: Vec<Literal>,
+    /// End of each clause in `literals`
+    ends: Vec<usize>,
+}
+
+impl OriginalClauses {
+    pub fn new(clauses: &[Clause]) -> Self {
+        let mut original_clauses = OriginalClauses {
+            literals: Vec::with_capacity(clauses.iter().map(|clause| clause.literals.len()).sum()),
+            ends: Vec::with_capacity(clauses.len()),
+        };
+        for clause in clauses {
+            original_clauses.push(&clause.literals);
+        }
+        original_clauses
+    }
+
+    pub fn push(&mut self, literals: &[Literal]) {
+        self.literals.extend_from_slice(literals);
+        self.ends.push(self.literals.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// The literals of the clause with the index in the order the clauses were added
+    pub fn get(&self, index: usize) -> &[Literal] {
+        let start = match index {
+            0 => 0,
+            _ => self.ends[index - 1],
+        };
+        &self.literals[start..self.ends[index]]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[Literal]> {
+        (0..self.len()).map(|index| self.get(index))
+    }
+
+    pub fn to_clauses(&self) -> Vec<Clause> {
+        self.iter()
+            .map(|literals| Clause::from(literals.to_vec()))
+            .collect()
+    }
+}
@@ -0,0 +1,173 @@
+//! Experimental propagation with multiple threads, to compare parallelism within a single search
+//! against running independent solvers and sharing their clauses.
+//!
+//! The literals on the trail waiting for propagation form a batch. The watch lists of the batch
+//! are sharded by variable across the threads, which find the updates of the watching clauses
+//! against the shared assignment without changing anything. The updates are then applied in the
+//! order of the trail. An update is outdated if another literal of the batch already moved one
+//! of the watches of its clause, which is then updated again sequentially. All other updates
+//! stay valid, as the assignment only grows during a batch: true literals stay true, false
+//! literals stay false and implied literals are enqueued, so they may be found to be already
+//! assigned. New watches may have become false, but then they are propagated later.
+
+use crate::cnf::Literal;
+use crate::solver::literal_watching::WatchUpdate;
+use crate::solver::state::State;
+use crate::solver::trail::Trail;
+use alloc::{vec, vec::Vec};
+use itertools::Itertools;
+use std::thread;
+
+/// Batches watched by fewer clauses are propagated sequentially, as starting the threads would
+/// take longer
+const MIN_PARALLEL_WATCHES: usize = if cfg!(test) { 1 } else { 1024 };
+
+/// Update of a clause together with its watches at the time it was found
+type ShardedUpdate = ([Literal; 2], WatchUpdate);
+
+impl Trail {
+    pub(crate) fn propagate_in_parallel(&mut self, state: &mut State) {
+        while self.propagated < self.assignment_stack.len() && state.conflict.is_none() {
+            if !self.saved.is_empty() {
+                self.replay_saved(state);
+            }
+            let batch = self.assignment_stack[self.propagated..]
+                .iter()
+                .map(|assignment| assignment.literal)
+                .collect_vec();
+            let num_watches: usize = batch
+                .iter()
+                .map(|lit| state.literal_watcher.watches(-*lit).len())
+                .sum();
+            if num_watches < MIN_PARALLEL_WATCHES {
+                for lit in batch {
+                    if state.conflict.is_some() {
+                        break;
+                    }
+                    self.propagated += 1;
+                    state.propagate(lit, self);
+                }
+                continue;
+            }
+
+            state.stats.num_parallel_batches += 1;
+            let updates = find_updates(state, &batch);
+            for (lit, updates) in batch.into_iter().zip(updates) {
+                if state.conflict.is_some() {
+                    break;
+                }
+                self.propagated += 1;
+                state.apply_sharded_updates(lit, updates, self);
+            }
+        }
+    }
+}
+
+impl State {
+    fn apply_sharded_updates(
+        &mut self,
+        lit: Literal,
+        updates: Vec<ShardedUpdate>,
+        trail: &mut Trail,
+    ) {
+        self.stats.num_propagations += 1;
+        for (i, (watched, watch_update)) in updates.into_iter().enumerate() {
+            if self.conflict.is_some() {
+                break;
+            }
+
+            let clause_id = self.literal_watcher.affected_clauses(lit)[i];
+            let watch_update = match self.literal_watcher.watched_literals(clause_id) == watched {
+                true => watch_update,
+                false => self.find_watch_update(lit, clause_id),
+            };
+            self.apply_watch_update(lit, i, clause_id, watch_update, trail);
+        }
+
+        self.finish_propagation(lit, trail);
+    }
+}
+
+/// Updates of the clauses affected by each literal of the batch, in the order of their watches
+fn find_updates(state: &State, batch: &[Literal]) -> Vec<Vec<ShardedUpdate>> {
+    let num_threads = state.propagation_threads;
+    let mut updates = vec![vec![]; batch.len()];
+    thread::scope(|scope| {
+        let workers = (0..num_threads)
+            .map(|shard| {
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, lit)| lit.var().index() % num_threads == shard)
+                        .map(|(position, &lit)| (position, find_shard_updates(state, lit)))
+                        .collect_vec()
+                })
+            })
+            .collect_vec();
+        for worker in workers {
+            for (position, shard_updates) in worker.join().expect("propagation thread panicked") {
+                updates[position] = shard_updates;
+            }
+        }
+    });
+    updates
+}
+
+fn find_shard_updates(state: &State, lit: Literal) -> Vec<ShardedUpdate> {
+    state
+        .literal_watcher
+        .watches(-lit)
+        .iter()
+        .map(|&clause_id| {
+            (
+                state.literal_watcher.watched_literals(clause_id),
+                state.find_watch_update(lit, clause_id),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cnf::check_assignment;
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+    use crate::tester::{brute_force_sat, random_cnf};
+
+    fn config() -> Config {
+        Config {
+            propagation_threads: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parallel_propagation_agrees_with_brute_force() {
+        for seed in 0..100 {
+            let clauses = random_cnf(12, 4.2, 3, seed);
+            let mut solver = Solver::new(clauses.clone(), 12, config());
+            match solver.solve().into_model() {
+                Some(model) => assert!(check_assignment(&clauses, &model), "seed {}", seed),
+                None => assert!(!brute_force_sat(&clauses, 12), "seed {}", seed),
+            }
+            assert!(solver.stats().num_parallel_batches > 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn parallel_propagation_solves_lecture_files() {
+        for (file, is_sat) in [
+            ("testfiles/lecture_testfiles/unsat/hole6.cnf", false),
+            ("testfiles/lecture_testfiles/sat/ii16e1.cnf", true),
+        ] {
+            let dimacs = clauses_from_dimacs_file(file).unwrap();
+            let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config());
+            match solver.solve().into_model() {
+                Some(model) => assert!(is_sat && check_assignment(&dimacs.clauses, &model)),
+                None => assert!(!is_sat),
+            }
+        }
+    }
+}
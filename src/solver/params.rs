@@ -0,0 +1,522 @@
+use crate::error::{self, Error};
+use alloc::{format, string::String, vec::Vec};
+use itertools::Itertools;
+
+/// The numeric constants of the search in one place, so they can be tuned automatically, e.g.
+/// by SMAC or ParamILS. They are written and read as flat TOML (`name = value` per line) or as
+/// a flat JSON object, missing parameters keep their defaults. Limits that only keep numbers in
+/// range, like the rescaling of heuristic scores, and the feature thresholds of the presets
+/// aren't parameters of the search. Optional limits are disabled by 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// Conflicts between restarts of the fixed interval policy
+    pub restart_fixed_interval: usize,
+    /// First interval of the geometric policy, which grows by `restart_geometric_factor`
+    pub restart_geometric_interval: usize,
+    pub restart_geometric_factor: f64,
+    /// Conflicts per element of the Luby sequence for the Luby policy
+    pub luby_unit: usize,
+    /// Conflicts per unit of reluctant doubling, also used in the stable mode
+    pub reluctant_doubling_unit: usize,
+    /// Smoothing factors of the moving averages of Glucose-EMA, the long-term ones as in
+    /// A. Biere and A. Fröhlich, “Evaluating CDCL Restart Schemes,” in Pragmatics of SAT 2015
+    pub lbd_ema_short_term_alpha: f64,
+    pub lbd_ema_long_term_alpha: f64,
+    pub assignment_ema_short_term_alpha: f64,
+    pub assignment_ema_long_term_alpha: f64,
+    /// Glucose-EMA forces a restart if the short-term LBD exceeds the long-term LBD by this ratio
+    pub margin_ratio_forcing_restart: f64,
+    /// Glucose-EMA blocks restarts if the short-term trail size exceeds the long-term one by this
+    /// ratio
+    pub margin_ratio_blocking_restart: f64,
+    /// Glucose-EMA additionally blocks restarts while the trail is larger than this percentile
+    /// (0.0 to 1.0) of the last `trail_size_window` trail sizes, recomputed every
+    /// `trail_size_percentile_interval` conflicts. 0 disables it.
+    pub restart_blocking_trail_percentile: f64,
+    pub trail_size_window: usize,
+    pub trail_size_percentile_interval: usize,
+    /// Restarts can't be blocked once the last one is this many seconds ago, or this many
+    /// conflicts in deterministic mode, 0 for no limit
+    pub max_time_restart_blocked: f64,
+    pub max_conflicts_restart_blocked: usize,
+    /// Glucose-EMA doesn't restart before this many conflicts since the last restart
+    pub restart_min_conflicts: usize,
+    /// The n-th reduction of the clause database happens `reduce_interval + n *
+    /// reduce_interval_increment` conflicts after the previous one
+    pub reduce_interval: usize,
+    pub reduce_interval_increment: usize,
+    /// The VSIDS increment grows by `1 / vsids_decay` after every conflict
+    pub vsids_decay: f64,
+    /// Time spent on inprocessing relative to the search, or resolutions per conflict in
+    /// deterministic mode relative to `resolutions_per_conflict`
+    pub inprocessing_ratio: f64,
+    pub resolutions_per_conflict: f64,
+    /// Inprocessing only starts once its budget exceeds the effort so far by this many seconds,
+    /// or by this many resolutions in deterministic mode
+    pub min_inprocessing_time: f64,
+    pub min_inprocessing_resolutions: f64,
+    /// Variables are only eliminated if there are fewer resolvents than removed clauses plus
+    /// this number
+    pub bve_clause_growth: usize,
+    /// Eliminations producing longer resolvents are aborted, 0 for no limit
+    pub bve_max_resolvent_length: usize,
+    /// Conflicts of the first focused and stable mode, both grow by this factor after every
+    /// stable mode
+    pub stabilization_interval: usize,
+    pub stabilization_factor: usize,
+    /// Conflicts before the first local search, the interval grows by this after every walk
+    pub walk_interval: usize,
+    /// Flips of a walk per propagation of the search since the previous walk, but at least
+    /// `walk_min_flips`
+    pub walk_effort: f64,
+    pub walk_min_flips: usize,
+    /// Conflicts after which the adaptive heuristic compares VSIDS and VMTF
+    pub heuristic_switch_interval: usize,
+    /// The decay heuristic decays all values by `decay_factor` every `decay_interval` decisions
+    pub decay_interval: usize,
+    pub decay_factor: f64,
+    /// Chronological backtracking only undoes the last level if a backjump would skip more than
+    /// this many levels
+    pub chronological_backtracking_threshold: usize,
+    /// Learned clauses up to this length are minimized with the binary implications of their
+    /// first literal
+    pub binary_minimization_max_len: usize,
+    /// XOR constraints up to this size are detected for Gaussian elimination, each takes
+    /// 2^(size - 1) clauses
+    pub max_xor_size: usize,
+    /// The clause database is compacted at a restart once this share of the clause ids belongs
+    /// to deleted clauses, see `Solver::compact`
+    pub compaction_threshold: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            restart_fixed_interval: 700,
+            restart_geometric_interval: 100,
+            restart_geometric_factor: 1.5,
+            luby_unit: 32,
+            reluctant_doubling_unit: 512,
+            // window size of 50
+            lbd_ema_short_term_alpha: 2.0 / 51.0,
+            lbd_ema_long_term_alpha: 2e-6,
+            assignment_ema_short_term_alpha: 2.0 / 51.0,
+            assignment_ema_long_term_alpha: 2e-6,
+            margin_ratio_forcing_restart: 1.15,
+            margin_ratio_blocking_restart: 1.4,
+            restart_blocking_trail_percentile: 0.0,
+            trail_size_window: 5000,
+            trail_size_percentile_interval: 100,
+            max_time_restart_blocked: 0.0,
+            max_conflicts_restart_blocked: 0,
+            restart_min_conflicts: 50,
+            reduce_interval: 2000,
+            reduce_interval_increment: 300,
+            vsids_decay: 0.95,
+            inprocessing_ratio: 0.1,
+            resolutions_per_conflict: 100.0,
+            min_inprocessing_time: 0.1,
+            min_inprocessing_resolutions: 10_000.0,
+            bve_clause_growth: 0,
+            bve_max_resolvent_length: 0,
+            stabilization_interval: 1000,
+            stabilization_factor: 2,
+            walk_interval: 2000,
+            walk_effort: 0.1,
+            walk_min_flips: 10_000,
+            heuristic_switch_interval: 5000,
+            decay_interval: 100,
+            decay_factor: 0.95,
+            chronological_backtracking_threshold: 100,
+            binary_minimization_max_len: 30,
+            max_xor_size: 6,
+            compaction_threshold: 0.5,
+        }
+    }
+}
+
+enum Value<'a> {
+    Integer(&'a mut usize),
+    Float(&'a mut f64),
+}
+
+impl Value<'_> {
+    fn format(&self) -> String {
+        match self {
+            Value::Integer(value) => format!("{}", value),
+            Value::Float(value) => format!("{:?}", value),
+        }
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> error::Result<()> {
+        let invalid = || Error::Parse(format!("Invalid value '{}' of parameter {}", value, name));
+        match self {
+            Value::Integer(param) => **param = value.parse().map_err(|_| invalid())?,
+            Value::Float(param) => **param = value.parse().map_err(|_| invalid())?,
+        }
+        Ok(())
+    }
+}
+
+impl Params {
+    fn values(&mut self) -> [(&'static str, Value<'_>); 38] {
+        [
+            (
+                "restart_fixed_interval",
+                Value::Integer(&mut self.restart_fixed_interval),
+            ),
+            (
+                "restart_geometric_interval",
+                Value::Integer(&mut self.restart_geometric_interval),
+            ),
+            (
+                "restart_geometric_factor",
+                Value::Float(&mut self.restart_geometric_factor),
+            ),
+            ("luby_unit", Value::Integer(&mut self.luby_unit)),
+            (
+                "reluctant_doubling_unit",
+                Value::Integer(&mut self.reluctant_doubling_unit),
+            ),
+            (
+                "lbd_ema_short_term_alpha",
+                Value::Float(&mut self.lbd_ema_short_term_alpha),
+            ),
+            (
+                "lbd_ema_long_term_alpha",
+                Value::Float(&mut self.lbd_ema_long_term_alpha),
+            ),
+            (
+                "assignment_ema_short_term_alpha",
+                Value::Float(&mut self.assignment_ema_short_term_alpha),
+            ),
+            (
+                "assignment_ema_long_term_alpha",
+                Value::Float(&mut self.assignment_ema_long_term_alpha),
+            ),
+            (
+                "margin_ratio_forcing_restart",
+                Value::Float(&mut self.margin_ratio_forcing_restart),
+            ),
+            (
+                "margin_ratio_blocking_restart",
+                Value::Float(&mut self.margin_ratio_blocking_restart),
+            ),
+            (
+                "restart_blocking_trail_percentile",
+                Value::Float(&mut self.restart_blocking_trail_percentile),
+            ),
+            (
+                "trail_size_window",
+                Value::Integer(&mut self.trail_size_window),
+            ),
+            (
+                "trail_size_percentile_interval",
+                Value::Integer(&mut self.trail_size_percentile_interval),
+            ),
+            (
+                "max_time_restart_blocked",
+                Value::Float(&mut self.max_time_restart_blocked),
+            ),
+            (
+                "max_conflicts_restart_blocked",
+                Value::Integer(&mut self.max_conflicts_restart_blocked),
+            ),
+            (
+                "restart_min_conflicts",
+                Value::Integer(&mut self.restart_min_conflicts),
+            ),
+            ("reduce_interval", Value::Integer(&mut self.reduce_interval)),
+            (
+                "reduce_interval_increment",
+                Value::Integer(&mut self.reduce_interval_increment),
+            ),
+            ("vsids_decay", Value::Float(&mut self.vsids_decay)),
+            (
+                "inprocessing_ratio",
+                Value::Float(&mut self.inprocessing_ratio),
+            ),
+            (
+                "resolutions_per_conflict",
+                Value::Float(&mut self.resolutions_per_conflict),
+            ),
+            (
+                "min_inprocessing_time",
+                Value::Float(&mut self.min_inprocessing_time),
+            ),
+            (
+                "min_inprocessing_resolutions",
+                Value::Float(&mut self.min_inprocessing_resolutions),
+            ),
+            (
+                "bve_clause_growth",
+                Value::Integer(&mut self.bve_clause_growth),
+            ),
+            (
+                "bve_max_resolvent_length",
+                Value::Integer(&mut self.bve_max_resolvent_length),
+            ),
+            (
+                "stabilization_interval",
+                Value::Integer(&mut self.stabilization_interval),
+            ),
+            (
+                "stabilization_factor",
+                Value::Integer(&mut self.stabilization_factor),
+            ),
+            ("walk_interval", Value::Integer(&mut self.walk_interval)),
+            ("walk_effort", Value::Float(&mut self.walk_effort)),
+            ("walk_min_flips", Value::Integer(&mut self.walk_min_flips)),
+            (
+                "heuristic_switch_interval",
+                Value::Integer(&mut self.heuristic_switch_interval),
+            ),
+            ("decay_interval", Value::Integer(&mut self.decay_interval)),
+            ("decay_factor", Value::Float(&mut self.decay_factor)),
+            (
+                "chronological_backtracking_threshold",
+                Value::Integer(&mut self.chronological_backtracking_threshold),
+            ),
+            (
+                "binary_minimization_max_len",
+                Value::Integer(&mut self.binary_minimization_max_len),
+            ),
+            ("max_xor_size", Value::Integer(&mut self.max_xor_size)),
+            (
+                "compaction_threshold",
+                Value::Float(&mut self.compaction_threshold),
+            ),
+        ]
+    }
+
+    /// The parameters by name, formatted so they are read back exactly
+    pub fn to_strings(&self) -> Vec<(&'static str, String)> {
+        self.clone()
+            .values()
+            .iter()
+            .map(|(name, value)| (*name, value.format()))
+            .collect()
+    }
+
+    pub fn to_toml(&self) -> String {
+        let lines = self
+            .to_strings()
+            .into_iter()
+            .map(|(name, value)| format!("{} = {}\n", name, value));
+        format!("# Parameters of utopia\n{}", lines.format(""))
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .to_strings()
+            .into_iter()
+            .map(|(name, value)| format!("  \"{}\": {}", name, value));
+        format!("{{\n{}\n}}\n", entries.format(",\n"))
+    }
+
+    /// Reads a flat JSON object if the input starts with `{`, otherwise flat TOML
+    pub fn parse(input: &str) -> error::Result<Self> {
+        match input.trim_start().starts_with('{') {
+            true => Self::from_json(input),
+            false => Self::from_toml(input),
+        }
+    }
+
+    pub fn from_toml(input: &str) -> error::Result<Self> {
+        let mut params = Params::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let syntax_error = |message: String| Error::Syntax {
+                line: i + 1,
+                message,
+            };
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| syntax_error(format!("Expected 'name = value', got '{}'", line)))?;
+            params
+                .set(name.trim(), value.trim())
+                .map_err(|err| syntax_error(format!("{}", err)))?;
+        }
+        params.validate()?;
+        Ok(params)
+    }
+
+    pub fn from_json(input: &str) -> error::Result<Self> {
+        let mut params = Params::default();
+        let entries = input
+            .trim()
+            .strip_prefix('{')
+            .and_then(|input| input.strip_suffix('}'))
+            .ok_or_else(|| Error::Parse(String::from("Expected a JSON object")))?;
+        for entry in entries.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (name, value) = entry
+                .split_once(':')
+                .ok_or_else(|| Error::Parse(format!("Expected \"name\": value, got {}", entry)))?;
+            params.set(name.trim().trim_matches('"'), value.trim())?;
+        }
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Reads a parameter file, see `Params::parse`
+    #[cfg(feature = "std")]
+    pub fn from_file(path: &str) -> error::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes JSON for files ending in `.json` and TOML otherwise
+    #[cfg(feature = "std")]
+    pub fn write(&self, path: &str) -> error::Result<()> {
+        let content = match path.ends_with(".json") {
+            true => self.to_json(),
+            false => self.to_toml(),
+        };
+        Ok(std::fs::write(path, content)?)
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> error::Result<()> {
+        let mut values = self.values();
+        let (_, param) = values
+            .iter_mut()
+            .find(|(param, _)| *param == name)
+            .ok_or_else(|| Error::Parse(format!("Unknown parameter {}", name)))?;
+        param.set(name, value)
+    }
+
+    /// Rejects values the search can't work with, e.g. after setting parameters directly
+    pub fn validate(&self) -> error::Result<()> {
+        let invalid = |name: &str, expected: &str| {
+            Err(Error::Parse(format!(
+                "Parameter {} must be {}",
+                name, expected
+            )))
+        };
+        for (name, alpha) in [
+            ("lbd_ema_short_term_alpha", self.lbd_ema_short_term_alpha),
+            ("lbd_ema_long_term_alpha", self.lbd_ema_long_term_alpha),
+            (
+                "assignment_ema_short_term_alpha",
+                self.assignment_ema_short_term_alpha,
+            ),
+            (
+                "assignment_ema_long_term_alpha",
+                self.assignment_ema_long_term_alpha,
+            ),
+        ] {
+            if !(0.0 < alpha && alpha < 1.0) {
+                return invalid(name, "between 0 and 1");
+            }
+        }
+        for (name, decay) in [
+            ("vsids_decay", self.vsids_decay),
+            ("decay_factor", self.decay_factor),
+        ] {
+            if !(0.0 < decay && decay <= 1.0) {
+                return invalid(name, "in (0, 1]");
+            }
+        }
+        if !(0.0..=1.0).contains(&self.restart_blocking_trail_percentile) {
+            return invalid("restart_blocking_trail_percentile", "between 0 and 1");
+        }
+        for (name, value) in [
+            ("restart_geometric_factor", self.restart_geometric_factor),
+            ("inprocessing_ratio", self.inprocessing_ratio),
+            ("resolutions_per_conflict", self.resolutions_per_conflict),
+            ("walk_effort", self.walk_effort),
+            ("max_time_restart_blocked", self.max_time_restart_blocked),
+            ("min_inprocessing_time", self.min_inprocessing_time),
+            (
+                "min_inprocessing_resolutions",
+                self.min_inprocessing_resolutions,
+            ),
+            ("compaction_threshold", self.compaction_threshold),
+        ] {
+            if !(value >= 0.0 && value.is_finite()) {
+                return invalid(name, "a non-negative number");
+            }
+        }
+        for (name, value) in [
+            ("restart_fixed_interval", self.restart_fixed_interval),
+            (
+                "restart_geometric_interval",
+                self.restart_geometric_interval,
+            ),
+            ("luby_unit", self.luby_unit),
+            ("reluctant_doubling_unit", self.reluctant_doubling_unit),
+            ("stabilization_interval", self.stabilization_interval),
+            ("heuristic_switch_interval", self.heuristic_switch_interval),
+            ("trail_size_window", self.trail_size_window),
+            (
+                "trail_size_percentile_interval",
+                self.trail_size_percentile_interval,
+            ),
+            ("stabilization_factor", self.stabilization_factor),
+            ("decay_interval", self.decay_interval),
+        ] {
+            if value == 0 {
+                return invalid(name, "positive");
+            }
+        }
+        if !(2..=32).contains(&self.max_xor_size) {
+            return invalid("max_xor_size", "between 2 and 32");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuned() -> Params {
+        Params {
+            luby_unit: 64,
+            lbd_ema_long_term_alpha: 3e-5,
+            vsids_decay: 0.8,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn params_round_trip() {
+        assert_eq!(Params::parse(&tuned().to_toml()).unwrap(), tuned());
+        assert_eq!(Params::parse(&tuned().to_json()).unwrap(), tuned());
+        assert!(tuned()
+            .to_toml()
+            .contains("\nlbd_ema_long_term_alpha = 3e-5\n"));
+    }
+
+    #[test]
+    fn partial_and_invalid_params() {
+        let params = Params::parse("luby_unit = 64 # doubled\n\nvsids_decay=0.8").unwrap();
+        assert_eq!(params.luby_unit, 64);
+        assert_eq!(params.vsids_decay, 0.8);
+        assert_eq!(params.walk_interval, Params::default().walk_interval);
+        let params = Params::parse("{\"luby_unit\": 64}").unwrap();
+        assert_eq!(params.luby_unit, 64);
+
+        for invalid in [
+            "luby_unit",
+            "luby_unit = 1.5",
+            "unknown = 1",
+            "vsids_decay = 1.5",
+            "luby_unit = 0",
+            "restart_fixed_interval = 0",
+            "{\"walk_effort\": -1}",
+            "decay_factor = 0",
+            "restart_blocking_trail_percentile = 1.5",
+            "max_xor_size = 1",
+            "max_xor_size = 33",
+            "max_time_restart_blocked = -1",
+        ] {
+            assert!(Params::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(matches!(
+            Params::parse("\nluby_unit = x"),
+            Err(Error::Syntax { line: 2, .. })
+        ));
+    }
+}
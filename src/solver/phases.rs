@@ -0,0 +1,212 @@
+use crate::cnf::{Literal, Var};
+use crate::solver::heuristic::Decision;
+use crate::solver::state::State;
+use crate::solver::trail::Trail;
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+#[cfg(not(feature = "std"))]
+use num_traits::float::FloatCore;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Value a decision variable is assigned first
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum PhaseMode {
+    #[cfg_attr(feature = "std", clap(name = "always-true"))]
+    AlwaysTrue,
+    #[cfg_attr(feature = "std", clap(name = "always-false"))]
+    AlwaysFalse,
+    /// Phase saving, the last value the variable was assigned
+    #[cfg_attr(feature = "std", clap(name = "saved"))]
+    Saved,
+    #[cfg_attr(feature = "std", clap(name = "random"))]
+    Random,
+    /// Phase saving, starting with the polarity of the higher Jeroslow-Wang score
+    #[cfg_attr(feature = "std", clap(name = "jeroslow-wang-init"))]
+    JeroslowWangInit,
+    /// The value in the longest conflict-free trail since the last restart, otherwise the saved
+    /// phase, see A. Biere and M. Fleury, “Chasing Target Phases,” in Workshop on the Pragmatics
+    /// of SAT, 2020.
+    #[cfg_attr(feature = "std", clap(name = "target"))]
+    Target,
+}
+
+#[derive(Debug, Clone)]
+pub struct PhaseSelector {
+    mode: PhaseMode,
+    rng: StdRng,
+    target_phases: Vec<Option<bool>>,
+    best_trail_len: usize,
+    /// Phases set by `Solver::set_preferred_phase`, which override all modes
+    preferred_phases: Vec<Option<bool>>,
+    /// The stable mode of the `Stabilizer` always uses target phases
+    stable: bool,
+}
+
+impl PhaseSelector {
+    pub fn init(mode: PhaseMode, state: &mut State) -> Self {
+        if mode == PhaseMode::JeroslowWangInit {
+            let scores = jeroslow_wang_scores(state);
+            for (phase, [negative, positive]) in state.var_phases.iter_mut().zip(scores) {
+                *phase = positive >= negative;
+            }
+        }
+
+        PhaseSelector {
+            mode,
+            rng: StdRng::seed_from_u64(0),
+            target_phases: vec![None; state.num_vars + 1],
+            best_trail_len: 0,
+            preferred_phases: vec![None; state.num_vars + 1],
+            stable: false,
+        }
+    }
+
+    fn active_mode(&self) -> PhaseMode {
+        match self.stable {
+            true => PhaseMode::Target,
+            false => self.mode,
+        }
+    }
+
+    pub fn set_stable(&mut self, stable: bool) {
+        self.stable = stable;
+    }
+
+    pub fn set_preferred_phase(&mut self, var_id: Var, phase: Option<bool>) {
+        self.preferred_phases[var_id] = phase;
+    }
+
+    /// The literal deciding the variable of the heuristic. A phase set by
+    /// `Solver::set_preferred_phase` comes first, then the hint of the heuristic and finally the
+    /// phase mode.
+    pub fn decide(&mut self, decision: Decision, state: &State) -> Literal {
+        let phase = match (self.preferred_phases[decision.var], decision.phase_hint) {
+            (Some(phase), _) | (None, Some(phase)) => phase,
+            (None, None) => self.phase(decision.var, state),
+        };
+        Literal::from_value(decision.var, phase)
+    }
+
+    pub fn phase(&mut self, var_id: Var, state: &State) -> bool {
+        if let Some(phase) = self.preferred_phases[var_id] {
+            return phase;
+        }
+        match self.active_mode() {
+            PhaseMode::AlwaysTrue => true,
+            PhaseMode::AlwaysFalse => false,
+            PhaseMode::Saved | PhaseMode::JeroslowWangInit => state.var_phases[var_id],
+            PhaseMode::Random => self.rng.gen_bool(0.5),
+            PhaseMode::Target => self.target_phases[var_id].unwrap_or(state.var_phases[var_id]),
+        }
+    }
+
+    /// Saves the assignments below the conflict level as target phases if they form the
+    /// longest conflict-free trail since the last restart
+    pub fn conflict(&mut self, trail: &Trail) {
+        let consistent_len = trail
+            .assignment_stack
+            .partition_point(|assignment| assignment.decision_level < trail.decision_level);
+        self.update_targets(trail, consistent_len);
+    }
+
+    /// Saves the whole trail as target phases if it is the longest since the last restart,
+    /// as restarts only happen without a conflict. Must be called before backtracking.
+    pub fn restart(&mut self, trail: &Trail) {
+        self.update_targets(trail, trail.assignment_stack.len());
+        self.best_trail_len = 0;
+    }
+
+    fn update_targets(&mut self, trail: &Trail, consistent_len: usize) {
+        if self.active_mode() != PhaseMode::Target {
+            return;
+        }
+        if consistent_len <= self.best_trail_len {
+            return;
+        }
+        self.best_trail_len = consistent_len;
+        for assignment in &trail.assignment_stack[..consistent_len] {
+            let (var_id, value) = assignment.literal.var_val();
+            self.target_phases[var_id] = Some(value);
+        }
+    }
+
+    /// Forgets the target phases, so the saved phases are used until the next conflict or restart
+    pub fn reset_targets(&mut self) {
+        self.target_phases.fill(None);
+        self.best_trail_len = 0;
+    }
+}
+
+/// Jeroslow-Wang scores of the negative and positive literal of every variable, the sum of
+/// `2^-|C|` over the clauses `C` containing the literal
+pub fn jeroslow_wang_scores(state: &State) -> Vec<[f64; 2]> {
+    let mut scores = vec![[0.0; 2]; state.num_vars + 1];
+    for clause_id in state.clause_database.iter() {
+        let clause = &state.clause_database[clause_id];
+        let weight = 0.5f64.powi(clause.literals.len() as i32);
+        for lit in &clause.literals {
+            scores[lit.var()][lit.positive() as usize] += weight;
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::Clause;
+    use crate::solver::heuristic::HeuristicType;
+    use crate::solver::trail::AssignmentReason;
+
+    #[test]
+    fn target_phases_from_the_longest_trail() {
+        let clauses = vec![Clause::from("1 2 3"), Clause::from("-1 -2 -3")];
+        let mut state = State::init(clauses, 3, false);
+        let mut heuristic = HeuristicType::VSIDS.create(&state);
+        let mut trail = Trail::new(3);
+        let mut phases = PhaseSelector::init(PhaseMode::Target, &mut state);
+        let decide = |lits: &[i32], trail: &mut Trail, state: &mut State| {
+            for lit in lits {
+                trail.assign(state, Literal::from(*lit), AssignmentReason::Heuristic);
+            }
+        };
+
+        decide(&[1, -2], &mut trail, &mut state);
+        phases.restart(&trail);
+        trail.restart(&mut state, heuristic.as_mut());
+        state.var_phases = vec![true; 4];
+        assert!(phases.phase(Var::new(1), &state));
+        assert!(!phases.phase(Var::new(2), &state));
+        assert!(phases.phase(Var::new(3), &state));
+
+        // the conflict level isn't consistent, so only the decision of 3 is a target
+        decide(&[-3, -1], &mut trail, &mut state);
+        phases.conflict(&trail);
+        assert!(!phases.phase(Var::new(3), &state));
+        assert!(phases.phase(Var::new(1), &state));
+
+        phases.reset_targets();
+        assert!(phases.phase(Var::new(2), &state));
+    }
+
+    #[test]
+    fn preferred_phases_before_hints_before_modes() {
+        let mut state = State::init(vec![Clause::from("1 2 3")], 3, false);
+        let mut phases = PhaseSelector::init(PhaseMode::AlwaysFalse, &mut state);
+        phases.set_preferred_phase(Var::new(1), Some(false));
+        let hinted = |var_id: usize| Decision {
+            var: Var::new(var_id),
+            phase_hint: Some(true),
+        };
+
+        assert_eq!(phases.decide(hinted(1), &state), Literal::new(-1));
+        assert_eq!(phases.decide(hinted(2), &state), Literal::new(2));
+        assert_eq!(
+            phases.decide(Decision::new(Var::new(3)), &state),
+            Literal::new(-3)
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::cnf::{Clause, Literal, Var};
+use crate::solver::config::Config;
+use crate::solver::params::Params;
+use crate::solver::restarts::RestartPolicy;
+use crate::solver::xor::find_xors;
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+use core::fmt::{Display, Formatter};
+use hashbrown::HashSet;
+use itertools::Itertools;
+
+/// Share of the clauses encoding XOR constraints for the XOR preset
+const MIN_XOR_CLAUSES: f64 = 0.1;
+
+/// Share of the variables in at-most-one constraints for the cardinality preset
+const MIN_AT_MOST_ONE_VARS: f64 = 0.5;
+
+/// Smallest at-most-one constraint that is counted, pairs are just binary clauses
+const MIN_AT_MOST_ONE_SIZE: usize = 3;
+
+/// Whether the configuration is taken as given or adapted to the instance
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum ConfigMode {
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "default"))]
+    Default,
+    /// Adapt the configuration to instances with many XOR or at-most-one constraints and to
+    /// uniform random k-SAT, recognized by their clauses
+    #[cfg_attr(feature = "std", clap(name = "auto"))]
+    Auto,
+}
+
+/// Features of an instance that are cheap to compute before the search
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceFeatures {
+    pub num_vars: usize,
+    pub num_clauses: usize,
+    pub clause_var_ratio: f64,
+    pub mean_clause_len: f64,
+    pub min_clause_len: usize,
+    pub max_clause_len: usize,
+    /// Share of the clauses with two and three literals
+    pub binary_fraction: f64,
+    pub ternary_fraction: f64,
+    /// Clauses encoding the XOR constraints over at least three variables found by `find_xors`,
+    /// binary ones are equivalences, which are substituted anyway
+    pub num_xor_clauses: usize,
+    /// Disjoint at-most-one constraints of at least three literals, which are pairwise encoded
+    /// by binary clauses, found greedily
+    pub num_at_most_one: usize,
+    pub num_at_most_one_vars: usize,
+}
+
+impl InstanceFeatures {
+    pub fn compute(clauses: &[Clause], num_vars: usize) -> Self {
+        let num_clauses = clauses.len();
+        let lengths = clauses.iter().map(|clause| clause.literals.len());
+        let count_len = |len: usize| {
+            clauses
+                .iter()
+                .filter(|clause| clause.literals.len() == len)
+                .count()
+        };
+        let fraction = |count: usize| count as f64 / num_clauses.max(1) as f64;
+        let num_xor_clauses = find_xors(clauses, Params::default().max_xor_size)
+            .iter()
+            .filter(|xor| xor.vars.len() > 2)
+            .map(|xor| 1 << (xor.vars.len() - 1))
+            .sum();
+        let at_most_one = find_at_most_one(clauses, num_vars);
+
+        InstanceFeatures {
+            num_vars,
+            num_clauses,
+            clause_var_ratio: num_clauses as f64 / num_vars.max(1) as f64,
+            mean_clause_len: fraction(lengths.clone().sum()),
+            min_clause_len: lengths.clone().min().unwrap_or(0),
+            max_clause_len: lengths.max().unwrap_or(0),
+            binary_fraction: fraction(count_len(2)),
+            ternary_fraction: fraction(count_len(3)),
+            num_xor_clauses,
+            num_at_most_one: at_most_one.len(),
+            num_at_most_one_vars: at_most_one.iter().map(|group| group.len()).sum(),
+        }
+    }
+}
+
+/// Disjoint groups of literals of which at most one can be true, as every pair is excluded by a
+/// binary clause. Each group starts with the unused literal with the most such pairs and is
+/// extended by its partners that exclude all literals of the group.
+fn find_at_most_one(clauses: &[Clause], num_vars: usize) -> Vec<Vec<Literal>> {
+    // the clause (a ∨ b) is the at-most-one constraint over ¬a and ¬b
+    let mut partners = vec![vec![]; 2 * (num_vars + 1)];
+    let mut pairs = HashSet::new();
+    for clause in clauses.iter().filter(|clause| clause.literals.len() == 2) {
+        let [a, b] = [-clause.literals[0], -clause.literals[1]];
+        if a.var() != b.var() && pairs.insert((a.min(b), a.max(b))) {
+            partners[a.code()].push(b);
+            partners[b.code()].push(a);
+        }
+    }
+
+    let mut used = vec![false; 2 * (num_vars + 1)];
+    let mut groups = vec![];
+    let candidates = Var::range(num_vars)
+        .flat_map(|var| [true, false].map(|value| Literal::from_value(var, value)))
+        .filter(|lit| partners[lit.code()].len() + 1 >= MIN_AT_MOST_ONE_SIZE)
+        .sorted_by_key(|lit| core::cmp::Reverse(partners[lit.code()].len()))
+        .collect_vec();
+    for lit in candidates {
+        if used[lit.code()] {
+            continue;
+        }
+        let mut group = vec![lit];
+        for &partner in &partners[lit.code()] {
+            let excludes_group = group
+                .iter()
+                .all(|&other| pairs.contains(&(partner.min(other), partner.max(other))));
+            if !used[partner.code()] && excludes_group {
+                group.push(partner);
+            }
+        }
+        if group.len() >= MIN_AT_MOST_ONE_SIZE {
+            for lit in &group {
+                used[lit.code()] = true;
+            }
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Settings for a class of instances, applied on top of the configuration by `--config auto`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Preset {
+    /// Structured instances keep the configuration
+    Default,
+    /// Many XOR constraints, e.g. parity learning and cryptographic instances, where local search
+    /// doesn't help. Gaussian elimination is too slow on the short XORs of the usual encodings.
+    Xor,
+    /// Many at-most-one constraints, e.g. pigeonhole, scheduling and coloring, where the Luby
+    /// restarts leave the search more time between restarts
+    Cardinality,
+    /// Uniform random k-SAT, where elimination only adds resolvents and restarts follow Luby
+    Random,
+}
+
+impl Preset {
+    pub fn select(features: &InstanceFeatures) -> Self {
+        let xor_fraction = features.num_xor_clauses as f64 / features.num_clauses.max(1) as f64;
+        let at_most_one_fraction =
+            features.num_at_most_one_vars as f64 / features.num_vars.max(1) as f64;
+        if xor_fraction >= MIN_XOR_CLAUSES {
+            Preset::Xor
+        } else if at_most_one_fraction >= MIN_AT_MOST_ONE_VARS {
+            Preset::Cardinality
+        } else if features.min_clause_len >= 3
+            && features.min_clause_len == features.max_clause_len
+            && features.num_at_most_one == 0
+        {
+            Preset::Random
+        } else {
+            Preset::Default
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        match self {
+            Preset::Default => {}
+            Preset::Xor => config.walk = false,
+            Preset::Cardinality => config.restart_policy = RestartPolicy::Luby,
+            Preset::Random => {
+                config.restart_policy = RestartPolicy::Luby;
+                config.inprocessing = false;
+            }
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Preset::Default => "default",
+            Preset::Xor => "xor",
+            Preset::Cardinality => "cardinality",
+            Preset::Random => "random",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::tester::random_cnf;
+
+    fn preset_of(file: &str) -> Preset {
+        let dimacs = clauses_from_dimacs_file(file).unwrap();
+        Preset::select(&InstanceFeatures::compute(&dimacs.clauses, dimacs.num_vars))
+    }
+
+    #[test]
+    fn features_of_random_formulas() {
+        let features = InstanceFeatures::compute(&random_cnf(100, 4.0, 3, 0), 100);
+        assert_eq!(features.num_clauses, 400);
+        assert_eq!(features.clause_var_ratio, 4.0);
+        assert_eq!(features.mean_clause_len, 3.0);
+        assert_eq!(features.ternary_fraction, 1.0);
+        assert_eq!(Preset::select(&features), Preset::Random);
+    }
+
+    #[test]
+    fn at_most_one_groups() {
+        let clauses = ["-1 -2", "-1 -3", "-2 -3", "-3 -4", "1 2 3 4"].map(Clause::from);
+        let groups = find_at_most_one(&clauses, 4);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]
+                .iter()
+                .map(|lit| lit.var().index())
+                .sorted()
+                .collect_vec(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn presets_of_lecture_files() {
+        assert_eq!(
+            preset_of("testfiles/lecture_testfiles/unsat/hole6.cnf"),
+            Preset::Cardinality
+        );
+        assert_eq!(
+            preset_of("testfiles/lecture_testfiles/sat/par16-1.cnf"),
+            Preset::Xor
+        );
+        assert_eq!(
+            preset_of("testfiles/lecture_testfiles/sat/uf50-01.cnf"),
+            Preset::Random
+        );
+        assert_eq!(
+            preset_of("testfiles/lecture_testfiles/unsat/ssa0432-003.cnf"),
+            Preset::Default
+        );
+    }
+}
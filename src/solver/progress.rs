@@ -1,33 +1,103 @@
+//! The progress is only printed with `std`
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+use crate::solver::clock::Instant;
 use crate::solver::statistics::StateStatistics;
+#[cfg(feature = "tui")]
+use crate::solver::tui::LiveView;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
 use clap::ValueEnum;
+#[cfg(feature = "std")]
 use colored::{ColoredString, Colorize};
+use core::str::FromStr;
 
+/// Progress is printed to stderr, so it never mixes with the result on stdout
 pub struct Progress {
-    printing_interval: Option<std::time::Duration>,
+    format: ProgressFormat,
+    printing_interval: Option<core::time::Duration>,
+    /// Conflicts between two prints in deterministic mode, replacing the time interval
+    conflict_interval: Option<usize>,
+    conflicts_of_last_print: usize,
     header_is_printed: bool,
-    time_of_last_print: std::time::Instant,
+    time_of_last_print: Instant,
     last_num_conflicts: usize,
     last_num_total_assignments: usize,
     last_num_cur_assignments: usize,
     last_assigned_vars_percent: usize,
     last_num_clauses: usize,
+    last_num_learned_clauses: usize,
+    last_num_propagations: usize,
+    last_propagation_rate: usize,
     last_num_restarts: usize,
     last_inprocessor_total_time: u128,
     last_inprocessor_resolved: usize,
+    #[cfg(feature = "tui")]
+    live_view: Option<LiveView>,
 }
 
-#[derive(ValueEnum, Clone, PartialEq, Eq)]
+/// Parsed from `short` (or `fast`), `medium`, `long` (or `slow`), `conflicts:<n>` and `off` (or
+/// `none`). The first three print every 1, 5 or 30 seconds, or every 1000, 10000 or 100000
+/// conflicts in deterministic mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProgressPrintingInterval {
-    #[clap(name = "short")]
     Short,
-    #[clap(name = "medium")]
     Medium,
-    #[clap(name = "long")]
     Long,
-    #[clap(name = "off")]
+    /// Every given number of conflicts, also without deterministic mode
+    Conflicts(usize),
     Off,
 }
 
+impl FromStr for ProgressPrintingInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "short" | "fast" => Ok(ProgressPrintingInterval::Short),
+            "medium" => Ok(ProgressPrintingInterval::Medium),
+            "long" | "slow" => Ok(ProgressPrintingInterval::Long),
+            "off" | "none" => Ok(ProgressPrintingInterval::Off),
+            _ => match s.strip_prefix("conflicts:").map(str::parse) {
+                Some(Ok(conflicts)) if conflicts > 0 => {
+                    Ok(ProgressPrintingInterval::Conflicts(conflicts))
+                }
+                _ => Err(format!(
+                    "invalid interval '{}', expected short, medium, long, conflicts:<n> or off",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum ProgressFormat {
+    /// Table with box-drawing characters, colored on a terminal unless `--no-color` is given
+    #[default]
+    #[cfg_attr(feature = "std", clap(name = "table"))]
+    Table,
+    /// One JSON object per line for scripts
+    #[cfg_attr(feature = "std", clap(name = "json"))]
+    Json,
+    /// Statistics, the LBD histogram and recent events redrawn in place, for long runs in a
+    /// terminal
+    #[cfg(feature = "tui")]
+    #[clap(name = "tui")]
+    Tui,
+    #[cfg_attr(feature = "std", clap(name = "none"))]
+    None,
+}
+
+/// Events of the search shown by the live view
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    Restart,
+    Reduction,
+}
+
 const TIME: usize = 5;
 const CONFLICTS_TOTAL: usize = 10;
 const RESTARTS_TOTAL: usize = 8;
@@ -35,88 +105,167 @@ const ASSIGNMENTS_TOTAL: usize = 13;
 const ASSIGNED_VARS_NUM: usize = 11;
 const ASSIGNED_VARS_PERC: usize = 8;
 const CLAUSES_CUR: usize = 12;
+const LEARNED_CUR: usize = 10;
+const PROPAGATION_RATE: usize = 12;
 const INPROCESSOR_RESOLVED: usize = 11;
 const INPROCESSOR_TIME: usize = 10;
 
+/// Time between two frames of the live view, which replaces the printing interval
+#[cfg(feature = "tui")]
+const LIVE_VIEW_INTERVAL: core::time::Duration = core::time::Duration::from_millis(250);
+
 impl Progress {
-    pub fn init(progress_printing_interval: &ProgressPrintingInterval) -> Self {
-        let printing_interval = match progress_printing_interval {
-            ProgressPrintingInterval::Short => Some(std::time::Duration::from_secs(1)),
-            ProgressPrintingInterval::Medium => Some(std::time::Duration::from_secs(5)),
-            ProgressPrintingInterval::Long => Some(std::time::Duration::from_secs(30)),
-            ProgressPrintingInterval::Off => None,
+    pub fn init(
+        progress_printing_interval: &ProgressPrintingInterval,
+        format: ProgressFormat,
+        deterministic: bool,
+    ) -> Self {
+        let (printing_interval, conflict_interval) = match progress_printing_interval {
+            ProgressPrintingInterval::Short => (1, 1_000),
+            ProgressPrintingInterval::Medium => (5, 10_000),
+            ProgressPrintingInterval::Long => (30, 100_000),
+            ProgressPrintingInterval::Conflicts(conflicts) => (0, *conflicts),
+            ProgressPrintingInterval::Off => (0, 0),
         };
+        let is_on = *progress_printing_interval != ProgressPrintingInterval::Off
+            && format != ProgressFormat::None;
+        let by_conflicts = deterministic
+            || matches!(
+                progress_printing_interval,
+                ProgressPrintingInterval::Conflicts(_)
+            );
+        #[allow(unused_mut)]
+        let mut printing_interval = core::time::Duration::from_secs(printing_interval);
+        #[cfg(feature = "tui")]
+        if format == ProgressFormat::Tui {
+            printing_interval = LIVE_VIEW_INTERVAL;
+        }
 
         Progress {
-            printing_interval,
-            time_of_last_print: std::time::Instant::now(),
+            format,
+            printing_interval: (is_on && !by_conflicts).then_some(printing_interval),
+            conflict_interval: (is_on && by_conflicts).then_some(conflict_interval),
+            conflicts_of_last_print: 0,
+            time_of_last_print: Instant::now(),
             header_is_printed: false,
             last_num_conflicts: 0,
             last_num_total_assignments: 0,
             last_num_cur_assignments: 0,
             last_assigned_vars_percent: 0,
             last_num_clauses: 0,
+            last_num_learned_clauses: 0,
+            last_num_propagations: 0,
+            last_propagation_rate: 0,
             last_num_restarts: 0,
             last_inprocessor_total_time: 0,
             last_inprocessor_resolved: 0,
+            #[cfg(feature = "tui")]
+            live_view: (is_on && format == ProgressFormat::Tui).then(LiveView::new),
         }
     }
 
+    /// Shows a restart or a database reduction in the live view, the event is only built if the
+    /// view is shown
+    #[cfg_attr(not(feature = "tui"), allow(unused_variables))]
+    pub fn event(&mut self, kind: EventKind, event: impl FnOnce() -> String) {
+        #[cfg(feature = "tui")]
+        if let Some(live_view) = &mut self.live_view {
+            live_view.event(kind, event());
+        }
+    }
+
+    #[cfg(feature = "std")]
     pub fn print_progress_if_necessary(
         &mut self,
         state_statistics: &StateStatistics,
         current_num_assignments: usize,
         current_num_clauses: usize,
-        resolved_vars: usize,
-        inprocessor_time: u128,
     ) {
-        if let Some(interval) = self.printing_interval {
-            if self.time_of_last_print.elapsed() > interval {
+        let num_conflicts = state_statistics.num_conflicts;
+        let is_due = match (self.printing_interval, self.conflict_interval) {
+            (Some(interval), _) => self.time_of_last_print.elapsed() > interval,
+            (_, Some(interval)) => num_conflicts >= self.conflicts_of_last_print + interval,
+            (None, None) => false,
+        };
+        if is_due {
+            #[cfg(feature = "tui")]
+            if let Some(live_view) = &mut self.live_view {
+                live_view.draw(
+                    state_statistics,
+                    current_num_assignments,
+                    current_num_clauses,
+                );
+                self.time_of_last_print = Instant::now();
+                self.conflicts_of_last_print = num_conflicts;
+                return;
+            }
+            if self.format == ProgressFormat::Json {
+                self.print_json(
+                    state_statistics,
+                    current_num_assignments,
+                    current_num_clauses,
+                );
+            } else {
                 if !self.header_is_printed {
                     Self::print_header();
                     self.header_is_printed = true;
                 }
-
                 self.print_progress(
                     state_statistics,
                     current_num_assignments,
                     current_num_clauses,
-                    resolved_vars,
-                    inprocessor_time,
                 );
-                self.time_of_last_print = std::time::Instant::now();
             }
+            self.time_of_last_print = Instant::now();
+            self.conflicts_of_last_print = num_conflicts;
         }
     }
 
+    #[cfg(feature = "std")]
     fn print_header() {
-        println!(
+        eprintln!(
             "c ┌─\
             {:─<TIME$}─┬─\
             {:─<CONFLICTS_TOTAL$}─┬─\
             {:─<RESTARTS_TOTAL$}─┬─\
             {:─<ASSIGNMENTS_TOTAL$}─{:─<ASSIGNED_VARS_NUM$}─{:─<ASSIGNED_VARS_PERC$}─┬─\
             {:─<CLAUSES_CUR$}─┬─\
+            {:─<LEARNED_CUR$}─┬─\
+            {:─<PROPAGATION_RATE$}─┬─\
             {:─<INPROCESSOR_RESOLVED$}─{:─<INPROCESSOR_TIME$}─┐",
-            "", "", "", "", "", "", "", "", ""
+            "", "", "", "", "", "", "", "", "", "", ""
         );
-        println!(
+        eprintln!(
             "c │ \
             {:<TIME$} │ \
             {:<CONFLICTS_TOTAL$} │ \
             {:<RESTARTS_TOTAL$} │ \
             {:<ASSIGNMENTS_TOTAL$} {:<ASSIGNED_VARS_NUM$} {:<ASSIGNED_VARS_PERC$} │ \
             {:<CLAUSES_CUR$} │ \
+            {:<LEARNED_CUR$} │ \
+            {:<PROPAGATION_RATE$} │ \
             {:<INPROCESSOR_RESOLVED$} {:<INPROCESSOR_TIME$} │",
-            "Time", "Conflicts", "Restarts", "Assignments", "", "", "Clauses", "Inprocessor", ""
+            "Time",
+            "Conflicts",
+            "Restarts",
+            "Assignments",
+            "",
+            "",
+            "Clauses",
+            "Learned",
+            "Props/sec.",
+            "Inprocessor",
+            ""
         );
-        println!(
+        eprintln!(
             "c │ \
             {:<TIME$} │ \
             {:>CONFLICTS_TOTAL$} │ \
             {:>RESTARTS_TOTAL$} │ \
             {:>ASSIGNMENTS_TOTAL$} {:>ASSIGNED_VARS_NUM$} {:<ASSIGNED_VARS_PERC$} │ \
             {:<CLAUSES_CUR$} │ \
+            {:<LEARNED_CUR$} │ \
+            {:<PROPAGATION_RATE$} │ \
             {:>INPROCESSOR_RESOLVED$} {:>INPROCESSOR_TIME$} │",
             "",
             "",
@@ -125,19 +274,22 @@ impl Progress {
             "current".truecolor(100, 100, 100),
             "vars".truecolor(100, 100, 100),
             "",
+            "",
+            "",
             "resolved".truecolor(100, 100, 100),
             "time".truecolor(100, 100, 100)
         );
     }
 
+    #[cfg(feature = "std")]
     fn print_progress(
         &mut self,
         state_statistics: &StateStatistics,
         current_num_assignments: usize,
         current_num_clauses: usize,
-        resolved_vars: usize,
-        inprocessor_time_millis: u128,
     ) {
+        let resolved_vars = state_statistics.num_eliminated_vars;
+        let inprocessor_time_millis = state_statistics.inprocessing_time.as_millis();
         if state_statistics.num_vars == 0 {
             return;
         }
@@ -145,24 +297,29 @@ impl Progress {
         let assigned_vars_percent =
             (current_num_assignments as f64 / state_statistics.num_vars as f64 * 100.0).round()
                 as usize;
+        let propagation_rate = self.propagation_rate(state_statistics);
 
-        println!(
+        eprintln!(
             "c │┈\
             {:┈<TIME$}┈│┈\
             {:┈<CONFLICTS_TOTAL$}┈│┈\
             {:┈<RESTARTS_TOTAL$}┈│┈\
             {:┈<ASSIGNMENTS_TOTAL$}┈{:┈>ASSIGNED_VARS_NUM$}┈{:┈>ASSIGNED_VARS_PERC$}┈│┈\
             {:┈<CLAUSES_CUR$}┈│┈\
+            {:┈<LEARNED_CUR$}┈│┈\
+            {:┈<PROPAGATION_RATE$}┈│┈\
             {:┈<INPROCESSOR_RESOLVED$}┈{:┈<INPROCESSOR_TIME$}┈│",
-            "", "", "", "", "", "", "", "", ""
+            "", "", "", "", "", "", "", "", "", "", ""
         );
-        println!(
+        eprintln!(
             "c │ \
             {:<TIME$} │ \
             {:>CONFLICTS_TOTAL$} │ \
             {:>RESTARTS_TOTAL$} │ \
             {:>ASSIGNMENTS_TOTAL$} {:>ASSIGNED_VARS_NUM$} {:<ASSIGNED_VARS_PERC$} │ \
             {:>CLAUSES_CUR$} │ \
+            {:>LEARNED_CUR$} │ \
+            {:>PROPAGATION_RATE$} │ \
             {:>INPROCESSOR_RESOLVED$} {:>INPROCESSOR_TIME$} │",
             state_statistics.start_time.elapsed().as_secs(),
             state_statistics.num_conflicts,
@@ -171,16 +328,20 @@ impl Progress {
             current_num_assignments,
             format!("({}%)", assigned_vars_percent),
             current_num_clauses,
+            state_statistics.num_learned_clauses,
+            propagation_rate,
             resolved_vars,
             format!("{}ms", inprocessor_time_millis),
         );
-        println!(
+        eprintln!(
             "c │ \
             {:<TIME$} │ \
             {:>CONFLICTS_TOTAL$} │ \
             {:>RESTARTS_TOTAL$} │ \
             {:>ASSIGNMENTS_TOTAL$} {:>ASSIGNED_VARS_NUM$} {:<ASSIGNED_VARS_PERC$} │ \
             {:>CLAUSES_CUR$} │ \
+            {:>LEARNED_CUR$} │ \
+            {:>PROPAGATION_RATE$} │ \
             {:>INPROCESSOR_RESOLVED$} {:>INPROCESSOR_TIME$} │",
             "sec.".truecolor(100, 100, 100),
             Self::print_delta(
@@ -225,6 +386,20 @@ impl Progress {
                 "",
                 "",
             ),
+            Self::print_delta(
+                self.last_num_learned_clauses as i32,
+                state_statistics.num_learned_clauses as i32,
+                true,
+                "",
+                "",
+            ),
+            Self::print_delta(
+                self.last_propagation_rate as i32,
+                propagation_rate as i32,
+                true,
+                "",
+                "",
+            ),
             Self::print_delta(
                 self.last_inprocessor_resolved as i32,
                 resolved_vars as i32,
@@ -247,25 +422,80 @@ impl Progress {
         self.last_num_cur_assignments = current_num_assignments;
         self.last_assigned_vars_percent = assigned_vars_percent;
         self.last_num_clauses = current_num_clauses;
+        self.last_num_learned_clauses = state_statistics.num_learned_clauses;
+        self.last_num_propagations = state_statistics.num_propagations;
+        self.last_propagation_rate = propagation_rate;
         self.last_inprocessor_resolved = resolved_vars;
         self.last_inprocessor_total_time = inprocessor_time_millis;
     }
 
+    /// Propagations per second since the last print
+    #[cfg(feature = "std")]
+    fn propagation_rate(&self, state_statistics: &StateStatistics) -> usize {
+        ((state_statistics.num_propagations - self.last_num_propagations) as f64
+            / self.time_of_last_print.elapsed().as_secs_f64()) as usize
+    }
+
+    #[cfg(feature = "std")]
+    fn print_json(
+        &mut self,
+        state_statistics: &StateStatistics,
+        current_num_assignments: usize,
+        current_num_clauses: usize,
+    ) {
+        let resolved_vars = state_statistics.num_eliminated_vars;
+        let inprocessor_time_millis = state_statistics.inprocessing_time.as_millis();
+        let propagation_rate = self.propagation_rate(state_statistics);
+        eprintln!(
+            "{{\"time\": {:.3}, \"conflicts\": {}, \"restarts\": {}, \"assignments\": {}, \
+            \"current_assignments\": {}, \"clauses\": {}, \"learned_clauses\": {}, \
+            \"propagations_per_second\": {}, \"resolved_vars\": {}, \"inprocessing_ms\": {}}}",
+            state_statistics.start_time.elapsed().as_secs_f64(),
+            state_statistics.num_conflicts,
+            state_statistics.num_restarts,
+            state_statistics.num_assignments,
+            current_num_assignments,
+            current_num_clauses,
+            state_statistics.num_learned_clauses,
+            propagation_rate,
+            resolved_vars,
+            inprocessor_time_millis,
+        );
+        self.last_num_propagations = state_statistics.num_propagations;
+    }
+
+    /// Without `std` there is nowhere to print to
+    #[cfg(not(feature = "std"))]
+    pub fn print_progress_if_necessary(
+        &mut self,
+        _state_statistics: &StateStatistics,
+        _current_num_assignments: usize,
+        _current_num_clauses: usize,
+    ) {
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn close_table(&self) {}
+
+    #[cfg(feature = "std")]
     pub fn close_table(&self) {
-        if self.printing_interval.is_some() && self.header_is_printed {
-            println!(
+        if self.header_is_printed {
+            eprintln!(
                 "c └─\
             {:─<TIME$}─┴─\
             {:─<CONFLICTS_TOTAL$}─┴─\
             {:─<RESTARTS_TOTAL$}─┴─\
             {:─<ASSIGNMENTS_TOTAL$}─{:─<ASSIGNED_VARS_NUM$}─{:─<ASSIGNED_VARS_PERC$}─┴─\
             {:─<CLAUSES_CUR$}─┴─\
+            {:─<LEARNED_CUR$}─┴─\
+            {:─<PROPAGATION_RATE$}─┴─\
             {:─<INPROCESSOR_RESOLVED$}─{:─<INPROCESSOR_TIME$}─┘",
-                "", "", "", "", "", "", "", "", ""
+                "", "", "", "", "", "", "", "", "", "", ""
             );
         }
     }
 
+    #[cfg(feature = "std")]
     fn print_delta(
         old_value: i32,
         new_value: i32,
@@ -293,3 +523,24 @@ impl Progress {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_printing_interval() {
+        let parse = |s: &str| s.parse::<ProgressPrintingInterval>();
+        assert_eq!(parse("fast"), Ok(ProgressPrintingInterval::Short));
+        assert_eq!(parse("medium"), Ok(ProgressPrintingInterval::Medium));
+        assert_eq!(parse("slow"), Ok(ProgressPrintingInterval::Long));
+        assert_eq!(parse("none"), Ok(ProgressPrintingInterval::Off));
+        assert_eq!(
+            parse("conflicts:500"),
+            Ok(ProgressPrintingInterval::Conflicts(500))
+        );
+        assert!(parse("conflicts:0").is_err());
+        assert!(parse("conflicts:").is_err());
+        assert!(parse("sometimes").is_err());
+    }
+}
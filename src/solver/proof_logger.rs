@@ -1,4 +1,11 @@
-use crate::cnf::Clause;
+use crate::cnf::{Clause, ClauseId, Literal, Var};
+#[cfg(feature = "std")]
+use crate::error::{Error, Result};
+use crate::solver::resolution::{ProofClause, ResolutionLemma, ResolutionProof};
+use crate::solver::trail::{AssignmentReason, ConstraintRef, Trail};
+use alloc::{vec, vec::Vec};
+use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "std")]
 use std::io::{BufWriter, Write};
 
 #[derive(Debug, Clone)]
@@ -10,6 +17,61 @@ pub enum ProofStep {
 pub struct ProofLogger {
     pub active: bool,
     pub proof: Vec<ProofStep>,
+    /// Track the resolution chains of the lemmas, see `enable_tracking`
+    tracking: bool,
+    /// Remove the lemmas the empty clause doesn't depend on at the end, see `trim`
+    trimming: bool,
+    /// Origin of every step of the proof while tracking
+    origins: Vec<StepOrigin>,
+    /// Clause of the formula or lemma step with the clause id, `None` if it can't be traced
+    /// back
+    clause_refs: Vec<Option<ProofClause>>,
+    /// Step of the unit lemma of every variable fixed at level 0, `None` if it wasn't logged
+    unit_steps: Vec<Option<usize>>,
+    /// Assignments at level 0 on the trail whose unit lemmas were logged
+    num_logged_units: usize,
+    /// Chain of the next lemma, see `set_chain`
+    next_chain: Option<Option<Vec<ProofClause>>>,
+}
+
+/// Clauses a lemma is resolved from in the order of the resolutions, followed by the
+/// variables fixed at level 0 whose unit lemmas resolve away the remaining literals
+#[derive(Debug, Clone, Default)]
+pub struct Antecedents {
+    pub clauses: Vec<ClauseId>,
+    pub fixed_vars: Vec<Var>,
+    /// A reason that isn't a clause was used, e.g. an XOR constraint, so the lemma can't be
+    /// traced back
+    pub incomplete: bool,
+}
+
+impl Antecedents {
+    /// Adds the reason with its literals, the ones fixed at level 0 are resolved away at the end
+    pub fn add(&mut self, reason: ConstraintRef, literals: &[Literal], trail: &Trail) {
+        match reason {
+            ConstraintRef::Clause(clause_id) => self.clauses.push(clause_id),
+            _ => self.incomplete = true,
+        }
+        self.fixed_vars.extend(
+            literals
+                .iter()
+                .map(|lit| lit.var())
+                .filter(|var_id| trail.var_decision_level[*var_id] == 0),
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+enum StepOrigin {
+    /// Lemma resolved from the chain of clauses
+    Derived(Vec<ProofClause>),
+    /// Literal fixed at level 0, resolved from the chain of its reason and unit lemmas
+    Unit(Vec<ProofClause>),
+    /// Lemma with unknown antecedents, e.g. from inprocessing, which may depend on every clause
+    /// present at that point
+    Unknown,
+    /// Deletion of a lemma or a clause of the formula, `None` if it isn't tracked
+    Deletion(Option<ProofClause>),
 }
 
 // TODO: the file should already be written during the search to avoid the log file
@@ -20,27 +82,362 @@ impl ProofLogger {
         ProofLogger {
             proof: vec![],
             active,
+            ..Default::default()
         }
     }
 
+    /// Tracks the resolution chain of every lemma from now on. The clauses of the database are
+    /// the ones of the formula with the given indices.
+    pub fn enable_tracking(&mut self, formula_indices: impl IntoIterator<Item = usize>) {
+        self.tracking = self.active;
+        self.clause_refs = formula_indices
+            .into_iter()
+            .map(|index| Some(ProofClause::Original(index)))
+            .collect();
+        self.origins = self
+            .proof
+            .iter()
+            .map(|step| match step {
+                ProofStep::AddClause(_) => StepOrigin::Unknown,
+                ProofStep::DeleteClause(_) => StepOrigin::Deletion(None),
+            })
+            .collect();
+    }
+
+    /// Trims the proof at the end, see `trim`. Requires tracking.
+    pub fn enable_trimming(&mut self) {
+        self.trimming = self.tracking;
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
     pub fn log(&mut self, clause: &Clause) {
         if !self.active {
             return;
         }
 
+        if self.tracking {
+            let origin = match self.next_chain.take().flatten() {
+                Some(chain) => StepOrigin::Derived(chain),
+                None => StepOrigin::Unknown,
+            };
+            self.origins.push(origin);
+        }
         self.proof.push(ProofStep::AddClause(clause.clone()));
     }
 
+    /// Logs the lemma that the clause id refers to from now on
+    pub fn log_clause(&mut self, clause_id: ClauseId, clause: &Clause) {
+        self.log(clause);
+        if self.tracking {
+            self.set_clause_ref(clause_id, ProofClause::Lemma(self.proof.len() - 1));
+        }
+    }
+
+    fn set_clause_ref(&mut self, clause_id: ClauseId, clause: ProofClause) {
+        if self.clause_refs.len() <= clause_id {
+            self.clause_refs.resize(clause_id + 1, None);
+        }
+        self.clause_refs[clause_id] = Some(clause);
+    }
+
+    /// Applies new clause ids after `ClauseDatabase::compact`, the proof itself doesn't refer to
+    /// clause ids
+    pub fn renumber(&mut self, mapping: &[Option<ClauseId>]) {
+        if self.clause_refs.is_empty() {
+            return;
+        }
+        let mut clause_refs = vec![None; mapping.iter().flatten().count()];
+        for (old, new) in mapping.iter().enumerate() {
+            if let Some(new) = new {
+                clause_refs[*new] = self.clause_refs.get(old).copied().flatten();
+            }
+        }
+        self.clause_refs = clause_refs;
+    }
+
+    /// Logs the new literals of the clause with the id before deleting the old ones, so the
+    /// chain of the new lemma can refer to the old one
+    pub fn log_replacement(&mut self, clause_id: ClauseId, clause: &Clause, old_clause: &Clause) {
+        if !self.active {
+            return;
+        }
+
+        let old_clause_ref = self.clause_refs.get(clause_id).copied().flatten();
+        self.log_clause(clause_id, clause);
+        if self.tracking {
+            self.origins.push(StepOrigin::Deletion(old_clause_ref));
+        }
+        self.proof.push(ProofStep::DeleteClause(old_clause.clone()));
+    }
+
+    /// The chain of the antecedents with the clause ids referring to the current clauses,
+    /// `None` if a clause or unit can't be traced back or nothing is tracked
+    pub fn chain(&self, antecedents: &Antecedents) -> Option<Vec<ProofClause>> {
+        if !self.tracking || antecedents.incomplete {
+            return None;
+        }
+        let clauses = antecedents
+            .clauses
+            .iter()
+            .map(|clause_id| self.clause_refs.get(*clause_id).copied().flatten());
+        let mut seen = HashSet::new();
+        let units = antecedents
+            .fixed_vars
+            .iter()
+            .filter(|var_id| seen.insert(**var_id))
+            .map(|var_id| {
+                self.unit_steps
+                    .get(var_id.index())
+                    .copied()
+                    .flatten()
+                    .map(ProofClause::Lemma)
+            });
+        clauses.chain(units).collect()
+    }
+
+    /// The chain of the clause of the formula with the index without the literals of the
+    /// variables fixed at level 0, e.g. for clauses added incrementally, which are logged as
+    /// lemmas
+    pub fn original_chain(&self, index: usize, fixed_vars: &[Var]) -> Option<Vec<ProofClause>> {
+        let units = self.chain(&Antecedents {
+            fixed_vars: fixed_vars.to_vec(),
+            ..Default::default()
+        })?;
+        Some(
+            [ProofClause::Original(index)]
+                .into_iter()
+                .chain(units)
+                .collect(),
+        )
+    }
+
+    /// Sets the chain of the next logged lemma, which otherwise has unknown antecedents
+    pub fn set_chain(&mut self, chain: Option<Vec<ProofClause>>) {
+        if self.tracking {
+            self.next_chain = Some(chain);
+        }
+    }
+
+    /// Sets the chain of the next logged lemma from its antecedents, see `chain`
+    pub fn set_antecedents(&mut self, antecedents: &Antecedents) {
+        self.set_chain(self.chain(antecedents));
+    }
+
+    /// Logs the literals fixed at level 0 since the last call as unit lemmas derived from their
+    /// reasons, so lemmas depending on them can be traced back. They are left out of the trimmed
+    /// proof, as unit propagation derives them again from the reasons. Has to be called at
+    /// level 0 after propagation, while the reasons are unchanged. Literals that can't be traced
+    /// back, e.g. implied by XOR constraints, are not logged.
+    pub fn log_units(&mut self, trail: &Trail, clauses: &[Clause]) {
+        if !self.tracking {
+            return;
+        }
+        for assignment in trail.assignment_stack.iter().skip(self.num_logged_units) {
+            let var_id = assignment.literal.var();
+            if self.unit_steps.len() <= var_id.index() {
+                self.unit_steps.resize(var_id.index() + 1, None);
+            }
+            let AssignmentReason::Forced(reason @ ConstraintRef::Clause(clause_id)) =
+                assignment.reason
+            else {
+                continue;
+            };
+            let mut antecedents = Antecedents::default();
+            antecedents.add(reason, &clauses[clause_id].literals, trail);
+            antecedents.fixed_vars.retain(|other| *other != var_id);
+            let Some(chain) = self.chain(&antecedents) else {
+                continue;
+            };
+            self.origins.push(StepOrigin::Unit(chain));
+            self.proof
+                .push(ProofStep::AddClause(Clause::from(vec![assignment.literal])));
+            self.unit_steps[var_id.index()] = Some(self.proof.len() - 1);
+        }
+        self.num_logged_units = trail.assignment_stack.len();
+    }
+
+    /// Logs the empty clause, which is RUP once unit propagation at level 0 yields a conflict.
+    /// Only logged once, even if the solver is called again.
+    pub fn log_empty_clause(&mut self) {
+        let logged = matches!(
+            self.proof.last(),
+            Some(ProofStep::AddClause(clause)) if clause.literals.is_empty()
+        );
+        if logged {
+            self.next_chain = None;
+        } else {
+            self.log(&Clause::from(vec![]));
+        }
+    }
+
+    /// Deletes a clause of the formula
     pub fn delete(&mut self, clause: &Clause) {
         if !self.active {
             return;
         }
 
+        if self.tracking {
+            self.origins.push(StepOrigin::Deletion(None));
+        }
         self.proof.push(ProofStep::DeleteClause(clause.clone()));
     }
 
-    pub fn write_to_file(&self, filename: &str) {
-        let mut file = BufWriter::new(std::fs::File::create(filename).unwrap());
+    /// Deletes the clause with the id, which may be a clause of the formula or a lemma
+    pub fn delete_clause(&mut self, clause_id: ClauseId, clause: &Clause) {
+        if !self.active {
+            return;
+        }
+
+        if self.tracking {
+            let clause_ref = self.clause_refs.get_mut(clause_id).and_then(Option::take);
+            self.origins.push(StepOrigin::Deletion(clause_ref));
+        }
+        self.proof.push(ProofStep::DeleteClause(clause.clone()));
+    }
+
+    /// Steps the empty clause at the end of the proof depends on, with every lemma present at
+    /// a needed lemma with unknown antecedents
+    fn needed_steps(&self) -> Vec<bool> {
+        let mut deleted_at = vec![usize::MAX; self.proof.len()];
+        for (step, origin) in self.origins.iter().enumerate() {
+            if let StepOrigin::Deletion(Some(ProofClause::Lemma(lemma))) = origin {
+                deleted_at[*lemma] = step;
+            }
+        }
+
+        let mut needed = vec![false; self.proof.len()];
+        if let Some(last) = needed.last_mut() {
+            *last = true;
+        }
+        // every lemma present at a needed lemma with unknown antecedents is needed, which are the
+        // ones deleted after the first of them
+        let mut first_unknown = usize::MAX;
+        for step in (0..self.proof.len()).rev() {
+            match &self.origins[step] {
+                StepOrigin::Deletion(_) => continue,
+                _ if first_unknown < deleted_at[step] => needed[step] = true,
+                _ => {}
+            }
+            if !needed[step] {
+                continue;
+            }
+            match &self.origins[step] {
+                StepOrigin::Derived(chain) | StepOrigin::Unit(chain) => {
+                    for clause in chain {
+                        if let ProofClause::Lemma(lemma) = clause {
+                            needed[*lemma] = true;
+                        }
+                    }
+                }
+                _ => first_unknown = step,
+            }
+        }
+        needed
+    }
+
+    /// Removes the lemmas the empty clause at the end of the proof doesn't depend on, together
+    /// with their deletions, as in the backward trimming of N. Wetzler, M. J. H. Heule, and
+    /// W. A. Hunt, “DRAT-trim: Efficient Checking and Trimming Using Expressive Clausal
+    /// Proofs,” in Theory and Applications of Satisfiability Testing – SAT 2014, pp. 422–429,
+    /// but with the antecedents tracked during the search instead of found by checking.
+    /// Lemmas with unknown antecedents keep every lemma present when they were logged. Returns
+    /// the number of removed steps, the proof is not tracked afterwards.
+    pub fn trim(&mut self) -> usize {
+        if !self.trimming {
+            return 0;
+        }
+        let needed = self.needed_steps();
+        // chains refer to clauses before they were strengthened, whose deletions are only kept
+        // after their last use
+        let mut last_use = HashMap::new();
+        for (step, origin) in self.origins.iter().enumerate() {
+            if let (true, StepOrigin::Derived(chain) | StepOrigin::Unit(chain)) =
+                (needed[step], origin)
+            {
+                for clause in chain {
+                    last_use.insert(*clause, step);
+                }
+            }
+        }
+        let is_used_after = |clause: &ProofClause, step: usize| {
+            last_use
+                .get(clause)
+                .is_some_and(|last_use| *last_use > step)
+        };
+        let num_steps = self.proof.len();
+        let origins = core::mem::take(&mut self.origins);
+        let mut kept = origins
+            .iter()
+            .enumerate()
+            .map(|(step, origin)| match origin {
+                StepOrigin::Deletion(None) => true,
+                StepOrigin::Deletion(Some(clause @ ProofClause::Original(_))) => {
+                    !is_used_after(clause, step)
+                }
+                StepOrigin::Deletion(Some(clause @ ProofClause::Lemma(lemma))) => {
+                    needed[*lemma] && !is_used_after(clause, step)
+                }
+                StepOrigin::Unit(_) => false,
+                _ => needed[step],
+            });
+        self.proof.retain(|_| kept.next().unwrap());
+        self.tracking = false;
+        self.trimming = false;
+        self.clause_refs = vec![];
+        self.unit_steps = vec![];
+        num_steps - self.proof.len()
+    }
+
+    /// The resolution proof of the empty clause at the end of the proof, with the lemmas it
+    /// depends on. `None` if the chains aren't tracked or one of the lemmas has none, e.g. as
+    /// it's implied by an XOR constraint.
+    pub fn resolution_proof(&self) -> Option<ResolutionProof> {
+        if !self.tracking {
+            return None;
+        }
+        let needed = self.needed_steps();
+        let mut lemma_indices = vec![usize::MAX; self.proof.len()];
+        let mut lemmas = vec![];
+        for (step, origin) in self.origins.iter().enumerate() {
+            if !needed[step] {
+                continue;
+            }
+            let (StepOrigin::Derived(chain) | StepOrigin::Unit(chain)) = origin else {
+                return None;
+            };
+            let ProofStep::AddClause(clause) = &self.proof[step] else {
+                unreachable!("only lemmas are needed");
+            };
+            let chain = chain
+                .iter()
+                .map(|clause| match *clause {
+                    ProofClause::Lemma(step) => ProofClause::Lemma(lemma_indices[step]),
+                    original => original,
+                })
+                .collect();
+            lemma_indices[step] = lemmas.len();
+            lemmas.push(ResolutionLemma {
+                literals: clause.literals.clone(),
+                chain,
+            });
+        }
+        let proves_unsat = lemmas
+            .last()
+            .is_some_and(|lemma: &ResolutionLemma| lemma.literals.is_empty());
+        proves_unsat.then_some(ResolutionProof { lemmas })
+    }
+
+    /// Writes the proof in DRUP format, see `write_atomically`
+    #[cfg(feature = "std")]
+    pub fn write_to_file(&self, filename: &str) -> Result<()> {
+        write_atomically(filename, |file| self.write_to(file))
+    }
+
+    #[cfg(feature = "std")]
+    fn write_to(&self, file: &mut dyn Write) -> std::io::Result<()> {
         for proof_step in &self.proof {
             let clause = match proof_step {
                 ProofStep::AddClause(clause) => clause,
@@ -51,16 +448,55 @@ impl ProofLogger {
                 .literals
                 .iter()
                 .map(|lit| format!("{}", lit))
+                .chain(core::iter::once("0".to_string()))
                 .collect::<Vec<String>>()
                 .join(" ");
 
             match proof_step {
                 ProofStep::AddClause(_) => {}
-                ProofStep::DeleteClause(_) => write!(file, "d ").unwrap(),
+                ProofStep::DeleteClause(_) => write!(file, "d ")?,
             }
 
-            writeln!(file, "{} 0", clause_str).unwrap();
+            writeln!(file, "{}", clause_str)?;
         }
-        file.flush().unwrap()
+        Ok(())
     }
 }
+
+/// Writes a file with the function, to stdout for the filename `-`. Files are first written to
+/// `<filename>.tmp` and renamed once complete, so a failed write, e.g. on a full disk, never
+/// leaves a truncated file behind.
+#[cfg(feature = "std")]
+pub(crate) fn write_atomically(
+    filename: &str,
+    write: impl Fn(&mut dyn Write) -> std::io::Result<()>,
+) -> Result<()> {
+    let written = if filename == "-" {
+        let mut stdout = BufWriter::new(std::io::stdout().lock());
+        write(&mut stdout).and_then(|()| stdout.flush())
+    } else {
+        let temp_file = format!("{}.tmp", filename);
+        let written =
+            write_file(&temp_file, &write).and_then(|()| std::fs::rename(&temp_file, filename));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp_file);
+        }
+        written
+    };
+    written.map_err(|err| Error::Write {
+        path: filename.to_string(),
+        source: err,
+    })
+}
+
+#[cfg(feature = "std")]
+fn write_file(
+    path: &str,
+    write: &impl Fn(&mut dyn Write) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut file = BufWriter::new(std::fs::File::create(path)?);
+    write(&mut file)?;
+    file.into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()
+}
@@ -0,0 +1,187 @@
+use crate::cnf::{Clause, Literal, Model};
+use alloc::vec::Vec;
+
+/// Clauses removed by simplifications that only preserve satisfiability, each with a witness
+/// literal that satisfies the clause when flipped, see M. Järvisalo, M. J. H. Heule, and
+/// A. Biere, “Inprocessing Rules,” in Automated Reasoning, vol. 7364, B. Gramlich, D. Miller,
+/// and U. Sattler, Eds., Berlin, Heidelberg: Springer Berlin Heidelberg, 2012, pp. 355–370.
+/// doi: 10.1007/978-3-642-31365-3_28.
+/// All simplification layers push onto the same stack, so the models of the simplified formula
+/// are extended in the reverse order of all removals, no matter which layer removed a clause.
+#[derive(Debug, Clone, Default)]
+pub struct ReconstructionStack {
+    entries: Vec<(Literal, Clause)>,
+}
+
+impl ReconstructionStack {
+    pub fn push(&mut self, witness: Literal, clause: Clause) {
+        debug_assert!(clause.literals.contains(&witness));
+        self.entries.push((witness, clause));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Witnesses and removed clauses in the order of removal
+    pub fn entries(&self) -> &[(Literal, Clause)] {
+        &self.entries
+    }
+
+    /// Extends a model of the simplified formula to a model of the original one
+    pub fn extend_model(&self, model: &mut Model) {
+        self.extend_model_traced(model);
+    }
+
+    /// Extends the model like `extend_model` and returns the witnesses that were flipped, each
+    /// with the removed clause that was falsified, in the order of the flips
+    pub fn extend_model_traced(&self, model: &mut Model) -> Vec<(Literal, &Clause)> {
+        let mut trace = Vec::new();
+        for (witness, clause) in self.entries.iter().rev() {
+            if !clause.literals.iter().any(|lit| model.value(*lit)) {
+                model.set(witness.var(), witness.positive());
+                trace.push((*witness, clause));
+            }
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{check_assignment, Var};
+    use crate::solver::config::Config;
+    use crate::solver::Solver;
+    use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Replaces all clauses containing the variable by their non-tautological resolvents, unless
+    /// there are more resolvents than clauses
+    fn eliminate(clauses: &mut Vec<Clause>, var_id: Var, stack: &mut ReconstructionStack) -> bool {
+        let (occurrences, rest): (Vec<_>, Vec<_>) = clauses
+            .iter()
+            .cloned()
+            .partition(|clause| clause.literals.iter().any(|lit| lit.var() == var_id));
+        let (positive, negative): (Vec<_>, Vec<_>) = occurrences
+            .iter()
+            .partition(|clause| clause.literals.contains(&Literal::from_value(var_id, true)));
+        let resolvents = positive
+            .iter()
+            .cartesian_product(&negative)
+            .map(|(c1, c2)| {
+                c1.literals
+                    .iter()
+                    .chain(&c2.literals)
+                    .filter(|lit| lit.var() != var_id)
+                    .copied()
+                    .unique()
+                    .collect_vec()
+            })
+            .filter(|literals| literals.iter().map(|lit| lit.var()).all_unique())
+            .map(Clause::from)
+            .collect_vec();
+        if resolvents.len() > occurrences.len() {
+            return false;
+        }
+
+        for clause in occurrences {
+            let witness = *clause
+                .literals
+                .iter()
+                .find(|lit| lit.var() == var_id)
+                .unwrap();
+            stack.push(witness, clause);
+        }
+        *clauses = rest;
+        clauses.extend(resolvents);
+        true
+    }
+
+    /// Random satisfiable 3-CNF, each clause is satisfied by a hidden model
+    fn random_cnf(rng: &mut StdRng, num_vars: usize, num_clauses: usize) -> Vec<Clause> {
+        let hidden = (0..=num_vars).map(|_| rng.gen_bool(0.5)).collect_vec();
+        (0..num_clauses)
+            .map(|_| {
+                let mut literals = (0..3)
+                    .map(|_| {
+                        let var_id = Var::new(rng.gen_range(1..=num_vars));
+                        Literal::from_value(var_id, rng.gen_bool(0.5))
+                    })
+                    .unique_by(|lit| lit.var())
+                    .collect_vec();
+                let var_id = literals[0].var();
+                literals[0] = Literal::from_value(var_id, hidden[var_id]);
+                Clause::from(literals)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layered_elimination_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let config = Config {
+            inprocessing: false,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let num_vars = 12;
+            let original = random_cnf(&mut rng, num_vars, 30);
+            let mut clauses = original.clone();
+            let mut stack = ReconstructionStack::default();
+
+            let eliminated = Var::range(num_vars)
+                .filter(|_| rng.gen_bool(0.5))
+                .filter(|var_id| eliminate(&mut clauses, *var_id, &mut stack))
+                .collect_vec();
+            assert!(eliminated.len() <= stack.len());
+
+            let mut model = Solver::new(clauses.clone(), num_vars, config.clone())
+                .solve()
+                .into_model()
+                .expect("elimination preserves satisfiability");
+            // eliminated variables are unconstrained in the simplified formula
+            for var_id in eliminated {
+                model.set(var_id, rng.gen_bool(0.5));
+            }
+            stack.extend_model(&mut model);
+            assert!(check_assignment(&original, &model));
+        }
+    }
+
+    #[test]
+    fn reconstructed_models_satisfy_original_formula() {
+        let config = Config::default();
+        let num_vars = 12;
+        let mut num_flips = 0;
+        for seed in 0..200 {
+            let mut clauses = crate::tester::random_cnf(num_vars, 3.0, 3, seed);
+            clauses.extend(crate::tester::random_cnf(num_vars, 0.5, 2, seed + 1000));
+            let mut solver = Solver::new(clauses.clone(), num_vars, config.clone());
+            solver.simplify();
+            let satisfiable = crate::tester::brute_force_sat(&clauses, num_vars);
+            let Some(model) = solver.solve().into_model() else {
+                assert!(!satisfiable, "seed {}", seed);
+                continue;
+            };
+            assert!(satisfiable, "seed {}", seed);
+            assert!(check_assignment(&clauses, &model), "seed {}", seed);
+
+            // every flip satisfies its clause, and the last flip of a variable gives its value
+            let trace = solver.reconstruction_trace();
+            for (witness, clause) in &trace {
+                assert!(clause.literals.contains(witness), "seed {}", seed);
+            }
+            for (witness, _) in trace.iter().rev().unique_by(|(witness, _)| witness.var()) {
+                assert!(model.value(*witness), "seed {}", seed);
+            }
+            num_flips += trace.len();
+        }
+        assert!(num_flips > 0);
+    }
+}
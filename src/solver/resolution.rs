@@ -0,0 +1,165 @@
+use crate::cnf::{Clause, Literal, Var};
+#[cfg(feature = "std")]
+use crate::error::Result;
+#[cfg(feature = "std")]
+use crate::solver::proof_logger::write_atomically;
+use alloc::{vec, vec::Vec};
+use hashbrown::HashSet;
+use itertools::Itertools;
+
+/// Clause of a resolution chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProofClause {
+    /// Clause of the formula, by its index in the order the clauses were added
+    Original(usize),
+    /// Lemma of the proof, by its index
+    Lemma(usize),
+}
+
+/// Lemma of a `ResolutionProof`. Resolving the first clause of the chain with each following
+/// one in turn, always on the only variable occurring with opposite signs, yields exactly the
+/// literals of the lemma.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolutionLemma {
+    pub literals: Vec<Literal>,
+    pub chain: Vec<ProofClause>,
+}
+
+/// Resolution refutation recorded during the search, see `Config::resolution_chains`. Holds
+/// the lemmas the empty clause depends on in the order they were derived, so chains only refer
+/// to earlier lemmas, and ends with the empty clause.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolutionProof {
+    pub lemmas: Vec<ResolutionLemma>,
+}
+
+impl ResolutionProof {
+    /// Indices of the clauses of the formula the empty clause is derived from, in ascending
+    /// order
+    pub fn core(&self) -> Vec<usize> {
+        self.lemmas
+            .iter()
+            .flat_map(|lemma| &lemma.chain)
+            .filter_map(|clause| match clause {
+                ProofClause::Original(index) => Some(*index),
+                ProofClause::Lemma(_) => None,
+            })
+            .sorted_unstable()
+            .dedup()
+            .collect()
+    }
+
+    /// Pivot variables of the resolution steps of the lemma's chain, or `None` if the chain
+    /// isn't a sequence of resolutions yielding the lemma
+    pub fn pivots(&self, lemma: usize, original_clauses: &[Clause]) -> Option<Vec<Var>> {
+        let literals = |clause: &ProofClause| match *clause {
+            ProofClause::Original(index) => original_clauses
+                .get(index)
+                .map(|clause| clause.literals.as_slice()),
+            ProofClause::Lemma(index) if index < lemma => Some(&self.lemmas[index].literals[..]),
+            ProofClause::Lemma(_) => None,
+        };
+        let (first, rest) = self.lemmas[lemma].chain.split_first()?;
+        let mut resolvent: HashSet<Literal> = literals(first)?.iter().copied().collect();
+        let mut pivots = Vec::with_capacity(rest.len());
+        for clause in rest {
+            let clause = literals(clause)?;
+            let mut clashing = clause.iter().filter(|lit| resolvent.contains(&-**lit));
+            let pivot = *clashing.next()?;
+            if clashing.any(|lit| *lit != pivot) {
+                return None;
+            }
+            resolvent.remove(&-pivot);
+            resolvent.extend(clause.iter().filter(|lit| **lit != pivot));
+            pivots.push(pivot.var());
+        }
+        let lemma_literals: HashSet<Literal> =
+            self.lemmas[lemma].literals.iter().copied().collect();
+        (resolvent == lemma_literals).then_some(pivots)
+    }
+
+    /// Whether every chain yields its lemma and the last lemma is the empty clause
+    pub fn is_valid(&self, original_clauses: &[Clause]) -> bool {
+        self.lemmas
+            .last()
+            .is_some_and(|lemma| lemma.literals.is_empty())
+            && (0..self.lemmas.len()).all(|lemma| self.pivots(lemma, original_clauses).is_some())
+    }
+
+    /// Writes the proof in the LRAT format of L. Cruz-Filipe, M. J. H. Heule, W. A. Hunt,
+    /// M. Kaufmann, and P. Schneider-Kamp, “Efficient Certified RAT Verification,” in Automated
+    /// Deduction – CADE 26, 2017, pp. 220–236. The clauses of the formula have the ids 1 to
+    /// `original_clauses.len()` and the lemmas follow, the hints are given by `hints`. Files
+    /// are written like by `ProofLogger::write_to_file`.
+    #[cfg(feature = "std")]
+    pub fn write_lrat(&self, filename: &str, original_clauses: &[Clause]) -> Result<()> {
+        let id = |clause: &ProofClause| match *clause {
+            ProofClause::Original(index) => index + 1,
+            ProofClause::Lemma(index) => original_clauses.len() + index + 1,
+        };
+        write_atomically(filename, |file| {
+            for (index, lemma) in self.lemmas.iter().enumerate() {
+                let hints = self.hints(index, original_clauses);
+                let line = core::iter::once(id(&ProofClause::Lemma(index)).to_string())
+                    .chain(lemma.literals.iter().map(|lit| lit.to_string()))
+                    .chain(["0".to_string()])
+                    .chain(hints.iter().map(|clause| id(clause).to_string()))
+                    .chain(["0".to_string()])
+                    .join(" ");
+                writeln!(file, "{}", line)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Clauses of the lemma's chain in an order where each is unit or falsified after
+    /// assigning the negated lemma and the units of the previous ones, found by unit
+    /// propagation over the chain in reverse. Clauses whose resolution is undone later in the
+    /// chain, e.g. by reintroducing a literal removed by minimization, are left out.
+    pub fn hints(&self, lemma: usize, original_clauses: &[Clause]) -> Vec<ProofClause> {
+        let literals = |clause: &ProofClause| match *clause {
+            ProofClause::Original(index) => &original_clauses[index].literals[..],
+            ProofClause::Lemma(index) => &self.lemmas[index].literals[..],
+        };
+        let mut true_literals: HashSet<Literal> = self.lemmas[lemma]
+            .literals
+            .iter()
+            .map(|lit| -*lit)
+            .collect();
+        let mut seen = HashSet::new();
+        let mut pending = self.lemmas[lemma]
+            .chain
+            .iter()
+            .rev()
+            .filter(|clause| seen.insert(**clause))
+            .collect_vec();
+        let mut hints = vec![];
+        loop {
+            let num_pending = pending.len();
+            let mut index = 0;
+            while index < pending.len() {
+                let clause = literals(pending[index]);
+                if clause.iter().any(|lit| true_literals.contains(lit)) {
+                    pending.remove(index);
+                    continue;
+                }
+                let mut open = clause.iter().filter(|lit| !true_literals.contains(&-**lit));
+                match open.next() {
+                    None => {
+                        hints.push(*pending[index]);
+                        return hints;
+                    }
+                    Some(&unit) if open.all(|lit| *lit == unit) => {
+                        true_literals.insert(unit);
+                        hints.push(*pending.remove(index));
+                    }
+                    Some(_) => index += 1,
+                }
+            }
+            if pending.len() == num_pending {
+                // not a valid chain, the hints don't yield a conflict
+                return hints;
+            }
+        }
+    }
+}
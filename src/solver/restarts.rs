@@ -1,9 +1,11 @@
+use crate::solver::config::Config;
 use crate::solver::ema_policy::EMAPolicy;
+use crate::solver::params::Params;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use clap::ValueEnum;
-
-const FIXED_INTERVAL_SIZE: usize = 700;
-const GEOMETRIC_INTERVAL_SIZE: usize = 100;
-const GEOMETRIC_MAGNIFICATION_FACTOR: f64 = 1.5;
+#[cfg(not(feature = "std"))]
+use num_traits::float::FloatCore;
 
 #[derive(Debug, Clone)]
 pub struct Restarter {
@@ -11,32 +13,63 @@ pub struct Restarter {
     conflicts_since_last_restart: usize,
     restart_policy: RestartPolicy,
     ema_policy: Option<EMAPolicy>,
+    reluctant_doubling: (usize, usize),
+    custom_schedule: Vec<usize>,
+    params: Params,
+    /// Restarts in the stable mode use reluctant doubling regardless of the policy
+    stable: bool,
 }
 
-#[derive(Debug, Copy, Clone, ValueEnum, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
 pub enum RestartPolicy {
-    #[clap(name = "fixed-interval")]
+    #[cfg_attr(feature = "std", clap(name = "fixed-interval"))]
     FixedInterval,
-    #[clap(name = "geometric")]
+    #[cfg_attr(feature = "std", clap(name = "geometric"))]
     Geometric,
-    #[clap(name = "luby")]
+    #[cfg_attr(feature = "std", clap(name = "luby"))]
     Luby,
-    #[clap(name = "glucose-ema")]
+    #[cfg_attr(feature = "std", clap(name = "glucose-ema"))]
     GlucoseEma,
-    #[clap(name = "no-restarts")]
+    #[cfg_attr(feature = "std", clap(name = "reluctant-doubling"))]
+    ReluctantDoubling,
+    /// Restarts after the numbers of conflicts given by `--restart-schedule`, repeating the last
+    /// one
+    #[cfg_attr(feature = "std", clap(name = "custom"))]
+    Custom,
+    #[cfg_attr(feature = "std", clap(name = "no-restarts"))]
     NoRestarts,
 }
 
 impl Restarter {
-    pub fn init(restart_policy: RestartPolicy) -> Self {
+    pub fn init(config: &Config) -> Self {
         Restarter {
             num_restarts: 0,
             conflicts_since_last_restart: 0,
-            restart_policy,
-            ema_policy: match restart_policy {
-                RestartPolicy::GlucoseEma => Some(EMAPolicy::init()),
+            restart_policy: config.restart_policy,
+            ema_policy: match config.restart_policy {
+                RestartPolicy::GlucoseEma => Some(EMAPolicy::init(config)),
                 _ => None,
             },
+            reluctant_doubling: (1, 1),
+            custom_schedule: config.restart_schedule.clone(),
+            params: config.params.clone(),
+            stable: false,
+        }
+    }
+
+    pub fn set_stable(&mut self, stable: bool) {
+        self.stable = stable;
+        self.conflicts_since_last_restart = 0;
+        if let Some(ema_policy) = &mut self.ema_policy {
+            ema_policy.restarted();
+        }
+    }
+
+    fn active_policy(&self) -> RestartPolicy {
+        match self.stable {
+            true => RestartPolicy::ReluctantDoubling,
+            false => self.restart_policy,
         }
     }
 
@@ -52,51 +85,95 @@ impl Restarter {
     }
 
     pub fn check_if_restart_necessary(&mut self) -> bool {
-        let restart_necessary = match self.restart_policy {
+        let restart_necessary = match self.active_policy() {
             RestartPolicy::FixedInterval => self.fixed_interval_check_necessary(),
             RestartPolicy::Geometric => self.geometric_check_necessary(),
             RestartPolicy::Luby => self.luby_check_necessary(),
             RestartPolicy::GlucoseEma => self.lbd_ema_check_necessary(),
+            RestartPolicy::ReluctantDoubling => self.reluctant_doubling_check_necessary(),
+            RestartPolicy::Custom => self.custom_check_necessary(),
             RestartPolicy::NoRestarts => false,
         };
 
         if restart_necessary {
             self.conflicts_since_last_restart = 0;
             self.num_restarts += 1;
+            if let Some(ema_policy) = &mut self.ema_policy {
+                ema_policy.restarted();
+            }
+            if self.active_policy() == RestartPolicy::ReluctantDoubling {
+                self.advance_reluctant_doubling();
+            }
         }
         restart_necessary
     }
 
     fn fixed_interval_check_necessary(&mut self) -> bool {
-        self.conflicts_since_last_restart >= FIXED_INTERVAL_SIZE
+        self.conflicts_since_last_restart >= self.params.restart_fixed_interval
     }
 
     fn geometric_check_necessary(&mut self) -> bool {
         (self.conflicts_since_last_restart as f64)
-            >= (GEOMETRIC_INTERVAL_SIZE as f64
-                * (GEOMETRIC_MAGNIFICATION_FACTOR.powi(self.num_restarts as i32)))
+            >= (self.params.restart_geometric_interval as f64
+                * (self
+                    .params
+                    .restart_geometric_factor
+                    .powi(self.num_restarts as i32)))
     }
 
     fn luby_check_necessary(&mut self) -> bool {
         // luby sequence defined for i >= 1, but num_restarts >= 0 --> num_restarts + 1
-        self.conflicts_since_last_restart >= 32 * Restarter::luby(self.num_restarts + 1)
+        // a unit of 0 would restart without conflicts forever
+        let unit = self.params.luby_unit.max(1);
+        let interval = Restarter::luby(self.num_restarts + 1).saturating_mul(unit);
+        self.conflicts_since_last_restart >= interval
     }
 
-    fn luby(i: usize) -> usize {
-        // don't store any variables inside of luby() calls -> otherwise stack overflow
-        for k in 1..32 {
-            if i == (1 << k) - 1 {
-                return 1 << (k - 1);
-            }
-        }
+    fn reluctant_doubling_check_necessary(&self) -> bool {
+        self.conflicts_since_last_restart
+            >= self.params.reluctant_doubling_unit * self.reluctant_doubling.1
+    }
+
+    /// Reluctant doubling as described in D. E. Knuth, “The Art of Computer Programming,
+    /// Volume 4, Fascicle 6: Satisfiability,” 2015. Computes the Luby sequence iteratively:
+    /// (u, v) ← (u & -u == v) ? (u + 1, 1) : (u, 2v)
+    fn advance_reluctant_doubling(&mut self) {
+        let (u, v) = self.reluctant_doubling;
+        self.reluctant_doubling = if u & u.wrapping_neg() == v {
+            (u + 1, 1)
+        } else {
+            (u, 2 * v)
+        };
+    }
 
-        let mut k = 1;
+    /// Restarts after the number of conflicts given by the user-defined schedule. Once the
+    /// schedule is exhausted, its last entry is repeated. An empty schedule never restarts.
+    fn custom_check_necessary(&self) -> bool {
+        let interval = self
+            .custom_schedule
+            .get(self.num_restarts)
+            .or(self.custom_schedule.last());
 
+        // an interval of 0 would restart without conflicts forever
+        match interval {
+            Some(interval) => self.conflicts_since_last_restart >= (*interval).max(1),
+            None => false,
+        }
+    }
+
+    /// The i-th element of the Luby sequence 1, 1, 2, 1, 1, 2, 4, … for i >= 1, as defined in
+    /// M. Luby, A. Sinclair, and D. Zuckerman, “Optimal speedup of Las Vegas algorithms,”
+    /// Information Processing Letters, vol. 47, no. 4, pp. 173–180, 1993.
+    /// With 2^(k-1) <= i < 2^k, it is 2^(k-1) if i = 2^k - 1 and otherwise the element at
+    /// i - 2^(k-1) + 1, which is unrolled into a loop.
+    fn luby(mut i: usize) -> usize {
+        assert!(i >= 1, "the Luby sequence starts at 1");
         loop {
-            if (1 << (k - 1)) <= i && i < (1 << k) - 1 {
-                return Restarter::luby(i - (1 << (k - 1)) + 1);
+            let k = usize::BITS - i.leading_zeros();
+            if i.trailing_ones() == k {
+                return 1 << (k - 1);
             }
-            k += 1;
+            i -= (1 << (k - 1)) - 1;
         }
     }
     fn lbd_ema_check_necessary(&self) -> bool {
@@ -113,13 +190,182 @@ impl Restarter {
 
 #[cfg(test)]
 mod tests {
-    use crate::solver::restarts::Restarter;
+    use crate::solver::config::Config;
+    use crate::solver::params::Params;
+    use crate::solver::restarts::{RestartPolicy, Restarter};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn reluctant_doubling_is_luby() {
+        let config = Config {
+            restart_policy: RestartPolicy::ReluctantDoubling,
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        for i in 1..1000 {
+            assert_eq!(restarter.reluctant_doubling.1, Restarter::luby(i));
+            restarter.advance_reluctant_doubling();
+        }
+    }
+
+    #[test]
+    fn custom_schedule() {
+        let config = Config {
+            restart_policy: RestartPolicy::Custom,
+            restart_schedule: vec![2, 1],
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        let mut restarts = vec![];
+        for _ in 0..6 {
+            restarter.conflict(2, 10);
+            restarts.push(restarter.check_if_restart_necessary());
+        }
+        assert_eq!(restarts, vec![false, true, true, true, true, true]);
+    }
+
+    #[test]
+    fn custom_schedule_of_zero_still_needs_conflicts() {
+        let config = Config {
+            restart_policy: RestartPolicy::Custom,
+            restart_schedule: vec![0],
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        assert!(!restarter.check_if_restart_necessary());
+        restarter.conflict(2, 10);
+        assert!(restarter.check_if_restart_necessary());
+        assert!(!restarter.check_if_restart_necessary());
+    }
+
+    /// The recursive definition of the Luby sequence
+    fn reference_luby(i: usize) -> usize {
+        let k = (1..).find(|k| i < (1 << k)).unwrap();
+        if i == (1 << k) - 1 {
+            1 << (k - 1)
+        } else {
+            reference_luby(i - (1 << (k - 1)) + 1)
+        }
+    }
+
+    #[test]
+    fn luby() {
+        let prefix = (1..=15).map(Restarter::luby).collect::<Vec<_>>();
+        assert_eq!(prefix, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+        for i in 1..100_000 {
+            assert_eq!(Restarter::luby(i), reference_luby(i), "i = {}", i);
+        }
+    }
 
     #[test]
-    #[ignore]
-    fn test_luby() {
-        for i in 1..200000 {
-            println!("{}", Restarter::luby(i));
+    fn luby_is_self_similar_without_overflow() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in 1..usize::BITS {
+            assert_eq!(Restarter::luby((1 << k) - 1), 1 << (k - 1));
+            // the next 2^k - 1 elements repeat the sequence from the start
+            let offset = rng.gen_range(1..(1 << k));
+            assert_eq!(
+                Restarter::luby((1 << k) - 1 + offset),
+                Restarter::luby(offset)
+            );
         }
+        assert_eq!(Restarter::luby(usize::MAX), 1 << (usize::BITS - 1));
+    }
+
+    #[test]
+    fn luby_unit_scales_the_intervals() {
+        let config = Config {
+            restart_policy: RestartPolicy::Luby,
+            params: Params {
+                luby_unit: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        let mut intervals = vec![];
+        let mut conflicts = 0;
+        while intervals.len() < 7 {
+            restarter.conflict(2, 10);
+            conflicts += 1;
+            if restarter.check_if_restart_necessary() {
+                intervals.push(conflicts);
+                conflicts = 0;
+            }
+        }
+        assert_eq!(intervals, vec![3, 3, 6, 3, 3, 6, 12]);
+    }
+
+    #[test]
+    fn luby_unit_of_zero_still_needs_conflicts() {
+        let params = Params {
+            luby_unit: 0,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+        let config = Config {
+            restart_policy: RestartPolicy::Luby,
+            params,
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        assert!(!restarter.check_if_restart_necessary());
+        restarter.conflict(2, 10);
+        assert!(restarter.check_if_restart_necessary());
+        assert!(!restarter.check_if_restart_necessary());
+    }
+
+    /// Restarts are always necessary and always blocked, until the limit lifts the blocking
+    fn blocked_ema_restarts(params: Params, deterministic: bool) -> Vec<bool> {
+        let config = Config {
+            restart_policy: RestartPolicy::GlucoseEma,
+            deterministic,
+            params: Params {
+                margin_ratio_forcing_restart: 0.0,
+                margin_ratio_blocking_restart: 0.0,
+                restart_min_conflicts: 2,
+                ..params
+            },
+            ..Default::default()
+        };
+        let mut restarter = Restarter::init(&config);
+        (0..6)
+            .map(|_| {
+                restarter.conflict(2, 10);
+                restarter.check_if_restart_necessary()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn restart_blocking_limits() {
+        assert_eq!(
+            blocked_ema_restarts(Params::default(), false),
+            vec![false; 6]
+        );
+        let time_limit = Params {
+            max_time_restart_blocked: 1e-9,
+            max_conflicts_restart_blocked: 1000,
+            ..Default::default()
+        };
+        assert_eq!(
+            blocked_ema_restarts(time_limit, false),
+            vec![false, true, false, true, false, true]
+        );
+        // the conflict limit replaces the time limit in deterministic mode
+        let conflict_limit = Params {
+            max_time_restart_blocked: 1000.0,
+            max_conflicts_restart_blocked: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            blocked_ema_restarts(conflict_limit.clone(), false),
+            vec![false; 6]
+        );
+        assert_eq!(
+            blocked_ema_restarts(conflict_limit, true),
+            vec![false, false, true, false, false, true]
+        );
     }
 }
@@ -0,0 +1,157 @@
+use crate::cnf::{Clause, Literal};
+use crate::error;
+use crate::solver::trail::ConstraintRef;
+use crate::solver::Solver;
+use alloc::{boxed::Box, vec::Vec};
+use hashbrown::HashSet;
+use itertools::Itertools;
+
+impl Solver {
+    /// Learned clauses with an LBD of at most `max_lbd` in the DIMACS convention, to share them
+    /// with other solvers working on the same formula
+    pub fn export_clauses(&self, max_lbd: usize) -> Vec<Vec<i32>> {
+        self.learned_clauses()
+            .filter(|clause| clause.lbd.is_some_and(|lbd| lbd <= max_lbd))
+            .map(|clause| clause.literals.iter().map(|lit| lit.to_i32()).collect())
+            .collect()
+    }
+
+    /// Adds clauses exported by another solver as learned clauses, so they may be deleted by
+    /// later reductions. They must be implied by the formula, otherwise models may be lost.
+    /// Clauses already in the database are skipped. Returns the number of added clauses.
+    /// Like `add_clause_incremental`, variables eliminated by inprocessing can't be used.
+    /// Proofs are only valid if every imported clause is RUP.
+    pub fn import_clauses(&mut self, clauses: &[Vec<i32>]) -> error::Result<usize> {
+        let clauses = clauses
+            .iter()
+            .map(|clause| self.parse_clause(clause))
+            .collect::<error::Result<Vec<_>>>()?;
+        Ok(self.import_literals(clauses))
+    }
+
+    /// Calls `callback` at every restart and imports the returned clauses like
+    /// `import_clauses`, skipping invalid ones. Replaces any previous callback.
+    pub fn set_import_callback(&mut self, callback: impl FnMut() -> Vec<Vec<i32>> + 'static) {
+        self.import_callback = Some(Box::new(callback));
+    }
+
+    pub(crate) fn import_literals(&mut self, clauses: Vec<Vec<Literal>>) -> usize {
+        self.trail.restart(&mut self.state, &mut self.heuristic);
+        let database = &self.state.clause_database;
+        let mut known: HashSet<Vec<Literal>> = database
+            .iter()
+            .map(|clause_id| {
+                database[clause_id]
+                    .literals
+                    .iter()
+                    .copied()
+                    .sorted_unstable()
+                    .collect()
+            })
+            .collect();
+
+        let mut num_imported = 0;
+        for literals in clauses {
+            if self.is_unsat {
+                break;
+            }
+            let vars = &self.state.vars;
+            if literals.iter().any(|lit| lit.is_true(vars)) {
+                continue;
+            }
+            let literals = literals.into_iter().sorted_unstable().dedup().collect_vec();
+            let is_tautology = literals
+                .windows(2)
+                .any(|pair| pair[0].var() == pair[1].var());
+            if is_tautology || !known.insert(literals.clone()) {
+                continue;
+            }
+            let unassigned = literals
+                .iter()
+                .filter(|lit| !lit.is_false(vars))
+                .copied()
+                .collect_vec();
+            if unassigned.is_empty() {
+                self.is_unsat = true;
+                break;
+            }
+
+            let lbd = literals.len();
+            let clause_id = self.state.clause_database.add_clause(
+                Clause::from_literals_and_lbd(literals, lbd),
+                &mut self.state.literal_watcher,
+                &self.state.vars,
+                &self.trail,
+            );
+            if let [unit] = unassigned[..] {
+                self.trail
+                    .enqueue(&mut self.state, unit, ConstraintRef::Clause(clause_id));
+            }
+            num_imported += 1;
+        }
+        self.state.stats.num_imported_clauses += num_imported;
+        num_imported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::check_assignment;
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::solver::config::Config;
+    use crate::tester::random_cnf;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn config() -> Config {
+        Config {
+            inprocessing: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn imported_clauses_keep_the_models() {
+        for seed in 0..20 {
+            let clauses = random_cnf(30, 4.0, 3, seed);
+            let mut exporter = Solver::new(clauses.clone(), 30, config());
+            let exporter_is_sat = exporter.solve().is_sat();
+            let exported = exporter.export_clauses(usize::MAX);
+
+            let mut importer = Solver::new(clauses.clone(), 30, config());
+            let num_imported = importer.import_clauses(&exported).unwrap();
+            assert!(num_imported <= exported.len());
+            assert_eq!(importer.import_clauses(&exported).unwrap(), 0);
+            match importer.solve().into_model() {
+                Some(model) => assert!(check_assignment(&clauses, &model)),
+                None => assert!(!exporter_is_sat, "seed {}", seed),
+            }
+        }
+    }
+
+    #[test]
+    fn import_callback() {
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/unsat/hole6.cnf").unwrap();
+        let mut exporter = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config());
+        assert!(exporter.solve().is_unsat());
+        let exported = exporter.export_clauses(4);
+        assert!(!exported.is_empty());
+
+        let mut importer = Solver::new(dimacs.clauses, dimacs.num_vars, config());
+        let num_calls = Rc::new(Cell::new(0));
+        let calls = num_calls.clone();
+        importer.set_import_callback(move || {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 => exported.clone(),
+                _ => vec![],
+            }
+        });
+        assert!(importer.solve().is_unsat());
+        assert!(num_calls.get() > 0);
+        assert!(importer.stats().num_imported_clauses > 0);
+        assert!(importer.import_clauses(&[vec![0]]).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::cnf::{Clause, Literal, Model, PartialAssignment, Var};
+#[cfg(feature = "std")]
+use crate::dimacs::clauses_to_dimacs;
+use alloc::{format, string::String, vec, vec::Vec};
+use itertools::Itertools;
+
+/// Formula after simplification, without the fixed literals and with the remaining variables
+/// numbered from 1, so it can be passed to other solvers. Models of it are mapped back by
+/// setting the variables of `original_vars` and the fixed literals, and by extending the
+/// assignment with the removed clauses in reverse order: if a removed clause is falsified, its
+/// witness is set.
+#[derive(Debug, Clone)]
+pub struct SimplifiedFormula {
+    pub clauses: Vec<Clause>,
+    pub num_vars: usize,
+    /// Original variable of each variable of the simplified formula, index 0 is unused
+    pub original_vars: Vec<Var>,
+    pub fixed_literals: Vec<Literal>,
+    /// Witnesses and clauses removed by simplifications that only preserve satisfiability
+    pub removed_clauses: Vec<(Literal, Clause)>,
+}
+
+impl SimplifiedFormula {
+    pub fn new<'a>(
+        clauses: impl Iterator<Item = &'a Clause>,
+        fixed_literals: Vec<Literal>,
+        removed_clauses: &[(Literal, Clause)],
+        num_vars: usize,
+    ) -> Self {
+        let mut values = PartialAssignment::new(num_vars);
+        for lit in &fixed_literals {
+            values.assign(*lit);
+        }
+
+        let remaining = clauses
+            .filter(|clause| !clause.literals.iter().any(|lit| lit.is_true(&values)))
+            .map(|clause| {
+                clause
+                    .literals
+                    .iter()
+                    .filter(|lit| !lit.is_false(&values))
+                    .copied()
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let original_vars = core::iter::once(Var::default())
+            .chain(
+                remaining
+                    .iter()
+                    .flatten()
+                    .map(|lit| lit.var())
+                    .sorted()
+                    .dedup(),
+            )
+            .collect_vec();
+        let mut new_vars = vec![Var::default(); num_vars + 1];
+        for (new_var, original_var) in original_vars.iter().enumerate() {
+            new_vars[*original_var] = Var::new(new_var);
+        }
+        let clauses = remaining
+            .into_iter()
+            .map(|literals| {
+                let literals = literals
+                    .into_iter()
+                    .map(|lit| Literal::from_value(new_vars[lit.var()], lit.positive()))
+                    .collect_vec();
+                Clause::from(literals)
+            })
+            .collect_vec();
+
+        SimplifiedFormula {
+            clauses,
+            num_vars: original_vars.len() - 1,
+            original_vars,
+            fixed_literals,
+            removed_clauses: removed_clauses.to_vec(),
+        }
+    }
+
+    /// The empty clause over no variables, for formulas already refuted by simplification
+    pub fn unsatisfiable() -> Self {
+        SimplifiedFormula {
+            clauses: vec![Clause::from(Vec::new())],
+            num_vars: 0,
+            original_vars: vec![Var::default()],
+            fixed_literals: vec![],
+            removed_clauses: vec![],
+        }
+    }
+
+    /// Maps a model of the simplified formula to a model of the original one
+    pub fn extend_model(&self, model: &Model, num_original_vars: usize) -> Model {
+        let mut extended = Model::new(vec![true; num_original_vars]);
+        for (new_var, original_var) in self.original_vars.iter().enumerate().skip(1) {
+            extended.set(*original_var, model[Var::new(new_var)]);
+        }
+        for lit in &self.fixed_literals {
+            extended.set(lit.var(), lit.positive());
+        }
+        for (witness, clause) in self.removed_clauses.iter().rev() {
+            if !clause.literals.iter().any(|lit| extended.value(*lit)) {
+                extended.set(witness.var(), witness.positive());
+            }
+        }
+        extended
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_dimacs(&self) -> String {
+        clauses_to_dimacs(&self.clauses, self.num_vars)
+    }
+
+    /// Information to map models back to the original variables, one entry per line:
+    /// `m <variable> <original variable>`, `f <original literal>` for fixed literals and
+    /// `r <witness> <original literals> 0` for removed clauses in the order of removal
+    pub fn map_to_string(&self) -> String {
+        let mut map = String::new();
+        for (new_var, original_var) in self.original_vars.iter().enumerate().skip(1) {
+            map.push_str(&format!("m {} {}\n", new_var, original_var));
+        }
+        for lit in &self.fixed_literals {
+            map.push_str(&format!("f {}\n", lit));
+        }
+        for (witness, clause) in &self.removed_clauses {
+            map.push_str(&format!(
+                "r {} {} 0\n",
+                witness,
+                clause.literals.iter().join(" ")
+            ));
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_literals_are_removed_and_vars_compacted() {
+        let clauses = ["2 -5 7", "-2 9", "5 7", "3 -9"].map(Clause::from);
+        let fixed = vec![Literal::new(-2), Literal::new(3)];
+        let removed = [(Literal::new(4), Clause::from("4 7"))];
+        let formula = SimplifiedFormula::new(clauses.iter(), fixed, &removed, 9);
+
+        assert_eq!(
+            formula.clauses,
+            vec![Clause::from("-1 2"), Clause::from("1 2")]
+        );
+        assert_eq!(formula.original_vars, [0, 5, 7].map(Var::new));
+        assert_eq!(formula.to_dimacs(), "p cnf 2 2\n-1 2 0\n1 2 0\n");
+        assert_eq!(
+            formula.map_to_string(),
+            "m 1 5\nm 2 7\nf -2\nf 3\nr 4 4 7 0\n"
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_formula() {
+        let formula = SimplifiedFormula::unsatisfiable();
+        assert_eq!(formula.to_dimacs(), "p cnf 0 1\n0\n");
+        assert_eq!(formula.map_to_string(), "");
+    }
+}
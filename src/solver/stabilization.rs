@@ -0,0 +1,72 @@
+use crate::solver::config::Config;
+
+/// Alternation between the focused mode, searching with the configured restart policy, heuristic
+/// and phases, and the stable mode with rare restarts by reluctant doubling, the stable heuristic
+/// and target phases, as in A. Biere, K. Fazekas, M. Fleury, and M. Heisinger, “CaDiCaL,
+/// Kissat, Paracooba, Plingeling and Treengeling Entering the SAT Competition 2020,” in Proc. of
+/// SAT Competition 2020, pp. 50–53. Both modes take the same number of conflicts, which grows
+/// geometrically.
+#[derive(Debug, Clone)]
+pub struct Stabilizer {
+    stable: bool,
+    mode_length: usize,
+    /// Growth of the mode lengths after every stable mode
+    mode_length_factor: usize,
+    conflicts_in_mode: usize,
+}
+
+impl Stabilizer {
+    pub fn init(config: &Config) -> Self {
+        Stabilizer {
+            stable: false,
+            mode_length: config.params.stabilization_interval,
+            mode_length_factor: config.params.stabilization_factor,
+            conflicts_in_mode: 0,
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.stable
+    }
+
+    /// Counts the conflict and returns whether the mode has to be switched
+    pub fn conflict(&mut self) -> bool {
+        self.conflicts_in_mode += 1;
+        if self.conflicts_in_mode < self.mode_length {
+            return false;
+        }
+
+        self.conflicts_in_mode = 0;
+        if self.stable {
+            self.mode_length *= self.mode_length_factor;
+        }
+        self.stable = !self.stable;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::params::Params;
+
+    #[test]
+    fn modes_alternate_with_growing_length() {
+        let config = Config {
+            params: Params {
+                stabilization_interval: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut stabilizer = Stabilizer::init(&config);
+        let modes = (0..14)
+            .map(|_| {
+                stabilizer.conflict();
+                stabilizer.is_stable()
+            })
+            .collect::<Vec<_>>();
+        let expected = [0, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0];
+        assert_eq!(modes, expected.map(|mode| mode == 1));
+    }
+}
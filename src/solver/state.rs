@@ -1,116 +1,233 @@
-use std::collections::HashMap;
+use alloc::borrow::Cow;
+use alloc::{vec, vec::Vec};
 
-use itertools::Itertools;
-
-use crate::cnf::{Clause, ClauseId, Literal, VarId};
+use crate::cnf::{Clause, ClauseId, Literal, PartialAssignment};
+use crate::solver::cardinality::CardinalityPropagator;
 use crate::solver::clause_database::ClauseDatabase;
+use crate::solver::external::ExternalReasons;
 use crate::solver::literal_watching::{LiteralWatcher, WatchUpdate};
+use crate::solver::params::Params;
+use crate::solver::reconstruction::ReconstructionStack;
 use crate::solver::statistics::StateStatistics;
-use crate::solver::unit_propagation::UnitPropagator;
+use crate::solver::tracer::VarTracer;
+use crate::solver::trail::{Assignment, AssignmentReason, ConstraintRef, Trail};
+use crate::solver::xor::XorPropagator;
 
 const MARKED_FOR_DELETION: ClauseId = ClauseId::MAX;
 
 #[derive(Debug, Clone)]
 pub struct State {
-    pub conflict_clause_id: Option<ClauseId>,
-    pub vars: Vec<Option<bool>>,
+    pub conflict: Option<ConstraintRef>,
+    pub vars: PartialAssignment,
     pub var_phases: Vec<bool>,
     pub clause_database: ClauseDatabase,
     pub literal_watcher: LiteralWatcher,
+    pub cardinality_propagator: CardinalityPropagator,
+    pub xor_propagator: XorPropagator,
+    pub external_reasons: ExternalReasons,
+    /// Clauses removed by the simplifications to extend the models
+    pub reconstruction_stack: ReconstructionStack,
     pub num_vars: usize,
     pub stats: StateStatistics,
+    /// See `Config::propagation_threads`
+    pub propagation_threads: usize,
+    /// Parameters of the search, as the heuristics are created from the state
+    pub params: Params,
+    pub tracer: VarTracer,
 }
 
 impl State {
     pub fn init(clauses: Vec<Clause>, n_vars: usize, proof_logging: bool) -> Self {
         // remove tautologies
-        let relevant_clauses = clauses
-            .clone()
+        let (relevant_clauses, tautologies): (Vec<_>, Vec<_>) = clauses
             .into_iter()
-            .filter(|clause| {
-                !clause
-                    .literals
-                    .iter()
-                    .any(|lit| clause.literals.contains(&-*lit))
-            })
-            .collect_vec();
+            .partition(|clause| !clause.is_tautology());
+        let literal_watcher = LiteralWatcher::new(&relevant_clauses, n_vars);
+        let stats = StateStatistics::new(relevant_clauses.len(), n_vars);
+        let mut clause_database = ClauseDatabase::init(relevant_clauses, proof_logging);
+        for tautology in &tautologies {
+            clause_database.proof_logger.delete(tautology);
+        }
 
         State {
-            conflict_clause_id: None,
-            vars: vec![None; n_vars + 1],
+            conflict: None,
+            vars: PartialAssignment::new(n_vars),
             var_phases: vec![true; n_vars + 1],
-            literal_watcher: LiteralWatcher::new(&relevant_clauses, n_vars),
-            stats: StateStatistics::new(relevant_clauses.len(), n_vars),
-            clause_database: ClauseDatabase::init(relevant_clauses.as_ref(), proof_logging),
+            literal_watcher,
+            cardinality_propagator: CardinalityPropagator::new(n_vars),
+            xor_propagator: XorPropagator::default(),
+            external_reasons: ExternalReasons::default(),
+            reconstruction_stack: ReconstructionStack::default(),
+            stats,
+            clause_database,
             num_vars: n_vars,
+            propagation_threads: 1,
+            params: Params::default(),
+            tracer: VarTracer::default(),
         }
     }
 
-    pub fn assign(&mut self, lit: Literal, unit_propagator: &mut UnitPropagator) {
+    pub fn assign(&mut self, lit: Literal) {
         self.stats.num_assignments += 1;
 
-        let (var_id, value) = lit.id_val();
-        if self.vars[var_id].is_some() {
+        let (var_id, value) = lit.var_val();
+        if self.vars.is_assigned(var_id) {
             panic!("Variable {} is already assigned!", var_id);
         }
-        self.vars[var_id] = Some(value);
+        self.vars.assign(lit);
         self.var_phases[var_id] = value;
+    }
 
+    /// Updates the watches of the clauses and constraints watching the literal that became
+    /// true, assigning the implied literals on the trail
+    pub fn propagate(&mut self, lit: Literal, trail: &mut Trail) {
+        self.stats.num_propagations += 1;
         let len = self.literal_watcher.affected_clauses(lit).len();
         for i in 0..len {
             // skip rest of clauses if conflict is detected
-            if self.conflict_clause_id.is_some() {
+            if self.conflict.is_some() {
                 break;
             }
 
             let clause_id = self.literal_watcher.affected_clauses(lit)[i];
+            let watch_update = self.find_watch_update(lit, clause_id);
+            self.apply_watch_update(lit, i, clause_id, watch_update, trail);
+        }
 
-            let clause = &mut self.clause_database[clause_id];
-
-            // check the blocking literal first
-            if clause.check_blocking_literal(&self.vars) {
-                continue;
-            }
+        self.finish_propagation(lit, trail);
+    }
 
-            let watch_update = self.literal_watcher.update_clause(clause, -lit, &self.vars);
+    pub(crate) fn find_watch_update(&self, lit: Literal, clause_id: ClauseId) -> WatchUpdate {
+        // check the blocking literal first
+        let blocking_literal = self.clause_database.blocking_literal(clause_id);
+        if blocking_literal.is_true(&self.vars) {
+            return WatchUpdate::Satisfied(blocking_literal);
+        }
 
-            match watch_update {
-                WatchUpdate::FoundNewWatch => {
-                    self.literal_watcher.affected_clauses(lit)[i] = MARKED_FOR_DELETION;
+        let clause = &self.clause_database[clause_id];
+        self.literal_watcher
+            .next_watch(clause, clause_id, -lit, &self.vars)
+    }
 
-                    self.literal_watcher
-                        .add_watch(clause.literals[0], clause_id);
-                }
-                WatchUpdate::Satisfied(blocking_literal) => {
-                    clause.blocking_literal = blocking_literal;
-                }
-                WatchUpdate::Unit(unit) => {
-                    unit_propagator.enqueue(unit, clause_id);
-                }
-                WatchUpdate::Conflict => {
-                    self.conflict_clause_id = Some(clause_id);
-                    self.stats.num_conflicts += 1;
-                }
+    /// Applies the update of the clause at position `i` of the clauses affected by `lit`. Units
+    /// are enqueued, as they may have been assigned since the update was found.
+    pub(crate) fn apply_watch_update(
+        &mut self,
+        lit: Literal,
+        i: usize,
+        clause_id: ClauseId,
+        watch_update: WatchUpdate,
+        trail: &mut Trail,
+    ) {
+        match watch_update {
+            WatchUpdate::FoundNewWatch(new_watch) => {
+                self.literal_watcher
+                    .replace_watch(clause_id, -lit, new_watch);
+                self.literal_watcher.affected_clauses(lit)[i] = MARKED_FOR_DELETION;
+            }
+            WatchUpdate::Satisfied(blocking_literal) => {
+                self.clause_database
+                    .set_blocking_literal(clause_id, blocking_literal);
+            }
+            WatchUpdate::Unit(unit) => {
+                trail.enqueue(self, unit, ConstraintRef::Clause(clause_id));
+            }
+            WatchUpdate::Conflict => {
+                self.conflict = Some(ConstraintRef::Clause(clause_id));
+                self.stats.num_conflicts += 1;
             }
         }
+    }
 
+    /// Removes the moved watches and propagates the other constraints
+    pub(crate) fn finish_propagation(&mut self, lit: Literal, trail: &mut Trail) {
         self.literal_watcher
             .affected_clauses(lit)
             .retain(|id| *id != MARKED_FOR_DELETION);
+
+        if self.conflict.is_none() && !self.cardinality_propagator.is_empty() {
+            let mut units = vec![];
+            match self
+                .cardinality_propagator
+                .propagate(lit, &self.vars, &mut units)
+            {
+                Some(id) => {
+                    self.conflict = Some(ConstraintRef::Cardinality(id));
+                    self.stats.num_conflicts += 1;
+                }
+                None => {
+                    for (unit, reason) in units {
+                        trail.enqueue(self, unit, reason);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Literals of the clause or the clause-like explanation of another constraint, containing
+    /// the implied literal. For conflicts, `implied` is `None`.
+    pub fn reason_literals(
+        &self,
+        reason: ConstraintRef,
+        implied: Option<Literal>,
+        trail: &Trail,
+    ) -> Cow<'_, [Literal]> {
+        match reason {
+            ConstraintRef::Clause(clause_id) => {
+                Cow::Borrowed(&self.clause_database[clause_id].literals)
+            }
+            ConstraintRef::Cardinality(id) => {
+                Cow::Owned(self.cardinality_propagator.explain(id, implied, trail))
+            }
+            ConstraintRef::Xor => Cow::Borrowed(self.xor_propagator.explain(implied)),
+            ConstraintRef::External => Cow::Borrowed(self.external_reasons.explain(implied)),
+        }
+    }
+
+    pub fn update_clause_statistics(&mut self) {
+        let num_learned_clauses = self.clause_database.num_learned_clauses();
+        self.stats.num_learned_clauses = num_learned_clauses;
+        self.stats.num_irredundant_clauses =
+            self.clause_database.num_clauses() - num_learned_clauses;
+        let fragmentation = self.clause_database.fragmentation();
+        self.stats.num_free_clause_ids = fragmentation.num_free_ids;
+        self.stats.wasted_literal_capacity = fragmentation.wasted_literal_capacity;
+    }
+
+    /// Compacts the clause database, see `ClauseDatabase::compact`, and renumbers the reasons on
+    /// the trail and the conflict
+    pub fn compact_clause_database(&mut self, trail: &mut Trail) {
+        let mapping = self.clause_database.compact(&mut self.literal_watcher);
+        trail.renumber_clauses(&mapping);
+        if let Some(ConstraintRef::Clause(clause_id)) = &mut self.conflict {
+            *clause_id = mapping[*clause_id].expect("the conflict clause isn't deleted");
+        }
+        self.stats.num_compactions += 1;
+        self.update_clause_statistics();
     }
 
-    pub fn unassign(&mut self, lit: Literal) {
-        self.vars[lit.id()] = None;
+    pub fn unassign(&mut self, assignment: &Assignment) {
+        if self.tracer.is_traced(assignment.literal.var()) {
+            self.tracer.unassigned(assignment);
+        }
+        self.vars.unassign(assignment.literal.var());
+        if let AssignmentReason::Forced(ConstraintRef::Clause(clause_id)) = assignment.reason {
+            self.clause_database.unlock(clause_id);
+        }
     }
 
     pub fn check_satisfied_and_update_blocking_literals(&mut self) -> bool {
         let mut new_blockings: Vec<(ClauseId, Literal)> = Vec::new();
         let mut is_sat = true;
         for clause_id in self.clause_database.necessary_clauses_iter() {
-            let clause = &self.clause_database[clause_id];
-            if clause.check_blocking_literal(&self.vars) {
+            if self
+                .clause_database
+                .blocking_literal(clause_id)
+                .is_true(&self.vars)
+            {
                 continue;
             }
+            let clause = &self.clause_database[clause_id];
             let true_lit = clause.literals.iter().find(|lit| lit.is_true(&self.vars));
             if let Some(lit) = true_lit {
                 new_blockings.push((clause_id, *lit));
@@ -120,19 +237,9 @@ impl State {
             }
         }
         for (clause_id, lit) in new_blockings {
-            self.clause_database[clause_id].blocking_literal = lit;
-        }
-        is_sat
-    }
-
-    pub fn get_assignment(&self) -> HashMap<VarId, bool> {
-        let mut result = HashMap::new();
-        for (id, val) in self.vars.iter().enumerate().skip(1) {
-            if let Some(val) = val {
-                result.insert(id as VarId, *val);
-            }
+            self.clause_database.set_blocking_literal(clause_id, lit);
         }
-        result
+        is_sat && self.cardinality_propagator.all_satisfied(&self.vars)
     }
 
     /// Verifies the watched literal invariant.
@@ -150,22 +257,20 @@ impl State {
             if self.clause_database[clause].literals.len() == 1 {
                 continue;
             }
-            let watches = &self.clause_database[clause].literals[0..2];
-            let zero = self.vars[watches[0].id()].is_none()
-                || self.vars[watches[0].id()] == Some(watches[0].positive());
-            let one = self.vars[watches[1].id()].is_none()
-                || self.vars[watches[1].id()] == Some(watches[1].positive());
-            assert!(zero || one || self.conflict_clause_id.is_some());
+            let watches = self.literal_watcher.watched_literals(clause);
+            let zero = watches[0].non_false(&self.vars);
+            let one = watches[1].non_false(&self.vars);
+            assert!(zero || one || self.conflict.is_some());
         }
 
         for clause_id in self.clause_database.iter() {
             if self.clause_database[clause_id].literals.len() == 1 {
                 continue;
             }
-            for lit in &self.clause_database[clause_id].literals[0..2] {
+            for lit in self.literal_watcher.watched_literals(clause_id) {
                 assert!(
                     self.literal_watcher
-                        .affected_clauses(-*lit)
+                        .affected_clauses(-lit)
                         .contains(&clause_id),
                     "Clause {} is not watched by {}",
                     clause_id,
@@ -178,8 +283,8 @@ impl State {
 
 #[cfg(test)]
 mod tests {
-    use crate::cnf::Clause;
-    use crate::cnf::Literal;
+    use crate::cnf::{Clause, Literal, PartialAssignment, Var};
+    use crate::solver::trail::Assignment;
 
     use super::*;
 
@@ -190,42 +295,234 @@ mod tests {
             Clause::from("1 -2 3"),
             Clause::from("-1 -2 3"),
         ];
-        let state = State::init(clauses);
+        let state = State::init(clauses, 3, false);
         assert_eq!(state.num_vars, 3);
-        assert_eq!(state.vars, vec![None, None, None, None]);
+        assert_eq!(state.vars, PartialAssignment::new(3));
         //assert_eq!(state.clause_database.len(), 3);
     }
 
     #[test]
     fn test_state_assign() {
         let clauses = vec![Clause::from("1 2 3"), Clause::from("-1 -2 3")];
-        let mut state = State::init(clauses);
-        let mut unit_prop = UnitPropagator::default();
-        state.assign(Literal::from(1), &mut unit_prop);
-        assert_eq!(state.vars[1], Some(true));
-        state.assign(Literal::from(2), &mut unit_prop);
-        assert_eq!(state.vars[2], Some(true));
+        let mut state = State::init(clauses, 3, false);
+        let mut trail = Trail::new(3);
+        trail.assign(&mut state, Literal::from(1), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
+        assert_eq!(state.vars.value(Var::new(1)), Some(true));
+        trail.assign(&mut state, Literal::from(2), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
+        assert_eq!(state.vars.value(Var::new(2)), Some(true));
         println!("{:?}", state);
-        assert_eq!(unit_prop.unit_queue[0], (Literal::from(3), 1));
-        state.assign(Literal::from(-3), &mut unit_prop);
-        assert!(state.conflict_clause_id.is_some());
+        assert_eq!(
+            trail.assignment_stack[2],
+            Assignment::forced(Literal::from(3), 2, ConstraintRef::Clause(1))
+        );
+        assert_eq!(trail.propagated, 3);
+        trail.enqueue(&mut state, Literal::from(-3), ConstraintRef::Clause(1));
+        assert_eq!(state.conflict, Some(ConstraintRef::Clause(1)));
     }
 
     #[test]
     fn test_var_watches() {
         let clauses = vec![Clause::from("1 2 3"), Clause::from("-1 -2 3")];
-        let mut state = State::init(clauses);
-        let mut unit_prop = UnitPropagator::default();
+        let mut state = State::init(clauses, 3, false);
+        let mut trail = Trail::new(3);
         println!("{:?}", state);
-        assert_eq!(state.literal_watcher.var_watches[1].pos, vec![0]);
-        assert_eq!(state.literal_watcher.var_watches[1].neg, vec![1]);
-        assert_eq!(state.literal_watcher.var_watches[3].pos, vec![]);
+        let watches = |state: &State, lit: i32| state.literal_watcher.watches(lit.into()).to_vec();
+        assert_eq!(watches(&state, 1), vec![0]);
+        assert_eq!(watches(&state, -1), vec![1]);
+        assert_eq!(watches(&state, 3), vec![]);
 
-        state.assign(Literal::from(1), &mut unit_prop);
-        state.assign(Literal::from(2), &mut unit_prop);
+        trail.assign(&mut state, Literal::from(1), AssignmentReason::Heuristic);
+        trail.assign(&mut state, Literal::from(2), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
         println!("{:?}", state);
 
-        assert_eq!(state.literal_watcher.var_watches[1].pos, vec![0]);
-        assert_eq!(state.literal_watcher.var_watches[3].pos, vec![1]);
+        assert_eq!(watches(&state, 1), vec![0]);
+        assert_eq!(watches(&state, 3), vec![1]);
+    }
+
+    #[test]
+    fn watches_are_independent_of_the_literal_order() {
+        let clauses = vec![Clause::from("1 2 3 4"), Clause::from("-1 -2 -3 -4")];
+        let mut state = State::init(clauses, 4, false);
+        let mut trail = Trail::new(4);
+        let watched = state.literal_watcher.watched_literals(0);
+
+        state.clause_database[0].literals.reverse();
+        state.clause_database[1].literals.sort();
+        assert_eq!(state.literal_watcher.watched_literals(0), watched);
+
+        for lit in [1, 2, 3] {
+            trail.assign(&mut state, Literal::from(lit), AssignmentReason::Heuristic);
+            trail.propagate(&mut state);
+            state.verify_watches();
+        }
+        assert_eq!(
+            trail.assignment_stack.last(),
+            Some(&Assignment::forced(
+                Literal::from(-4),
+                3,
+                ConstraintRef::Clause(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn compaction_renumbers_reasons_and_watches() {
+        let clauses = vec![
+            Clause::from("1 2 3"),
+            Clause::from("-1 -2 3"),
+            Clause::from("1 -3 4"),
+            Clause::from("2 3 -4"),
+        ];
+        let mut state = State::init(clauses, 4, false);
+        let mut trail = Trail::new(4);
+        for clause_id in [0, 2] {
+            state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher);
+        }
+        let fragmentation = state.clause_database.fragmentation();
+        assert_eq!((fragmentation.num_free_ids, fragmentation.num_ids), (2, 4));
+        assert_eq!(fragmentation.ratio(), 0.5);
+
+        trail.assign(&mut state, Literal::from(1), AssignmentReason::Heuristic);
+        trail.assign(&mut state, Literal::from(-3), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
+        assert_eq!(
+            trail.assignment_stack[2],
+            Assignment::forced(Literal::from(-2), 2, ConstraintRef::Clause(1))
+        );
+        assert_eq!(
+            trail.assignment_stack[3],
+            Assignment::forced(Literal::from(-4), 2, ConstraintRef::Clause(3))
+        );
+
+        state.compact_clause_database(&mut trail);
+        assert_eq!(state.clause_database.iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(
+            state.clause_database[0].literals,
+            Clause::from("-1 -2 3").literals
+        );
+        assert_eq!(
+            trail.assignment_stack[2],
+            Assignment::forced(Literal::from(-2), 2, ConstraintRef::Clause(0))
+        );
+        assert!(state.clause_database.is_reason(0));
+        assert_eq!(state.stats.num_free_clause_ids, 0);
+        state.verify_watches();
+
+        assert_eq!(
+            trail.assignment_stack[3],
+            Assignment::forced(Literal::from(-4), 2, ConstraintRef::Clause(1))
+        );
+        let clause_id = state.clause_database.add_clause(
+            Clause::from("-2 -4"),
+            &mut state.literal_watcher,
+            &state.vars,
+            &trail,
+        );
+        assert_eq!(clause_id, 2);
+        state.verify_watches();
+    }
+
+    #[test]
+    fn deleted_clause_ids_are_reused_unless_referenced() {
+        let clauses = vec![
+            Clause::from("1 -2 3"),
+            Clause::from("-1 -2 3"),
+            Clause::from("2 -3"),
+        ];
+        let mut state = State::init(clauses, 3, false);
+        let mut trail = Trail::new(3);
+        trail.assign(&mut state, Literal::from(-2), AssignmentReason::Heuristic);
+        trail.propagate(&mut state);
+        assert_eq!(
+            trail.assignment_stack[1],
+            Assignment::forced(Literal::from(-3), 1, ConstraintRef::Clause(2))
+        );
+
+        // the reason of ¬3 is kept, the other clause is deleted and its id reused
+        for clause_id in [0, 2] {
+            state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher);
+        }
+        assert_eq!(state.clause_database.iter().collect::<Vec<_>>(), vec![1, 2]);
+        let clause_id = state.clause_database.add_clause(
+            Clause::from("1 -2 -3"),
+            &mut state.literal_watcher,
+            &state.vars,
+            &trail,
+        );
+        assert_eq!(clause_id, 0);
+        state.verify_watches();
+    }
+
+    #[test]
+    fn last_deleted_clause_id_is_reused_first() {
+        let clauses = ["1 2", "-1 2", "1 -2", "-1 -2 3", "2 3"].map(Clause::from);
+        let mut state = State::init(clauses.to_vec(), 3, false);
+        let trail = Trail::new(3);
+        for clause_id in [3, 0, 2] {
+            state
+                .clause_database
+                .delete_clause_if_allowed(clause_id, &mut state.literal_watcher);
+        }
+        assert_eq!(state.clause_database.iter().collect::<Vec<_>>(), vec![1, 4]);
+        let reused = [Clause::from("1 3"), Clause::from("-1 3")].map(|clause| {
+            state.clause_database.add_clause(
+                clause,
+                &mut state.literal_watcher,
+                &state.vars,
+                &trail,
+            )
+        });
+        assert_eq!(reused, [2, 0]);
+        assert_eq!(
+            state.clause_database.iter().collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(state.clause_database.num_clauses(), 4);
+        state.verify_watches();
+    }
+
+    #[test]
+    fn used_learned_clauses_survive_one_reduction() {
+        let mut state = State::init(vec![Clause::from("1 2")], 5, false);
+        let trail = Trail::new(5);
+        let learned = ["1 2 3", "-1 2 3", "1 -2 3 4", "-1 -2 3 4 5"]
+            .into_iter()
+            .zip([2, 3, 5, 6])
+            .map(|(literals, lbd)| {
+                let literals = Clause::from(literals).literals;
+                state.clause_database.add_clause(
+                    Clause::from_literals_and_lbd(literals, lbd),
+                    &mut state.literal_watcher,
+                    &state.vars,
+                    &trail,
+                )
+            })
+            .collect::<Vec<_>>();
+        let params = crate::solver::params::Params {
+            reduce_interval: 0,
+            reduce_interval_increment: 0,
+            ..Default::default()
+        };
+        let reduce = |state: &mut State| {
+            state.clause_database.delete_clauses_if_necessary(
+                ConstraintRef::Clause(0),
+                &mut state.literal_watcher,
+                &params,
+            )
+        };
+
+        state.clause_database.mark_used(learned[3]);
+        assert!(reduce(&mut state));
+        assert_eq!(state.clause_database.num_learned_clauses(), 4);
+        assert!(reduce(&mut state));
+        assert_eq!(state.clause_database.num_learned_clauses(), 3);
+        state.verify_watches();
     }
 }
@@ -1,6 +1,105 @@
+use crate::cnf::Clause;
+use crate::solver::clock::Instant;
+use alloc::{format, string::String, string::ToString, vec, vec::Vec};
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+use itertools::Itertools;
+#[cfg(feature = "std")]
 use prettytable::{row, Table};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+/// Values of at least this size share the last bucket of a histogram
+const HISTOGRAM_BUCKETS: usize = 10;
+
+/// Distribution of a clause property like the LBD or the length
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Histogram {
+    buckets: Vec<usize>,
+    sum: usize,
+    count: usize,
+}
+
+impl Histogram {
+    pub fn add(&mut self, value: usize) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; HISTOGRAM_BUCKETS + 1];
+        }
+        self.buckets[value.min(HISTOGRAM_BUCKETS)] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.sum as f64 / count as f64,
+        }
+    }
+
+    pub fn sum(&self) -> usize {
+        self.sum
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of values per bucket, the last one counts all values of at least its size
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    /// Non-empty buckets as `value:count`, the last one as `value+:count`
+    pub fn buckets_to_string(&self) -> String {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(value, count)| match value {
+                HISTOGRAM_BUCKETS => format!("{}+:{}", value, count),
+                _ => format!("{}:{}", value, count),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Machine-readable output of the final statistics
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum StatisticsFormat {
+    /// One object with the histograms as arrays of bucket counts
+    #[cfg_attr(feature = "std", clap(name = "json"))]
+    Json,
+    /// A header and a row of values, without the histograms
+    #[cfg_attr(feature = "std", clap(name = "csv"))]
+    Csv,
+}
+
+/// Memory usage in KiB from `/proc/self/status`, only available on Linux
+#[cfg(feature = "std")]
+fn memory_kib(field: &str) -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with(field))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(feature = "std"))]
+fn memory_kib(_field: &str) -> Option<usize> {
+    None
+}
+
+/// Peak resident set size in KiB
+fn peak_memory_kib() -> Option<usize> {
+    memory_kib("VmHWM:")
+}
+
+/// Current resident set size in KiB
+pub fn resident_memory_kib() -> Option<usize> {
+    memory_kib("VmRSS:")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateStatistics {
     pub num_clauses: usize,
     pub num_vars: usize,
@@ -10,10 +109,61 @@ pub struct StateStatistics {
     pub num_propagations: usize,
     pub num_assignments: usize,
     pub num_restarts: usize,
+    /// Decision levels kept by restarts reusing the trail
+    pub num_reused_levels: usize,
+    /// Implied literals assigned again from the saved trail, see `Config::trail_saving`
+    pub num_replayed_assignments: usize,
     pub num_unassignments: usize,
     pub num_ple: usize,
-    pub start_time: std::time::Instant,
-    pub time: std::time::Duration,
+    pub num_strengthened_clauses: usize,
+    /// Clauses learned in addition to the asserting ones, see `AdditionalClause`
+    pub num_additional_clauses: usize,
+    /// Clauses added by `Solver::import_clauses` and the import callback
+    pub num_imported_clauses: usize,
+    /// Batches of literals propagated by multiple threads, see `Config::propagation_threads`
+    pub num_parallel_batches: usize,
+    /// Local searches setting the saved phases, see `Walker`
+    pub num_walks: usize,
+    pub num_walk_flips: usize,
+    pub learned_lbds: Histogram,
+    pub learned_lengths: Histogram,
+    /// Clauses currently in the database, updated by `State::update_clause_statistics`
+    pub num_learned_clauses: usize,
+    pub num_irredundant_clauses: usize,
+    /// Ids of deleted clauses waiting for reuse and the literals still allocated for them, see
+    /// `ClauseDatabase::fragmentation`
+    pub num_free_clause_ids: usize,
+    pub wasted_literal_capacity: usize,
+    /// Renumberings of the clause database, see `Solver::compact`
+    pub num_compactions: usize,
+    pub num_inprocessings: usize,
+    pub num_eliminated_vars: usize,
+    /// Eliminations which only resolved the gate clauses with the other clauses
+    pub num_gate_eliminations: usize,
+    /// Variables replaced by an equivalent literal
+    pub num_substituted_vars: usize,
+    /// Variables of autarkies, whose clauses were removed
+    pub num_autarky_vars: usize,
+    /// Clauses added by variable elimination
+    pub num_resolvents: usize,
+    /// Irredundant clauses deleted as they were subsumed by another one
+    pub num_subsumed_clauses: usize,
+    /// Literals removed by self-subsuming resolution
+    pub num_self_subsumptions: usize,
+    /// Clauses shortened by vivification
+    pub num_vivified_clauses: usize,
+    /// Literals whose propagation led to a conflict, so their negation was fixed
+    pub num_failed_literals: usize,
+    /// Irredundant clauses deleted as they were blocked
+    pub num_blocked_clauses: usize,
+    /// Clauses removed by variable elimination, substitution, autarkies, subsumption and
+    /// blocked clause elimination
+    pub num_inprocessing_deleted_clauses: usize,
+    pub inprocessing_time: core::time::Duration,
+    pub peak_memory_kib: Option<usize>,
+    #[serde(skip, default = "crate::solver::clock::Instant::now")]
+    pub start_time: Instant,
+    pub time: core::time::Duration,
 }
 
 impl Default for StateStatistics {
@@ -27,10 +177,39 @@ impl Default for StateStatistics {
             num_propagations: 0,
             num_assignments: 0,
             num_restarts: 0,
+            num_reused_levels: 0,
+            num_replayed_assignments: 0,
             num_unassignments: 0,
             num_ple: 0,
-            start_time: std::time::Instant::now(),
-            time: std::time::Duration::from_secs(123),
+            num_strengthened_clauses: 0,
+            num_additional_clauses: 0,
+            num_imported_clauses: 0,
+            num_parallel_batches: 0,
+            num_walks: 0,
+            num_walk_flips: 0,
+            learned_lbds: Histogram::default(),
+            learned_lengths: Histogram::default(),
+            num_learned_clauses: 0,
+            num_irredundant_clauses: 0,
+            num_free_clause_ids: 0,
+            wasted_literal_capacity: 0,
+            num_compactions: 0,
+            num_inprocessings: 0,
+            num_eliminated_vars: 0,
+            num_gate_eliminations: 0,
+            num_substituted_vars: 0,
+            num_autarky_vars: 0,
+            num_resolvents: 0,
+            num_subsumed_clauses: 0,
+            num_self_subsumptions: 0,
+            num_vivified_clauses: 0,
+            num_failed_literals: 0,
+            num_blocked_clauses: 0,
+            num_inprocessing_deleted_clauses: 0,
+            inprocessing_time: core::time::Duration::ZERO,
+            peak_memory_kib: None,
+            start_time: Instant::now(),
+            time: core::time::Duration::from_secs(123),
         }
     }
 }
@@ -46,12 +225,154 @@ impl StateStatistics {
 
     pub fn stop_timing(&mut self) {
         self.time = self.start_time.elapsed();
+        self.peak_memory_kib = peak_memory_kib();
     }
 
     pub fn start_timing(&mut self) {
-        self.start_time = std::time::Instant::now();
+        self.start_time = Instant::now();
+    }
+
+    pub fn learned_clause(&mut self, clause: &Clause) {
+        self.learned_lbds.add(clause.lbd.unwrap_or_default());
+        self.learned_lengths.add(clause.literals.len());
+    }
+
+    /// Scalar statistics by name, `None` if unavailable
+    fn values(&self) -> Vec<(&'static str, Option<f64>)> {
+        let per_second = |count: usize| count as f64 / self.time.as_secs_f64();
+        vec![
+            ("num_clauses", Some(self.num_clauses as f64)),
+            ("num_vars", Some(self.num_vars as f64)),
+            ("num_assignments", Some(self.num_assignments as f64)),
+            ("num_propagations", Some(self.num_propagations as f64)),
+            ("num_conflicts", Some(self.num_conflicts as f64)),
+            ("num_decisions", Some(self.num_decisions as f64)),
+            ("num_restarts", Some(self.num_restarts as f64)),
+            ("num_reused_levels", Some(self.num_reused_levels as f64)),
+            (
+                "num_replayed_assignments",
+                Some(self.num_replayed_assignments as f64),
+            ),
+            (
+                "num_strengthened_clauses",
+                Some(self.num_strengthened_clauses as f64),
+            ),
+            (
+                "num_additional_clauses",
+                Some(self.num_additional_clauses as f64),
+            ),
+            (
+                "num_imported_clauses",
+                Some(self.num_imported_clauses as f64),
+            ),
+            (
+                "num_parallel_batches",
+                Some(self.num_parallel_batches as f64),
+            ),
+            ("num_walks", Some(self.num_walks as f64)),
+            ("num_walk_flips", Some(self.num_walk_flips as f64)),
+            ("num_learned_clauses", Some(self.num_learned_clauses as f64)),
+            (
+                "num_irredundant_clauses",
+                Some(self.num_irredundant_clauses as f64),
+            ),
+            ("num_free_clause_ids", Some(self.num_free_clause_ids as f64)),
+            (
+                "wasted_literal_capacity",
+                Some(self.wasted_literal_capacity as f64),
+            ),
+            ("num_compactions", Some(self.num_compactions as f64)),
+            ("learned_lbd_mean", Some(self.learned_lbds.mean())),
+            ("learned_length_mean", Some(self.learned_lengths.mean())),
+            ("num_inprocessings", Some(self.num_inprocessings as f64)),
+            ("num_eliminated_vars", Some(self.num_eliminated_vars as f64)),
+            (
+                "num_gate_eliminations",
+                Some(self.num_gate_eliminations as f64),
+            ),
+            (
+                "num_substituted_vars",
+                Some(self.num_substituted_vars as f64),
+            ),
+            ("num_autarky_vars", Some(self.num_autarky_vars as f64)),
+            ("num_resolvents", Some(self.num_resolvents as f64)),
+            (
+                "num_subsumed_clauses",
+                Some(self.num_subsumed_clauses as f64),
+            ),
+            (
+                "num_self_subsumptions",
+                Some(self.num_self_subsumptions as f64),
+            ),
+            (
+                "num_vivified_clauses",
+                Some(self.num_vivified_clauses as f64),
+            ),
+            ("num_failed_literals", Some(self.num_failed_literals as f64)),
+            ("num_blocked_clauses", Some(self.num_blocked_clauses as f64)),
+            (
+                "num_inprocessing_deleted_clauses",
+                Some(self.num_inprocessing_deleted_clauses as f64),
+            ),
+            (
+                "inprocessing_time",
+                Some(self.inprocessing_time.as_secs_f64()),
+            ),
+            (
+                "assignments_per_second",
+                Some(per_second(self.num_assignments)),
+            ),
+            (
+                "propagations_per_second",
+                Some(per_second(self.num_propagations)),
+            ),
+            ("conflicts_per_second", Some(per_second(self.num_conflicts))),
+            (
+                "peak_memory_kib",
+                self.peak_memory_kib.map(|kib| kib as f64),
+            ),
+            ("time", Some(self.time.as_secs_f64())),
+        ]
     }
 
+    pub fn to_json(&self) -> String {
+        let values = self.values().into_iter().map(|(name, value)| {
+            let value = value.map_or("null".to_string(), |value| value.to_string());
+            format!("  \"{}\": {}", name, value)
+        });
+        let histograms = [
+            ("learned_lbd_histogram", &self.learned_lbds),
+            ("learned_length_histogram", &self.learned_lengths),
+        ]
+        .into_iter()
+        .map(|(name, histogram)| {
+            format!(
+                "  \"{}\": [{}]",
+                name,
+                histogram.buckets().iter().join(", ")
+            )
+        });
+        format!("{{\n{}\n}}\n", values.chain(histograms).join(",\n"))
+    }
+
+    pub fn to_csv(&self) -> String {
+        let values = self.values();
+        let header = values.iter().map(|(name, _)| name).join(",");
+        let row = values
+            .iter()
+            .map(|(_, value)| value.map_or(String::new(), |value| value.to_string()))
+            .join(",");
+        format!("{}\n{}\n", header, row)
+    }
+
+    pub fn to_format(&self, format: StatisticsFormat) -> String {
+        match format {
+            StatisticsFormat::Json => self.to_json(),
+            StatisticsFormat::Csv => self.to_csv(),
+        }
+    }
+
+    #[cfg(feature = "std")]
     pub fn to_table(&self) -> Table {
         let mut table = Table::new();
         table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
@@ -67,17 +388,80 @@ impl StateStatistics {
 
         // each row with name -> property
         table.add_row(row!["Assignments", self.num_assignments]);
+        table.add_row(row!["Propagations", self.num_propagations]);
         table.add_row(row!["Conflicts", self.num_conflicts]);
         table.add_row(row!["Restarts", self.num_restarts]);
+        table.add_row(row!["Reused decision levels", self.num_reused_levels]);
+        table.add_row(row!["Replayed assignments", self.num_replayed_assignments]);
+        table.add_row(row!["Strengthened clauses", self.num_strengthened_clauses]);
+        table.add_row(row!["Learned clauses", self.num_learned_clauses]);
+        table.add_row(row!["Irredundant clauses", self.num_irredundant_clauses]);
+        table.add_row(row![
+            "Learned clause LBD",
+            format!("{:.2} avg", self.learned_lbds.mean())
+        ]);
+        table.add_row(row!["", self.learned_lbds.buckets_to_string()]);
+        table.add_row(row![
+            "Learned clause length",
+            format!("{:.2} avg", self.learned_lengths.mean())
+        ]);
+        table.add_row(row!["", self.learned_lengths.buckets_to_string()]);
+        table.add_row(row![
+            "Inprocessing",
+            format!(
+                "{} runs, {} eliminated vars, {:.3}s",
+                self.num_inprocessings,
+                self.num_eliminated_vars,
+                self.inprocessing_time.as_secs_f32()
+            )
+        ]);
+        table.add_row(row![
+            "",
+            format!(
+                "{} by gates, {} substituted vars, {} autarky vars",
+                self.num_gate_eliminations, self.num_substituted_vars, self.num_autarky_vars
+            )
+        ]);
+        table.add_row(row![
+            "",
+            format!(
+                "{} added clauses, {} deleted clauses",
+                self.num_resolvents, self.num_inprocessing_deleted_clauses
+            )
+        ]);
+        table.add_row(row![
+            "",
+            format!(
+                "{} subsumed, {} self-subsumptions, {} vivified clauses",
+                self.num_subsumed_clauses, self.num_self_subsumptions, self.num_vivified_clauses
+            )
+        ]);
+        table.add_row(row![
+            "",
+            format!(
+                "{} failed literals, {} blocked clauses",
+                self.num_failed_literals, self.num_blocked_clauses
+            )
+        ]);
         table.add_row(row![
             "Assignments per second",
             (self.num_assignments as f32 / self.time.as_secs_f32()) as u32
         ]);
+        table.add_row(row![
+            "Propagations per second",
+            (self.num_propagations as f32 / self.time.as_secs_f32()) as u32
+        ]);
         table.add_row(row![
             "Conflicts per second",
             (self.num_conflicts as f32 / self.time.as_secs_f32()) as u32
         ]);
 
+        if let Some(peak_memory_kib) = self.peak_memory_kib {
+            table.add_row(row![
+                "Peak memory",
+                format!("{:.1} MiB", peak_memory_kib as f32 / 1024.0)
+            ]);
+        }
         table.add_row(row![
             "Time (approx.)",
             format!("{:.3}s", self.time.as_secs_f32())
@@ -85,3 +469,39 @@ impl StateStatistics {
         table
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets() {
+        let mut histogram = Histogram::default();
+        for value in [2, 2, 3, 20, 10] {
+            histogram.add(value);
+        }
+        assert_eq!(histogram.mean(), 37.0 / 5.0);
+        assert_eq!(histogram.buckets_to_string(), "2:2 3:1 10+:2");
+    }
+
+    #[test]
+    fn machine_readable_output() {
+        let mut stats = StateStatistics::new(3, 2);
+        stats.learned_lbds.add(2);
+        stats.peak_memory_kib = None;
+
+        let json = stats.to_json();
+        assert!(json.starts_with("{\n  \"num_clauses\": 3,\n  \"num_vars\": 2,"));
+        assert!(json.contains("\"peak_memory_kib\": null,"));
+        assert!(json.contains("\"learned_lbd_histogram\": [0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0],"));
+        assert!(json.ends_with("\"learned_length_histogram\": []\n}\n"));
+
+        let csv = stats.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let num_columns = lines[0].split(',').count();
+        assert_eq!(lines[1].split(',').count(), num_columns);
+        assert!(lines[0].starts_with("num_clauses,num_vars,"));
+        assert!(lines[1].starts_with("3,2,"));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cnf::{Literal, Model, Var};
+use crate::solver::state::State;
+use crate::solver::trail::{Assignment, AssignmentReason, ConstraintRef};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::{Debug, Formatter};
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+/// Writes every assignment with its reason and every unassignment of the variables in
+/// `Config::trace_vars`, one per line, to debug wrong models without a debugger:
+///
+/// ```text
+/// assign 3 level 2 decision
+/// assign -4 level 2 clause 17: -4 -3 5
+/// unassign -4 level 2
+/// model -4
+/// ```
+///
+/// Only available with `std`, the writer is shared by clones of the solver state.
+#[derive(Clone, Default)]
+pub struct VarTracer {
+    /// Whether each variable is traced, empty if none is
+    traced: Vec<bool>,
+    #[cfg(feature = "std")]
+    writer: Option<Arc<Mutex<dyn Write + Send>>>,
+}
+
+impl Debug for VarTracer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VarTracer")
+            .field("traced", &self.traced)
+            .finish()
+    }
+}
+
+impl VarTracer {
+    #[cfg(feature = "std")]
+    pub fn new(vars: &[Var], num_vars: usize, writer: impl Write + Send + 'static) -> Self {
+        let mut traced = alloc::vec![false; num_vars + 1];
+        for var in vars {
+            if var.index() <= num_vars {
+                traced[*var] = true;
+            }
+        }
+        VarTracer {
+            traced,
+            writer: Some(Arc::new(Mutex::new(writer))),
+        }
+    }
+
+    pub fn is_traced(&self, var: Var) -> bool {
+        !self.traced.is_empty() && self.traced[var]
+    }
+
+    pub fn assigned(&self, assignment: &Assignment, state: &State) {
+        let reason = match assignment.reason {
+            AssignmentReason::Heuristic => "decision".into(),
+            AssignmentReason::Assumption => "assumption".into(),
+            AssignmentReason::Forced(ConstraintRef::Clause(clause_id)) => {
+                let literals = &state.clause_database[clause_id].literals;
+                format!("clause {}: {}", clause_id, join(literals))
+            }
+            AssignmentReason::Forced(reason) => format!("{}", reason),
+        };
+        self.write(format!(
+            "assign {} level {} {}",
+            assignment.literal, assignment.decision_level, reason
+        ));
+    }
+
+    pub fn unassigned(&self, assignment: &Assignment) {
+        self.write(format!(
+            "unassign {} level {}",
+            assignment.literal, assignment.decision_level
+        ));
+    }
+
+    /// The values of the traced variables in a model, after extending it to eliminated ones
+    pub fn model(&self, model: &Model) {
+        for var in Var::range(self.traced.len().saturating_sub(1)) {
+            if self.traced[var] && var.index() <= model.num_vars() {
+                let lit = Literal::from_value(var, model[var]);
+                self.write(format!("model {}", lit));
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn write(&self, line: String) {
+        if let Some(writer) = &self.writer {
+            let mut writer = writer.lock().unwrap();
+            // tracing must not abort the search
+            let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn write(&self, _line: String) {}
+}
+
+fn join(literals: &[Literal]) -> String {
+    literals
+        .iter()
+        .map(|lit| format!("{}", lit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
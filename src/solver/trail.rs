@@ -1,7 +1,13 @@
-use crate::cnf::{ClauseId, Literal};
+use crate::cnf::{ClauseId, Literal, Var};
+use crate::solver::cardinality::CardinalityId;
+use crate::solver::conflict_graph::ConflictGraph;
 use crate::solver::heuristic::Heuristic;
 use crate::solver::state::State;
-use crate::solver::unit_propagation::UnitPropagator;
+use alloc::{string::String, vec, vec::Vec};
+#[cfg(feature = "std")]
+use clap::ValueEnum;
+use core::fmt::{Display, Formatter};
+use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Assignment {
@@ -19,7 +25,7 @@ impl Assignment {
         }
     }
 
-    pub fn forced(literal: Literal, decision_level: usize, reason: ClauseId) -> Self {
+    pub fn forced(literal: Literal, decision_level: usize, reason: ConstraintRef) -> Self {
         Assignment {
             literal,
             reason: AssignmentReason::Forced(reason),
@@ -32,7 +38,93 @@ impl Assignment {
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssignmentReason {
     Heuristic,
-    Forced(ClauseId),
+    /// Decision of an assumption. Like a heuristic decision it opens a decision level, but the
+    /// conflict analysis of a failed assumption stops at it, see `Solver::failed`.
+    Assumption,
+    Forced(ConstraintRef),
+}
+
+impl AssignmentReason {
+    /// Whether the assignment is a decision and opens a new decision level
+    pub fn is_decision(&self) -> bool {
+        matches!(
+            self,
+            AssignmentReason::Heuristic | AssignmentReason::Assumption
+        )
+    }
+}
+
+/// Constraint forcing an assignment or being in conflict
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstraintRef {
+    Clause(ClauseId),
+    Cardinality(CardinalityId),
+    /// The reason clauses of XOR constraints are stored by the implied variable
+    Xor,
+    /// Reason clauses of the external propagator, also stored by the implied variable
+    External,
+}
+
+impl Display for ConstraintRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConstraintRef::Clause(clause_id) => write!(f, "clause {}", clause_id),
+            ConstraintRef::Cardinality(id) => write!(f, "cardinality {}", id),
+            ConstraintRef::Xor => write!(f, "xor"),
+            ConstraintRef::External => write!(f, "external"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum BacktrackingMode {
+    #[cfg_attr(feature = "std", clap(name = "non-chronological"))]
+    NonChronological,
+    #[cfg_attr(feature = "std", clap(name = "chronological"))]
+    Chronological,
+}
+
+/// Which decision levels a restart keeps, see `Trail::restart_reusing_trail`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(ValueEnum))]
+pub enum ReuseStrategy {
+    /// The levels whose decisions the heuristic prefers over the next variable it would decide
+    #[cfg_attr(feature = "std", clap(name = "preferred"))]
+    Preferred,
+    /// The levels up to where the order of the heuristic diverges from the trail, i.e. whose
+    /// decisions the heuristic also prefers over all later decisions
+    #[cfg_attr(feature = "std", clap(name = "best-first"))]
+    BestFirst,
+}
+
+impl BacktrackingMode {
+    /// Chronological backtracking as described in A. Nadel and V. Ryvchin, “Chronological
+    /// Backtracking,” in Theory and Applications of Satisfiability Testing – SAT 2018, pp. 111–121.
+    /// Instead of jumping back to the assertion level, only the last decision level is undone if
+    /// the jump would throw away more levels than the threshold. This is a simplification of the
+    /// paper: the UIP is assigned at the current level instead of its lower assertion level, so
+    /// there is no out-of-order trail and conflict analysis stays unchanged. Every new assignment
+    /// gets the current level, so levels on the trail stay monotone and each conflict has a literal
+    /// at the current level. The price is that such levels overestimate the real ones, which only
+    /// makes later backjumps shorter and LBDs larger.
+    pub fn backtrack_level(
+        &self,
+        decision_level: usize,
+        assertion_level: usize,
+        threshold: usize,
+    ) -> usize {
+        match self {
+            BacktrackingMode::NonChronological => assertion_level,
+            BacktrackingMode::Chronological => {
+                if decision_level - assertion_level > threshold {
+                    decision_level - 1
+                } else {
+                    assertion_level
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +133,18 @@ pub struct Trail {
     pub var_decision_level: Vec<usize>,
     pub var_assignment_pos: Vec<usize>,
     pub decision_level: usize,
+    /// Number of decision levels opened by assumptions. Assumptions are decided before any
+    /// other decision, so these are the levels 1 to `assumption_level`.
+    pub assumption_level: usize,
+    /// Length of the prefix of the trail whose consequences have been propagated. The rest of
+    /// the trail serves as the propagation queue.
+    pub propagated: usize,
+    /// Length of the prefix of the trail the external propagator has been notified about
+    pub num_notified: usize,
+    /// Whether notified assignments have been undone since the last notification
+    pub notified_backtrack: bool,
+    /// Assignments undone by the last backjump in reverse order, see `Trail::backjump_saving`
+    pub saved: Vec<Assignment>,
 }
 
 impl Trail {
@@ -50,106 +154,281 @@ impl Trail {
             var_decision_level: vec![0; num_vars + 1],
             var_assignment_pos: vec![0; num_vars + 1],
             decision_level: 0,
+            assumption_level: 0,
+            propagated: 0,
+            num_notified: 0,
+            notified_backtrack: false,
+            saved: vec![],
         }
     }
-    pub fn assign(
-        &mut self,
-        state: &mut State,
-        unit_propagator: &mut UnitPropagator,
-        literal: Literal,
-        reason: AssignmentReason,
-    ) {
+    pub fn assign(&mut self, state: &mut State, literal: Literal, reason: AssignmentReason) {
         // increase before because every
         // forced assigment is at the same level
-        if reason == AssignmentReason::Heuristic {
+        if reason.is_decision() {
             self.decision_level += 1;
         }
+        if reason == AssignmentReason::Assumption {
+            self.assumption_level = self.decision_level;
+        }
         let assignment = Assignment {
             literal,
             reason,
             decision_level: self.decision_level,
         };
 
+        if let AssignmentReason::Forced(ConstraintRef::Clause(clause_id)) = assignment.reason {
+            state.clause_database.lock(clause_id);
+        }
         self.push_assignment(assignment.clone());
-        self.var_decision_level[literal.id()] = self.decision_level;
-        self.var_assignment_pos[literal.id()] = self.assignment_stack.len() - 1;
+        self.var_decision_level[literal.var()] = self.decision_level;
+        self.var_assignment_pos[literal.var()] = self.assignment_stack.len() - 1;
+
+        if state.tracer.is_traced(literal.var()) {
+            state.tracer.assigned(&assignment, state);
+        }
+        state.assign(assignment.into());
+    }
+
+    /// Assigns a literal implied by the reason, unless it is already true. If it is already
+    /// false, the reason is in conflict.
+    pub fn enqueue(&mut self, state: &mut State, literal: Literal, reason: ConstraintRef) {
+        match literal.value(&state.vars) {
+            Some(true) => {}
+            Some(false) => {
+                if state.conflict.is_none() {
+                    state.conflict = Some(reason);
+                    state.stats.num_conflicts += 1;
+                }
+            }
+            None => self.assign(state, literal, AssignmentReason::Forced(reason)),
+        }
+    }
+
+    /// Propagates all assignments on the trail that haven't been propagated yet, in the order
+    /// they were made. Implied literals are assigned right away, so they are propagated once
+    /// the loop reaches them.
+    pub fn propagate(&mut self, state: &mut State) {
+        #[cfg(feature = "parallel")]
+        if state.propagation_threads > 1 {
+            return self.propagate_in_parallel(state);
+        }
+
+        while self.propagated < self.assignment_stack.len() && state.conflict.is_none() {
+            if !self.saved.is_empty() {
+                self.replay_saved(state);
+            }
+            let literal = self.assignment_stack[self.propagated].literal;
+            self.propagated += 1;
+            state.propagate(literal, self);
+        }
+    }
 
-        state.assign(assignment.into(), unit_propagator);
+    /// Assigns the implied literals of the saved trail, in the order they were assigned before
+    /// the backjump, as long as the earlier saved literals hold again. Then the reasons of the
+    /// implied literals are unit again, which is checked as they may have been strengthened.
+    /// A saved decision that isn't assigned yet stops the replay until it is decided again, a
+    /// false decision or a reason that isn't unit discards the rest. A false implied literal
+    /// makes its reason a conflict.
+    pub(crate) fn replay_saved(&mut self, state: &mut State) {
+        while let Some(assignment) = self.saved.last() {
+            let literal = assignment.literal;
+            match (literal.value(&state.vars), &assignment.reason) {
+                (Some(true), _) => {}
+                (None, reason) if reason.is_decision() => return,
+                (value, AssignmentReason::Forced(ConstraintRef::Clause(clause_id)))
+                    if is_unit_reason(state, literal, *clause_id) =>
+                {
+                    let reason = ConstraintRef::Clause(*clause_id);
+                    if value.is_none() {
+                        state.stats.num_replayed_assignments += 1;
+                    }
+                    self.enqueue(state, literal, reason);
+                    if state.conflict.is_some() {
+                        self.saved.clear();
+                        return;
+                    }
+                }
+                _ => {
+                    self.saved.clear();
+                    return;
+                }
+            }
+            self.saved.pop();
+        }
+    }
+
+    /// Applies new clause ids after `ClauseDatabase::compact` to the reasons on the trail. The
+    /// saved trail is discarded, as its reasons may have been deleted.
+    pub fn renumber_clauses(&mut self, mapping: &[Option<ClauseId>]) {
+        for assignment in &mut self.assignment_stack {
+            if let AssignmentReason::Forced(ConstraintRef::Clause(clause_id)) =
+                &mut assignment.reason
+            {
+                *clause_id = mapping[*clause_id].expect("reason clauses aren't deleted");
+            }
+        }
+        self.saved.clear();
     }
 
     /// Backtracks completely, including the unit clause forced assignments
     /// This is necessary for inprocessing
     pub fn backtrack_completely(&mut self, state: &mut State, heuristic: &mut dyn Heuristic) {
         while let Some(assignment) = self.assignment_stack.pop() {
-            state.unassign(assignment.literal);
+            state.unassign(&assignment);
             heuristic.unassign(&assignment);
         }
         self.decision_level = 0;
-        state.conflict_clause_id = None;
+        self.assumption_level = 0;
+        self.propagated = 0;
+        state.conflict = None;
+        self.saved.clear();
+        self.truncate_notified();
     }
 
-    /// Backtrack to the last heuristic assignment
-    /// and forces it to be the opposite value
-    /// returns the forced assignment or none (implies unsat)
+    /// Backjumps after a conflict like `backtrack`, but saves the undone assignments, so their
+    /// implications are assigned again by `Trail::replay_saved` without searching the watch
+    /// lists once their decisions hold again. Trail saving as described in R. Hickey and
+    /// F. Bacchus, “Trail Saving on Backtrack,” in Theory and Applications of Satisfiability
+    /// Testing – SAT 2020, pp. 46–61.
+    pub fn backjump_saving(
+        &mut self,
+        state: &mut State,
+        heuristic: &mut dyn Heuristic,
+        backtrack_level: usize,
+    ) {
+        let kept = self
+            .assignment_stack
+            .iter()
+            .rposition(|assignment| assignment.decision_level <= backtrack_level)
+            .map_or(0, |position| position + 1);
+        let saved = self.assignment_stack[kept..]
+            .iter()
+            .rev()
+            .cloned()
+            .collect();
+        self.backtrack(state, heuristic, backtrack_level);
+        self.saved = saved;
+    }
+
+    /// Backtracks to the given decision level, keeping all assignments made at or below it.
+    /// This is usually the assertion level of the learned clause, but may be higher when
+    /// backtracking chronologically.
     pub fn backtrack(
         &mut self,
         state: &mut State,
         heuristic: &mut dyn Heuristic,
-        assertion_level: usize,
+        backtrack_level: usize,
     ) {
         while let Some(assignment) = self.assignment_stack.last().cloned() {
-            if assignment.decision_level == assertion_level {
+            if assignment.decision_level <= backtrack_level {
                 break;
             }
             heuristic.unassign(&assignment);
             self.assignment_stack.pop();
-            state.unassign(assignment.literal);
+            state.unassign(&assignment);
         }
 
-        self.decision_level = assertion_level;
-        state.conflict_clause_id = None;
+        self.decision_level = backtrack_level;
+        self.assumption_level = self.assumption_level.min(backtrack_level);
+        self.propagated = self.propagated.min(self.assignment_stack.len());
+        state.conflict = None;
+        self.saved.clear();
+        self.truncate_notified();
+    }
+
+    fn truncate_notified(&mut self) {
+        if self.num_notified > self.assignment_stack.len() {
+            self.num_notified = self.assignment_stack.len();
+            self.notified_backtrack = true;
+        }
     }
 
     pub fn restart(&mut self, state: &mut State, heuristic: &mut dyn Heuristic) {
         self.backtrack(state, heuristic, 0);
     }
 
+    /// Restart reusing the trail as described in P. van der Tak, A. Ramos, and M. J. H. Heule,
+    /// “Reusing the Assignment Trail in CDCL Solvers,” Journal on Satisfiability, Boolean
+    /// Modeling and Computation, vol. 7, no. 4, pp. 133–138, 2011. doi: 10.3233/SAT190082.
+    /// The decision levels whose decisions the heuristic prefers over the next variable it
+    /// would decide are kept, as they would be decided again after a full restart, and so are the
+    /// assumption levels, which are always decided first. `ReuseStrategy::BestFirst` only keeps
+    /// the levels the heuristic would also decide in the same order. Returns the number of kept
+    /// decision levels.
+    pub fn restart_reusing_trail(
+        &mut self,
+        state: &mut State,
+        heuristic: &mut dyn Heuristic,
+        strategy: ReuseStrategy,
+    ) -> usize {
+        let reused_decisions = match heuristic.peek(&state.vars) {
+            Some(next) => self.reused_decisions(heuristic, next, strategy),
+            None => 0,
+        };
+        let reused_levels = self.assumption_level + reused_decisions;
+        self.backtrack(state, heuristic, reused_levels);
+        reused_levels
+    }
+
+    /// Number of decisions at the start of the trail a restart keeps
+    fn reused_decisions(
+        &self,
+        heuristic: &dyn Heuristic,
+        next: Var,
+        strategy: ReuseStrategy,
+    ) -> usize {
+        let decisions = self
+            .assignment_stack
+            .iter()
+            .filter(|assignment| assignment.reason == AssignmentReason::Heuristic)
+            .map(|assignment| assignment.literal.var())
+            .collect_vec();
+        match strategy {
+            ReuseStrategy::Preferred => decisions
+                .iter()
+                .take_while(|var_id| heuristic.prefers(**var_id, next))
+                .count(),
+            ReuseStrategy::BestFirst => {
+                // the variable the heuristic prefers most among the later decisions and `next`
+                let mut best_later = vec![next; decisions.len()];
+                for index in (1..decisions.len()).rev() {
+                    let later = decisions[index];
+                    best_later[index - 1] = match heuristic.prefers(later, best_later[index]) {
+                        true => later,
+                        false => best_later[index],
+                    };
+                }
+                decisions
+                    .iter()
+                    .zip(best_later)
+                    .take_while(|(var_id, best)| heuristic.prefers(**var_id, *best))
+                    .count()
+            }
+        }
+    }
+
     pub fn push_assignment(&mut self, assignment: Assignment) {
         self.assignment_stack.push(assignment);
     }
 
     pub fn get_reason(&self, literal: Literal) -> &AssignmentReason {
-        let pos = self.var_assignment_pos[literal.id()];
+        let pos = self.var_assignment_pos[literal.var()];
         &self.assignment_stack[pos].reason
     }
 
+    /// The implication graph in the DOT format, see `ConflictGraph`
     pub fn implication_graph(&self, state: &State) -> String {
-        let mut out = String::from("digraph G {\n");
-        for assignment in self.assignment_stack.iter() {
-            if let AssignmentReason::Forced(reason) = &assignment.reason {
-                for lit in &state.clause_database[*reason].literals {
-                    if lit == &assignment.literal {
-                        continue;
-                    }
-                    let dl_lit = self.var_decision_level[lit.id()];
-                    let dl_re = self.var_decision_level[assignment.literal.id()];
-                    out.push_str(&format!(
-                        "\"{}@{}\" -> \"{}@{}\" [label=\"{}\"];\n",
-                        -*lit, dl_lit, assignment.literal, dl_re, reason,
-                    ));
-                }
-            }
-        }
-        if let Some(conflict_clause_id) = &state.conflict_clause_id {
-            for lit in &state.clause_database[*conflict_clause_id].literals {
-                let dl_lit = self.var_decision_level[lit.id()];
-                out.push_str(&format!(
-                    "\"{}@{}\" -> C [color=red, label=\"\"];\n",
-                    -*lit, dl_lit
-                ));
-            }
-        }
-        out.push_str("}\n");
-        out
+        ConflictGraph::new(self, state).to_dot()
     }
 }
+
+/// Whether the clause implies the literal under the current assignment, i.e. it contains the
+/// literal and all other literals are false
+fn is_unit_reason(state: &State, literal: Literal, clause_id: ClauseId) -> bool {
+    let clause = &state.clause_database[clause_id];
+    clause.literals.contains(&literal)
+        && clause
+            .literals
+            .iter()
+            .all(|lit| *lit == literal || lit.is_false(&state.vars))
+}
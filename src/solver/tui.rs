@@ -0,0 +1,192 @@
+//! Live view of the search in the terminal, redrawn in place with ANSI escape codes instead of
+//! appending rows to the progress table
+
+use crate::solver::clock::Instant;
+use crate::solver::progress::EventKind;
+use crate::solver::statistics::StateStatistics;
+use alloc::collections::VecDeque;
+use colored::Colorize;
+use std::io::Write;
+
+/// Events kept for the bottom of the view, older ones scroll out
+const MAX_EVENTS: usize = 8;
+
+/// Width of the longest bar of the LBD histogram
+const BAR_WIDTH: usize = 40;
+
+pub struct LiveView {
+    start_time: Instant,
+    /// Restarts and database reductions with the seconds since the start of the search
+    events: VecDeque<(EventKind, f64, String)>,
+    /// Lines of the last frame, which are overwritten by the next one
+    num_drawn_lines: usize,
+    time_of_last_draw: Instant,
+    conflicts_of_last_draw: usize,
+    propagations_of_last_draw: usize,
+}
+
+impl LiveView {
+    pub fn new() -> Self {
+        LiveView {
+            start_time: Instant::now(),
+            events: VecDeque::with_capacity(MAX_EVENTS),
+            num_drawn_lines: 0,
+            time_of_last_draw: Instant::now(),
+            conflicts_of_last_draw: 0,
+            propagations_of_last_draw: 0,
+        }
+    }
+
+    /// Consecutive events of the same kind replace each other, so restarts don't push the
+    /// reductions out
+    pub fn event(&mut self, kind: EventKind, event: String) {
+        if self.events.back().is_some_and(|(last, _, _)| *last == kind) {
+            self.events.pop_back();
+        } else if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        let seconds = self.start_time.elapsed().as_secs_f64();
+        self.events.push_back((kind, seconds, event));
+    }
+
+    /// Replaces the previous frame on stderr by the current one
+    pub fn draw(
+        &mut self,
+        stats: &StateStatistics,
+        current_num_assignments: usize,
+        current_num_clauses: usize,
+    ) {
+        let lines = self.frame(stats, current_num_assignments, current_num_clauses);
+        let mut out = String::new();
+        if self.num_drawn_lines > 0 {
+            // move to the start of the previous frame and clear everything below
+            out.push_str(&format!("\x1b[{}F\x1b[J", self.num_drawn_lines));
+        }
+        for line in &lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        let mut stderr = std::io::stderr().lock();
+        // a broken terminal must not abort the search
+        let _ = stderr
+            .write_all(out.as_bytes())
+            .and_then(|_| stderr.flush());
+        self.num_drawn_lines = lines.len();
+        self.time_of_last_draw = Instant::now();
+        self.conflicts_of_last_draw = stats.num_conflicts;
+        self.propagations_of_last_draw = stats.num_propagations;
+    }
+
+    fn frame(
+        &self,
+        stats: &StateStatistics,
+        current_num_assignments: usize,
+        current_num_clauses: usize,
+    ) -> Vec<String> {
+        let seconds = self.time_of_last_draw.elapsed().as_secs_f64().max(1e-3);
+        let per_second = |now: usize, before: usize| ((now - before) as f64 / seconds) as usize;
+        let assigned_percent = match stats.num_vars {
+            0 => 0.0,
+            num_vars => current_num_assignments as f64 / num_vars as f64 * 100.0,
+        };
+        let mut lines = vec![
+            format!(
+                "c {} {:.1}s",
+                "utopia".bold(),
+                stats.start_time.elapsed().as_secs_f64()
+            ),
+            format!(
+                "c conflicts {:>12} {:>10}/s   decisions {:>12}   restarts {:>8}",
+                stats.num_conflicts,
+                per_second(stats.num_conflicts, self.conflicts_of_last_draw),
+                stats.num_decisions,
+                stats.num_restarts,
+            ),
+            format!(
+                "c props/s   {:>12}   assigned {:>10} ({:.0}%)   eliminated vars {:>8}",
+                per_second(stats.num_propagations, self.propagations_of_last_draw),
+                current_num_assignments,
+                assigned_percent,
+                stats.num_eliminated_vars,
+            ),
+            format!(
+                "c clauses   {:>12}   learned {:>11}",
+                current_num_clauses, stats.num_learned_clauses,
+            ),
+            format!(
+                "c {} (mean {:.2})",
+                "LBDs of learned clauses".bold(),
+                stats.learned_lbds.mean()
+            ),
+        ];
+        let buckets = stats.learned_lbds.buckets();
+        let max_count = buckets.iter().copied().max().unwrap_or(0).max(1);
+        for (lbd, &count) in buckets.iter().enumerate().skip(1) {
+            let label = match lbd == buckets.len() - 1 {
+                true => format!("{}+", lbd),
+                false => format!("{}", lbd),
+            };
+            let bar = "█".repeat((count * BAR_WIDTH).div_ceil(max_count));
+            lines.push(format!(
+                "c {:>3} {:<BAR_WIDTH$} {}",
+                label,
+                bar.cyan(),
+                count
+            ));
+        }
+        lines.push(format!("c {}", "Events".bold()));
+        for (_, seconds, event) in &self.events {
+            lines.push(format!("c {:>8.2}s {}", seconds, event));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_shows_histogram_and_recent_events() {
+        colored::control::set_override(false);
+        let mut stats = StateStatistics {
+            num_conflicts: 42,
+            ..Default::default()
+        };
+        for lbd in [2, 2, 3, 2, 15] {
+            stats.learned_lbds.add(lbd);
+        }
+        let mut view = LiveView::new();
+        for reduction in 0..MAX_EVENTS {
+            view.event(EventKind::Reduction, format!("reduction {}", reduction));
+            view.event(EventKind::Restart, format!("restart {}", 2 * reduction));
+            view.event(EventKind::Restart, format!("restart {}", 2 * reduction + 1));
+        }
+
+        let frame = view.frame(&stats, 0, 0);
+        assert!(frame[1].starts_with("c conflicts           42"));
+        let bar = |n| "█".repeat(n);
+        assert!(frame.contains(&format!("c   2 {:<40} 3", bar(40))));
+        assert!(frame.contains(&format!("c   3 {:<40} 1", bar(14))));
+        assert!(frame.contains(&format!("c 10+ {:<40} 1", bar(14))));
+        let events = frame
+            .iter()
+            .skip_while(|line| *line != "c Events")
+            .skip(1)
+            .map(|line| line.split_once("s ").unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                "reduction 4",
+                "restart 9",
+                "reduction 5",
+                "restart 11",
+                "reduction 6",
+                "restart 13",
+                "reduction 7",
+                "restart 15"
+            ]
+        );
+    }
+}
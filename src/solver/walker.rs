@@ -0,0 +1,277 @@
+use crate::cnf::{Literal, Var};
+use crate::solver::config::Config;
+use crate::solver::state::State;
+use alloc::{vec, vec::Vec};
+#[cfg(not(feature = "std"))]
+use num_traits::float::FloatCore;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Bases of the break values for average clause lengths, interpolated linearly in between
+const BREAK_BASES: [(f64, f64); 6] = [
+    (0.0, 2.0),
+    (3.0, 2.5),
+    (4.0, 2.85),
+    (5.0, 3.7),
+    (6.0, 5.1),
+    (7.0, 7.4),
+];
+
+/// Local search with ProbSAT, see A. Balint and U. Schöning, “Choosing Probability
+/// Distributions for Stochastic Local Search and the Role of Make versus Break,” in Theory and
+/// Applications of Satisfiability Testing – SAT 2012, Springer, 2012, pp. 16–29. doi:
+/// 10.1007/978-3-642-31612-8_3. It runs on the irredundant clauses at level 0 every few
+/// restarts, starting from the saved phases. The assignment falsifying the fewest clauses is
+/// saved as phases, so the search continues close to it, like rephasing by walking in Kissat.
+#[derive(Debug, Clone)]
+pub struct Walker {
+    enabled: bool,
+    /// See `Params::walk_interval`, `Params::walk_effort` and `Params::walk_min_flips`
+    interval: usize,
+    effort: f64,
+    min_flips: usize,
+    rng: StdRng,
+    num_walks: usize,
+    next_walk: usize,
+    propagations_at_last_walk: usize,
+}
+
+/// Outcome of a walk
+#[derive(Debug, Clone, Copy)]
+pub struct Walk {
+    pub num_flips: usize,
+    /// Clauses falsified by the saved phases, 0 if the walk found a model of the clauses
+    pub num_falsified: usize,
+}
+
+impl Walker {
+    pub fn init(config: &Config) -> Self {
+        Walker {
+            enabled: config.walk,
+            interval: config.params.walk_interval,
+            effort: config.params.walk_effort,
+            min_flips: config.params.walk_min_flips,
+            rng: StdRng::seed_from_u64(0),
+            num_walks: 0,
+            next_walk: config.params.walk_interval,
+            propagations_at_last_walk: 0,
+        }
+    }
+
+    pub fn is_due(&self, num_conflicts: usize) -> bool {
+        self.enabled && num_conflicts >= self.next_walk
+    }
+
+    /// Walks from the saved phases and replaces them by the best assignment found. Must be
+    /// called at level 0.
+    pub fn walk(&mut self, state: &mut State) -> Walk {
+        self.num_walks += 1;
+        self.next_walk = state.stats.num_conflicts + self.interval * (self.num_walks + 1);
+        let propagations = state.stats.num_propagations - self.propagations_at_last_walk;
+        self.propagations_at_last_walk = state.stats.num_propagations;
+        let max_flips = self
+            .min_flips
+            .max((propagations as f64 * self.effort) as usize);
+
+        let mut search = LocalSearch::new(state);
+        let mut best = search.values.clone();
+        let mut num_best = search.falsified.len();
+        let mut num_flips = 0;
+        while num_best > 0 && num_flips < max_flips {
+            let clause = search.falsified[self.rng.gen_range(0..search.falsified.len())];
+            let var = search.pick_var(clause, &mut self.rng);
+            search.flip(var);
+            num_flips += 1;
+            if search.falsified.len() < num_best {
+                num_best = search.falsified.len();
+                best.clone_from(&search.values);
+            }
+        }
+
+        for var in Var::range(state.num_vars) {
+            if !state.vars.is_assigned(var) {
+                state.var_phases[var] = best[var];
+            }
+        }
+        state.stats.num_walks += 1;
+        state.stats.num_walk_flips += num_flips;
+        Walk {
+            num_flips,
+            num_falsified: num_best,
+        }
+    }
+}
+
+/// The irredundant clauses without the literals fixed at level 0, with the clauses they
+/// falsify under the current assignment
+struct LocalSearch {
+    clauses: Vec<Vec<Literal>>,
+    /// Clauses containing each literal, indexed by `Literal::code`
+    occurrences: Vec<Vec<usize>>,
+    values: Vec<bool>,
+    num_true: Vec<usize>,
+    falsified: Vec<usize>,
+    /// Position of each clause in `falsified`
+    falsified_positions: Vec<usize>,
+    /// Weights of the variables of a clause for picking one, reused between flips
+    weights: Vec<f64>,
+    break_base: f64,
+}
+
+impl LocalSearch {
+    fn new(state: &State) -> Self {
+        let database = &state.clause_database;
+        let clauses: Vec<Vec<Literal>> = database
+            .iter()
+            .filter(|&clause_id| database[clause_id].lbd.is_none())
+            .map(|clause_id| &database[clause_id].literals)
+            .filter(|literals| !literals.iter().any(|lit| lit.is_true(&state.vars)))
+            .map(|literals| {
+                literals
+                    .iter()
+                    .filter(|lit| lit.is_free(&state.vars))
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            // falsified clauses are conflicts the search finds anyway
+            .filter(|literals| !literals.is_empty())
+            .collect();
+
+        let mut occurrences = vec![vec![]; 2 * (state.num_vars + 1)];
+        for (clause, literals) in clauses.iter().enumerate() {
+            for lit in literals {
+                occurrences[lit.code()].push(clause);
+            }
+        }
+        let num_literals: usize = clauses.iter().map(|literals| literals.len()).sum();
+        let average_len = num_literals as f64 / clauses.len().max(1) as f64;
+
+        let mut search = LocalSearch {
+            values: state.var_phases.clone(),
+            num_true: vec![0; clauses.len()],
+            falsified: vec![],
+            falsified_positions: vec![0; clauses.len()],
+            weights: vec![],
+            break_base: break_base(average_len),
+            occurrences,
+            clauses,
+        };
+        for clause in 0..search.clauses.len() {
+            search.num_true[clause] = search.clauses[clause]
+                .iter()
+                .filter(|lit| search.is_true(**lit))
+                .count();
+            if search.num_true[clause] == 0 {
+                search.falsify(clause);
+            }
+        }
+        search
+    }
+
+    fn is_true(&self, lit: Literal) -> bool {
+        self.values[lit.var()] == lit.positive()
+    }
+
+    /// Clauses only satisfied by the variable, which would become falsified by flipping it
+    fn break_value(&self, var: Var) -> usize {
+        let lit = Literal::from_value(var, self.values[var]);
+        self.occurrences[lit.code()]
+            .iter()
+            .filter(|&&clause| self.num_true[clause] == 1)
+            .count()
+    }
+
+    /// Picks a variable of the falsified clause with a probability decreasing exponentially with
+    /// its break value
+    fn pick_var(&mut self, clause: usize, rng: &mut StdRng) -> Var {
+        let mut weights = core::mem::take(&mut self.weights);
+        weights.clear();
+        weights.extend(
+            self.clauses[clause]
+                .iter()
+                .map(|lit| self.break_base.powi(-(self.break_value(lit.var()) as i32))),
+        );
+        let mut threshold = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        let literals = &self.clauses[clause];
+        let mut picked = literals[literals.len() - 1].var();
+        for (lit, weight) in literals.iter().zip(&weights) {
+            if threshold < *weight {
+                picked = lit.var();
+                break;
+            }
+            threshold -= weight;
+        }
+        self.weights = weights;
+        picked
+    }
+
+    fn flip(&mut self, var: Var) {
+        self.values[var] = !self.values[var];
+        let satisfied = Literal::from_value(var, self.values[var]);
+        for i in 0..self.occurrences[satisfied.code()].len() {
+            let clause = self.occurrences[satisfied.code()][i];
+            self.num_true[clause] += 1;
+            if self.num_true[clause] == 1 {
+                self.satisfy(clause);
+            }
+        }
+        for i in 0..self.occurrences[(-satisfied).code()].len() {
+            let clause = self.occurrences[(-satisfied).code()][i];
+            self.num_true[clause] -= 1;
+            if self.num_true[clause] == 0 {
+                self.falsify(clause);
+            }
+        }
+    }
+
+    fn falsify(&mut self, clause: usize) {
+        self.falsified_positions[clause] = self.falsified.len();
+        self.falsified.push(clause);
+    }
+
+    fn satisfy(&mut self, clause: usize) {
+        let position = self.falsified_positions[clause];
+        let last = *self.falsified.last().unwrap();
+        self.falsified_positions[last] = position;
+        self.falsified.swap_remove(position);
+    }
+}
+
+fn break_base(average_len: f64) -> f64 {
+    for pair in BREAK_BASES.windows(2) {
+        let [(len1, base1), (len2, base2)] = [pair[0], pair[1]];
+        if average_len <= len2 {
+            let ratio = ((average_len - len1) / (len2 - len1)).max(0.0);
+            return base1 + ratio * (base2 - base1);
+        }
+    }
+    BREAK_BASES[BREAK_BASES.len() - 1].1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::{check_assignment, Model};
+    use crate::tester::random_cnf;
+
+    #[test]
+    fn walk_finds_models_of_easy_formulas() {
+        for seed in 0..20 {
+            let clauses = random_cnf(100, 3.0, 3, seed);
+            let mut state = State::init(clauses.clone(), 100, false);
+            let mut walker = Walker::init(&Config::default());
+            let walk = walker.walk(&mut state);
+            assert_eq!(walk.num_falsified, 0, "seed {}", seed);
+            let model = Model::new(state.var_phases[1..].to_vec());
+            assert!(check_assignment(&clauses, &model), "seed {}", seed);
+            assert_eq!(state.stats.num_walk_flips, walk.num_flips);
+        }
+    }
+
+    #[test]
+    fn break_base_interpolates() {
+        assert_eq!(break_base(3.0), 2.5);
+        assert_eq!(break_base(3.5), 2.675);
+        assert_eq!(break_base(20.0), 7.4);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::cnf::{Clause, Literal, PartialAssignment, Var};
+use crate::solver::trail::ConstraintRef;
+use alloc::{vec, vec::Vec};
+use hashbrown::HashMap;
+use itertools::Itertools;
+
+/// The sum of the variables modulo 2 equals the parity
+#[derive(Debug, Clone, PartialEq)]
+pub struct XorConstraint {
+    pub vars: Vec<Var>,
+    pub parity: bool,
+}
+
+pub enum XorPropagation {
+    Nothing,
+    Units,
+    Conflict,
+}
+
+/// Finds XOR constraints encoded directly in the clauses. An XOR over n variables is encoded
+/// by the 2^(n-1) clauses over these variables, whose number of negative literals has the
+/// opposite parity, as each of them forbids one assignment with the wrong parity. XORs over more
+/// than `max_size` variables aren't detected.
+pub fn find_xors<'a>(
+    clauses: impl IntoIterator<Item = &'a Clause>,
+    max_size: usize,
+) -> Vec<XorConstraint> {
+    let mut candidates: HashMap<Vec<Var>, [Vec<u32>; 2]> = HashMap::new();
+    for clause in clauses {
+        let size = clause.literals.len();
+        if !(2..=max_size).contains(&size) {
+            continue;
+        }
+        let literals = clause
+            .literals
+            .iter()
+            .sorted_by_key(|lit| lit.var())
+            .collect_vec();
+        let vars = literals.iter().map(|lit| lit.var()).collect_vec();
+        // sorted, so duplicates are adjacent
+        if vars.windows(2).any(|pair| pair[0] == pair[1]) {
+            continue;
+        }
+
+        let signs = literals
+            .iter()
+            .enumerate()
+            .filter(|(_, lit)| lit.negative())
+            .fold(0u32, |signs, (i, _)| signs | 1 << i);
+        let parity = signs.count_ones() as usize % 2;
+        candidates.entry(vars).or_default()[parity].push(signs);
+    }
+
+    candidates
+        .into_iter()
+        .flat_map(|(vars, mut signs)| {
+            let required = 1 << (vars.len() - 1);
+            [0, 1].into_iter().filter_map(move |parity| {
+                let patterns = &mut signs[parity];
+                patterns.sort_unstable();
+                patterns.dedup();
+                (patterns.len() == required).then(|| XorConstraint {
+                    vars: vars.clone(),
+                    parity: parity == 0,
+                })
+            })
+        })
+        .sorted_by(|a, b| a.vars.cmp(&b.vars))
+        .collect()
+}
+
+/// Propagation of XOR constraints with Gauss-Jordan elimination, inspired by
+/// M. Soos, K. Nohl, and C. Castelluccia, “Extending SAT Solvers to Cryptographic Problems,”
+/// in Theory and Applications of Satisfiability Testing - SAT 2009, pp. 244–257.
+/// At every propagation fixpoint, the matrix of all XORs is eliminated using only unassigned
+/// variables as pivots. Rows without unassigned variables and the wrong parity are conflicts,
+/// rows with a single unassigned variable imply it. As every row is a sum of XORs, the negated
+/// assignment of its assigned variables forms a clause-like reason.
+#[derive(Debug, Clone, Default)]
+pub struct XorPropagator {
+    columns: Vec<Var>,
+    rows: Vec<Vec<u64>>,
+    parities: Vec<bool>,
+    /// assignment of the columns during the last elimination
+    last_assignment: Vec<Option<bool>>,
+    /// reason clause per variable, containing the implied literal
+    reasons: HashMap<Var, Vec<Literal>>,
+    conflict: Vec<Literal>,
+}
+
+impl XorPropagator {
+    pub fn new(xors: &[XorConstraint]) -> Self {
+        let columns = xors
+            .iter()
+            .flat_map(|xor| xor.vars.iter().copied())
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let column_of: HashMap<Var, usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(column, var_id)| (*var_id, column))
+            .collect();
+
+        let num_words = columns.len().div_ceil(64);
+        let rows = xors
+            .iter()
+            .map(|xor| {
+                let mut row = vec![0u64; num_words];
+                for var_id in &xor.vars {
+                    let column = column_of[var_id];
+                    row[column / 64] ^= 1 << (column % 64);
+                }
+                row
+            })
+            .collect_vec();
+
+        XorPropagator {
+            last_assignment: vec![],
+            parities: xors.iter().map(|xor| xor.parity).collect(),
+            rows,
+            columns,
+            reasons: HashMap::new(),
+            conflict: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn vars(&self) -> &[Var] {
+        &self.columns
+    }
+
+    pub fn propagate(
+        &mut self,
+        vars: &PartialAssignment,
+        units: &mut Vec<(Literal, ConstraintRef)>,
+    ) -> XorPropagation {
+        let assignment = self
+            .columns
+            .iter()
+            .map(|var_id| vars.value(*var_id))
+            .collect_vec();
+        if assignment == self.last_assignment {
+            return XorPropagation::Nothing;
+        }
+        self.last_assignment = assignment;
+
+        let mut rows = self.rows.clone();
+        let mut parities = self.parities.clone();
+        let mut num_pivots = 0;
+        for column in 0..self.columns.len() {
+            if self.last_assignment[column].is_some() {
+                continue;
+            }
+            let (word, bit) = (column / 64, 1 << (column % 64));
+            let Some(pivot) = (num_pivots..rows.len()).find(|r| rows[*r][word] & bit != 0) else {
+                continue;
+            };
+            rows.swap(num_pivots, pivot);
+            parities.swap(num_pivots, pivot);
+
+            for r in 0..rows.len() {
+                if r != num_pivots && rows[r][word] & bit != 0 {
+                    let (pivot_row, row) = if r < num_pivots {
+                        let (left, right) = rows.split_at_mut(num_pivots);
+                        (&right[0], &mut left[r])
+                    } else {
+                        let (left, right) = rows.split_at_mut(r);
+                        (&left[num_pivots], &mut right[0])
+                    };
+                    row.iter_mut()
+                        .zip(pivot_row.iter())
+                        .for_each(|(a, b)| *a ^= b);
+                    parities[r] ^= parities[num_pivots];
+                }
+            }
+            num_pivots += 1;
+        }
+
+        let mut implications = vec![];
+        for (row, parity) in rows.iter().zip(parities) {
+            let mut unassigned = None;
+            let mut num_unassigned = 0;
+            let mut parity = parity;
+            let mut reason = vec![];
+            for column in (0..self.columns.len()).filter(|c| row[c / 64] & 1 << (c % 64) != 0) {
+                let var_id = self.columns[column];
+                match self.last_assignment[column] {
+                    Some(value) => {
+                        parity ^= value;
+                        reason.push(Literal::from_value(var_id, !value));
+                    }
+                    None => {
+                        num_unassigned += 1;
+                        unassigned = Some(var_id);
+                    }
+                }
+            }
+
+            match (num_unassigned, unassigned) {
+                (0, _) if parity => {
+                    self.conflict = reason;
+                    return XorPropagation::Conflict;
+                }
+                (1, Some(var_id)) => {
+                    let implied = Literal::from_value(var_id, parity);
+                    reason.push(implied);
+                    implications.push((var_id, reason));
+                }
+                _ => {}
+            }
+        }
+
+        if implications.is_empty() {
+            return XorPropagation::Nothing;
+        }
+        // the units may be discarded by a conflict, so the next elimination must not be skipped
+        self.last_assignment.clear();
+        for (var_id, reason) in implications {
+            units.push((*reason.last().unwrap(), ConstraintRef::Xor));
+            self.reasons.insert(var_id, reason);
+        }
+        XorPropagation::Units
+    }
+
+    /// Reason clause of the implied literal, or the conflict clause for `None`
+    pub fn explain(&self, implied: Option<Literal>) -> &[Literal] {
+        match implied {
+            Some(lit) => &self.reasons[&lit.var()],
+            None => &self.conflict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnf::check_assignment;
+    use crate::dimacs::clauses_from_dimacs_file;
+    use crate::solver::config::Config;
+    use crate::solver::params::Params;
+    use crate::solver::Solver;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn xor_clauses(vars: &[Var], parity: bool) -> Vec<Clause> {
+        (0..1u32 << vars.len())
+            .filter(|signs| (signs.count_ones() % 2 == 0) == parity)
+            .map(|signs| {
+                let literals = vars
+                    .iter()
+                    .enumerate()
+                    .map(|(i, var_id)| Literal::from_value(*var_id, signs >> i & 1 == 0))
+                    .collect_vec();
+                Clause::from(literals)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gaussian_elimination_preserves_models() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let num_vars = 10;
+        for _ in 0..100 {
+            let mut clauses = vec![];
+            for _ in 0..rng.gen_range(1..8) {
+                let size = rng.gen_range(2..=4);
+                let vars = Var::range(num_vars)
+                    .filter(|_| rng.gen_bool(0.4))
+                    .take(size)
+                    .collect_vec();
+                if vars.len() >= 2 {
+                    clauses.extend(xor_clauses(&vars, rng.gen()));
+                }
+            }
+            for _ in 0..rng.gen_range(0..6) {
+                let literals = (0..3)
+                    .map(|_| Literal::from_value(Var::new(rng.gen_range(1..=num_vars)), rng.gen()))
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                clauses.push(Clause::from(literals));
+            }
+
+            let counts = [false, true].map(|gaussian_elimination| {
+                let config = Config {
+                    gaussian_elimination,
+                    ..Default::default()
+                };
+                let mut solver = Solver::new(clauses.clone(), num_vars, config);
+                solver.count_models().unwrap().to_u128()
+            });
+            assert_eq!(counts[0], counts[1], "{:?}", clauses);
+        }
+    }
+
+    #[test]
+    fn detect_xors() {
+        let clauses = [
+            "1 2 3", "1 -2 -3", "-1 2 -3", "-1 -2 3", "4 5", "-4 -5", "1 4",
+        ]
+        .map(Clause::from);
+        assert_eq!(
+            find_xors(&clauses, Params::default().max_xor_size),
+            vec![
+                XorConstraint {
+                    vars: [1, 2, 3].map(Var::new).to_vec(),
+                    parity: true
+                },
+                XorConstraint {
+                    vars: [4, 5].map(Var::new).to_vec(),
+                    parity: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn solve_parity_instance() {
+        let dimacs =
+            clauses_from_dimacs_file("testfiles/lecture_testfiles/sat/par8-5-c.cnf").unwrap();
+        let config = Config {
+            gaussian_elimination: true,
+            ..Default::default()
+        };
+        let mut solver = Solver::new(dimacs.clauses.clone(), dimacs.num_vars, config);
+        let model = solver.solve().into_model().unwrap();
+        assert!(check_assignment(&dimacs.clauses, &model));
+    }
+}
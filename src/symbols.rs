@@ -0,0 +1,157 @@
+use crate::cnf::{Literal, Model, Var};
+use crate::error::{Error, Result};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::{format, vec::Vec};
+use hashbrown::HashMap;
+
+/// Separator between the scopes and the name of a variable
+pub const SCOPE_SEPARATOR: char = '.';
+
+/// Names of variables, read from `c var <var> <name>` lines of DIMACS files or given by encoders.
+/// Names inserted while scopes are entered get the scopes as prefix, e.g. `adder.carry.3`, so
+/// encodings of components can use the same local names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolTable {
+    name_of_var: BTreeMap<Var, String>,
+    var_of_name: HashMap<String, Var>,
+    scopes: Vec<String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enter_scope(&mut self, scope: &str) {
+        self.scopes.push(scope.to_string());
+    }
+
+    pub fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Names the variable, replacing its previous name. Names must be non-empty and must not
+    /// contain whitespace or `=`, so they can be written to DIMACS comments and named models.
+    pub fn insert(&mut self, var: Var, name: &str) -> Result<()> {
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+            return Err(Error::InvalidName(name.to_string()));
+        }
+        let name = self
+            .scopes
+            .iter()
+            .map(String::as_str)
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join(&SCOPE_SEPARATOR.to_string());
+        match self.var_of_name.get(&name) {
+            Some(&other) if other == var => return Ok(()),
+            Some(_) => return Err(Error::DuplicateName(name)),
+            None => {}
+        }
+        if let Some(previous) = self.name_of_var.insert(var, name.clone()) {
+            self.var_of_name.remove(&previous);
+        }
+        self.var_of_name.insert(name, var);
+        Ok(())
+    }
+
+    pub fn name(&self, var: Var) -> Option<&str> {
+        self.name_of_var.get(&var).map(String::as_str)
+    }
+
+    /// The variable of the full name including the scopes
+    pub fn var(&self, name: &str) -> Option<Var> {
+        self.var_of_name.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.name_of_var.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name_of_var.is_empty()
+    }
+
+    /// The named variables ordered by variable
+    pub fn iter(&self) -> impl Iterator<Item = (Var, &str)> {
+        self.name_of_var
+            .iter()
+            .map(|(var, name)| (*var, name.as_str()))
+    }
+
+    /// The named variables within the scope, e.g. `adder` contains `adder.carry.3`
+    pub fn in_scope<'a>(&'a self, scope: &'a str) -> impl Iterator<Item = (Var, &'a str)> {
+        self.iter().filter(move |(_, name)| {
+            name.strip_prefix(scope)
+                .is_some_and(|rest| rest.starts_with(SCOPE_SEPARATOR))
+        })
+    }
+
+    /// `name=true` or `name=false` for the named variables of the literals, e.g. the model or a
+    /// prime implicant. Variables without a name are left out.
+    pub fn named_values(&self, literals: impl IntoIterator<Item = Literal>) -> String {
+        literals
+            .into_iter()
+            .filter_map(|lit| {
+                let name = self.name(lit.var())?;
+                Some(format!("{}={}", name, lit.positive()))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The named values of all variables of the model
+    pub fn named_model(&self, model: &Model) -> String {
+        self.named_values(
+            self.iter()
+                .filter(|(var, _)| var.index() <= model.num_vars())
+                .map(|(var, _)| Literal::from_value(var, model[var])),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn scoped_names() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(Var::new(1), "x").unwrap();
+        symbols.enter_scope("adder");
+        symbols.insert(Var::new(2), "carry").unwrap();
+        symbols.enter_scope("bit0");
+        symbols.insert(Var::new(3), "sum").unwrap();
+        symbols.exit_scope();
+        symbols.exit_scope();
+
+        assert_eq!(symbols.name(Var::new(3)), Some("adder.bit0.sum"));
+        assert_eq!(symbols.var("adder.carry"), Some(Var::new(2)));
+        assert_eq!(symbols.var("carry"), None);
+        assert_eq!(
+            symbols
+                .in_scope("adder")
+                .map(|(var, _)| var)
+                .collect::<Vec<_>>(),
+            [Var::new(2), Var::new(3)]
+        );
+        assert!(matches!(
+            symbols.insert(Var::new(4), "adder.carry"),
+            Err(Error::DuplicateName(_))
+        ));
+        assert!(matches!(
+            symbols.insert(Var::new(4), "a b"),
+            Err(Error::InvalidName(_))
+        ));
+
+        symbols.insert(Var::new(1), "y").unwrap();
+        assert_eq!(symbols.var("x"), None);
+        let model = Model::new(vec![true, false, true, false]);
+        assert_eq!(
+            symbols.named_model(&model),
+            "y=true adder.carry=false adder.bit0.sum=true"
+        );
+    }
+}
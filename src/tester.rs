@@ -0,0 +1,159 @@
+use crate::cnf::{check_assignment, Clause, Literal, Model, SolveResult, Var};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+
+/// Brute force is only feasible for few variables
+pub const MAX_BRUTE_FORCE_VARS: usize = 24;
+
+/// Uniform random k-SAT with `round(ratio * num_vars)` clauses of `clause_length` distinct
+/// variables, reproducible by the seed. The hardest instances are around the phase transition,
+/// e.g. at a ratio of 4.26 for 3-SAT.
+pub fn random_cnf(num_vars: usize, ratio: f64, clause_length: usize, seed: u64) -> Vec<Clause> {
+    assert!(
+        clause_length <= num_vars,
+        "Clauses longer than the number of variables"
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_clauses = (ratio * num_vars as f64).round() as usize;
+    (0..num_clauses)
+        .map(|_| {
+            let literals = sample(&mut rng, num_vars, clause_length)
+                .into_iter()
+                .map(|index| Literal::from_value(Var::new(index + 1), rng.gen()))
+                .collect_vec();
+            Clause::from(literals)
+        })
+        .collect()
+}
+
+/// Whether any assignment satisfies the clauses, by trying all of them
+pub fn brute_force_sat(clauses: &[Clause], num_vars: usize) -> bool {
+    assert!(num_vars <= MAX_BRUTE_FORCE_VARS, "Too many variables");
+    (0..1u64 << num_vars).any(|bits| {
+        let model = Model::new((0..num_vars).map(|i| bits >> i & 1 == 1).collect());
+        check_assignment(clauses, &model)
+    })
+}
+
+/// Solves the clauses and compares the result to brute force, returns a description of the
+/// mismatch if the solver is wrong
+pub fn cross_check(clauses: &[Clause], num_vars: usize, config: &Config) -> Option<String> {
+    let expected = brute_force_sat(clauses, num_vars);
+    let result = Solver::new(clauses.to_vec(), num_vars, config.clone()).solve();
+    match (result, expected) {
+        (SolveResult::Sat(model), true) if check_assignment(clauses, &model) => None,
+        (SolveResult::Sat(_), true) => Some("the model does not satisfy the formula".to_string()),
+        (SolveResult::Sat(_), false) => Some("SAT, but the formula is UNSAT".to_string()),
+        (SolveResult::Unsat, true) => Some("UNSAT, but the formula is SAT".to_string()),
+        (SolveResult::Unsat, false) => None,
+        (SolveResult::Unknown(reason), _) => Some(format!("unknown result: {:?}", reason)),
+    }
+}
+
+/// Shrinks a formula for which `fails` holds by delta debugging, see A. Zeller and
+/// R. Hildebrandt, “Simplifying and isolating failure-inducing input,” IEEE Transactions on
+/// Software Engineering, vol. 28, no. 2, pp. 183–200, 2002, doi: 10.1109/32.988498.
+/// First chunks of clauses are removed with halving chunk sizes, then single literals. Clauses
+/// never become empty. The result still fails and is 1-minimal with respect to both steps.
+pub fn shrink(clauses: &[Clause], mut fails: impl FnMut(&[Clause]) -> bool) -> Vec<Clause> {
+    let mut clauses = clauses.to_vec();
+    let mut chunk_size = clauses.len().div_ceil(2).max(1);
+    loop {
+        let mut start = 0;
+        while start < clauses.len() {
+            let end = (start + chunk_size).min(clauses.len());
+            let candidate = [&clauses[..start], &clauses[end..]].concat();
+            if fails(&candidate) {
+                clauses = candidate;
+            } else {
+                start = end;
+            }
+        }
+        if chunk_size == 1 {
+            break;
+        }
+        chunk_size = chunk_size.div_ceil(2);
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for clause_index in 0..clauses.len() {
+            let mut literal_index = 0;
+            while literal_index < clauses[clause_index].literals.len()
+                && clauses[clause_index].literals.len() > 1
+            {
+                let mut candidate = clauses.clone();
+                let mut literals = candidate[clause_index].literals.clone();
+                literals.remove(literal_index);
+                candidate[clause_index] = Clause::from(literals);
+                if fails(&candidate) {
+                    clauses = candidate;
+                    changed = true;
+                } else {
+                    literal_index += 1;
+                }
+            }
+        }
+    }
+    clauses
+}
+
+/// Largest variable occurring in the clauses
+pub fn max_var(clauses: &[Clause]) -> usize {
+    clauses
+        .iter()
+        .flat_map(|clause| clause.literals.iter().map(|lit| lit.var().index()))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_cnf_shape() {
+        let clauses = random_cnf(20, 4.26, 3, 7);
+        assert_eq!(clauses.len(), 85);
+        for clause in &clauses {
+            assert_eq!(
+                clause.literals.iter().map(|lit| lit.var()).unique().count(),
+                3
+            );
+            assert!(clause
+                .literals
+                .iter()
+                .all(|lit| (1..=20).contains(&lit.var().index())));
+        }
+        assert_eq!(clauses, random_cnf(20, 4.26, 3, 7));
+    }
+
+    #[test]
+    fn shrinks_to_a_minimal_failing_formula() {
+        let clauses = ["1 2 3", "-1 4", "2 -3", "-4", "3 4 5", "-2 -5"].map(Clause::from);
+        // fails if 1 and -4 occur somewhere
+        let contains = |clauses: &[Clause], lit: i32| {
+            clauses
+                .iter()
+                .any(|clause| clause.literals.contains(&Literal::new(lit)))
+        };
+        let shrunk = shrink(&clauses, |clauses| {
+            contains(clauses, 1) && contains(clauses, -4)
+        });
+        assert_eq!(shrunk, vec![Clause::from("1"), Clause::from("-4")]);
+    }
+
+    #[test]
+    fn solver_agrees_with_brute_force() {
+        let config = Config::default();
+        for seed in 0..100 {
+            let clauses = random_cnf(12, 4.26, 3, seed);
+            assert_eq!(cross_check(&clauses, 12, &config), None, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::dimacs::{clauses_from_dimacs, solution_to_dimacs};
+use crate::solver::config::Config;
+use crate::solver::Solver;
+use wasm_bindgen::prelude::*;
+
+/// Solves a formula in DIMACS format and returns the result in the output format of the SAT
+/// competition, e.g. for in-browser demos:
+///
+/// ```js
+/// import init, { solve } from "./pkg/utopia.js";
+///
+/// await init();
+/// console.log(solve("p cnf 2 2\n1 2 0\n-1 0\n")); // s SATISFIABLE\nv -1 2 0
+/// ```
+///
+/// Invalid input is thrown as an `Error`.
+#[wasm_bindgen]
+pub fn solve(dimacs: &str) -> Result<String, JsError> {
+    let dimacs = clauses_from_dimacs(dimacs.to_string())?;
+    let config = Config::default();
+    let result = Solver::new(dimacs.clauses, dimacs.num_vars, config).solve();
+    Ok(solution_to_dimacs(&result))
+}